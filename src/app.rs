@@ -1,21 +1,49 @@
-use std::path::PathBuf;
-use std::sync::mpsc;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
 use vello::peniko::ImageData;
 use vello::Scene;
 
-use crate::layout::{self, Layout, LayoutConfig};
-use crate::render::colors::ColorSettings;
+use crate::layout::{self, Layout, LayoutCancel, LayoutConfig};
+use crate::render::colors::{AppColor, ColorSettings, PIN_COLORS};
 use crate::render::cushion::CushionConfig;
-use crate::render::scene::{build_scene, LabelHitRegion};
+use crate::render::scene::{build_scene, draw_background, BackgroundStyle, LabelHitRegion, SceneOptions};
 use crate::render::text::TextRenderer;
 use crate::scanner;
-use crate::scanner::types::ScanProgress;
-use crate::tree::arena::{FileTree, NodeId};
-use crate::ui::input::MouseState;
+use crate::scanner::types::{RawFileEntry, ScanControl, ScanOptions, ScanProgress};
+use crate::tree::analytics::Analytics;
+use crate::tree::arena::{FileTree, NodeId, SizeBasis};
+use crate::tree::dedupe::{self, DuplicateReport};
+use crate::tree::search;
+use crate::ui::input::{self, MouseState, ViewTransform};
 use crate::ui::navigation::NavigationState;
-use crate::ui::overlay::{Analytics, SidebarHitId, SidebarHitRegion};
+use crate::ui::overlay::{SidebarHitId, SidebarHitRegion};
+use crate::ui::tooltip::{PercentBasis, SizeUnits};
+
+/// Root name of a tree merged from several scanned folders.
+const MERGED_ROOT_NAME: &str = "Selected folders";
+
+/// Space between the two halves of the split view, in px.
+const SPLIT_GAP: f32 = 12.0;
+/// How long the viewport must stay unchanged before a resize triggers a relayout.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(120);
+
+/// How often a background refresh is checked for completion.
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How often the loading spinner steps when scan updates wake the event loop themselves.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest gap between two clicks on a file tile that still opens it.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// Default cap on redraws per second while something animates (scan progress, spinner).
+pub const DEFAULT_MAX_ANIMATION_FPS: u32 = 30;
+/// Default time the cursor must rest on a node before its hover info appears.
+pub const DEFAULT_HOVER_DELAY: Duration = Duration::from_millis(250);
+
+/// Minimum time between partial-tree rebuilds while a scan is streaming in.
+const PREVIEW_MIN_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Application state machine phases.
 #[derive(Debug, PartialEq, Eq)]
@@ -28,17 +56,35 @@ pub enum AppPhase {
     Ready,
 }
 
+/// The pin-color context menu, opened on `folder` at window px (`x`, `y`).
+#[derive(Debug, Clone)]
+pub struct PinMenu {
+    pub folder: PathBuf,
+    pub x: f32,
+    pub y: f32,
+}
+
 /// Top-level application state.
 pub struct App {
     pub phase: AppPhase,
     pub scan_path: PathBuf,
+    /// Folders scanned along with `scan_path` and merged with it under one root
+    /// (empty for an ordinary single scan).
+    pub extra_scan_paths: Vec<PathBuf>,
 
     // Scan state
+    /// Latest counts the running scan reported, for the loading overlay.
     pub scan_progress: Option<ScanProgress>,
+    /// Latest directory the running scan reported, for the loading overlay.
+    pub scan_current_path: Option<PathBuf>,
+    /// Incremented per scan so stale partial trees from an earlier scan are ignored.
+    pub scan_generation: u64,
     scan_rx: Option<mpsc::Receiver<ScanProgress>>,
+    /// Pauses, resumes or cancels the running scan.
+    scan_control: Option<Arc<ScanControl>>,
 
     // Data
-    pub tree: Option<FileTree>,
+    pub tree: Option<Arc<FileTree>>,
     pub layout: Option<Layout>,
     pub layout_config: LayoutConfig,
     pub cushion_config: CushionConfig,
@@ -49,26 +95,177 @@ pub struct App {
     pub navigation: Option<NavigationState>,
     pub mouse: MouseState,
     pub hover_node: Option<NodeId>,
+    /// When the cursor last moved while the hover info was still pending.
+    pub hover_since: Option<Instant>,
+    /// Whether the hover info for `hover_node` is showing; it then stays put until the node changes.
+    pub hover_info_shown: bool,
+    /// How long the cursor must rest before hover info appears.
+    pub hover_delay: Duration,
+    /// Node focused by a command such as "reveal largest" or a click on a file; outlined in
+    /// the scene and shown in the inspector.
+    pub selected_node: Option<NodeId>,
+    /// The last file tile clicked and when, to tell a double-click that opens it.
+    last_file_click: Option<(NodeId, Instant)>,
+    /// Result of the last duplicate-file search, for the current tree.
+    pub duplicates: Option<DuplicateReport>,
+    /// Pending duplicate search running on a worker thread.
+    pub duplicates_rx: Option<mpsc::Receiver<DuplicateReport>>,
+    /// Index of the duplicate group being shown.
+    pub duplicate_group: usize,
+    /// Text of the search prompt; `Some` while it is open.
+    pub search_query: Option<String>,
+    /// Matches for `search_query` under the view it ran in, best first.
+    pub search_results: Vec<NodeId>,
+    /// Index of the match being shown; `None` until the query is run.
+    pub search_index: Option<usize>,
+    /// Text of the "go to path" prompt; `Some` while it is open.
+    pub path_query: Option<String>,
+    /// What the typed path, missing from the tree, turned out to be on disk; `None` until
+    /// it is looked up.
+    path_on_disk: Option<PathOnDisk>,
+    /// Lookup of the typed path running on a worker thread.
+    path_check: Option<mpsc::Receiver<PathOnDisk>>,
     pub analytics: Analytics,
+    /// Analytics for the whole scanned tree, computed once per tree and hidden-file setting.
+    pub root_analytics: Option<Analytics>,
+    /// Whether percentages (and the analytics panel) describe the view or the whole scan.
+    pub percent_basis: PercentBasis,
     pub show_analytics_panel: bool,
+    /// Key to the current color mode in the treemap's bottom-left corner.
+    pub show_legend: bool,
+    /// Node, layout and GPU counts in the treemap's top-left corner, for diagnosing
+    /// slow or memory-hungry views.
+    pub show_stats: bool,
+    /// How long the last relayout took.
+    pub layout_time: Duration,
+    /// Cushion tiles drawn by the last treemap render, reported by the renderer.
+    pub cushion_instances: u32,
     pub show_text_labels: bool,
+    /// Label every top-level folder that fits, ahead of the area-ranked labels.
+    pub label_top_level: bool,
+    /// Label large file tiles with their name or extension.
+    pub show_file_labels: bool,
+    /// Draw big folders' names faintly across their tiles, so they stay identifiable
+    /// when their header band is too small or collapsed away.
+    pub show_dir_watermarks: bool,
+    /// Dim everything outside the hovered folder.
+    pub focus_hovered_folder: bool,
+    /// Wrap the treemap around the sidebar into the space below it, instead of keeping it
+    /// to the column right of the sidebar.
+    pub lshape_layout: bool,
+    /// Lay out only the biggest files in view as one flat map, ignoring folders.
+    pub flat_layout: bool,
+    /// Outline files modified within this many days (`None` = off).
+    pub highlight_recent_days: Option<u32>,
     pub label_font_scale: f32,
     pub label_font_path: String,
     pub label_hit_regions: Vec<LabelHitRegion>,
     pub sidebar_hit_regions: Vec<SidebarHitRegion>,
+    /// Sidebar control with keyboard focus (Tab / Shift-Tab), drawn with a focus ring.
+    pub sidebar_focus: Option<SidebarHitId>,
     pub available_drives: Vec<crate::ui::drives::DriveEntry>,
     pub show_hover_info: bool,
+    pub size_units: SizeUnits,
+    /// Measure tiles by logical size or by size on disk.
+    pub size_basis: SizeBasis,
+    /// Overlay per-category hatch patterns on file tiles (non-color differentiation).
+    pub show_category_patterns: bool,
+    /// Sidebar depth-slider override for `LayoutConfig::max_depth` (`None` = no extra limit).
+    pub depth_limit: Option<u16>,
     pub vibrancy_dragging: bool,
+    pub brightness_dragging: bool,
+    pub depth_dragging: bool,
     pub show_admin_slow_warning: bool,
+    /// Why the last scan could not start or finish, shown until the next scan.
+    pub scan_error: Option<String>,
+    /// Sidebar shrunk to a thin strip so the treemap can use a rectangular region.
+    pub sidebar_collapsed: bool,
+    /// How far the sidebar controls are scrolled up, for windows too short to show them all
+    pub sidebar_scroll: f32,
+    /// Right-side details inspector for the selected node (or the view root)
+    pub show_inspector: bool,
+    pub inspector_width: f32,
+    pub inspector_dragging: bool,
+    /// Include NTFS system metafiles as a synthetic top-level node (MFT scans only).
+    pub include_system_metadata: bool,
+    /// Scan only this many levels deep and list deeper folders on drill-down
+    /// (walkdir scans only; `None` = full scan).
+    pub scan_depth_limit: Option<usize>,
+    /// Rescan this long after each scan finishes, keeping the current tree on screen
+    /// until the new one is ready (`None` = never).
+    pub auto_refresh_interval: Option<Duration>,
+    /// When the last scan or refresh finished.
+    last_scan_finished: Option<Instant>,
+    /// A background refresh is running; the current tree stays up until it is done.
+    refreshing: bool,
+    /// The pin-color context menu, while open.
+    pub pin_menu: Option<PinMenu>,
+    /// Folders hidden from the layout, by full path so they stay hidden across rescans;
+    /// `layout_config.excluded` holds the ones found in the current tree.
+    excluded_paths: Vec<PathBuf>,
+    /// Deeper scan of a folder the depth limit cut off, running on a worker thread.
+    pub expansion: Option<(NodeId, mpsc::Receiver<Result<FileTree, String>>)>,
+    /// Right half of the side-by-side comparison view, navigated on its own
+    /// (`None` = single view).
+    pub split_view: Option<NavigationState>,
+    /// Where to write a JSON diagnostics report after each scan (`--diagnostics`); a merged
+    /// scan writes an array with one report per folder
+    pub diagnostics_path: Option<PathBuf>,
     pub loading_started: Option<Instant>,
 
     // Rendering
     pub scene: Scene,
+    /// Treemap content in layout coordinates, composed into `scene` through `view`.
+    pub treemap_scene: Scene,
+    /// Free zoom/pan of the treemap; reset whenever navigation changes.
+    pub view: ViewTransform,
     pub needs_relayout: bool,
     pub viewport_width: f32,
     pub viewport_height: f32,
+    /// Window DPI scale factor (physical px per logical px)
+    pub scale_factor: f32,
+    /// Keep the current layout through window resizes, letterboxing it into the new size.
+    pub freeze_layout: bool,
+    /// Viewport size the current layout was computed for.
+    pub layout_viewport: (f32, f32),
+    /// Lay out and render the treemap at this size regardless of the window, scaling it
+    /// to fit, so screenshots and captures come out the same on every machine.
+    pub fixed_resolution: Option<(u32, u32)>,
+    /// Time of the most recent viewport resize, used to debounce relayout during drags.
+    pub last_resize: Option<Instant>,
+    /// When the last frame was presented; paces animation redraws.
+    pub last_frame: Option<Instant>,
+    /// Redraw rate cap while animating, so scanning doesn't render at full vsync.
+    pub max_animation_fps: u32,
+    /// Wakes the event loop from a scan thread; set by the window code. Without one, or
+    /// with `poll_scan_progress`, scans are polled at the animation frame rate.
+    pub scan_waker: Option<Waker>,
+    /// Poll the scan every animation frame instead of waiting to be woken.
+    pub poll_scan_progress: bool,
+    /// Wakes the event loop when a background layout is done; without one it is polled.
+    pub layout_waker: Option<Waker>,
+    /// Compute layouts on a worker thread (`start_relayout`) instead of in the frame.
+    pub background_layout: bool,
+    /// The background layout in flight, if any.
+    layout_job: Option<mpsc::Receiver<LayoutOutcome>>,
+    /// Bumped to call off the background layout in flight once it is superseded.
+    layout_generation: Arc<AtomicU64>,
+    /// A scan woke the event loop and `poll_scan` hasn't picked the update up yet.
+    pub scan_update_pending: bool,
     /// Cached CPU-rasterized treemap image (only rebuilt on layout changes).
     pub cached_treemap_image: Option<ImageData>,
+    /// Pixel region `[x1, y1, x2, y2]` of the treemap image to redraw without a relayout.
+    pub cushion_dirty: Option<[f32; 4]>,
+    /// What fills the window behind the treemap.
+    pub background_style: BackgroundStyle,
+    /// PNG used by `BackgroundStyle::Image` (empty = none).
+    pub background_image_path: String,
+    pub background_image: Option<ImageData>,
+    /// Item being dragged over the window, as given. Whether a dropped file scans its
+    /// folder is only looked up once it is dropped.
+    pub drop_target: Option<PathBuf>,
+    /// Folder of a dropped path, being looked up on a worker thread.
+    drop_check: Option<mpsc::Receiver<PathBuf>>,
 }
 
 impl App {
@@ -84,8 +281,12 @@ impl App {
         Self {
             phase: AppPhase::WaitingForPath,
             scan_path: scan_path.clone(),
+            extra_scan_paths: Vec::new(),
             scan_rx: None,
+            scan_control: None,
             scan_progress: None,
+            scan_current_path: None,
+            scan_generation: 0,
             tree: None,
             layout: None,
             layout_config: LayoutConfig::default(),
@@ -95,30 +296,146 @@ impl App {
             navigation: None,
             mouse: MouseState::default(),
             hover_node: None,
+            hover_since: None,
+            hover_info_shown: false,
+            hover_delay: DEFAULT_HOVER_DELAY,
+            selected_node: None,
+            last_file_click: None,
+            duplicates: None,
+            duplicates_rx: None,
+            duplicate_group: 0,
+            search_query: None,
+            search_results: Vec::new(),
+            search_index: None,
+            path_query: None,
+            path_on_disk: None,
+            path_check: None,
             analytics: Analytics::default(),
+            root_analytics: None,
+            percent_basis: PercentBasis::default(),
             show_analytics_panel: false,  // Keep analytics panel off by default
+            show_legend: true,
+            show_stats: false,
+            layout_time: Duration::ZERO,
+            cushion_instances: 0,
             show_text_labels: true,       // Enable constrained labels for orientation
+            label_top_level: false,
+            show_file_labels: false,
+            show_dir_watermarks: false,
+            focus_hovered_folder: false,
+            lshape_layout: false,
+            flat_layout: false,
+            highlight_recent_days: None,
             label_font_scale: 1.0,
             label_font_path: String::new(),
             label_hit_regions: Vec::new(),
             sidebar_hit_regions: Vec::new(),
+            sidebar_focus: None,
             available_drives: crate::ui::drives::enumerate_drives(),
             show_hover_info: true,
+            size_units: SizeUnits::default(),
+            size_basis: SizeBasis::default(),
+            show_category_patterns: false,
+            depth_limit: None,
             vibrancy_dragging: false,
+            brightness_dragging: false,
+            depth_dragging: false,
             show_admin_slow_warning: false,
+            scan_error: None,
+            sidebar_collapsed: false,
+            sidebar_scroll: 0.0,
+            show_inspector: false,
+            inspector_width: crate::ui::overlay::INSPECTOR_DEFAULT_W,
+            inspector_dragging: false,
+            include_system_metadata: false,
+            scan_depth_limit: None,
+            auto_refresh_interval: None,
+            last_scan_finished: None,
+            refreshing: false,
+            pin_menu: None,
+            excluded_paths: Vec::new(),
+            expansion: None,
+            split_view: None,
+            diagnostics_path: None,
             loading_started: None,
             scene: Scene::new(),
+            treemap_scene: Scene::new(),
+            view: ViewTransform::default(),
             needs_relayout: true,
             viewport_width: 800.0,
             viewport_height: 600.0,
+            scale_factor: 1.0,
+            freeze_layout: false,
+            layout_viewport: (800.0, 600.0),
+            fixed_resolution: None,
+            last_resize: None,
+            last_frame: None,
+            max_animation_fps: DEFAULT_MAX_ANIMATION_FPS,
+            scan_waker: None,
+            poll_scan_progress: false,
+            layout_waker: None,
+            background_layout: true,
+            layout_job: None,
+            layout_generation: Arc::new(AtomicU64::new(0)),
+            scan_update_pending: false,
             cached_treemap_image: None,
+            cushion_dirty: None,
+            background_style: BackgroundStyle::default(),
+            background_image_path: String::new(),
+            background_image: None,
+            drop_target: None,
+            drop_check: None,
+        }
+    }
+
+    /// Set the background style, loading the image lazily when `Image` is chosen.
+    /// A failed load is logged and falls back to the flat background.
+    pub fn set_background(&mut self, style: BackgroundStyle, image_path: String) {
+        if image_path.trim() != self.background_image_path.trim() {
+            self.background_image = None;
+        }
+        self.background_style = style;
+        self.background_image_path = image_path;
+
+        let path = self.background_image_path.trim();
+        if style == BackgroundStyle::Image && self.background_image.is_none() && !path.is_empty() {
+            match crate::render::scene::load_background_image(std::path::Path::new(path)) {
+                Ok(image) => self.background_image = Some(image),
+                Err(e) => tracing::warn!("Failed to load background image '{}': {}", path, e),
+            }
         }
     }
 
+    /// Queue a partial cushion redraw of `region` (e.g. the tile of a refreshed subtree),
+    /// merged with any region already pending.
+    pub fn invalidate_cushion(&mut self, region: [f32; 4]) {
+        self.cushion_dirty = Some(match self.cushion_dirty {
+            Some(d) => [d[0].min(region[0]), d[1].min(region[1]), d[2].max(region[2]), d[3].max(region[3])],
+            None => region,
+        });
+    }
+
+    /// Colors changed but the layout didn't: redraw the cushions in place.
+    pub fn recolor_treemap(&mut self) {
+        self.invalidate_cushion([0.0, 0.0, self.viewport_width, self.viewport_height]);
+    }
+
     /// Start scanning the filesystem in a background thread.
     pub fn start_scan(&mut self) {
-        self.phase = AppPhase::Scanning;
-        self.loading_started = Some(Instant::now());
+        self.scan_error = None;
+        for path in self.scan_paths() {
+            if let Err(e) = scanner::check_scan_path(&path) {
+                tracing::error!("Not scanning: {}", e);
+                self.fail_scan(e.user_message());
+                return;
+            }
+        }
+        let refreshing = self.refreshing;
+        if !refreshing {
+            self.phase = AppPhase::Scanning;
+            self.loading_started = Some(Instant::now());
+            self.scan_current_path = None;
+        }
         #[cfg(windows)]
         {
             let is_root = self
@@ -133,35 +450,111 @@ impl App {
             self.show_admin_slow_warning = false;
         }
         let (tx, rx) = mpsc::channel();
+        let waker = self.scan_waker.clone().filter(|_| !self.poll_scan_progress);
+        let tx = match &waker {
+            Some(waker) => relay_waking(tx, waker.clone()),
+            None => tx,
+        };
         self.scan_rx = Some(rx);
+        // A scan still running for the previous path would only be thrown away.
+        if let Some(previous) = self.scan_control.take() {
+            previous.cancel();
+        }
+        let control = Arc::new(ScanControl::default());
+        self.scan_control = Some(control.clone());
 
+        self.scan_generation += 1;
+        let generation = self.scan_generation;
+        let (partial_tx, partial_rx) = mpsc::channel();
         let path = self.scan_path.clone();
+        let preview_root = path.clone();
+        std::thread::spawn(move || stream_previews(partial_rx, generation, &preview_root, waker));
+
+        let extra_paths = self.extra_scan_paths.clone();
+        let diagnostics_path = self.diagnostics_path.clone();
+        let options = ScanOptions {
+            include_system_metadata: self.include_system_metadata,
+            // Previews show one scan's partial tree, which would flicker between folders; a
+            // refresh keeps the old tree up instead.
+            partial_tx: (extra_paths.is_empty() && !refreshing).then_some(partial_tx),
+            diagnostics: diagnostics_path.as_ref().map(|_| Default::default()),
+            max_depth: self.scan_depth_limit,
+            control: Some(control),
+        };
+        if !extra_paths.is_empty() {
+            std::thread::spawn(move || {
+                let started = Instant::now();
+                let mut scans = Vec::with_capacity(extra_paths.len() + 1);
+                let mut reports = Vec::new();
+                let mut failure = None;
+                for path in std::iter::once(path).chain(extra_paths) {
+                    let folder_options = ScanOptions {
+                        include_system_metadata: options.include_system_metadata,
+                        max_depth: options.max_depth,
+                        control: options.control.clone(),
+                        diagnostics: options.diagnostics.as_ref().map(|_| Default::default()),
+                        ..Default::default()
+                    };
+                    let diagnostics = folder_options.diagnostics.clone();
+                    // Auto picks the MFT reader per drive root and the walker for folders.
+                    let result = scanner::scan(&path, scanner::ScanMethod::Auto, folder_options, tx.clone());
+                    reports.extend(diagnostics.map(|d| d.lock().unwrap().clone()));
+                    match result {
+                        Ok(entries) => {
+                            let tree = crate::tree::build_tree(&entries);
+                            scans.push((path, tree));
+                        }
+                        Err(e) => {
+                            failure = Some((path, e));
+                            break;
+                        }
+                    }
+                }
+                // One report per folder scanned, up to and including a failed one.
+                if let Some(out) = diagnostics_path {
+                    match scanner::diagnostics::write_json_list(&reports, &out) {
+                        Ok(()) => tracing::info!("Wrote scan diagnostics to {:?}", out),
+                        Err(e) => tracing::warn!("{:#}", e),
+                    }
+                }
+                if let Some((path, e)) = failure {
+                    tracing::error!("Scan of {:?} failed: {}", path, e);
+                    let _ = tx.send(ScanProgress::Failed { error: Arc::new(e) });
+                    return;
+                }
+                let tree = crate::tree::merge_trees(MERGED_ROOT_NAME, &scans);
+                tracing::info!("Merged {} folders: {} nodes", scans.len(), tree.len());
+                let completed = completed_scan(&tree, started);
+                // Each folder's scan already sent a Completed; only this one finds a result.
+                SCAN_RESULT.lock().unwrap().replace(tree);
+                let _ = tx.send(completed);
+            });
+            return;
+        }
         std::thread::spawn(move || {
+            let started = Instant::now();
             let progress_tx = tx.clone();
-            match scanner::scan(&path, scanner::ScanMethod::Auto, progress_tx) {
+            let diagnostics = options.diagnostics.clone();
+            let result = scanner::scan(&path, scanner::ScanMethod::Auto, options, progress_tx);
+            if let (Some(out), Some(diagnostics)) = (diagnostics_path, diagnostics) {
+                match diagnostics.lock().unwrap().write_json(&out) {
+                    Ok(()) => tracing::info!("Wrote scan diagnostics to {:?}", out),
+                    Err(e) => tracing::warn!("{:#}", e),
+                }
+            }
+            match result {
                 Ok(entries) => {
                     let tree = crate::tree::build_tree(&entries);
                     tracing::info!("Tree built: {} nodes", tree.len());
-                    // Send a final completion signal with the tree
-                    // (We'll send the tree via a separate channel in a real impl;
-                    //  for now we serialize through progress)
-                    let _ = tx.send(ScanProgress::Completed {
-                        total_files: tree.len() as u64,
-                        total_dirs: 0,
-                        total_bytes: tree.get(tree.root).size,
-                        elapsed_ms: 0,
-                    });
-
-                    // Store tree — in production we'd use a shared Arc<Mutex<>>
-                    // For now, we'll use a different approach in the actual event loop
+                    let completed = completed_scan(&tree, started);
+                    // Store the tree before signalling, so whoever wakes on the Completed
+                    // finds it.
                     SCAN_RESULT.lock().unwrap().replace(tree);
+                    let _ = tx.send(completed);
                 }
                 Err(e) => {
                     tracing::error!("Scan failed: {}", e);
-                    let _ = tx.send(ScanProgress::Error {
-                        path,
-                        message: e.to_string(),
-                    });
+                    let _ = tx.send(ScanProgress::Failed { error: Arc::new(e) });
                 }
             }
         });
@@ -169,28 +562,174 @@ impl App {
 
     /// Start scanning a new path (resets current tree/layout state).
     pub fn start_scan_path(&mut self, path: PathBuf) {
-        self.scan_path = path.clone();
+        self.start_scan_paths(vec![path]);
+    }
+
+    /// Everything the current scan covers: `scan_path`, then any merged folders.
+    pub fn scan_paths(&self) -> Vec<PathBuf> {
+        std::iter::once(self.scan_path.clone())
+            .chain(self.extra_scan_paths.iter().cloned())
+            .collect()
+    }
+
+    /// Start scanning one or more paths; several are merged under one root, each as a
+    /// top-level folder. Does nothing for an empty list.
+    pub fn start_scan_paths(&mut self, paths: Vec<PathBuf>) {
+        let mut paths = paths.into_iter();
+        let Some(first) = paths.next() else {
+            return;
+        };
+        self.scan_path = first;
+        self.extra_scan_paths = paths.collect();
         self.tree = None;
+        // Hidden folders are found again by path in the new tree.
+        self.layout_config.clear_excluded();
         self.layout = None;
         self.navigation = None;
-        self.hover_node = None;
+        self.set_hover_node(None);
+        self.selected_node = None;
+        self.clear_duplicates();
+        self.expansion = None;
+        self.split_view = None;
+        self.close_search();
+        self.root_analytics = None;
+        self.view = ViewTransform::default();
         self.cached_treemap_image = None;
         self.label_hit_regions.clear();
         self.sidebar_hit_regions.clear();
         self.scan_progress = None;
+        self.refreshing = false;
         self.needs_relayout = true;
         self.start_scan();
     }
 
+    /// Add folders to what is being shown, e.g. from another drive, and rescan everything
+    /// merged under one root. Folders already covered are skipped; with nothing scanned
+    /// yet this is an ordinary scan of `paths`.
+    pub fn add_scan_paths(&mut self, paths: Vec<PathBuf>) {
+        if self.phase == AppPhase::WaitingForPath {
+            self.start_scan_paths(paths);
+            return;
+        }
+        let mut all = self.scan_paths();
+        let before = all.len();
+        for path in paths {
+            if !all.contains(&path) {
+                all.push(path);
+            }
+        }
+        if all.len() > before {
+            self.start_scan_paths(all);
+        }
+    }
+
+    /// Rescan in the background, keeping the current tree and view root on screen until the
+    /// new tree replaces them. Does nothing unless a finished tree is showing.
+    pub fn start_refresh(&mut self) {
+        if self.phase != AppPhase::Ready || self.scan_rx.is_some() {
+            return;
+        }
+        if self.tree.is_none() || self.navigation.is_none() {
+            return;
+        }
+        tracing::info!("Refreshing {:?}", self.scan_path);
+        self.refreshing = true;
+        self.start_scan();
+    }
+
+    /// Pause the running scan, or resume it if it is paused. Returns whether a scan was
+    /// running to toggle. Background refreshes show no loading panel, so they can't be paused.
+    pub fn toggle_scan_pause(&mut self) -> bool {
+        let scanning = self.phase == AppPhase::Scanning && self.scan_rx.is_some();
+        let Some(control) = self.scan_control.as_ref().filter(|_| scanning) else {
+            return false;
+        };
+        if control.is_paused() {
+            control.resume();
+        } else {
+            control.pause();
+        }
+        true
+    }
+
+    /// Whether the running scan is paused.
+    pub fn scan_paused(&self) -> bool {
+        self.scan_control.as_ref().is_some_and(|control| control.is_paused())
+    }
+
+    /// Whether a background refresh is running.
+    pub fn is_refreshing(&self) -> bool {
+        self.refreshing
+    }
+
+    /// When the next automatic refresh is due, if one is configured.
+    pub fn refresh_deadline(&self) -> Option<Instant> {
+        let interval = self.auto_refresh_interval?;
+        if self.phase != AppPhase::Ready || self.scan_rx.is_some() {
+            return None;
+        }
+        self.last_scan_finished.map(|at| at + interval)
+    }
+
+    /// Scan a path dropped onto the window. Folders are scanned directly and files
+    /// scan their parent, once `poll_dropped_path` has the lookup.
+    pub fn scan_dropped_path(&mut self, path: &Path) {
+        self.drop_target = None;
+        // Telling a file from a folder can stall on a sleeping disk or a network share.
+        let (tx, rx) = mpsc::channel();
+        self.drop_check = Some(rx);
+        let path = path.to_path_buf();
+        std::thread::spawn(move || {
+            let _ = tx.send(drop_scan_target(&path));
+        });
+    }
+
+    /// Start scanning the folder of the dropped path once it has been looked up. Returns
+    /// true when a scan started. Multi-item drops arrive one event per item, so a drop that
+    /// resolves to the folder already being scanned is ignored.
+    pub fn poll_dropped_path(&mut self) -> bool {
+        let Some(target) = self.drop_check.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return false;
+        };
+        self.drop_check = None;
+        if self.phase == AppPhase::Scanning && target == self.scan_path {
+            return false;
+        }
+        tracing::info!("Scanning dropped path: {}", target.display());
+        self.start_scan_path(target);
+        true
+    }
+
     /// Poll for scan completion. Call this from the event loop.
     pub fn poll_scan(&mut self) -> bool {
+        self.scan_update_pending = false;
+        // Swap in the latest partial tree so the treemap fills in while scanning.
+        if self.phase == AppPhase::Scanning {
+            let preview = SCAN_PREVIEW.lock().unwrap().take();
+            if let Some((generation, mut tree)) = preview {
+                if generation == self.scan_generation {
+                    crate::tree::set_size_basis(&mut tree, self.size_basis);
+                    // Previews of one scan only ever gain nodes, so node ids stay valid from
+                    // one to the next: the view root, selection and layout are kept, and the
+                    // user can look around while the scan runs.
+                    if self.navigation.is_none() {
+                        self.navigation = Some(NavigationState::new(tree.root));
+                    }
+                    self.resolve_excluded(&tree);
+                    self.tree = Some(Arc::new(tree));
+                    self.root_analytics = None;
+                    self.needs_relayout = true;
+                }
+            }
+        }
+
         if let Some(rx) = &self.scan_rx {
             // Drain all available messages
             while let Ok(progress) = rx.try_recv() {
                 match &progress {
                     ScanProgress::Completed { .. } => {
                         // Check if the tree is ready
-                        if let Some(tree) = SCAN_RESULT.lock().unwrap().take() {
+                        if let Some(mut tree) = SCAN_RESULT.lock().unwrap().take() {
                             let root = tree.root;
 
                             // Validate tree has actual data
@@ -203,86 +742,314 @@ impl App {
                                      - Or run as Administrator"
                                 );
                             } else {
-                                tracing::info!("Tree built: {} nodes", tree.len());
+                                tracing::info!(
+                                    "Tree built: {} nodes, {} empty-directory anomalies, {} orphaned entries",
+                                    tree.len(),
+                                    tree.empty_dir_anomalies,
+                                    tree.orphaned_entries
+                                );
                             }
 
-                            self.tree = Some(tree);
-                            self.navigation = Some(NavigationState::new(root));
+                            crate::tree::set_size_basis(&mut tree, self.size_basis);
+                            self.refreshing = false;
+                            // Node ids are not stable across rebuilds. A refresh, or a scan whose
+                            // preview was up, carries the view over to the same paths, as it is
+                            // now rather than when the scan started.
+                            let shown = self.tree.take().zip(self.navigation.take());
+                            let view_root = shown.as_ref().and_then(|(old, nav)| {
+                                let root = carry_node(old, &tree, nav.current_root, &self.scan_path);
+                                self.carry_view_state(old, &tree);
+                                root
+                            });
+                            self.resolve_excluded(&tree);
+                            if shown.is_none() {
+                                self.layout_config.ungrouped_dirs.clear();
+                                self.selected_node = None;
+                                self.clear_duplicates();
+                                self.split_view = None;
+                                self.forget_search_results();
+                            }
+                            self.navigation = Some(match view_root {
+                                Some(node) => NavigationState::at(node, &tree),
+                                None => NavigationState::new(root),
+                            });
+                            self.tree = Some(Arc::new(tree));
+                            self.layout = None;
+                            self.set_hover_node(None);
+                            self.expansion = None;
+                            self.root_analytics = None;
                             self.phase = AppPhase::Ready;
+                            if let Some(line) = self.drive_reconciliation() {
+                                tracing::info!("{}", line);
+                            }
                             self.loading_started = None;
+                            self.last_scan_finished = Some(Instant::now());
                             self.needs_relayout = true;
                             self.scan_rx = None;
+                            self.scan_control = None;
                             return true;
                         }
                     }
+                    ScanProgress::Progress {
+                        current_path: Some(path),
+                        ..
+                    } => {
+                        self.scan_current_path = Some(path.clone());
+                    }
+                    ScanProgress::Failed { error } => {
+                        let message = error.user_message();
+                        if std::mem::take(&mut self.refreshing) {
+                            // Keep showing the last good tree and try again next interval.
+                            tracing::warn!("Refresh failed, keeping the previous tree: {}", message);
+                            self.scan_rx = None;
+                            self.scan_control = None;
+                            self.last_scan_finished = Some(Instant::now());
+                            return false;
+                        }
+                        self.fail_scan(message);
+                        return true;
+                    }
                     _ => {}
                 }
-                self.scan_progress = Some(progress);
+                if matches!(progress, ScanProgress::Progress { .. }) {
+                    self.scan_progress = Some(progress);
+                }
             }
         }
         false
     }
 
-    /// Force a recomputation of the layout for the current viewport.
+    /// Drop back to waiting for a path and keep `message` on screen.
+    fn fail_scan(&mut self, message: String) {
+        self.phase = AppPhase::WaitingForPath;
+        self.scan_rx = None;
+        if let Some(control) = self.scan_control.take() {
+            control.cancel();
+        }
+        self.refreshing = false;
+        self.scan_progress = None;
+        self.loading_started = None;
+        self.show_admin_slow_warning = false;
+        // Drop any partial preview and stop accepting more from the abandoned scan.
+        self.scan_generation += 1;
+        self.tree = None;
+        self.layout = None;
+        self.navigation = None;
+        self.set_hover_node(None);
+        self.selected_node = None;
+        self.cached_treemap_image = None;
+        self.expansion = None;
+        self.split_view = None;
+        self.scan_error = Some(message);
+    }
+
+    /// Force a recomputation of the layout for the current viewport, on this thread.
+    /// Supersedes a background relayout still in flight.
     pub fn relayout(&mut self) {
-        if let (Some(tree), Some(nav)) = (&self.tree, &self.navigation) {
-            let [tx, ty, tw, th] = self.treemap_layout_rect();
-            let exclusion = self.sidebar_exclusion_rect();
-            tracing::info!(
-                "Computing layout for tree with {} nodes, root={:?}, viewport={}x{}, treemap={}x{}@{},{} exclusion={:?}",
-                tree.len(),
-                nav.current_root,
-                self.viewport_width,
-                self.viewport_height,
-                tw,
-                th,
-                tx,
-                ty,
-                exclusion
-            );
+        self.cancel_layout_job();
+        if let Some(request) = self.layout_request() {
+            self.layout_job = None;
+            let outcome = request.run();
+            self.install_layout(outcome);
+            self.needs_relayout = false;
+        }
+    }
 
-            let computed_layout = layout::compute_layout_lshape(
-                tree,
-                nav.current_root,
-                self.viewport_width,
-                self.viewport_height,
-                exclusion,
-                &self.layout_config,
-            );
+    /// Start recomputing the layout on a worker thread; the current layout stays up until
+    /// `poll_layout` swaps the new one in. One job runs at a time: while one is in flight
+    /// `needs_relayout` stays set, the job is called off, and the next starts once it has
+    /// wound down.
+    pub fn start_relayout(&mut self) {
+        if self.layout_job.is_some() {
+            self.cancel_layout_job();
+            return;
+        }
+        let Some(request) = self.layout_request() else {
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        self.layout_job = Some(rx);
+        self.needs_relayout = false;
+        let wake = self.layout_waker.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(request.run());
+            if let Some(wake) = wake {
+                wake();
+            }
+        });
+    }
 
-            tracing::info!("Layout computed: {} rectangles generated", computed_layout.rects.len());
+    /// Swap in a finished background layout. Results that were superseded while they ran
+    /// (navigation, resize, a new or changed tree) are dropped. Returns true when one was
+    /// installed.
+    pub fn poll_layout(&mut self) -> bool {
+        let Some(rx) = &self.layout_job else {
+            return false;
+        };
+        let outcome = match rx.try_recv() {
+            Ok(outcome) => outcome,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                tracing::error!("Background layout ended without a result; retrying");
+                self.layout_job = None;
+                self.needs_relayout = true;
+                return false;
+            }
+        };
+        self.layout_job = None;
+        let current = self.tree.as_ref().is_some_and(|tree| Arc::ptr_eq(tree, &outcome.tree));
+        if self.needs_relayout || !current {
+            tracing::debug!("Dropping a superseded layout");
+            return false;
+        }
+        self.install_layout(outcome);
+        true
+    }
 
-            self.layout = Some(computed_layout);
+    /// Call off the background layout in flight, if any; it stops descending and its
+    /// result is dropped.
+    fn cancel_layout_job(&self) {
+        self.layout_generation.fetch_add(1, Ordering::Relaxed);
+    }
 
-            // Recompute analytics for the current view
-            self.analytics = crate::ui::overlay::compute_analytics(tree, nav.current_root);
+    /// Call off the background layout in flight and wait for it to let go of the tree, so
+    /// the next `Arc::make_mut` changes the tree in place instead of copying it.
+    fn finish_layout_job(&mut self) {
+        if let Some(rx) = self.layout_job.take() {
+            self.cancel_layout_job();
+            let _ = rx.recv();
+            self.needs_relayout = true;
+        }
+    }
 
-            self.needs_relayout = false;
+    /// Everything the next relayout needs, captured so it can run off the UI thread.
+    fn layout_request(&self) -> Option<LayoutRequest> {
+        let (tree, nav) = (self.tree.as_ref()?, self.navigation.as_ref()?);
+        let mut config = self.layout_config.clone();
+        config.dpi_scale = self.scale_factor;
+        config.cancel = Some(LayoutCancel::new(self.layout_generation.clone()));
+        if let Some(limit) = self.depth_limit {
+            config.max_depth = config.max_depth.min(limit);
+        }
+        Some(LayoutRequest {
+            tree: tree.clone(),
+            root: nav.current_root,
+            split_root: self.split_view.as_ref().map(|split| split.current_root),
+            canvas: self.layout_canvas_rect(),
+            split_x: self.split_x(),
+            exclusion: self.layout_exclusion_rect(),
+            config,
+            flat: self.flat_layout,
+            lshape: self.lshape_layout && !self.flat_layout && self.fixed_resolution.is_none(),
+            viewport: self.layout_size(),
+            with_root_analytics: self.root_analytics.is_none(),
+        })
+    }
+
+    fn install_layout(&mut self, outcome: LayoutOutcome) {
+        self.layout = Some(outcome.layout);
+        self.layout_viewport = outcome.viewport;
+        self.layout_time = outcome.elapsed;
+        self.analytics = outcome.analytics;
+        if outcome.root_analytics.is_some() {
+            self.root_analytics = outcome.root_analytics;
+        }
+        if self.analytics.unreclaimable_size > 0 {
+            tracing::info!(
+                "Unreclaimable system files: {} of {} in view",
+                crate::ui::tooltip::format_size(self.analytics.unreclaimable_size, self.size_units),
+                crate::ui::tooltip::format_size(self.analytics.total_size, self.size_units)
+            );
+        }
+    }
+
+    /// What the treemap overlays show, from the current settings.
+    fn scene_options(&self) -> SceneOptions {
+        SceneOptions {
+            show_text_labels: self.show_text_labels,
+            label_top_level: self.label_top_level,
+            show_file_labels: self.show_file_labels,
+            show_dir_watermarks: self.show_dir_watermarks,
+            label_font_scale: self.label_font_scale,
+            show_hover_info: self.show_hover_info && self.hover_info_shown,
+            focus_hovered_folder: self.focus_hovered_folder,
+            size_units: self.size_units,
+            share_of: self.share_of(),
+            show_category_patterns: self.show_category_patterns,
         }
     }
 
     /// Rebuild the Vello scene from the current layout.
     pub fn rebuild_scene(&mut self) {
+        let options = self.scene_options();
+        let hover_rect = self.hover_rect();
         if let (Some(tree), Some(layout)) = (&self.tree, &self.layout) {
             self.label_hit_regions = build_scene(
-                &mut self.scene,
+                &mut self.treemap_scene,
                 self.cached_treemap_image.as_ref(),
                 &layout.rects,
+                &self.layout_config,
                 tree,
-                self.hover_node,
+                hover_rect,
                 &mut self.text_renderer,
-                self.show_text_labels,
-                self.label_font_scale,
-                self.show_hover_info,
+                &options,
             );
+            crate::render::scene::draw_overflow_badges(&mut self.treemap_scene, &mut self.text_renderer, layout);
+            if let Some(days) = self.highlight_recent_days {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let cutoff = now.saturating_sub(u64::from(days) * 86_400);
+                crate::render::scene::draw_recent_outlines(&mut self.treemap_scene, layout, tree, cutoff);
+            }
+            if let Some(group) = self.duplicates.as_ref().and_then(|d| d.groups.get(self.duplicate_group)) {
+                crate::render::scene::draw_duplicate_outlines(&mut self.treemap_scene, layout, &group.files);
+            }
+            if let Some(selected) = self.selected_node {
+                crate::render::scene::draw_selection_outline(&mut self.treemap_scene, layout, selected);
+            }
+            self.scene.reset();
+            draw_background(
+                &mut self.scene,
+                self.background_style,
+                self.background_image.as_ref(),
+                self.viewport_width,
+                self.viewport_height,
+            );
+            self.scene.append(&self.treemap_scene, Some(self.screen_view().affine()));
 
             // Add UI overlays
             if self.show_analytics_panel {
+                let analytics = match (self.percent_basis, &self.root_analytics) {
+                    (PercentBasis::Scan, Some(root)) => root,
+                    _ => &self.analytics,
+                };
                 crate::ui::overlay::render_analytics_panel(
                     &mut self.scene,
-                    &self.analytics,
+                    analytics,
+                    self.viewport_width,
+                    self.viewport_height,
+                );
+            }
+
+            if let (Some([x1, ..]), Some(nav)) = (self.inspector_bounds(), &self.navigation) {
+                let inspected = self.selected_node.unwrap_or(nav.current_root);
+                let reconciliation = (inspected == tree.root)
+                    .then(|| self.drive_reconciliation())
+                    .flatten();
+                crate::ui::overlay::render_inspector_panel(
+                    &mut self.scene,
+                    &mut self.text_renderer,
+                    &crate::ui::overlay::Inspected {
+                        tree,
+                        node_id: inspected,
+                        view_root: nav.current_root,
+                        reconciliation: reconciliation.as_deref(),
+                    },
+                    x1,
                     self.viewport_width,
                     self.viewport_height,
+                    self.size_units,
                 );
             }
 
@@ -311,30 +1078,263 @@ impl App {
             self.label_hit_regions.clear();
         }
 
+        let sidebar = crate::ui::overlay::Sidebar {
+            drives: &self.available_drives,
+            selected_scan_path: &self.scan_path,
+            color_settings: &self.color_settings,
+            min_brightness: self.cushion_config.min_brightness,
+            depth_limit: self.depth_limit,
+            show_hover_info: self.show_hover_info,
+            size_units: self.size_units,
+            size_basis: self.size_basis,
+            show_category_patterns: self.show_category_patterns,
+            collapsed: self.sidebar_collapsed,
+            can_navigate_up: self.navigation.as_ref().is_some_and(|nav| nav.depth() > 0),
+            can_navigate_forward: self.navigation.as_ref().is_some_and(|nav| nav.can_forward()),
+            show_hidden_files: self.layout_config.show_hidden,
+            freeze_layout: self.freeze_layout,
+            scroll: self.sidebar_scroll,
+        };
         self.sidebar_hit_regions = crate::ui::overlay::render_left_sidebar(
             &mut self.scene,
             &mut self.text_renderer,
             self.viewport_height,
-            &self.available_drives,
-            &self.scan_path,
-            &self.color_settings,
-            self.show_hover_info,
+            &sidebar,
         );
+        if let Some(region) = self
+            .sidebar_hit_regions
+            .iter()
+            .find(|r| self.sidebar_focus.as_ref() == Some(&r.id))
+        {
+            crate::ui::overlay::render_focus_ring(&mut self.scene, region.bounds);
+        }
 
-        if self.phase == AppPhase::Scanning {
-            crate::ui::overlay::render_loading_overlay(
+        if self.show_legend && self.tree.is_some() {
+            let [x, ..] = self.treemap_layout_rect();
+            crate::ui::overlay::render_color_legend(
                 &mut self.scene,
                 &mut self.text_renderer,
+                &self.color_settings,
+                &self.analytics,
+                x + 8.0,
+                self.viewport_height - crate::ui::overlay::STATUS_BAR_H - 8.0,
+            );
+        }
+
+        if let Some(tree) = self.tree.as_ref().filter(|_| !self.layout_config.excluded.is_empty()) {
+            let mut hidden: Vec<(NodeId, String, u64)> = self
+                .layout_config
+                .excluded
+                .iter()
+                .map(|&id| (id, tree.get(id).name.to_string(), tree.get(id).size))
+                .collect();
+            hidden.sort_by_key(|h| std::cmp::Reverse(h.2));
+            let [x, y, w, _] = self.treemap_layout_rect();
+            let regions = crate::ui::overlay::render_excluded_list(
+                &mut self.scene,
+                &mut self.text_renderer,
+                &hidden,
+                self.size_units,
+                x + w - 8.0,
+                y + 8.0,
+            );
+            self.sidebar_hit_regions.extend(regions);
+        }
+
+        if self.show_stats {
+            let [x, y, ..] = self.treemap_layout_rect();
+            let lines = self.stats_lines();
+            crate::ui::overlay::render_stats_panel(&mut self.scene, &mut self.text_renderer, &lines, x + 8.0, y + 8.0);
+        }
+
+        if let Some(summary) = self.status_bar_text() {
+            let [x, _, w, _] = self.treemap_layout_rect();
+            crate::ui::overlay::render_status_bar(
+                &mut self.scene,
+                &mut self.text_renderer,
+                &summary,
+                x,
+                x + w,
+                self.viewport_height,
+            );
+        }
+
+        if let Some(status) = self
+            .search_status()
+            .or_else(|| self.path_status())
+            .or_else(|| self.duplicate_status())
+            .or_else(|| self.expansion_status())
+            .or_else(|| self.scan_error.clone())
+        {
+            crate::ui::overlay::render_status_banner(
+                &mut self.scene,
+                &mut self.text_renderer,
+                &status,
+                self.viewport_width,
+                self.viewport_height - crate::ui::overlay::STATUS_BAR_H,
+            );
+        }
+
+        if let Some(menu) = &self.pin_menu {
+            let name = menu.folder.file_name().map_or_else(|| menu.folder.to_string_lossy(), |n| n.to_string_lossy());
+            let regions = crate::ui::overlay::render_pin_menu(
+                &mut self.scene,
+                &mut self.text_renderer,
+                &name,
+                self.color_settings.pinned.get(&menu.folder).copied(),
+                menu.x,
+                menu.y,
+                (self.viewport_width, self.viewport_height),
+            );
+            self.sidebar_hit_regions.extend(regions);
+        }
+
+        if let Some(target) = &self.drop_target {
+            crate::ui::overlay::render_drop_target(
+                &mut self.scene,
+                &mut self.text_renderer,
+                target,
                 self.viewport_width,
                 self.viewport_height,
-                self.loading_started.map(|t| t.elapsed().as_secs_f32()).unwrap_or(0.0),
-                self.show_admin_slow_warning,
             );
+        } else if self.phase == AppPhase::Scanning {
+            let (paused, fraction) = (self.scan_paused(), self.scan_fraction());
+            let pause_button = crate::ui::overlay::render_loading_overlay(
+                &mut self.scene,
+                &mut self.text_renderer,
+                self.viewport_width,
+                self.viewport_height,
+                &crate::ui::overlay::ScanStatus {
+                    elapsed_seconds: self.loading_started.map(|t| t.elapsed().as_secs_f32()).unwrap_or(0.0),
+                    current_path: self.scan_current_path.as_deref(),
+                    progress: self.scan_progress.as_ref(),
+                    fraction,
+                    show_admin_warning: self.show_admin_slow_warning,
+                    paused,
+                },
+                self.size_units,
+            );
+            self.sidebar_hit_regions.push(pause_button);
+        }
+    }
+
+    /// Rough share of the running scan that is done: bytes found so far against what the
+    /// drive reports in use. None unless a single whole drive is being scanned.
+    fn scan_fraction(&self) -> Option<f32> {
+        let Some(ScanProgress::Progress { total_bytes, .. }) = &self.scan_progress else {
+            return None;
+        };
+        if !self.extra_scan_paths.is_empty() {
+            return None;
+        }
+        let used = crate::ui::drives::drive_for_root(&self.available_drives, &self.scan_path)?.used_bytes();
+        (used > 0).then(|| (*total_bytes as f64 / used as f64).min(0.99) as f32)
+    }
+
+    /// Path and totals of the current view for the status bar.
+    fn status_bar_text(&self) -> Option<String> {
+        let (tree, nav) = (self.tree.as_ref()?, self.navigation.as_ref()?);
+        let node = tree.get(nav.current_root);
+        let shown = self.layout_config.size_of(tree, nav.current_root);
+        let (files, dirs) = self.layout_config.counts_of(tree, nav.current_root);
+        let mut text = format!(
+            "{}  —  {}  ·  {} files  ·  {} folders",
+            crate::ui::tooltip::build_path(tree, nav.current_root),
+            crate::ui::tooltip::format_size(shown, self.size_units),
+            crate::ui::tooltip::format_count(files as u64),
+            crate::ui::tooltip::format_count(dirs as u64)
+        );
+        if shown < node.size {
+            text.push_str(&format!(
+                "  ·  {} hidden",
+                crate::ui::tooltip::format_size(node.size - shown, self.size_units)
+            ));
+        }
+        if self.analytics.empty_dirs > 0 {
+            text.push_str(&format!(
+                "  ·  {} empty folders",
+                crate::ui::tooltip::format_count(self.analytics.empty_dirs)
+            ));
+        }
+        if self.analytics.zero_byte_files > 0 {
+            text.push_str(&format!(
+                "  ·  {} zero-byte files",
+                crate::ui::tooltip::format_count(self.analytics.zero_byte_files)
+            ));
+        }
+        if self.layout.as_ref().is_some_and(|l| l.coarsening > 0) {
+            text.push_str("  ·  detail reduced to stay under the tile limit");
+        }
+        Some(text)
+    }
+
+    /// Scanned-vs-drive-used comparison, for whole-drive scans once the scan is done.
+    pub fn drive_reconciliation(&self) -> Option<String> {
+        let tree = self.tree.as_ref().filter(|_| self.phase == AppPhase::Ready)?;
+        if !self.extra_scan_paths.is_empty() {
+            return None;
         }
+        let drive = crate::ui::drives::drive_for_root(&self.available_drives, &self.scan_path)?;
+        crate::ui::drives::reconciliation_summary(
+            tree.get(tree.root).allocated,
+            drive,
+            self.include_system_metadata,
+            self.size_units,
+        )
+    }
+
+    /// du-style text listing of the current view, limited by the sidebar depth slider.
+    pub fn current_view_text(&self) -> Option<String> {
+        let (tree, nav) = (self.tree.as_ref()?, self.navigation.as_ref()?);
+        let depth = self
+            .depth_limit
+            .unwrap_or(crate::tree::export::DEFAULT_EXPORT_DEPTH);
+        Some(crate::tree::export::to_text(
+            tree,
+            nav.current_root,
+            Some(depth),
+            self.size_units,
+        ))
+    }
+
+    /// Size that node percentages are measured against, under `percent_basis`.
+    fn share_of(&self) -> (u64, PercentBasis) {
+        let base = match (&self.tree, &self.navigation, self.percent_basis) {
+            (Some(tree), _, PercentBasis::Scan) => self.layout_config.size_of(tree, tree.root),
+            (Some(tree), Some(nav), PercentBasis::View) => self.layout_config.size_of(tree, nav.current_root),
+            _ => 0,
+        };
+        (base, self.percent_basis)
+    }
+
+    /// Map a cursor position (physical window px, as winit reports it) into layout
+    /// coordinates, undoing the frozen-layout fit and the user's zoom/pan. Every treemap hit
+    /// test goes through here; the sidebar and inspector are drawn unzoomed in window px.
+    pub fn layout_point(&self, physical_x: f32, physical_y: f32) -> (f32, f32) {
+        self.screen_view().to_content(physical_x, physical_y)
+    }
+
+    /// Index of the hovered node's tile: the one under the cursor, since in the split view a
+    /// node shown in both halves has two.
+    fn hover_rect(&self) -> Option<usize> {
+        let (layout, node) = (self.layout.as_ref()?, self.hover_node?);
+        let (x, y) = self.layout_point(self.mouse.x, self.mouse.y);
+        layout
+            .rects
+            .iter()
+            .rposition(|r| r.node == node && x >= r.x && x < r.x + r.w && y >= r.y && y < r.y + r.h)
+            .or_else(|| layout.node_to_rect.get(&node).copied())
+    }
+
+    /// The deepest treemap tile under a cursor position, if any.
+    pub fn hit_test_treemap(&self, x: f32, y: f32) -> Option<NodeId> {
+        let (x, y) = self.layout_point(x, y);
+        input::hit_test(&self.layout.as_ref()?.rects, x, y)
     }
 
     /// Hit-test interactive folder labels (used for label-only drill-down).
     pub fn hit_test_label(&self, x: f32, y: f32) -> Option<NodeId> {
+        let (x, y) = self.layout_point(x, y);
         for region in self.label_hit_regions.iter().rev() {
             let [x1, y1, x2, y2] = region.bounds;
             if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
@@ -344,6 +1344,27 @@ impl App {
         None
     }
 
+    /// Move keyboard focus to the next (or previous) sidebar control, wrapping around.
+    pub fn cycle_sidebar_focus(&mut self, backwards: bool) {
+        let count = self.sidebar_hit_regions.len();
+        if count == 0 {
+            self.sidebar_focus = None;
+            return;
+        }
+        let current = self
+            .sidebar_focus
+            .as_ref()
+            .and_then(|focus| self.sidebar_hit_regions.iter().position(|r| &r.id == focus));
+        let next = match (current, backwards) {
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count,
+            (None, false) => 0,
+            (None, true) => count - 1,
+        };
+        self.sidebar_focus = Some(self.sidebar_hit_regions[next].id.clone());
+    }
+
+    /// Sidebar regions are recorded in window px, the same space as the cursor.
     pub fn hit_test_sidebar(&self, x: f32, y: f32) -> Option<SidebarHitId> {
         for region in self.sidebar_hit_regions.iter().rev() {
             let [x1, y1, x2, y2] = region.bounds;
@@ -354,53 +1375,872 @@ impl App {
         None
     }
 
+    /// Scroll the sidebar controls by `dy` px (positive moves them down, toward the top
+    /// of the list), within what the window height leaves hidden.
+    pub fn scroll_sidebar(&mut self, dy: f32) {
+        let max = crate::ui::overlay::sidebar_max_scroll(self.viewport_height, self.available_drives.len());
+        self.sidebar_scroll = (self.sidebar_scroll.min(max) - dy).clamp(0.0, max);
+    }
+
     pub fn sidebar_exclusion_rect(&self) -> [f32; 4] {
-        crate::ui::overlay::sidebar_panel_bounds(self.viewport_height, self.available_drives.len())
+        crate::ui::overlay::sidebar_panel_bounds(
+            self.viewport_height,
+            self.available_drives.len(),
+            self.sidebar_collapsed,
+        )
+    }
+
+    /// Screen bounds of the details inspector, if shown.
+    pub fn inspector_bounds(&self) -> Option<[f32; 4]> {
+        if !self.show_inspector {
+            return None;
+        }
+        let w = self.inspector_width.clamp(
+            crate::ui::overlay::INSPECTOR_MIN_W,
+            (self.viewport_width * 0.6).max(crate::ui::overlay::INSPECTOR_MIN_W),
+        );
+        let x1 = (self.viewport_width - w).max(0.0);
+        Some([x1, 0.0, self.viewport_width, self.viewport_height])
+    }
+
+    /// Whether a screen point is on the inspector's drag handle.
+    pub fn hit_test_inspector_grip(&self, x: f32, y: f32) -> bool {
+        self.inspector_bounds().is_some_and(|[x1, y1, _, y2]| {
+            (x - x1).abs() <= crate::ui::overlay::INSPECTOR_GRIP_W && y >= y1 && y <= y2
+        })
+    }
+
+    /// Whether a screen point is over the inspector panel.
+    pub fn over_inspector(&self, x: f32, y: f32) -> bool {
+        self.inspector_bounds()
+            .is_some_and(|[x1, y1, x2, y2]| x >= x1 - crate::ui::overlay::INSPECTOR_GRIP_W && x <= x2 && y >= y1 && y <= y2)
+    }
+
+    pub fn toggle_inspector(&mut self) {
+        self.show_inspector = !self.show_inspector;
+        self.needs_relayout = true;
+    }
+
+    /// Resize the inspector while dragging its edge; relayout waits like a window resize.
+    pub fn set_inspector_width(&mut self, width: f32) {
+        self.inspector_width = width;
+        self.last_resize = Some(Instant::now());
+        if !(self.freeze_layout && self.layout.is_some()) {
+            self.needs_relayout = true;
+        }
+    }
+
+    /// Switch between logical sizes and sizes on disk, re-measuring the tree in place.
+    pub fn toggle_size_basis(&mut self) {
+        self.size_basis = self.size_basis.toggled();
+        self.finish_layout_job();
+        if let Some(tree) = &mut self.tree {
+            crate::tree::set_size_basis(Arc::make_mut(tree), self.size_basis);
+            self.layout_config.recount_excluded(tree);
+            self.root_analytics = None;
+            self.needs_relayout = true;
+        }
+        tracing::info!("Size basis: {}", self.size_basis.label());
+    }
+
+    /// Switch between the folder hierarchy and a flat map of the biggest files in view.
+    pub fn toggle_flat_layout(&mut self) {
+        self.flat_layout = !self.flat_layout;
+        self.view = ViewTransform::default();
+        self.needs_relayout = true;
+        tracing::info!("Flat layout: {}", self.flat_layout);
+    }
+
+    /// Turn the per-folder rollup of small files by extension on or off.
+    pub fn toggle_file_grouping(&mut self) {
+        self.layout_config.group_small_files = !self.layout_config.group_small_files;
+        self.layout_config.ungrouped_dirs.clear();
+        self.needs_relayout = true;
+        tracing::info!("Group small files: {}", self.layout_config.group_small_files);
+    }
+
+    /// Hide the hovered subtree from the layout for this session, so everything else is
+    /// laid out to scale without it. The view's root can't be hidden.
+    pub fn exclude_hovered(&mut self) {
+        let (Some(tree), Some(nav), Some(node)) = (&self.tree, &self.navigation, self.hover_node) else {
+            return;
+        };
+        if node == nav.current_root || self.split_view.as_ref().is_some_and(|s| s.current_root == node) {
+            return;
+        }
+        tracing::info!("Hiding {} from the layout", crate::ui::tooltip::build_path(tree, node));
+        self.excluded_paths.push(crate::tree::node_path(tree, node, &self.scan_path));
+        self.layout_config.exclude(tree, node);
+        self.set_hover_node(None);
+        self.needs_relayout = true;
+    }
+
+    /// Bring back one hidden subtree, or all of them for `None`.
+    pub fn restore_excluded(&mut self, node: Option<NodeId>) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        match node {
+            Some(node) => {
+                let path = crate::tree::node_path(tree, node, &self.scan_path);
+                self.excluded_paths.retain(|p| *p != path);
+                self.layout_config.restore(tree, node);
+            }
+            None => {
+                self.excluded_paths.clear();
+                self.layout_config.clear_excluded();
+            }
+        }
+        self.needs_relayout = true;
+    }
+
+    /// Lay out the files of a group tile's folder individually. Returns false if `node`
+    /// isn't shown as a group.
+    pub fn open_file_group(&mut self, node: NodeId) -> bool {
+        let (Some(tree), Some(layout)) = (&self.tree, &self.layout) else {
+            return false;
+        };
+        if layout.rect_of(node).is_none_or(|rect| rect.grouped == 0) {
+            return false;
+        }
+        let Some(parent) = tree.get(node).parent else {
+            return false;
+        };
+        self.layout_config.ungrouped_dirs.insert(parent);
+        self.needs_relayout = true;
+        true
+    }
+
+    /// Select a clicked file tile so the inspector and outline follow it. A second click
+    /// on the same file within `DOUBLE_CLICK_INTERVAL` opens it with its default app.
+    pub fn click_file(&mut self, node: NodeId) {
+        let now = Instant::now();
+        let double = self
+            .last_file_click
+            .is_some_and(|(last, at)| last == node && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL);
+        self.selected_node = Some(node);
+        self.last_file_click = (!double).then_some((node, now));
+        if !double {
+            return;
+        }
+        if let Some(tree) = &self.tree {
+            let path = crate::tree::node_path(tree, node, &self.scan_path);
+            tracing::info!("Opening {}", path.display());
+            if let Err(e) = open_with_default_app(&path) {
+                tracing::warn!("Could not open {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Toggle keeping the layout fixed across resizes. Unfreezing relayouts if the
+    /// window size changed in the meantime.
+    pub fn toggle_freeze_layout(&mut self) {
+        self.freeze_layout = !self.freeze_layout;
+        if !self.freeze_layout && self.layout_viewport != (self.viewport_width, self.viewport_height) {
+            self.view = ViewTransform::default();
+            self.needs_relayout = true;
+        }
+        tracing::info!("Freeze layout: {}", self.freeze_layout);
+    }
+
+    /// Transform fitting the current layout into the viewport: identity unless the window
+    /// was resized while the layout is frozen, in which case it is scaled and letterboxed.
+    /// A fixed resolution is always fitted into the treemap area beside the sidebar.
+    pub fn fit_transform(&self) -> ViewTransform {
+        let (lw, lh) = self.layout_viewport;
+        if self.fixed_resolution.is_some() && lw > 0.0 && lh > 0.0 {
+            let [x, y, w, h] = self.treemap_layout_rect();
+            let scale = (w / lw).min(h / lh);
+            return ViewTransform {
+                scale,
+                tx: x + (w - lw * scale) * 0.5,
+                ty: y + (h - lh * scale) * 0.5,
+            };
+        }
+        if !self.freeze_layout || lw <= 0.0 || lh <= 0.0 || (lw, lh) == (self.viewport_width, self.viewport_height) {
+            return ViewTransform::default();
+        }
+        let scale = (self.viewport_width / lw).min(self.viewport_height / lh);
+        ViewTransform {
+            scale,
+            tx: (self.viewport_width - lw * scale) * 0.5,
+            ty: (self.viewport_height - lh * scale) * 0.5,
+        }
+    }
+
+    /// Screen transform of the treemap: the user's zoom/pan applied over the fit.
+    pub fn screen_view(&self) -> ViewTransform {
+        let fit = self.fit_transform();
+        ViewTransform {
+            scale: self.view.scale * fit.scale,
+            tx: self.view.tx * fit.scale + fit.tx,
+            ty: self.view.ty * fit.scale + fit.ty,
+        }
+    }
+
+    /// Whether the user has zoomed in past the fitted view.
+    pub fn is_zoomed(&self) -> bool {
+        self.view.scale > 1.0
+    }
+
+    /// Pan the zoomed view by a screen-space delta.
+    pub fn pan_view(&mut self, dx: f32, dy: f32) {
+        let scale = self.fit_transform().scale;
+        self.view.pan(dx / scale, dy / scale);
     }
 
     /// Compute the rectangle available for treemap layout after reserving sidebar space.
     pub fn treemap_layout_rect(&self) -> [f32; 4] {
-        let [_sx1, sy1, sx2, sy2] = self.sidebar_exclusion_rect();
-        let pad = 8.0;
-        let right_x = (sx2 + pad).min(self.viewport_width);
-        let right_w = (self.viewport_width - right_x).max(64.0);
-        let right_h = self.viewport_height.max(64.0);
-
-        let bottom_y = (sy2 + pad).min(self.viewport_height);
-        let bottom_w = self.viewport_width.max(64.0);
-        let bottom_h = (self.viewport_height - bottom_y).max(64.0);
-
-        // Prefer reclaiming space below the compact sidebar so we avoid a full-height dead strip.
-        let panel_h = (sy2 - sy1).max(0.0);
-        if panel_h <= self.viewport_height * 0.75 && bottom_h >= 120.0 {
-            [0.0, bottom_y, bottom_w, bottom_h]
-        } else if right_w * right_h >= bottom_w * bottom_h {
-            [right_x, 0.0, right_w, right_h]
+        let [x, y, w, h] = self.treemap_layout_rect_beside_sidebar();
+        match self.inspector_bounds() {
+            Some([ix1, ..]) => [x, y, (ix1 - 8.0 - x).max(64.0), h],
+            None => [x, y, w, h],
+        }
+    }
+
+    /// Size the layout is computed for: the fixed resolution if one is set, else the window.
+    fn layout_size(&self) -> (f32, f32) {
+        self.fixed_resolution
+            .map_or((self.viewport_width, self.viewport_height), |(w, h)| (w as f32, h as f32))
+    }
+
+    /// Rectangle the layout fills, in layout space. A fixed resolution fills its whole
+    /// canvas; the sidebar and status bar only come into play once it is fitted on screen.
+    fn layout_canvas_rect(&self) -> [f32; 4] {
+        match self.fixed_resolution {
+            Some((w, h)) => [0.0, 0.0, w as f32, h as f32],
+            None => self.treemap_layout_rect(),
+        }
+    }
+
+    /// The sidebar exclusion in layout space; nothing is excluded at a fixed resolution.
+    pub fn layout_exclusion_rect(&self) -> [f32; 4] {
+        if self.fixed_resolution.is_some() {
+            [0.0; 4]
         } else {
-            [0.0, bottom_y, bottom_w, bottom_h]
+            self.sidebar_exclusion_rect()
         }
     }
 
+    /// Everything right of the sidebar column, down to the status bar. It depends only on
+    /// the sidebar's width, so the treemap doesn't jump around as the window or the drive
+    /// list changes.
+    fn treemap_layout_rect_beside_sidebar(&self) -> [f32; 4] {
+        let [_, _, sx2, _] = self.sidebar_exclusion_rect();
+        let x = (sx2 + 8.0).min(self.viewport_width);
+        let h = self.viewport_height - crate::ui::overlay::STATUS_BAR_H;
+        [x, 0.0, (self.viewport_width - x).max(64.0), h.max(64.0)]
+    }
+
     /// Handle viewport resize.
     pub fn resize(&mut self, width: u32, height: u32) {
         self.viewport_width = width as f32;
         self.viewport_height = height as f32;
+        self.last_resize = Some(Instant::now());
+        // A frozen layout keeps its size and is letterboxed into the new window.
+        if !(self.freeze_layout && self.layout.is_some()) {
+            self.needs_relayout = true;
+        }
+    }
+
+    /// Update the hovered node after the cursor moved. Hover info waits until the cursor
+    /// has rested for `hover_delay`; once shown it stays until the hovered node changes.
+    /// Returns true if the scene needs a redraw.
+    pub fn set_hover_node(&mut self, node: Option<NodeId>) -> bool {
+        if node != self.hover_node {
+            self.hover_node = node;
+            self.hover_info_shown = false;
+            self.hover_since = node.map(|_| Instant::now());
+            return true;
+        }
+        if !self.hover_info_shown && node.is_some() {
+            self.hover_since = Some(Instant::now());
+        }
+        false
+    }
+
+    /// When the pending hover info is due, if there is one.
+    pub fn hover_info_deadline(&self) -> Option<Instant> {
+        if !self.show_hover_info || self.hover_info_shown || self.hover_node.is_none() {
+            return None;
+        }
+        self.hover_since.map(|since| since + self.hover_delay)
+    }
+
+    /// The single place timed redraws are scheduled from: the earliest of the next
+    /// animation frame while scanning or searching for duplicates, the end of the resize debounce, and pending hover
+    /// info. None when nothing is waiting on the clock. Scans that wake the loop through
+    /// `scan_waker` only schedule a frame once they have reported something.
+    pub fn next_redraw_deadline(&self) -> Option<Instant> {
+        let woken = self.scan_waker.is_some() && !self.poll_scan_progress;
+        // A paused scan has nothing to report and its spinner stands still.
+        let scanning = self.phase == AppPhase::Scanning && !self.scan_paused();
+        let polling_scan = scanning && !woken;
+        // A background layout without a waker is polled like a scan.
+        let polling_layout = self.layout_job.is_some() && self.layout_waker.is_none();
+        let animating = polling_scan
+            || polling_layout
+            || self.scan_update_pending
+            || self.duplicates_rx.is_some()
+            || self.path_check.is_some()
+            || self.drop_check.is_some()
+            || self.expansion.is_some();
+        let animation = animating.then(|| {
+            let interval = Duration::from_secs(1) / self.max_animation_fps.max(1);
+            self.last_frame.map_or_else(Instant::now, |at| at + interval)
+        });
+        // Woken scans only need frames in between for the spinner.
+        let spinner = (scanning && woken)
+            .then(|| self.last_frame.map_or_else(Instant::now, |at| at + SPINNER_INTERVAL));
+        let resize = self
+            .last_resize
+            .filter(|_| self.needs_relayout && self.tree.is_some() && !self.resize_settled())
+            .map(|at| at + RESIZE_DEBOUNCE);
+        // Refreshes are polled slowly: the old tree stays up, so there's nothing to animate.
+        let refresh = (self.is_refreshing() && !woken)
+            .then(|| self.last_frame.map_or_else(Instant::now, |at| at + REFRESH_POLL_INTERVAL));
+        [animation, spinner, resize, refresh, self.hover_info_deadline(), self.refresh_deadline()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Whether enough time has passed since the last resize to relayout.
+    pub fn resize_settled(&self) -> bool {
+        self.last_resize.is_none_or(|at| at.elapsed() >= RESIZE_DEBOUNCE)
+    }
+
+    /// Tree size, layout and GPU counts for the stats overlay, one reading per line.
+    pub fn stats_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(tree) = &self.tree {
+            lines.push(format!("Nodes: {}", tree.len()));
+            lines.push(format!("Extensions: {}", tree.extensions.len()));
+            lines.push(format!(
+                "Tree memory: ~{}",
+                crate::ui::tooltip::format_size(tree.memory_estimate() as u64, self.size_units)
+            ));
+        }
+        if let Some(layout) = &self.layout {
+            lines.push(format!("Layout rects: {} (coarsened {}x)", layout.rects.len(), layout.coarsening));
+            lines.push(format!("Layout time: {:.1} ms", self.layout_time.as_secs_f64() * 1000.0));
+        }
+        lines.push(format!("Cushion instances: {}", self.cushion_instances));
+        lines
+    }
+
+    /// Where the split view divides the treemap, when it is on.
+    fn split_x(&self) -> Option<f32> {
+        let [x, _, w, _] = self.layout_canvas_rect();
+        self.split_view.as_ref().map(|_| (x + w / 2.0).round())
+    }
+
+    /// Whether the pointer is over the right half of the split view.
+    fn pointer_in_split(&self) -> bool {
+        let (x, _) = self.layout_point(self.mouse.x, self.mouse.y);
+        self.split_x().is_some_and(|split_x| x >= split_x)
+    }
+
+    /// Turn the side-by-side view on, comparing the hovered (else selected, else current)
+    /// folder against the main view, or turn it off.
+    pub fn toggle_split_view(&mut self) {
+        if self.split_view.take().is_none() {
+            let (Some(tree), Some(nav)) = (&self.tree, &self.navigation) else {
+                return;
+            };
+            let folder = self
+                .hover_node
+                .or(self.selected_node)
+                .map(|id| if tree.get(id).is_dir { id } else { tree.get(id).parent.unwrap_or(tree.root) })
+                .unwrap_or(nav.current_root);
+            self.split_view = Some(NavigationState::at(folder, tree));
+        }
+        self.set_hover_node(None);
+        self.view = ViewTransform::default();
         self.needs_relayout = true;
     }
 
-    /// Handle drill-down navigation.
+    /// Handle drill-down navigation, in whichever half of the split view the pointer is over.
     pub fn drill_down(&mut self, node: NodeId) {
-        if let (Some(tree), Some(nav)) = (&self.tree, &mut self.navigation) {
+        let nav = if self.pointer_in_split() { &mut self.split_view } else { &mut self.navigation };
+        if let (Some(tree), Some(nav)) = (&self.tree, nav) {
             if nav.drill_down(node, tree) {
+                self.view = ViewTransform::default();
                 self.needs_relayout = true;
+                let root = nav.current_root;
+                self.expand_subtree(root);
             }
         }
     }
 
-    /// Handle navigate-up.
-    pub fn navigate_up(&mut self) {
+    /// After a depth-limited scan, list `node`'s folder another `scan_depth_limit` levels
+    /// deep in the background if anything below it was cut off. One folder at a time.
+    fn expand_subtree(&mut self, node: NodeId) {
+        let (Some(tree), Some(depth)) = (&self.tree, self.scan_depth_limit) else {
+            return;
+        };
+        if self.expansion.is_some() || self.phase != AppPhase::Ready || !tree.needs_expansion(node) {
+            return;
+        }
+        let path = crate::tree::node_path(tree, node, &self.scan_path);
+        tracing::info!("Scanning {} {} more levels deep", path.display(), depth);
+
+        let (tx, rx) = mpsc::channel();
+        self.expansion = Some((node, rx));
+        std::thread::spawn(move || {
+            let (progress_tx, _) = mpsc::channel();
+            let options = ScanOptions {
+                max_depth: Some(depth),
+                ..Default::default()
+            };
+            let result = scanner::scan(&path, scanner::ScanMethod::WalkDir, options, progress_tx)
+                .map(|mut entries| {
+                    // A parentless entry roots the tree at the folder rather than its drive.
+                    if let Some(root) = entries.iter_mut().find(|e| e.path == path) {
+                        root.parent = None;
+                    }
+                    crate::tree::build_tree(&entries)
+                })
+                .map_err(|e| e.user_message());
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Graft a finished folder expansion into the tree. Returns true when one arrived.
+    pub fn poll_expansion(&mut self) -> bool {
+        let Some(result) = self.expansion.as_ref().and_then(|(_, rx)| rx.try_recv().ok()) else {
+            return false;
+        };
+        let Some((node, _)) = self.expansion.take() else {
+            return false;
+        };
+        if result.is_ok() {
+            self.finish_layout_job();
+        }
+        let (Some(tree), Some(nav)) = (&mut self.tree, &mut self.navigation) else {
+            return false;
+        };
+        match result {
+            Ok(subtree) => {
+                let tree = Arc::make_mut(tree);
+                // Ids below `node` are about to go stale.
+                nav.forget_inside(node, tree);
+                if let Some(split) = &mut self.split_view {
+                    split.forget_inside(node, tree);
+                }
+                crate::tree::replace_subtree(tree, node, &subtree);
+                // Hidden subtrees below `node` went stale with it; sizes above it changed.
+                self.layout_config.excluded = excluded_nodes(tree, &self.excluded_paths, &self.scan_path);
+                self.layout_config.recount_excluded(tree);
+                self.set_hover_node(None);
+                self.selected_node = None;
+                self.clear_duplicates();
+                self.forget_search_results();
+                self.root_analytics = None;
+                self.needs_relayout = true;
+            }
+            Err(message) => {
+                tracing::warn!("{}", message);
+                self.scan_error = Some(message);
+            }
+        }
+        true
+    }
+
+    /// Pinned colors resolved to the nodes of the current tree.
+    pub fn pinned_colors(&self) -> HashMap<NodeId, AppColor> {
+        match &self.tree {
+            Some(tree) => self.color_settings.pinned_nodes(tree, &self.scan_path),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Path of the folder a pin applies to: the hovered (else selected) one; a file pins its
+    /// folder.
+    fn pin_target(&self) -> Option<PathBuf> {
+        let tree = self.tree.as_ref()?;
+        let node = self.hover_node.or(self.selected_node)?;
+        let folder = match tree.get(node).is_dir {
+            true => node,
+            false => tree.get(node).parent?,
+        };
+        Some(crate::tree::node_path(tree, folder, &self.scan_path))
+    }
+
+    /// Pin the next of `PIN_COLORS` to the hovered (else selected) folder. Past the last
+    /// color the pin is removed.
+    pub fn cycle_pinned_color(&mut self) {
+        let Some(path) = self.pin_target() else {
+            return;
+        };
+        let next = self.color_settings.next_pin(&path);
+        self.set_pinned_color(path, next);
+    }
+
+    /// Open the pin-color menu at window px (`x`, `y`) for the folder under the cursor.
+    pub fn open_pin_menu(&mut self, x: f32, y: f32) {
+        self.pin_menu = self.pin_target().map(|folder| PinMenu { folder, x, y });
+    }
+
+    /// Close the pin-color menu; false if it wasn't open.
+    pub fn close_pin_menu(&mut self) -> bool {
+        self.pin_menu.take().is_some()
+    }
+
+    /// Apply a choice from the pin-color menu: a slot of `PIN_COLORS`, or `None` to unpin.
+    pub fn choose_pinned_color(&mut self, slot: Option<usize>) {
+        if let Some(menu) = self.pin_menu.take() {
+            self.set_pinned_color(menu.folder, slot.and_then(|i| PIN_COLORS.get(i).copied()));
+        }
+    }
+
+    fn set_pinned_color(&mut self, path: PathBuf, color: Option<AppColor>) {
+        match color {
+            Some(_) => tracing::info!("Pinned a color to {}", path.display()),
+            None => tracing::info!("Unpinned {}", path.display()),
+        }
+        self.color_settings.pin(path, color);
+        self.recolor_treemap();
+    }
+
+    /// Status banner text while a folder expansion runs.
+    fn expansion_status(&self) -> Option<String> {
+        let (node, _) = self.expansion.as_ref()?;
+        let tree = self.tree.as_ref()?;
+        Some(format!("Scanning {} deeper…", tree.get(*node).name))
+    }
+
+    /// Zoom the treemap view around a screen point (positive `delta` zooms in).
+    pub fn zoom_view(&mut self, delta: f32, x: f32, y: f32) {
+        let (x, y) = self.fit_transform().to_content(x, y);
+        self.view.zoom_at(1.15_f32.powf(delta), x, y);
+    }
+
+    /// Navigate to the parent of the largest file under the current view and select it.
+    pub fn reveal_largest(&mut self) {
+        if let (Some(tree), Some(nav)) = (&self.tree, &mut self.navigation) {
+            let Some(&largest) = tree.largest_files(nav.current_root, 1).first() else {
+                return;
+            };
+            tracing::info!(
+                "Revealing largest file: {} ({} bytes)",
+                crate::ui::tooltip::build_path(tree, largest),
+                tree.get(largest).size
+            );
+            nav.drill_down(largest, tree);
+            self.view = ViewTransform::default();
+            self.selected_node = Some(largest);
+            self.needs_relayout = true;
+        }
+    }
+
+    /// Open the search prompt with an empty query.
+    pub fn open_search(&mut self) {
+        self.search_query = Some(String::new());
+        self.forget_search_results();
+    }
+
+    pub fn close_search(&mut self) {
+        self.search_query = None;
+        self.forget_search_results();
+    }
+
+    /// Drop matches that no longer apply (query edited, tree replaced); the prompt stays.
+    fn forget_search_results(&mut self) {
+        self.search_results.clear();
+        self.search_index = None;
+    }
+
+    /// Edit the search prompt: append `text`, or drop the last character when `None`.
+    /// Editing discards the previous matches.
+    pub fn edit_search(&mut self, text: Option<&str>) {
+        let Some(query) = &mut self.search_query else {
+            return;
+        };
+        match text {
+            Some(text) => query.push_str(text),
+            None => {
+                query.pop();
+            }
+        }
+        self.forget_search_results();
+    }
+
+    /// Run the query under the current view and reveal the best match; once it has run,
+    /// step to the next match instead, wrapping around.
+    pub fn search_next(&mut self) {
+        let (Some(tree), Some(nav), Some(query)) = (&self.tree, &self.navigation, &self.search_query) else {
+            return;
+        };
+        let next = match self.search_index {
+            Some(i) if !self.search_results.is_empty() => (i + 1) % self.search_results.len(),
+            _ => {
+                self.search_results = search::search(tree, nav.current_root, query, search::DEFAULT_SEARCH_LIMIT)
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .collect();
+                tracing::info!("Search {:?}: {} matches", query, self.search_results.len());
+                0
+            }
+        };
+        self.search_index = Some(next);
+        if let Some(&node) = self.search_results.get(next) {
+            self.reveal(node);
+        }
+    }
+
+    /// Open the folder holding `node` and select it.
+    fn reveal(&mut self, node: NodeId) {
+        if let (Some(tree), Some(nav)) = (&self.tree, &mut self.navigation) {
+            nav.drill_down(node, tree);
+            self.view = ViewTransform::default();
+            self.selected_node = Some(node);
+            self.needs_relayout = true;
+        }
+    }
+
+    /// One-line search prompt for the status banner.
+    fn search_status(&self) -> Option<String> {
+        let query = self.search_query.as_ref()?;
+        let matches = match self.search_index {
+            None => "Enter to search".to_string(),
+            Some(_) if self.search_results.is_empty() => "no matches".to_string(),
+            Some(i) => format!("{}/{}  ·  Enter for next", i + 1, self.search_results.len()),
+        };
+        Some(format!("Search: {}_  ·  {}  ·  Esc to close", query, matches))
+    }
+
+    /// Open the "go to path" prompt with an empty path.
+    pub fn open_path_prompt(&mut self) {
+        self.path_query = Some(String::new());
+        self.path_on_disk = None;
+        self.path_check = None;
+    }
+
+    pub fn close_path_prompt(&mut self) {
+        self.path_query = None;
+        self.path_on_disk = None;
+        self.path_check = None;
+    }
+
+    /// Edit the path prompt: append `text`, or drop the last character when `None`.
+    pub fn edit_path_prompt(&mut self, text: Option<&str>) {
+        let Some(query) = &mut self.path_query else {
+            return;
+        };
+        match text {
+            Some(text) => query.push_str(text),
+            None => {
+                query.pop();
+            }
+        }
+        self.path_on_disk = None;
+        self.path_check = None;
+    }
+
+    /// Go to the typed path: open and select it when the tree has it, otherwise check the
+    /// disk and, if it is there, scan it (a file's folder, for a file) on the next Enter.
+    pub fn submit_path_prompt(&mut self) {
+        let Some(query) = &self.path_query else {
+            return;
+        };
+        let path = PathBuf::from(query.trim().trim_matches('"'));
+        match &self.path_on_disk {
+            Some(PathOnDisk::Scannable(folder)) => {
+                let folder = folder.clone();
+                self.close_path_prompt();
+                self.start_scan_path(folder);
+                return;
+            }
+            Some(PathOnDisk::Checking) => return,
+            _ => {}
+        }
+        let found = self
+            .tree
+            .as_ref()
+            .and_then(|tree| crate::tree::find_by_path(tree, &path, &self.scan_path));
+        if let Some(node) = found {
+            self.close_path_prompt();
+            self.reveal(node);
+            return;
+        }
+        // Looking a path up can stall on a sleeping disk or a network share.
+        let (tx, rx) = mpsc::channel();
+        self.path_check = Some(rx);
+        self.path_on_disk = Some(PathOnDisk::Checking);
+        std::thread::spawn(move || {
+            let _ = tx.send(PathOnDisk::of(path));
+        });
+    }
+
+    /// Pick up the disk lookup of the typed path. Returns true when it arrived.
+    pub fn poll_path_check(&mut self) -> bool {
+        let Some(found) = self.path_check.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return false;
+        };
+        self.path_check = None;
+        self.path_on_disk = Some(found);
+        true
+    }
+
+    /// One-line path prompt for the status banner.
+    fn path_status(&self) -> Option<String> {
+        let query = self.path_query.as_ref()?;
+        let typed = Path::new(query.trim().trim_matches('"'));
+        let hint = match &self.path_on_disk {
+            None => "Enter to go there",
+            Some(PathOnDisk::Checking) => "looking on disk…",
+            Some(PathOnDisk::Scannable(folder)) if folder != typed => "a file not in this scan  ·  Enter to scan its folder",
+            Some(PathOnDisk::Scannable(_)) => "not in this scan  ·  Enter to scan it",
+            Some(PathOnDisk::Missing) => "not found",
+        };
+        Some(format!("Go to: {}_  ·  {}  ·  Esc to close", query, hint))
+    }
+
+    /// Start a duplicate-file search under the current view. Sizes are grouped here; the
+    /// content sampling runs on a worker thread since it reads every candidate file.
+    pub fn find_duplicates(&mut self) {
+        if self.duplicates_rx.is_some() {
+            return;
+        }
+        let (Some(tree), Some(nav)) = (&self.tree, &self.navigation) else {
+            return;
+        };
+        let candidates = dedupe::size_candidates(
+            tree,
+            nav.current_root,
+            &self.scan_path,
+            dedupe::DEFAULT_MIN_DUPLICATE_SIZE,
+        );
+        tracing::info!("Duplicate search: hashing {} same-size candidates", candidates.len());
+
+        let (tx, rx) = mpsc::channel();
+        self.duplicates = None;
+        self.duplicates_rx = Some(rx);
+        std::thread::spawn(move || {
+            let _ = tx.send(dedupe::confirm_duplicates(candidates));
+        });
+    }
+
+    /// Pick up a finished duplicate search. Returns true when results arrived.
+    pub fn poll_duplicates(&mut self) -> bool {
+        let Some(report) = self.duplicates_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return false;
+        };
+        self.duplicates_rx = None;
+        tracing::info!(
+            "Duplicate search: {} groups, {} reclaimable",
+            report.groups.len(),
+            crate::ui::tooltip::format_size(report.reclaimable, self.size_units)
+        );
+        self.duplicates = Some(report);
+        self.duplicate_group = 0;
+        self.show_duplicate_group();
+        true
+    }
+
+    /// Step to the next (`delta` > 0) or previous duplicate group, wrapping around.
+    pub fn cycle_duplicate_group(&mut self, delta: isize) {
+        let Some(count) = self.duplicates.as_ref().map(|d| d.groups.len()).filter(|&n| n > 0) else {
+            return;
+        };
+        self.duplicate_group = (self.duplicate_group as isize + delta).rem_euclid(count as isize) as usize;
+        self.show_duplicate_group();
+    }
+
+    pub fn current_duplicate_group(&self) -> Option<&dedupe::DuplicateGroup> {
+        self.duplicates.as_ref()?.groups.get(self.duplicate_group)
+    }
+
+    /// Open the folder of the current group's first file and select it.
+    fn show_duplicate_group(&mut self) {
+        let Some(&first) = self.current_duplicate_group().and_then(|g| g.files.first()) else {
+            return;
+        };
+        self.reveal(first);
+    }
+
+    /// Find the hidden folders in `tree`, e.g. after a rescan, and take their bytes off
+    /// the folders above them.
+    fn resolve_excluded(&mut self, tree: &FileTree) {
+        self.layout_config.excluded = excluded_nodes(tree, &self.excluded_paths, &self.scan_path);
+        self.layout_config.recount_excluded(tree);
+    }
+
+    /// Move view state that names nodes of `old` (ungrouped folders, the
+    /// selection, the split view, search matches and duplicate groups) to the same paths
+    /// in its rebuild `new`. Whatever no longer exists is dropped.
+    fn carry_view_state(&mut self, old: &FileTree, new: &FileTree) {
+        let scan_path = self.scan_path.clone();
+        let carry = |id: NodeId| carry_node(old, new, id, &scan_path);
+        let config = &mut self.layout_config;
+        config.ungrouped_dirs = config.ungrouped_dirs.iter().filter_map(|&id| carry(id)).collect();
+        self.selected_node = self.selected_node.and_then(carry);
+        self.split_view = self
+            .split_view
+            .take()
+            .and_then(|split| carry(split.current_root))
+            .map(|id| NavigationState::at(id, new));
+
+        self.search_results = self.search_results.iter().filter_map(|&id| carry(id)).collect();
+        self.search_index = self
+            .search_index
+            .filter(|_| !self.search_results.is_empty())
+            .map(|i| i.min(self.search_results.len() - 1));
+
+        // A search still running would report ids of the old tree.
+        self.duplicates_rx = None;
+        if let Some(report) = &mut self.duplicates {
+            for group in &mut report.groups {
+                group.files = group.files.iter().filter_map(|&id| carry(id)).collect();
+            }
+            report.groups.retain(|group| group.files.len() > 1);
+            report.reclaimable = report.groups.iter().map(dedupe::DuplicateGroup::wasted).sum();
+            self.duplicate_group = self.duplicate_group.min(report.groups.len().saturating_sub(1));
+        }
+    }
+
+    fn clear_duplicates(&mut self) {
+        self.duplicates = None;
+        self.duplicates_rx = None;
+        self.duplicate_group = 0;
+    }
+
+    /// One-line summary of the duplicate search for the status banner.
+    fn duplicate_status(&self) -> Option<String> {
+        if self.duplicates_rx.is_some() {
+            return Some("Searching for duplicate files…".to_string());
+        }
+        let report = self.duplicates.as_ref()?;
+        let Some(group) = self.current_duplicate_group() else {
+            return Some("No duplicate files found".to_string());
+        };
+        Some(format!(
+            "Duplicates {}/{}: {} copies of {}  ·  {} reclaimable in total  ·  N / P to cycle",
+            self.duplicate_group + 1,
+            report.groups.len(),
+            group.files.len(),
+            crate::ui::tooltip::format_size(group.size, self.size_units),
+            crate::ui::tooltip::format_size(report.reclaimable, self.size_units)
+        ))
+    }
+
+    /// Handle navigate-forward (undo the last navigate-up).
+    pub fn navigate_forward(&mut self) {
         if let Some(nav) = &mut self.navigation {
+            if nav.navigate_forward() {
+                self.view = ViewTransform::default();
+                self.needs_relayout = true;
+            }
+        }
+    }
+
+    /// Handle navigate-up, in whichever half of the split view the pointer is over.
+    pub fn navigate_up(&mut self) {
+        let nav = if self.pointer_in_split() { &mut self.split_view } else { &mut self.navigation };
+        if let Some(nav) = nav {
             if nav.navigate_up() {
+                self.view = ViewTransform::default();
                 self.needs_relayout = true;
             }
         }
@@ -412,3 +2252,390 @@ impl App {
 use std::sync::Mutex;
 static SCAN_RESULT: std::sync::LazyLock<Mutex<Option<FileTree>>> =
     std::sync::LazyLock::new(|| Mutex::new(None));
+/// Latest partial tree built during a scan, tagged with its scan generation.
+static SCAN_PREVIEW: std::sync::LazyLock<Mutex<Option<(u64, FileTree)>>> =
+    std::sync::LazyLock::new(|| Mutex::new(None));
+
+/// What the "go to path" prompt found on disk for a path the tree doesn't have.
+#[derive(Debug, Clone, PartialEq)]
+enum PathOnDisk {
+    /// Still being looked up on a worker thread.
+    Checking,
+    Missing,
+    /// The folder to scan: the path itself, or the folder of a file.
+    Scannable(PathBuf),
+}
+
+impl PathOnDisk {
+    /// Look `path` up on disk. Blocks, so it belongs off the UI thread.
+    fn of(path: PathBuf) -> Self {
+        if !path.exists() {
+            return Self::Missing;
+        }
+        // A file without a parent folder resolves to itself, which isn't scannable.
+        let folder = drop_scan_target(&path);
+        if folder.is_dir() {
+            Self::Scannable(folder)
+        } else {
+            Self::Missing
+        }
+    }
+}
+
+/// Folder a dropped path resolves to: itself for a directory, otherwise its parent.
+fn drop_scan_target(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        return path.to_path_buf();
+    }
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Wakes the event loop from another thread.
+pub type Waker = Arc<dyn Fn() + Send + Sync>;
+
+/// The nodes of `tree` at `paths`, skipping those it doesn't have.
+fn excluded_nodes(tree: &FileTree, paths: &[PathBuf], scan_path: &Path) -> std::collections::HashSet<NodeId> {
+    paths.iter().filter_map(|path| crate::tree::find_by_path(tree, path, scan_path)).collect()
+}
+
+/// The node of `new` at the path `id` has in `old`, e.g. across a rescan.
+fn carry_node(old: &FileTree, new: &FileTree, id: NodeId, scan_path: &Path) -> Option<NodeId> {
+    crate::tree::find_by_path(new, &crate::tree::node_path(old, id, scan_path), scan_path)
+}
+
+/// The `Completed` message for a finished scan, with totals counted from its tree.
+fn completed_scan(tree: &FileTree, started: Instant) -> ScanProgress {
+    let root = tree.get(tree.root);
+    ScanProgress::Completed {
+        total_files: u64::from(root.file_count),
+        total_dirs: u64::from(root.dir_count),
+        total_bytes: root.size,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// A sender that forwards to `tx` and calls `wake` after each message, so the event loop
+/// picks scan progress up as it arrives instead of polling for it.
+fn relay_waking(tx: mpsc::Sender<ScanProgress>, wake: Waker) -> mpsc::Sender<ScanProgress> {
+    let (relay_tx, relay_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for progress in relay_rx {
+            if tx.send(progress).is_err() {
+                break;
+            }
+            wake();
+        }
+    });
+    relay_tx
+}
+
+/// Accumulate streamed scan entries and periodically publish a partial tree.
+/// Runs until the scanner drops its sender.
+fn stream_previews(rx: mpsc::Receiver<Vec<RawFileEntry>>, generation: u64, root: &Path, wake: Option<Waker>) {
+    let mut builder = crate::tree::TreeBuilder::new(root);
+    let mut interval = PREVIEW_MIN_INTERVAL;
+    let mut last_build = Instant::now();
+    let mut dirty = false;
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(batch) => {
+                builder.add(&batch);
+                dirty = true;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if dirty && last_build.elapsed() >= interval {
+            let started = Instant::now();
+            let tree = builder.snapshot();
+            // Snapshots get slower as the entry set grows; keep them a small share of scan time.
+            interval = PREVIEW_MIN_INTERVAL.max(started.elapsed() * 4);
+            SCAN_PREVIEW.lock().unwrap().replace((generation, tree));
+            if let Some(wake) = &wake {
+                wake();
+            }
+            last_build = Instant::now();
+            dirty = false;
+        }
+    }
+}
+
+/// Hand `path` to the system's default application for its type, without waiting.
+fn open_with_default_app(path: &Path) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let mut command = std::process::Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(path).spawn().map(drop)
+}
+
+/// Everything one relayout needs, captured from the app so it can run on a worker.
+struct LayoutRequest {
+    tree: Arc<FileTree>,
+    root: NodeId,
+    /// Root of the split view's right half, when it is on.
+    split_root: Option<NodeId>,
+    /// Rect the layout fills, in layout space.
+    canvas: [f32; 4],
+    split_x: Option<f32>,
+    exclusion: [f32; 4],
+    config: LayoutConfig,
+    flat: bool,
+    lshape: bool,
+    viewport: (f32, f32),
+    /// Also recompute the whole-tree analytics.
+    with_root_analytics: bool,
+}
+
+/// A computed layout and the analytics that go with it.
+struct LayoutOutcome {
+    /// The tree it was computed for, to spot results that went stale.
+    tree: Arc<FileTree>,
+    layout: Layout,
+    viewport: (f32, f32),
+    analytics: Analytics,
+    root_analytics: Option<Analytics>,
+    elapsed: Duration,
+}
+
+impl LayoutRequest {
+    fn run(self) -> LayoutOutcome {
+        let started = Instant::now();
+        let (tree, config) = (&*self.tree, &self.config);
+        let [tx, ty, tw, th] = self.canvas;
+        tracing::info!(
+            "Computing layout for tree with {} nodes, root={:?}, viewport={}x{}, treemap={}x{}@{},{} exclusion={:?}",
+            tree.len(),
+            self.root,
+            self.viewport.0,
+            self.viewport.1,
+            tw,
+            th,
+            tx,
+            ty,
+            self.exclusion
+        );
+
+        // Split view: the main root fills the left half and the compared folder the
+        // right half. Rects stay in screen space, so one layout serves rendering and
+        // hit testing for both.
+        let left_end = self.split_x.map_or(tx + tw, |x| x - SPLIT_GAP / 2.0);
+        let pane = |root, x: f32, w: f32| {
+            if self.flat {
+                layout::compute_flat_layout(tree, root, x, ty, w, th, config)
+            } else {
+                layout::compute_layout_in_rect(tree, root, x, ty, w, th, config)
+            }
+        };
+        let mut computed_layout = if self.lshape {
+            layout::compute_layout_lshape(tree, self.root, left_end, th, self.exclusion, config)
+        } else {
+            pane(self.root, tx, (left_end - tx).max(0.0))
+        };
+        if let (Some(split_root), Some(x)) = (self.split_root, self.split_x) {
+            let right_x = x + SPLIT_GAP / 2.0;
+            computed_layout.append(pane(split_root, right_x, (tx + tw - right_x).max(0.0)));
+        }
+
+        tracing::info!(
+            "Layout computed: {} rectangles generated (coarsened {} time(s))",
+            computed_layout.rects.len(),
+            computed_layout.coarsening
+        );
+
+        // Analytics for the current view, and the whole tree when asked. A called-off
+        // layout is dropped anyway, so it skips them.
+        if config.is_cancelled() {
+            return LayoutOutcome {
+                layout: computed_layout,
+                viewport: self.viewport,
+                analytics: Analytics::default(),
+                root_analytics: None,
+                elapsed: started.elapsed(),
+                tree: self.tree,
+            };
+        }
+        let analytics = crate::tree::analytics::compute_analytics(tree, self.root, config.show_hidden, &config.excluded);
+        let root_analytics = self
+            .with_root_analytics
+            .then(|| crate::tree::analytics::compute_analytics(tree, tree.root, config.show_hidden, &config.excluded));
+        LayoutOutcome {
+            layout: computed_layout,
+            viewport: self.viewport,
+            analytics,
+            root_analytics,
+            elapsed: started.elapsed(),
+            tree: self.tree,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with_tree() -> App {
+        let mut app = App::new(PathBuf::from("root"));
+        let tree = FileTree::new("root");
+        app.navigation = Some(NavigationState::new(tree.root));
+        app.tree = Some(Arc::new(tree));
+        app.resize(800, 600);
+        app
+    }
+
+    #[test]
+    fn superseded_background_layouts_are_dropped() {
+        let mut app = app_with_tree();
+        app.start_relayout();
+        assert!(app.layout_job.is_some() && !app.needs_relayout);
+
+        // Navigating while it runs supersedes it: the job is called off and its result dropped.
+        app.needs_relayout = true;
+        app.start_relayout();
+        while app.layout_job.is_some() {
+            assert!(!app.poll_layout());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(app.layout.is_none() && app.needs_relayout);
+
+        app.start_relayout();
+        while !app.poll_layout() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(app.layout.is_some() && app.layout_job.is_none());
+    }
+
+    #[test]
+    fn rebuilds_keep_view_state_by_path() {
+        let entry = |path: &str| crate::scanner::types::RawFileEntry {
+            path: PathBuf::from(path),
+            size: 10,
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir: false,
+            parent: None,
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        };
+        let old = crate::tree::build_tree(&[entry("/data/a/x.bin"), entry("/data/b/y.bin")]);
+        // The rescan finds the folders in another order, and `a` is gone.
+        let new = crate::tree::build_tree(&[entry("/data/c/z.bin"), entry("/data/b/y.bin")]);
+        let scan_path = Path::new("/data");
+        let find = |tree: &FileTree, path: &str| crate::tree::find_by_path(tree, Path::new(path), scan_path).unwrap();
+
+        let mut app = App::new(scan_path.to_path_buf());
+        app.navigation = Some(NavigationState::new(old.root));
+        app.tree = Some(Arc::new(old.clone()));
+        for folder in ["/data/a", "/data/b"] {
+            app.set_hover_node(Some(find(&old, folder)));
+            app.exclude_hovered();
+        }
+        app.selected_node = Some(find(&old, "/data/b/y.bin"));
+        app.search_results = vec![find(&old, "/data/a/x.bin"), find(&old, "/data/b/y.bin")];
+        app.search_index = Some(1);
+        app.carry_view_state(&old, &new);
+        app.resolve_excluded(&new);
+
+        let b = find(&new, "/data/b");
+        assert_eq!(app.layout_config.excluded, std::collections::HashSet::from([b]));
+        assert_eq!(app.layout_config.size_of(&new, new.root), 10);
+        assert_eq!(app.selected_node, Some(find(&new, "/data/b/y.bin")));
+        assert_eq!(app.search_results, vec![find(&new, "/data/b/y.bin")]);
+        assert_eq!(app.search_index, Some(0));
+    }
+
+    #[test]
+    fn typed_paths_resolve_to_a_folder_to_scan() {
+        let dir = std::env::temp_dir().join(format!("silvaview-goto-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, b"x").unwrap();
+
+        assert_eq!(PathOnDisk::of(dir.clone()), PathOnDisk::Scannable(dir.clone()));
+        assert_eq!(PathOnDisk::of(file), PathOnDisk::Scannable(dir.clone()));
+        assert_eq!(PathOnDisk::of(dir.join("missing")), PathOnDisk::Missing);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dropped_files_scan_their_folder() {
+        let dir = std::env::temp_dir().join(format!("silvaview-drop-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("photo.jpg");
+        std::fs::write(&file, b"x").unwrap();
+
+        assert_eq!(drop_scan_target(&dir), dir);
+        assert_eq!(drop_scan_target(&file), dir);
+        // A bare name has no parent to fall back to.
+        assert_eq!(drop_scan_target(Path::new("photo.jpg")), PathBuf::from("photo.jpg"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changing_the_tree_waits_out_the_layout_job() {
+        let mut app = app_with_tree();
+        app.start_relayout();
+        let before = Arc::as_ptr(app.tree.as_ref().unwrap());
+        app.toggle_size_basis();
+        // Same allocation: the job was wound down rather than the tree copied.
+        assert_eq!(Arc::as_ptr(app.tree.as_ref().unwrap()), before);
+        assert!(app.layout_job.is_none() && app.needs_relayout);
+    }
+
+    #[test]
+    fn hover_highlights_the_tile_under_the_cursor() {
+        let mut app = app_with_tree();
+        let node = NodeId(0);
+        let tile = |x| crate::layout::LayoutRect { node, x, y: 0.0, w: 100.0, h: 100.0, depth: 1, surface: [0.0; 4], grouped: 0 };
+        app.layout = Some(crate::layout::Layout {
+            rects: vec![tile(0.0), tile(200.0)],
+            node_to_rect: HashMap::from([(node, 0)]),
+            overflow: HashMap::new(),
+            coarsening: 0,
+        });
+        app.set_hover_node(Some(node));
+        (app.mouse.x, app.mouse.y) = (250.0, 50.0);
+        assert_eq!(app.hover_rect(), Some(1));
+        (app.mouse.x, app.mouse.y) = (50.0, 50.0);
+        assert_eq!(app.hover_rect(), Some(0));
+    }
+
+    #[test]
+    fn layout_jobs_that_died_are_cleared() {
+        let mut app = app_with_tree();
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        app.layout_job = Some(rx);
+        app.needs_relayout = false;
+        assert!(!app.poll_layout());
+        assert!(app.layout_job.is_none() && app.needs_relayout);
+    }
+
+    #[test]
+    fn only_the_loading_scan_can_be_paused() {
+        let mut app = app_with_tree();
+        let (_tx, rx) = mpsc::channel();
+        app.scan_rx = Some(rx);
+        app.scan_control = Some(Arc::new(ScanControl::default()));
+
+        // A background refresh of a ready tree keeps running.
+        app.phase = AppPhase::Ready;
+        assert!(!app.toggle_scan_pause());
+        assert!(!app.scan_paused());
+
+        app.phase = AppPhase::Scanning;
+        assert!(app.toggle_scan_pause());
+        assert!(app.scan_paused());
+        assert!(app.toggle_scan_pause());
+        assert!(!app.scan_paused());
+    }
+}
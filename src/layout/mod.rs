@@ -1,3 +1,6 @@
 pub mod squarify;
 
-pub use squarify::{compute_layout_lshape, Layout, LayoutConfig, LayoutRect};
+pub use squarify::{
+    compute_flat_layout, compute_layout_in_rect, compute_layout_lshape, grouped_files, DirInsets, Layout, LayoutCancel, LayoutConfig, LayoutRect,
+    LayoutSettings,
+};
@@ -1,5 +1,15 @@
 use crate::tree::arena::{FileTree, NodeId};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Shortest side an L-shape region may have before it is dropped; thinner slivers can
+/// still clear `min_area` and make squarify produce degenerate strips.
+const MIN_REGION_SIDE: f32 = 1.0;
+
+/// Most times a layout is redone with coarser culling to fit `LayoutConfig::max_rects`.
+const MAX_COARSENING: u32 = 4;
 
 /// A positioned rectangle in the treemap layout.
 #[derive(Debug, Clone, Copy)]
@@ -13,6 +23,9 @@ pub struct LayoutRect {
     /// Accumulated cushion surface coefficients [sx1, sx2, sy1, sy2]
     /// (linear_x, quad_x, linear_y, quad_y) from van Wijk & van de Wetering 1999.
     pub surface: [f32; 4],
+    /// Small files of one extension rolled into this tile, with `node` the largest of
+    /// them (0 for an ordinary tile; see `LayoutConfig::group_small_files`).
+    pub grouped: u32,
 }
 
 /// The full layout result (rects + fast lookup).
@@ -22,6 +35,44 @@ pub struct Layout {
     pub rects: Vec<LayoutRect>,
     /// node → index into `rects` (O(1) hover, tooltip, highlighting)
     pub node_to_rect: HashMap<NodeId, usize>,
+    /// Laid-out directory → how many of its children were culled (fan-out cap, coverage
+    /// target, or a dominant sibling), so the treemap can admit it shows a subset.
+    pub overflow: HashMap<NodeId, usize>,
+    /// How many times culling was coarsened to keep the rect count under
+    /// `LayoutConfig::max_rects` (0 = laid out at the configured detail).
+    pub coarsening: u32,
+}
+
+impl Layout {
+    /// The rect `node` was laid out in, if it is visible in this layout.
+    pub fn rect_of(&self, node: NodeId) -> Option<&LayoutRect> {
+        self.node_to_rect.get(&node).map(|&idx| &self.rects[idx])
+    }
+
+    /// Whether `node` got a rect in this layout (it may have been culled or collapsed away).
+    pub fn visible(&self, node: NodeId) -> bool {
+        self.node_to_rect.contains_key(&node)
+    }
+
+    /// Children of `node` left out of this layout (0 when all were laid out).
+    pub fn hidden_children(&self, node: NodeId) -> usize {
+        self.overflow.get(&node).copied().unwrap_or(0)
+    }
+
+    /// Add `other`'s rects after this layout's (hit testing prefers them where they
+    /// overlap). A node laid out in both keeps its rect from this layout for `rect_of`;
+    /// code that has to tell the two tiles apart goes by index into `rects`.
+    pub fn append(&mut self, other: Layout) {
+        let offset = self.rects.len();
+        for (node, idx) in other.node_to_rect {
+            self.node_to_rect.entry(node).or_insert(idx + offset);
+        }
+        for (node, hidden) in other.overflow {
+            self.overflow.entry(node).or_insert(hidden);
+        }
+        self.coarsening = self.coarsening.max(other.coarsening);
+        self.rects.extend(other.rects);
+    }
 }
 
 /// Configuration for treemap layout.
@@ -31,8 +82,13 @@ pub struct LayoutConfig {
     pub min_area: f32,
     /// Minimum side length (px) to render a node.
     pub min_side: f32,
-    /// Minimum side length (px) required before recursing into a directory.
+    /// Minimum side length (logical px) required before recursing into a directory.
+    /// Multiplied by `dpi_scale`, so high-density displays don't recurse into imperceptible tiles.
     pub recurse_min_side: f32,
+    /// Floor on the recursion threshold in physical (on-screen) px, whatever the DPI.
+    pub recurse_min_screen_px: f32,
+    /// Physical px per logical px of the target display; set by the app from the window.
+    pub dpi_scale: f32,
     /// Base padding between siblings (px)
     pub padding: f32,
     /// How much padding shrinks per nesting level (0.0 = constant, 0.7 = nice taper)
@@ -43,42 +99,332 @@ pub struct LayoutConfig {
     pub dir_header_px: f32,
     /// Per-level decay for frame/header dimensions.
     pub dir_frame_falloff: f32,
+    /// Directory tiles narrower or shorter than this (px) get no frame/header overlay.
+    pub dir_frame_min_w: f32,
+    pub dir_frame_min_h: f32,
     /// Maximum recursion depth (safety + performance)
     pub max_depth: u16,
     /// Target fractional area coverage to keep per directory before truncating tiny children.
     /// Remaining tail is redistributed to kept items to avoid interior "empty" regions.
     pub child_coverage_target: f64,
+    /// A folder holding at least this share of its parent's bytes, with its siblings
+    /// together under `dominant_sibling_share`, is chain-compressed into the parent's rect.
+    pub dominant_child_share: f64,
+    pub dominant_sibling_share: f64,
+    /// Share of a chain-compressed parent's area kept for the siblings of the dominant
+    /// folder, so the little that's left stays visible. 0.0 gives the folder the full rect.
+    pub min_sibling_share: f64,
     /// Hard cap on visible children per directory to avoid pathological stripe explosions.
     pub max_children_per_dir: usize,
+    /// Budget on rects for a whole layout. A layout that reaches it is redone with fewer
+    /// children per folder and larger area/recursion thresholds, so adversarial trees stay
+    /// cheap to draw.
+    pub max_rects: usize,
     /// Target aspect ratio for squarified layout (1.0 = square-ish)
     pub aspect_tolerance: f64,
     /// Initial cushion ridge height (paper default: 0.5)
     pub cushion_height: f32,
     /// Per-level height decay factor (paper default: 0.75)
     pub cushion_falloff: f32,
+    /// Lay out nodes flagged hidden or system; when false they and their subtrees are left out.
+    pub show_hidden: bool,
+    /// Fold single-directory chains (A -> B -> C) into their terminal directory's tile.
+    pub collapse_chains: bool,
+    /// Roll a folder's files that would get tiles under `group_below_area` into one tile
+    /// per extension, keeping bigger files individual.
+    pub group_small_files: bool,
+    /// Tile area (px²) below which a file is grouped.
+    pub group_below_area: f32,
+    /// How many of the biggest files `compute_flat_layout` shows.
+    pub flat_max_files: usize,
+    /// Folders whose groups were opened up; their files are laid out individually.
+    pub ungrouped_dirs: HashSet<NodeId>,
+    /// Give zero-byte files and empty folders an `EMPTY_TILE_AREA` tile instead of none,
+    /// so leftover clutter can be found.
+    pub show_empty: bool,
+    /// Subtrees hidden from the layout for this session; change with `exclude`/`restore`.
+    pub excluded: HashSet<NodeId>,
+    /// Bytes of hidden subtrees below each of their ancestors, taken off their sizes so
+    /// the rest of the tree is laid out to scale.
+    pub excluded_bytes: HashMap<NodeId, u64>,
+    /// Abandons a background layout once it has been superseded.
+    pub cancel: Option<LayoutCancel>,
 }
 
+/// Lets whoever started a background layout call it off: the layout stops descending
+/// once the shared generation moves past the one it was started at, and returns what it
+/// has so far.
+#[derive(Debug, Clone)]
+pub struct LayoutCancel {
+    generation: Arc<AtomicU64>,
+    started: u64,
+}
+
+impl LayoutCancel {
+    pub fn new(generation: Arc<AtomicU64>) -> Self {
+        let started = generation.load(Ordering::Relaxed);
+        Self { generation, started }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.generation.load(Ordering::Relaxed) != self.started
+    }
+}
+
+/// Tile area (px² at 1x DPI) of a zero-size node when `show_empty` is on.
+pub const EMPTY_TILE_AREA: f64 = 64.0;
+/// Share of a folder's area its zero-size children cover at least, together.
+const EMPTY_MIN_SHARE: f64 = 0.01;
+/// Share of a folder's area its zero-size children cover at most, together, however many.
+const EMPTY_MAX_SHARE: f64 = 0.1;
+
 impl Default for LayoutConfig {
     fn default() -> Self {
         Self {
             min_area: 49.0,       // Avoid tiny visual noise on million-node trees
             min_side: 6.0,        // Suppress thin strips that are not interactable
             recurse_min_side: 28.0, // Recurse only when child rect can show structure
+            recurse_min_screen_px: 28.0,
+            dpi_scale: 1.0,
             padding: 0.0,         // Paper-style treemap has no forced gaps
             padding_falloff: 1.0,
             dir_frame_px: 2.0,
             dir_header_px: 16.0,
             dir_frame_falloff: 0.92,
+            dir_frame_min_w: 24.0,
+            dir_frame_min_h: 20.0,
             max_depth: 64,
             child_coverage_target: 0.995, // Keep 99.5% of each directory's area before truncation
+            dominant_child_share: 0.98,
+            dominant_sibling_share: 0.02,
+            min_sibling_share: 0.03,
             max_children_per_dir: 1200,   // Prevent extreme stripe counts in very wide folders
+            max_rects: 150_000,
             aspect_tolerance: 1.0,
             cushion_height: 0.8, // Increased from 0.5 for more visible cushion effect
             cushion_falloff: 0.75,
+            show_hidden: true,
+            collapse_chains: true,
+            group_small_files: false,
+            group_below_area: 400.0,
+            flat_max_files: 500,
+            ungrouped_dirs: HashSet::new(),
+            show_empty: false,
+            excluded: HashSet::new(),
+            excluded_bytes: HashMap::new(),
+            cancel: None,
         }
     }
 }
 
+/// Insets of a directory tile, in px: `pad` of empty gap, then a `frame` on every side
+/// and a `header` band under the top frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirInsets {
+    pub pad: f32,
+    pub frame: f32,
+    pub header: f32,
+}
+
+/// The user-tunable subset of `LayoutConfig`, as persisted. Tuning constants and
+/// window-derived values (`dpi_scale`) stay out so the schema only holds choices.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub min_area: f32,
+    pub min_side: f32,
+    pub recurse_min_side: f32,
+    pub recurse_min_screen_px: f32,
+    pub dir_frame_px: f32,
+    pub dir_header_px: f32,
+    pub max_children_per_dir: usize,
+    pub max_rects: usize,
+    pub show_hidden: bool,
+    pub collapse_chains: bool,
+    pub group_small_files: bool,
+    pub show_empty: bool,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        LayoutConfig::default().settings()
+    }
+}
+
+impl LayoutConfig {
+    /// The user-tunable fields.
+    pub fn settings(&self) -> LayoutSettings {
+        LayoutSettings {
+            min_area: self.min_area,
+            min_side: self.min_side,
+            recurse_min_side: self.recurse_min_side,
+            recurse_min_screen_px: self.recurse_min_screen_px,
+            dir_frame_px: self.dir_frame_px,
+            dir_header_px: self.dir_header_px,
+            max_children_per_dir: self.max_children_per_dir,
+            max_rects: self.max_rects,
+            show_hidden: self.show_hidden,
+            collapse_chains: self.collapse_chains,
+            group_small_files: self.group_small_files,
+            show_empty: self.show_empty,
+        }
+    }
+
+    /// Overwrite the user-tunable fields, keeping internal ones.
+    pub fn apply_settings(&mut self, settings: &LayoutSettings) {
+        self.min_area = settings.min_area;
+        self.min_side = settings.min_side;
+        self.recurse_min_side = settings.recurse_min_side;
+        self.recurse_min_screen_px = settings.recurse_min_screen_px;
+        self.dir_frame_px = settings.dir_frame_px;
+        self.dir_header_px = settings.dir_header_px;
+        self.max_children_per_dir = settings.max_children_per_dir.max(1);
+        self.max_rects = settings.max_rects.max(1_000);
+        self.show_hidden = settings.show_hidden;
+        self.collapse_chains = settings.collapse_chains;
+        self.group_small_files = settings.group_small_files;
+        self.show_empty = settings.show_empty;
+    }
+
+    /// Whether `id` is zero-size and gets a fixed tile under `show_empty`. Files go by
+    /// their logical size: one small enough to live in the MFT takes no clusters but isn't
+    /// empty.
+    fn shows_as_empty(&self, tree: &FileTree, id: NodeId) -> bool {
+        let node = tree.get(id);
+        self.show_empty
+            && if node.is_dir { self.size_of(tree, id) == 0 } else { node.logical == 0 }
+    }
+
+    /// Size of `id` as laid out: its bytes minus those of hidden subtrees below it.
+    pub fn size_of(&self, tree: &FileTree, id: NodeId) -> u64 {
+        let size = tree.get(id).size;
+        match self.excluded_bytes.get(&id) {
+            Some(&hidden) => size.saturating_sub(hidden),
+            None => size,
+        }
+    }
+
+    /// Bytes `id`'s children are laid out from: `size_of` less the children the hidden-file
+    /// setting leaves out, so their share of the tile doesn't turn into a gap.
+    pub fn children_size(&self, tree: &FileTree, id: NodeId) -> u64 {
+        let size = self.size_of(tree, id);
+        if self.show_hidden {
+            return size;
+        }
+        let hidden: u64 = tree
+            .children(id)
+            .filter(|&child| tree.get(child).is_hidden() && !self.excluded.contains(&child))
+            .map(|child| self.size_of(tree, child))
+            .sum();
+        size.saturating_sub(hidden)
+    }
+
+    /// Files and folders below `id` as laid out, leaving out those in hidden subtrees.
+    pub fn counts_of(&self, tree: &FileTree, id: NodeId) -> (u32, u32) {
+        let node = tree.get(id);
+        let (mut files, mut dirs) = (node.file_count, node.dir_count);
+        for &hidden in &self.excluded {
+            let mut ancestors = tree.ancestors(hidden);
+            if !ancestors.any(|a| a == id) || tree.ancestors(hidden).any(|a| self.excluded.contains(&a)) {
+                continue;
+            }
+            let h = tree.get(hidden);
+            if h.is_dir {
+                files = files.saturating_sub(h.file_count);
+                dirs = dirs.saturating_sub(h.dir_count + 1);
+            } else {
+                files = files.saturating_sub(1);
+            }
+        }
+        (files, dirs)
+    }
+
+    /// Hide `id` and everything below it from the layout. The root can't be hidden.
+    pub fn exclude(&mut self, tree: &FileTree, id: NodeId) {
+        if id != tree.root && self.excluded.insert(id) {
+            self.recount_excluded(tree);
+        }
+    }
+
+    /// Whether the background layout this config drives was called off.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(LayoutCancel::is_cancelled)
+    }
+
+    /// Bring a hidden subtree back.
+    pub fn restore(&mut self, tree: &FileTree, id: NodeId) {
+        if self.excluded.remove(&id) {
+            self.recount_excluded(tree);
+        }
+    }
+
+    /// Forget every hidden subtree, e.g. when node ids go stale.
+    pub fn clear_excluded(&mut self) {
+        self.excluded.clear();
+        self.excluded_bytes.clear();
+    }
+
+    /// Whether `id` lies inside a hidden subtree, itself included.
+    pub fn is_excluded(&self, tree: &FileTree, id: NodeId) -> bool {
+        !self.excluded.is_empty()
+            && std::iter::once(id).chain(tree.ancestors(id)).any(|a| self.excluded.contains(&a))
+    }
+
+    /// Rebuild `excluded_bytes` after the hidden set or the tree's sizes changed. Subtrees
+    /// nested in another hidden one are already counted by it.
+    pub fn recount_excluded(&mut self, tree: &FileTree) {
+        self.excluded_bytes.clear();
+        for &id in &self.excluded {
+            if tree.ancestors(id).any(|a| self.excluded.contains(&a)) {
+                continue;
+            }
+            let size = tree.get(id).size;
+            for ancestor in tree.ancestors(id) {
+                *self.excluded_bytes.entry(ancestor).or_default() += size;
+            }
+        }
+    }
+
+    /// Area of child `id` out of its parent's `total_area`, in proportion to its size.
+    fn child_area(&self, tree: &FileTree, id: NodeId, parent_size: f64, total_area: f64) -> f64 {
+        if self.shows_as_empty(tree, id) {
+            return EMPTY_TILE_AREA * (self.dpi_scale.max(1.0) as f64).powi(2);
+        }
+        (self.size_of(tree, id) as f64 / parent_size) * total_area
+    }
+
+    /// Space a directory tile at `depth` (its `LayoutRect::depth`) and height `h` reserves
+    /// around its children. Layout insets children by this and the scene draws the frame
+    /// and header band from it, so both agree on where the header sits.
+    pub fn dir_insets(&self, depth: u16, h: f32) -> DirInsets {
+        if depth == 0 {
+            return DirInsets::default();
+        }
+        let level_scale = self.dir_frame_falloff.powi((depth - 1) as i32);
+        DirInsets {
+            pad: self.padding * self.padding_falloff.powi(depth as i32),
+            frame: (self.dir_frame_px * level_scale).max(1.0),
+            header: (self.dir_header_px * level_scale).min((h * 0.22).max(0.0)),
+        }
+    }
+
+    /// Whether a directory tile is big enough for the scene to draw its frame and header.
+    pub fn shows_dir_frame(&self, rect: &LayoutRect) -> bool {
+        rect.depth > 0 && rect.w >= self.dir_frame_min_w && rect.h >= self.dir_frame_min_h
+    }
+
+    /// Side length (physical px) a directory tile needs before its children are laid out.
+    pub fn recurse_threshold(&self) -> f32 {
+        (self.recurse_min_side * self.dpi_scale.max(1.0)).max(self.recurse_min_screen_px)
+    }
+
+    /// Whether `id` takes part in the layout under the hidden-file setting and isn't a
+    /// hidden subtree. Its ancestors are the caller's concern.
+    pub fn shows(&self, tree: &FileTree, id: NodeId) -> bool {
+        (self.show_hidden || !tree.get(id).is_hidden()) && !self.excluded.contains(&id)
+    }
+}
+
 /// Add a ridge to the cushion surface coefficients along one axis.
 /// Matches the CTM procedure from van Wijk & van de Wetering 1999.
 fn add_ridge(x1: f32, x2: f32, h: f32, s1: &mut f32, s2: &mut f32) {
@@ -101,6 +447,37 @@ pub fn compute_layout(
     compute_layout_in_rect(tree, root, 0.0, 0.0, viewport_w, viewport_h, config)
 }
 
+/// Run `layout` with `config`, coarsening culling until the result fits in
+/// `config.max_rects`. Each step quarters the per-folder child cap, quadruples `min_area`
+/// and doubles the side thresholds, cutting the tile count roughly fourfold; after
+/// `MAX_COARSENING` steps the last attempt is kept even if the cap truncated it.
+fn within_rect_budget(config: &LayoutConfig, layout: impl Fn(&LayoutConfig) -> Layout) -> Layout {
+    let mut config = config.clone();
+    let mut coarsening = 0;
+    loop {
+        let mut result = layout(&config);
+        if result.rects.len() < config.max_rects || coarsening == MAX_COARSENING || config.is_cancelled() {
+            if coarsening > 0 {
+                tracing::info!(
+                    "Layout coarsened {} time(s) to stay under {} rects (min_area {:.0}, min_side {:.0})",
+                    coarsening,
+                    config.max_rects,
+                    config.min_area,
+                    config.min_side
+                );
+            }
+            result.coarsening = coarsening;
+            return result;
+        }
+        coarsening += 1;
+        config.max_children_per_dir = (config.max_children_per_dir / 4).max(8);
+        config.min_area *= 4.0;
+        config.min_side *= 2.0;
+        config.recurse_min_side *= 2.0;
+        config.recurse_min_screen_px *= 2.0;
+    }
+}
+
 /// Compute layout around a reserved top-left sidebar rectangle by using a non-overlapping
 /// L-shape: top-right strip + full-width bottom strip.
 pub fn compute_layout_lshape(
@@ -110,9 +487,21 @@ pub fn compute_layout_lshape(
     viewport_h: f32,
     exclusion_rect: [f32; 4],
     config: &LayoutConfig,
+) -> Layout {
+    within_rect_budget(config, |config| layout_lshape(tree, root, viewport_w, viewport_h, exclusion_rect, config))
+}
+
+fn layout_lshape(
+    tree: &FileTree,
+    root: NodeId,
+    viewport_w: f32,
+    viewport_h: f32,
+    exclusion_rect: [f32; 4],
+    config: &LayoutConfig,
 ) -> Layout {
     let mut rects = Vec::with_capacity(tree.len() / 4);
     let mut node_to_rect = HashMap::with_capacity(rects.capacity());
+    let mut overflow = HashMap::new();
 
     let root_rect = LayoutRect {
         node: root,
@@ -122,12 +511,13 @@ pub fn compute_layout_lshape(
         h: viewport_h,
         depth: 0,
         surface: [0.0; 4],
+        grouped: 0,
     };
     rects.push(root_rect);
     node_to_rect.insert(root, 0);
 
     if !tree.get(root).is_dir {
-        return Layout { rects, node_to_rect };
+        return Layout { rects, node_to_rect, overflow, coarsening: 0 };
     }
 
     let pad = 8.0;
@@ -152,28 +542,28 @@ pub fn compute_layout_lshape(
     };
 
     let mut regions: Vec<Region> = Vec::new();
-    if top_right.area() >= config.min_area {
+    if top_right.is_usable(config) {
         regions.push(top_right);
     }
-    if bottom.area() >= config.min_area {
+    if bottom.is_usable(config) {
         regions.push(bottom);
     }
     if regions.is_empty() {
-        return compute_layout_in_rect(tree, root, 0.0, 0.0, viewport_w, viewport_h, config);
+        return layout_in_rect(tree, root, 0.0, 0.0, viewport_w, viewport_h, config);
     }
     if regions.len() == 1 {
         let r = regions[0];
-        return compute_layout_in_rect(tree, root, r.x, r.y, r.w, r.h, config);
+        return layout_in_rect(tree, root, r.x, r.y, r.w, r.h, config);
     }
 
     let parent_node = tree.get(root);
-    let parent_size = parent_node.size as f64;
-    if parent_size <= 0.0 {
-        return Layout { rects, node_to_rect };
+    let parent_size = config.children_size(tree, root) as f64;
+    if parent_size <= 0.0 && !config.show_empty {
+        return Layout { rects, node_to_rect, overflow, coarsening: 0 };
     }
 
     let total_available_area = regions.iter().map(|r| r.area() as f64).sum::<f64>();
-    let visible = collect_visible_children(
+    let (visible, dropped) = collect_visible_children(
         tree,
         root,
         parent_size,
@@ -182,8 +572,11 @@ pub fn compute_layout_lshape(
         config,
         &parent_node.name,
     );
+    if dropped > 0 {
+        overflow.insert(root, dropped);
+    }
     if visible.is_empty() {
-        return Layout { rects, node_to_rect };
+        return Layout { rects, node_to_rect, overflow, coarsening: 0 };
     }
 
     let total_visible_area = visible.iter().map(|(_, a)| *a).sum::<f64>();
@@ -221,14 +614,17 @@ pub fn compute_layout_lshape(
 
     let assignments = [(regions[0], top_items), (regions[1], bottom_items)];
     for (region, mut items) in assignments {
-        if items.is_empty() || region.area() < config.min_area {
+        if items.is_empty() || !region.is_usable(config) {
             continue;
         }
         let sum = items.iter().map(|(_, a)| *a).sum::<f64>();
-        if sum <= 0.0 {
+        if !sum.is_finite() || sum <= 0.0 {
             continue;
         }
         let scale = region.area() as f64 / sum;
+        if !scale.is_finite() {
+            continue;
+        }
         for (_, a) in &mut items {
             *a *= scale;
         }
@@ -241,6 +637,15 @@ pub fn compute_layout_lshape(
             region.h as f64,
         );
         for (i, pos) in positioned.iter().enumerate() {
+            let finite = pos.x.is_finite() && pos.y.is_finite() && pos.w.is_finite() && pos.h.is_finite();
+            debug_assert!(finite, "non-finite L-shape rect {:?}", (pos.x, pos.y, pos.w, pos.h));
+            if !finite {
+                continue;
+            }
+            if rects.len() >= config.max_rects {
+                *overflow.entry(root).or_default() += positioned.len() - i;
+                break;
+            }
             push_child_rect_and_recurse(
                 tree,
                 items[i].0,
@@ -254,11 +659,12 @@ pub fn compute_layout_lshape(
                 config,
                 &mut rects,
                 &mut node_to_rect,
+                &mut overflow,
             );
         }
     }
 
-    Layout { rects, node_to_rect }
+    Layout { rects, node_to_rect, overflow, coarsening: 0 }
 }
 
 #[derive(Clone, Copy)]
@@ -273,6 +679,16 @@ impl Region {
     fn area(self) -> f32 {
         self.w.max(0.0) * self.h.max(0.0)
     }
+
+    /// Whether the region is finite and large enough, in both area and either side, to lay out.
+    fn is_usable(self, config: &LayoutConfig) -> bool {
+        self.x.is_finite()
+            && self.y.is_finite()
+            && self.w.is_finite()
+            && self.h.is_finite()
+            && self.w.min(self.h) >= MIN_REGION_SIDE
+            && self.area() >= config.min_area
+    }
 }
 
 /// Compute layout for any subtree inside an explicit viewport rectangle.
@@ -284,9 +700,24 @@ pub fn compute_layout_in_rect(
     viewport_w: f32,
     viewport_h: f32,
     config: &LayoutConfig,
+) -> Layout {
+    within_rect_budget(config, |config| {
+        layout_in_rect(tree, root, viewport_x, viewport_y, viewport_w, viewport_h, config)
+    })
+}
+
+fn layout_in_rect(
+    tree: &FileTree,
+    root: NodeId,
+    viewport_x: f32,
+    viewport_y: f32,
+    viewport_w: f32,
+    viewport_h: f32,
+    config: &LayoutConfig,
 ) -> Layout {
     let mut rects = Vec::with_capacity(tree.len() / 4); // rough estimate
     let mut node_to_rect = HashMap::with_capacity(rects.capacity());
+    let mut overflow = HashMap::new();
 
     let root_rect = LayoutRect {
         node: root,
@@ -296,6 +727,7 @@ pub fn compute_layout_in_rect(
         h: viewport_h,
         depth: 0,
         surface: [0.0; 4],
+        grouped: 0,
     };
 
     rects.push(root_rect);
@@ -315,10 +747,85 @@ pub fn compute_layout_in_rect(
             config,
             &mut rects,
             &mut node_to_rect,
+            &mut overflow,
         );
     }
 
-    Layout { rects, node_to_rect }
+    Layout { rects, node_to_rect, overflow, coarsening: 0 }
+}
+
+/// Lay out the `config.flat_max_files` biggest files under `root` as direct children of a
+/// single root tile, ignoring the folders between them. Files too small to stay visible are
+/// dropped like any other culled tile, and counted as the root's hidden children.
+pub fn compute_flat_layout(
+    tree: &FileTree,
+    root: NodeId,
+    viewport_x: f32,
+    viewport_y: f32,
+    viewport_w: f32,
+    viewport_h: f32,
+    config: &LayoutConfig,
+) -> Layout {
+    let mut rects = vec![LayoutRect {
+        node: root,
+        x: viewport_x,
+        y: viewport_y,
+        w: viewport_w,
+        h: viewport_h,
+        depth: 0,
+        surface: [0.0; 4],
+        grouped: 0,
+    }];
+    let mut node_to_rect = HashMap::from([(root, 0)]);
+    let mut overflow = HashMap::new();
+
+    let files: Vec<NodeId> = tree
+        .largest_files(root, config.flat_max_files)
+        .into_iter()
+        .filter(|&id| config.shows(tree, id) && tree.get(id).size > 0 && !config.is_excluded(tree, id))
+        .collect();
+    let total: f64 = files.iter().map(|&id| tree.get(id).size as f64).sum();
+    if total <= 0.0 {
+        return Layout { rects, node_to_rect, overflow, coarsening: 0 };
+    }
+
+    let area = viewport_w as f64 * viewport_h as f64;
+    let areas: Vec<f64> = files.iter().map(|&id| tree.get(id).size as f64 / total * area).collect();
+    let positioned = squarify(
+        &areas,
+        viewport_x as f64,
+        viewport_y as f64,
+        viewport_w as f64,
+        viewport_h as f64,
+    );
+
+    let mut hidden = tree.descendants(root).filter(|&id| !tree.get(id).is_dir).count() - files.len();
+    for (&id, p) in files.iter().zip(&positioned) {
+        let (x, y, w, h) = (p.x as f32, p.y as f32, p.w as f32, p.h as f32);
+        if w * h < config.min_area || w.min(h) < config.min_side {
+            hidden += 1;
+            continue;
+        }
+        let [mut sx1, mut sx2, mut sy1, mut sy2] = [0.0; 4];
+        add_ridge(x, x + w, config.cushion_height, &mut sx1, &mut sx2);
+        add_ridge(y, y + h, config.cushion_height, &mut sy1, &mut sy2);
+        node_to_rect.insert(id, rects.len());
+        rects.push(LayoutRect {
+            node: id,
+            x,
+            y,
+            w,
+            h,
+            depth: 1,
+            surface: [sx1, sx2, sy1, sy2],
+            grouped: 0,
+        });
+    }
+    if hidden > 0 {
+        overflow.insert(root, hidden);
+    }
+
+    Layout { rects, node_to_rect, overflow, coarsening: 0 }
 }
 
 fn collect_visible_children(
@@ -329,18 +836,17 @@ fn collect_visible_children(
     depth: u16,
     config: &LayoutConfig,
     parent_name: &str,
-) -> Vec<(NodeId, f64)> {
+) -> (Vec<(NodeId, f64)>, usize) {
     let mut items: Vec<(NodeId, f64)> = tree
         .children(parent)
-        .map(|id| {
-            let area = (tree.get(id).size as f64 / parent_size) * total_area;
-            (id, area)
-        })
+        .filter(|&id| config.shows(tree, id))
+        .map(|id| (id, config.child_area(tree, id, parent_size, total_area)))
         .filter(|&(_, area)| area.is_finite() && area > 0.0)
         .collect();
+    reserve_empty_area(tree, &mut items, total_area, config);
     items.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
     if items.is_empty() {
-        return Vec::new();
+        return (Vec::new(), 0);
     }
 
     let mut visible: Vec<(NodeId, f64)> =
@@ -350,9 +856,12 @@ fn collect_visible_children(
         if visible.len() >= config.max_children_per_dir {
             break;
         }
-        let keep = idx == 0 || visible.len() < 8 || (covered_area / total_area) < config.child_coverage_target;
+        let keep = idx == 0
+            || visible.len() < 8
+            || (covered_area / total_area) < config.child_coverage_target
+            || config.shows_as_empty(tree, item.0);
         if !keep {
-            break;
+            continue;
         }
         visible.push(*item);
         covered_area += item.1;
@@ -362,7 +871,7 @@ fn collect_visible_children(
         covered_area = items[0].1;
     }
     if covered_area <= 0.0 {
-        return Vec::new();
+        return (Vec::new(), 0);
     }
     let dropped = items.len().saturating_sub(visible.len());
     if dropped > 0 && depth <= 2 {
@@ -381,7 +890,7 @@ fn collect_visible_children(
     for (_, area) in &mut visible {
         *area *= scale;
     }
-    visible
+    (visible, dropped)
 }
 
 fn push_child_rect_and_recurse(
@@ -397,6 +906,7 @@ fn push_child_rect_and_recurse(
     config: &LayoutConfig,
     rects: &mut Vec<LayoutRect>,
     node_to_rect: &mut HashMap<NodeId, usize>,
+    overflow: &mut HashMap<NodeId, usize>,
 ) {
     if cw <= 0.5 || ch <= 0.5 {
         return;
@@ -404,7 +914,7 @@ fn push_child_rect_and_recurse(
 
     let mut child_depth = depth.saturating_add(1);
     if tree.get(child_id).is_dir {
-        let (collapsed, collapsed_levels) = collapse_single_dir_chain(tree, child_id);
+        let (collapsed, collapsed_levels) = collapse_single_dir_chain(tree, child_id, config);
         child_id = collapsed;
         child_depth = child_depth.saturating_add(collapsed_levels as u16);
     }
@@ -422,13 +932,14 @@ fn push_child_rect_and_recurse(
         h: ch,
         depth: child_depth,
         surface,
+            grouped: 0,
     };
 
     let idx = rects.len();
     rects.push(rect);
     node_to_rect.insert(child_id, idx);
 
-    if tree.get(child_id).is_dir && cw >= config.recurse_min_side && ch >= config.recurse_min_side {
+    if tree.get(child_id).is_dir && cw.min(ch) >= config.recurse_threshold() {
         layout_children(
             tree,
             child_id,
@@ -442,6 +953,7 @@ fn push_child_rect_and_recurse(
             config,
             rects,
             node_to_rect,
+            overflow,
         );
     }
 }
@@ -460,34 +972,23 @@ fn layout_children(
     config: &LayoutConfig,
     rects: &mut Vec<LayoutRect>,
     node_to_rect: &mut HashMap<NodeId, usize>,
+    overflow: &mut HashMap<NodeId, usize>,
 ) {
-    if depth >= config.max_depth {
+    if depth >= config.max_depth || config.is_cancelled() {
+        return;
+    }
+    if rects.len() >= config.max_rects {
+        // Out of budget: the folder shows none of its children, and says so.
+        let children = tree.children(parent).filter(|&id| config.shows(tree, id)).count();
+        if children > 0 {
+            overflow.insert(parent, children);
+        }
         return;
     }
 
-    let level_scale = if depth == 0 {
-        1.0
-    } else {
-        config.dir_frame_falloff.powi((depth - 1) as i32)
-    };
-
-    // Dynamic padding that tapers with depth
-    let pad = if depth == 0 {
-        0.0
-    } else {
-        config.padding * config.padding_falloff.powi(depth as i32)
-    };
-    // Reserve a visible directory frame + top header band so parent/child nesting is obvious.
-    let frame = if depth == 0 {
-        0.0
-    } else {
-        (config.dir_frame_px * level_scale).max(1.0)
-    };
-    let header = if depth == 0 {
-        0.0
-    } else {
-        (config.dir_header_px * level_scale).min((h * 0.22).max(0.0))
-    };
+    // Padding that tapers with depth, plus a visible directory frame and top header band
+    // so parent/child nesting is obvious.
+    let DirInsets { pad, frame, header } = config.dir_insets(depth, h);
 
     let inset_x = pad + frame;
     let inset_y = pad + frame;
@@ -501,8 +1002,9 @@ fn layout_children(
     }
 
     let parent_node = tree.get(parent);
-    let parent_size = parent_node.size as f64;
-    if parent_size <= 0.0 {
+    let parent_size = config.children_size(tree, parent) as f64;
+    // An empty folder's empty subfolders still get tiles when they are shown.
+    if parent_size <= 0.0 && !config.show_empty {
         tracing::debug!(
             "Skipping layout for parent {:?} '{}' with zero size at depth {}",
             parent,
@@ -514,9 +1016,19 @@ fn layout_children(
 
     // Chain-compression: if one directory dominates almost all bytes of this parent,
     // recurse directly into it using the full parent rectangle to avoid barcode-like strips.
-    if let Some((dom_child, dom_ratio, sibling_ratio)) = dominant_dir_child(tree, parent, parent_size) {
-        if dom_ratio >= 0.98 && sibling_ratio <= 0.02 {
-            let (dom_child, collapsed_levels) = collapse_single_dir_chain(tree, dom_child);
+    // When its siblings hold any bytes, they instead get a reserved strip of at least
+    // `min_sibling_share` and the parent is laid out normally.
+    let mut sibling_floor = None;
+    if let Some((dom_child, dom_ratio, sibling_ratio)) = dominant_dir_child(tree, parent, parent_size, config) {
+        let dominates = dom_ratio >= config.dominant_child_share && sibling_ratio <= config.dominant_sibling_share;
+        if dominates && sibling_ratio > 0.0 && config.min_sibling_share > 0.0 {
+            sibling_floor = Some((dom_child, config.min_sibling_share));
+        } else if dominates {
+            let siblings = tree.children(parent).filter(|&id| config.shows(tree, id)).count() - 1;
+            if siblings > 0 {
+                overflow.insert(parent, siblings);
+            }
+            let (dom_child, collapsed_levels) = collapse_single_dir_chain(tree, dom_child, config);
             let child_depth = depth
                 .saturating_add(1)
                 .saturating_add(collapsed_levels as u16);
@@ -538,12 +1050,13 @@ fn layout_children(
                 h: ch,
                 depth: child_depth,
                 surface,
+                grouped: 0,
             };
             let idx = rects.len();
             rects.push(rect);
             node_to_rect.insert(dom_child, idx);
 
-            if cw >= config.recurse_min_side && ch >= config.recurse_min_side {
+            if cw.min(ch) >= config.recurse_threshold() {
                 layout_children(
                     tree,
                     dom_child,
@@ -557,6 +1070,7 @@ fn layout_children(
                     config,
                     rects,
                     node_to_rect,
+                    overflow,
                 );
             }
             return;
@@ -567,18 +1081,27 @@ fn layout_children(
     let total_area = (inner_w as f64) * (inner_h as f64);
     let mut items: Vec<(NodeId, f64)> = tree
         .children(parent)
-        .map(|id| {
-            let area = (tree.get(id).size as f64 / parent_size) * total_area;
-            (id, area)
-        })
+        .filter(|&id| config.shows(tree, id))
+        .map(|id| (id, config.child_area(tree, id, parent_size, total_area)))
         .filter(|&(_, area)| area.is_finite() && area > 0.0)
         .collect();
+    reserve_empty_area(tree, &mut items, total_area, config);
     items.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
     if items.is_empty() {
         return;
     }
 
+    if let Some((dom_child, share)) = sibling_floor {
+        reserve_sibling_area(&mut items, dom_child, share * total_area);
+    }
+
+    let groups = if config.group_small_files && !config.ungrouped_dirs.contains(&parent) {
+        group_small_files(tree, &mut items, config.group_below_area as f64)
+    } else {
+        HashMap::new()
+    };
+
     // Keep only the most important children for this level.
     // This intentionally trades tiny-detail fidelity for readability and performance,
     // while preserving visual coverage by redistributing the omitted tail.
@@ -590,9 +1113,11 @@ fn layout_children(
         }
         let keep = idx == 0
             || visible.len() < 8
-            || (covered_area / total_area) < config.child_coverage_target;
+            || (covered_area / total_area) < config.child_coverage_target
+            // Empty tiles sort last; keep them past the coverage cutoff or they never show.
+            || config.shows_as_empty(tree, item.0);
         if !keep {
-            break;
+            continue;
         }
         visible.push(*item);
         covered_area += item.1;
@@ -607,6 +1132,9 @@ fn layout_children(
     }
 
     let dropped = items.len().saturating_sub(visible.len());
+    if dropped > 0 {
+        overflow.insert(parent, dropped);
+    }
     if dropped > 0 && depth <= 2 {
         tracing::debug!(
             "LOD: parent '{}' depth {} keeps {} of {} children ({:.2}% area, dropped={})",
@@ -628,7 +1156,7 @@ fn layout_children(
 
     if depth == 0 {
         tracing::info!(
-            "Laying out {} children of root '{}' (size={:.2} GB) in {:.0}x{:.0} area",
+            "Laying out {} children of root '{}' (size={:.2} GiB) in {:.0}x{:.0} area",
             visible.len(),
             parent_node.name,
             parent_size / 1_073_741_824.0,
@@ -643,10 +1171,14 @@ fn layout_children(
     let positioned = squarify(&areas, inner_x as f64, inner_y as f64, inner_w as f64, inner_h as f64);
 
     for (i, pos) in positioned.iter().enumerate() {
+        if rects.len() >= config.max_rects {
+            *overflow.entry(parent).or_default() += positioned.len() - i;
+            break;
+        }
         let mut child_id = visible[i].0;
         let mut child_depth = depth.saturating_add(1);
         if tree.get(child_id).is_dir {
-            let (collapsed, collapsed_levels) = collapse_single_dir_chain(tree, child_id);
+            let (collapsed, collapsed_levels) = collapse_single_dir_chain(tree, child_id, config);
             child_id = collapsed;
             child_depth = child_depth.saturating_add(collapsed_levels as u16);
         }
@@ -678,6 +1210,7 @@ fn layout_children(
             h: ch,
             depth: child_depth,
             surface,
+            grouped: groups.get(&child_id).copied().unwrap_or(0),
         };
 
         let idx = rects.len();
@@ -685,7 +1218,7 @@ fn layout_children(
         node_to_rect.insert(child_id, idx);
 
         // Recurse only into directories
-        if tree.get(child_id).is_dir && cw >= config.recurse_min_side && ch >= config.recurse_min_side {
+        if tree.get(child_id).is_dir && cw.min(ch) >= config.recurse_threshold() {
             layout_children(
                 tree,
                 child_id,
@@ -699,20 +1232,121 @@ fn layout_children(
                 config,
                 rects,
                 node_to_rect,
+                overflow,
             );
         }
     }
 }
 
-fn dominant_dir_child(tree: &FileTree, parent: NodeId, parent_size: f64) -> Option<(NodeId, f64, f64)> {
+/// Replace files in `items` (sorted by area, descending) whose tiles would be smaller than
+/// `max_area` with one item per extension, represented by its largest file and sized to the
+/// whole group. Returns representative → files in its group, for groups of two or more.
+fn group_small_files(tree: &FileTree, items: &mut Vec<(NodeId, f64)>, max_area: f64) -> HashMap<NodeId, u32> {
+    let mut kept: Vec<(NodeId, f64)> = Vec::with_capacity(items.len());
+    let mut by_extension: HashMap<u16, usize> = HashMap::new();
+    let mut groups: HashMap<NodeId, u32> = HashMap::new();
+    for &(id, area) in items.iter() {
+        let node = tree.get(id);
+        if node.is_dir || area >= max_area {
+            kept.push((id, area));
+            continue;
+        }
+        match by_extension.get(&node.extension_id) {
+            Some(&at) => {
+                kept[at].1 += area;
+                *groups.entry(kept[at].0).or_insert(1) += 1;
+            }
+            None => {
+                by_extension.insert(node.extension_id, kept.len());
+                kept.push((id, area));
+            }
+        }
+    }
+    if !groups.is_empty() {
+        kept.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        *items = kept;
+    }
+    groups
+}
+
+/// Files rolled into the group tile represented by `representative`: its visible siblings
+/// with the same extension that are no bigger than it.
+pub fn grouped_files<'a>(
+    tree: &'a FileTree,
+    representative: NodeId,
+    config: &'a LayoutConfig,
+) -> impl Iterator<Item = NodeId> + 'a {
+    let rep = tree.get(representative);
+    let parent = rep.parent.unwrap_or(representative);
+    tree.children(parent).filter(move |&id| {
+        let node = tree.get(id);
+        !node.is_dir && node.extension_id == rep.extension_id && node.size <= rep.size && config.shows(tree, id)
+    })
+}
+
+/// Rescale `items` so everything but `dominant` covers `reserved` of the area, taking the
+/// difference from `dominant`. Order is kept: the dominant item stays the largest.
+fn reserve_sibling_area(items: &mut [(NodeId, f64)], dominant: NodeId, reserved: f64) {
+    let total: f64 = items.iter().map(|&(_, area)| area).sum();
+    let siblings: f64 = items.iter().filter(|&&(id, _)| id != dominant).map(|&(_, area)| area).sum();
+    if siblings <= 0.0 || siblings >= reserved || reserved >= total {
+        return;
+    }
+    let scale = reserved / siblings;
+    for (id, area) in items.iter_mut() {
+        if *id == dominant {
+            *area = total - reserved;
+        } else {
+            *area *= scale;
+        }
+    }
+}
+
+/// Share `total_area` out evenly between the zero-size items shown under `show_empty`:
+/// their fixed tiles together, kept between `EMPTY_MIN_SHARE` and `EMPTY_MAX_SHARE` of it
+/// (all of it when nothing else is there). Beside big siblings their tiles would otherwise
+/// be squeezed into a strip too thin to draw, and a folder of thousands would be swamped.
+fn reserve_empty_area(tree: &FileTree, items: &mut [(NodeId, f64)], total_area: f64, config: &LayoutConfig) {
+    if !config.show_empty {
+        return;
+    }
+    let (mut empty, mut count) = (0.0, 0usize);
+    for &(id, area) in items.iter() {
+        if config.shows_as_empty(tree, id) {
+            empty += area;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return;
+    }
+    let share = if count == items.len() {
+        total_area
+    } else {
+        empty.clamp(EMPTY_MIN_SHARE * total_area, EMPTY_MAX_SHARE * total_area)
+    };
+    let each = share / count as f64;
+    for (id, area) in items.iter_mut() {
+        if config.shows_as_empty(tree, *id) {
+            *area = each;
+        }
+    }
+}
+
+fn dominant_dir_child(
+    tree: &FileTree,
+    parent: NodeId,
+    parent_size: f64,
+    config: &LayoutConfig,
+) -> Option<(NodeId, f64, f64)> {
     if parent_size <= 0.0 {
         return None;
     }
     let mut best: Option<(NodeId, u64)> = None;
     let mut total_children = 0u64;
-    for child in tree.children(parent) {
+    for child in tree.children(parent).filter(|&id| config.shows(tree, id)) {
         let node = tree.get(child);
-        let size = node.size;
+        let size = config.size_of(tree, child);
         total_children = total_children.saturating_add(size);
         if !node.is_dir {
             continue;
@@ -732,11 +1366,15 @@ fn dominant_dir_child(tree: &FileTree, parent: NodeId, parent_size: f64) -> Opti
 
 /// Collapse a pure single-directory chain (A -> B -> C ...) into its terminal directory.
 /// This removes repeated full-rect nesting that otherwise creates stripe-heavy visuals.
-fn collapse_single_dir_chain(tree: &FileTree, start: NodeId) -> (NodeId, usize) {
+/// A no-op unless `config.collapse_chains` is set.
+fn collapse_single_dir_chain(tree: &FileTree, start: NodeId, config: &LayoutConfig) -> (NodeId, usize) {
     let mut node = start;
     let mut collapsed = 0usize;
+    if !config.collapse_chains {
+        return (node, collapsed);
+    }
     loop {
-        let mut children = tree.children(node);
+        let mut children = tree.children(node).filter(|&id| config.shows(tree, id));
         let first = match children.next() {
             Some(id) => id,
             None => break,
@@ -923,7 +1561,320 @@ fn worst_aspect_ratio_stats(min_r: f64, max_r: f64, sum: f64, side: f64) -> f64
 
 #[cfg(test)]
 mod tests {
-    use super::squarify;
+    use super::{
+        compute_flat_layout, compute_layout_in_rect, compute_layout_lshape, grouped_files, squarify, LayoutConfig, LayoutSettings,
+        reserve_empty_area,
+    };
+    use crate::tree::arena::{FileNode, FileTree, NodeId};
+
+    #[test]
+    fn rect_of_resolves_every_laid_out_node() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let sub = tree.add_child(root, FileNode::test_dir("sub", 600));
+        for (i, size) in [300u64, 200, 100].into_iter().enumerate() {
+            tree.add_child(sub, FileNode::test_file(&format!("s{i}"), size));
+        }
+        for (i, size) in [250u64, 150].into_iter().enumerate() {
+            tree.add_child(root, FileNode::test_file(&format!("f{i}"), size));
+        }
+        tree.get_mut(root).size = 1000;
+
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &LayoutConfig::default());
+
+        assert!(layout.node_to_rect.len() > 1);
+        for &node in layout.node_to_rect.keys() {
+            assert!(layout.visible(node));
+            assert_eq!(layout.rect_of(node).map(|r| r.node), Some(node));
+        }
+    }
+
+    #[test]
+    fn layouts_over_the_rect_budget_are_coarsened() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        for i in 0..2_000 {
+            tree.add_child(root, FileNode::test_file(&format!("f{i}"), 100));
+        }
+        tree.get_mut(root).size = 200_000;
+
+        let fine = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &LayoutConfig::default());
+        assert_eq!(fine.coarsening, 0);
+
+        let config = LayoutConfig { max_rects: 500, ..LayoutConfig::default() };
+        let coarse = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert!(coarse.coarsening > 0);
+        assert!(coarse.rects.len() < 500, "{} rects", coarse.rects.len());
+        assert!(coarse.rects.len() < fine.rects.len());
+
+        // Past the last coarsening step the cap truncates; the cut children still count.
+        let config = LayoutConfig { max_rects: 5, ..LayoutConfig::default() };
+        let capped = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert_eq!(capped.coarsening, super::MAX_COARSENING);
+        assert_eq!(capped.rects.len() - 1 + capped.hidden_children(root), 2_000);
+    }
+
+    #[test]
+    fn flat_layout_tiles_only_the_biggest_files() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let sub = tree.add_child(root, FileNode::test_dir("sub", 600));
+        let big = tree.add_child(sub, FileNode::test_file("big", 500));
+        let tiny = tree.add_child(sub, FileNode::test_file("tiny", 100));
+        let mid = tree.add_child(root, FileNode::test_file("mid", 400));
+        tree.get_mut(root).size = 1000;
+
+        let config = LayoutConfig { flat_max_files: 2, ..LayoutConfig::default() };
+        let layout = compute_flat_layout(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+
+        assert!(!layout.visible(sub) && !layout.visible(tiny));
+        let big_rect = layout.rect_of(big).unwrap();
+        let mid_rect = layout.rect_of(mid).unwrap();
+        assert_eq!((big_rect.depth, mid_rect.depth), (1, 1));
+        let covered = big_rect.w * big_rect.h + mid_rect.w * mid_rect.h;
+        assert!((covered - 800.0 * 600.0).abs() < 1.0);
+        assert_eq!(layout.hidden_children(root), 1);
+    }
+
+    #[test]
+    fn dominant_folders_leave_room_for_their_siblings() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let vm = tree.add_child(root, FileNode::test_dir("vm", 999_000));
+        tree.add_child(vm, FileNode::test_file("disk.vhdx", 999_000));
+        let notes = tree.add_child(root, FileNode::test_file("notes.txt", 1_000));
+        tree.get_mut(root).size = 1_000_000;
+
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &LayoutConfig::default());
+        let notes_rect = layout.rect_of(notes).unwrap();
+        assert!(notes_rect.w * notes_rect.h >= 0.03 * 800.0 * 600.0 - 1.0);
+        assert!(layout.visible(vm));
+
+        // Without a floor the folder takes the whole rect, as before.
+        let config = LayoutConfig { min_sibling_share: 0.0, ..LayoutConfig::default() };
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert!(!layout.visible(notes));
+        assert_eq!(layout.hidden_children(root), 1);
+    }
+
+    #[test]
+    fn empty_nodes_get_tiles_only_when_shown() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        tree.add_child(root, FileNode::test_file("data.bin", 50_000));
+        let empty = tree.add_child(root, FileNode::test_dir("leftover", 0));
+        let blank = tree.add_child(root, FileNode::test_file("blank.txt", 0));
+        tree.get_mut(root).size = 50_000;
+
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &LayoutConfig::default());
+        assert!(!layout.visible(empty) && !layout.visible(blank));
+
+        let config = LayoutConfig { show_empty: true, ..LayoutConfig::default() };
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert!(layout.visible(empty) && layout.visible(blank));
+        let rect = layout.rect_of(blank).unwrap();
+        assert!(rect.w * rect.h <= 0.01 * 800.0 * 600.0);
+    }
+
+    #[test]
+    fn many_empty_nodes_share_a_capped_area() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let data = tree.add_child(root, FileNode::test_file("data.bin", 50_000));
+        let blanks: Vec<_> =
+            (0..2000).map(|i| tree.add_child(root, FileNode::test_file(&format!("{i}.txt"), 0))).collect();
+        // Stored in the MFT: no clusters on the allocated basis, but not empty.
+        let tiny = tree.add_child(root, FileNode { size: 0, logical: 300, ..FileNode::test_file("tiny.ini", 0) });
+        tree.get_mut(root).size = 50_000;
+
+        let config = LayoutConfig { show_empty: true, ..LayoutConfig::default() };
+        assert!(config.shows_as_empty(&tree, blanks[0]) && !config.shows_as_empty(&tree, tiny));
+        let mut items: Vec<_> = tree
+            .children(root)
+            .filter(|&id| id != tiny)
+            .map(|id| (id, config.child_area(&tree, id, 50_000.0, 480_000.0)))
+            .collect();
+        reserve_empty_area(&tree, &mut items, 480_000.0, &config);
+        let empty: f64 = items.iter().filter(|&&(id, _)| id != data).map(|&(_, area)| area).sum();
+        assert!((empty - 0.1 * 480_000.0).abs() < 1e-6);
+        assert!(items.iter().filter(|&&(id, _)| id != data).all(|&(_, area)| area == items[1].1));
+    }
+
+    #[test]
+    fn excluded_subtrees_give_up_their_space() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let keep = tree.add_child(root, FileNode::test_file("keep.bin", 10_000));
+        let hidden = tree.add_child(root, FileNode::test_dir("cache", 30_000));
+        let inner = tree.add_child(hidden, FileNode::test_file("blob", 30_000));
+        tree.get_mut(root).size = 40_000;
+
+        let mut config = LayoutConfig::default();
+        config.exclude(&tree, hidden);
+        assert!(config.is_excluded(&tree, inner));
+        assert_eq!(config.size_of(&tree, root), 10_000);
+        tree.get_mut(root).file_count = 2;
+        tree.get_mut(root).dir_count = 1;
+        tree.get_mut(hidden).file_count = 1;
+        assert_eq!(config.counts_of(&tree, root), (1, 0));
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert!(!layout.visible(hidden) && !layout.visible(inner));
+        let rect = layout.rect_of(keep).unwrap();
+        assert!(rect.w * rect.h > 0.9 * 800.0 * 600.0);
+
+        config.restore(&tree, hidden);
+        assert_eq!(config.size_of(&tree, root), 40_000);
+        assert_eq!(config.counts_of(&tree, root), (2, 1));
+    }
+
+    #[test]
+    fn small_files_are_grouped_by_extension() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let (log, tmp) = (tree.intern_extension("log"), tree.intern_extension("tmp"));
+        let big = tree.add_child(root, FileNode { extension_id: log, ..FileNode::test_file("big.log", 100_000) });
+        let logs: Vec<_> = (0..40)
+            .map(|i| tree.add_child(root, FileNode { extension_id: log, ..FileNode::test_file(&format!("{i}.log"), 20) }))
+            .collect();
+        let lone_tmp = tree.add_child(root, FileNode { extension_id: tmp, ..FileNode::test_file("a.tmp", 15) });
+        tree.get_mut(root).size = 100_815;
+
+        let mut config = LayoutConfig { group_small_files: true, ..LayoutConfig::default() };
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+
+        // The big file stays on its own; the small logs share one tile led by one of them.
+        assert_eq!(layout.rect_of(big).unwrap().grouped, 0);
+        let groups: Vec<_> = layout.rects.iter().filter(|r| r.grouped > 0).collect();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].grouped, 40);
+        assert!(logs.contains(&groups[0].node));
+        assert_eq!(logs.iter().filter(|&&id| layout.visible(id)).count(), 1);
+        assert_eq!(grouped_files(&tree, groups[0].node, &config).count(), 40);
+        // A group of one is just the file.
+        assert_eq!(layout.rect_of(lone_tmp).unwrap().grouped, 0);
+
+        config.ungrouped_dirs.insert(root);
+        let opened = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        assert!(opened.rects.iter().all(|r| r.grouped == 0));
+        assert!(opened.visible(logs[39]));
+    }
+
+    #[test]
+    fn children_sit_inside_the_insets_the_scene_draws() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let sub = tree.add_child(root, FileNode::test_dir("sub", 600));
+        for (i, size) in [300u64, 200, 100].into_iter().enumerate() {
+            tree.add_child(sub, FileNode::test_file(&format!("s{i}"), size));
+        }
+        tree.add_child(root, FileNode::test_file("f", 400));
+        tree.get_mut(root).size = 1000;
+
+        let config = LayoutConfig {
+            padding: 3.0,
+            ..LayoutConfig::default()
+        };
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        let dir = layout.rect_of(sub).unwrap();
+        let insets = config.dir_insets(dir.depth, dir.h);
+        let top = dir.y + insets.pad + insets.frame + insets.header;
+        assert!(insets.header > 0.0);
+
+        let children: Vec<_> = layout.rects.iter().filter(|r| tree.get(r.node).parent == Some(sub)).collect();
+        assert_eq!(children.len(), 3);
+        for r in children {
+            assert!(r.y >= top - 1e-3);
+            assert!(r.x >= dir.x + insets.pad + insets.frame - 1e-3);
+        }
+    }
+
+    #[test]
+    fn layout_settings_round_trip_and_keep_internal_fields() {
+        let mut config = LayoutConfig {
+            dpi_scale: 2.0,
+            ..LayoutConfig::default()
+        };
+        let settings = LayoutSettings {
+            min_side: 3.0,
+            show_hidden: false,
+            ..config.settings()
+        };
+        let text = toml::to_string(&settings).unwrap();
+        let loaded: LayoutSettings = toml::from_str(&text).unwrap();
+        assert_eq!(loaded, settings);
+
+        config.apply_settings(&loaded);
+        assert_eq!(config.settings(), settings);
+        assert_eq!(config.dpi_scale, 2.0);
+    }
+
+    #[test]
+    fn hidden_files_leave_no_gap_when_left_out() {
+        use crate::scanner::types::FILE_ATTRIBUTE_HIDDEN;
+
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let shown = tree.add_child(root, FileNode::test_file("shown.bin", 1_000));
+        let hidden = FileNode {
+            attributes: FILE_ATTRIBUTE_HIDDEN,
+            ..FileNode::test_file("pagefile.sys", 3_000)
+        };
+        tree.add_child(root, hidden);
+        tree.get_mut(root).size = 4_000;
+
+        let config = LayoutConfig {
+            show_hidden: false,
+            ..LayoutConfig::default()
+        };
+        assert_eq!(config.children_size(&tree, root), 1_000);
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+        let rect = layout.rect_of(shown).unwrap();
+        assert!(rect.w * rect.h > 0.9 * 800.0 * 600.0);
+    }
+
+    #[test]
+    fn fan_out_cap_records_hidden_children() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        for i in 0..50 {
+            tree.add_child(root, FileNode::test_file(&format!("f{i}"), 10));
+        }
+        tree.get_mut(root).size = 500;
+
+        let config = LayoutConfig {
+            max_children_per_dir: 20,
+            ..LayoutConfig::default()
+        };
+        let layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 800.0, 600.0, &config);
+
+        assert_eq!(layout.rects.len(), 21);
+        assert_eq!(layout.hidden_children(root), 30);
+    }
+
+    #[test]
+    fn lshape_drops_sliver_region_and_stays_finite() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        for (i, size) in [500u64, 300, 120, 50, 20, 9, 1].into_iter().enumerate() {
+            tree.add_child(root, FileNode::test_file(&format!("f{i}"), size));
+        }
+        tree.get_mut(root).size = 1000;
+
+        // The exclusion leaves a 0.02px-wide, 4000px-tall strip on the right: enough area
+        // to pass `min_area`, far too thin to lay anything out in.
+        let (vw, vh) = (200.0, 5000.0);
+        let exclusion = [0.0, 0.0, 191.98, 4000.0];
+        let layout = compute_layout_lshape(&tree, root, vw, vh, exclusion, &LayoutConfig::default());
+
+        assert!(layout.rects.len() > 1);
+        for r in &layout.rects {
+            assert!(r.x.is_finite() && r.y.is_finite() && r.w.is_finite() && r.h.is_finite());
+            assert!(r.w >= 0.0 && r.h >= 0.0);
+            assert!(r.x >= -1e-3 && r.x + r.w <= vw + 1e-3);
+            // Everything lands in the bottom band below the exclusion.
+            assert!(r.y >= 4008.0 - 1e-3 && r.y + r.h <= vh + 1e-3);
+        }
+    }
 
     #[test]
     fn single_item_fills_viewport_without_axis_swap() {
@@ -942,4 +1893,39 @@ mod tests {
         let total_out: f64 = rects.iter().map(|r| r.w * r.h).sum();
         assert!((total_in - total_out).abs() < 1e-6);
     }
+
+    #[test]
+    fn appended_layouts_keep_their_own_indices() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let left = tree.add_child(root, FileNode::test_dir("left", 6_000));
+        let right = tree.add_child(root, FileNode::test_dir("right", 4_000));
+        for (dir, n) in [(left, 3), (right, 2)] {
+            for i in 0..n {
+                tree.add_child(dir, FileNode::test_file(&format!("{i}.bin"), 2_000));
+            }
+        }
+        tree.get_mut(root).size = 10_000;
+        let config = LayoutConfig::default();
+        let mut layout = compute_layout_in_rect(&tree, root, 0.0, 0.0, 400.0, 300.0, &config);
+        let mut other = compute_layout_in_rect(&tree, right, 420.0, 0.0, 400.0, 300.0, &config);
+        other.overflow.insert(right, 7);
+        other.overflow.insert(left, 9);
+        layout.overflow.insert(left, 1);
+        let (offset, right_child) = (layout.rects.len(), tree.children(right).next().unwrap());
+        let appended: Vec<(NodeId, f32)> = other.rects.iter().map(|r| (r.node, r.x)).collect();
+        let child_in_other = other.node_to_rect[&right_child];
+
+        layout.append(other);
+        assert_eq!(layout.rects.len(), offset + appended.len());
+        for (i, &(node, x)) in appended.iter().enumerate() {
+            assert_eq!((layout.rects[offset + i].node, layout.rects[offset + i].x), (node, x));
+        }
+        // A node laid out in both halves keeps its left-hand tile for lookups.
+        assert!(layout.node_to_rect[&right_child] < offset);
+        assert!(layout.rect_of(right_child).unwrap().x < 400.0);
+        assert_eq!(layout.rects[offset + child_in_other].node, right_child);
+        // Overflow counts are keyed by node; the first layout's win.
+        assert_eq!((layout.hidden_children(left), layout.hidden_children(right)), (1, 7));
+    }
 }
@@ -1,8 +1,49 @@
 // Public library interface for SilvaView-rs
-// This allows the debug CLI tool to use the core modules
+// This allows the debug CLI tool to use the core modules, and gives embedders and tests the
+// scan → tree → layout → rasterize pipeline as two calls
 
 pub mod layout;
 pub mod render;
 pub mod scanner;
 pub mod tree;
 pub mod ui;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use render::colors::ColorSettings;
+use render::cushion::CushionConfig;
+use scanner::types::ScanOptions;
+use scanner::{ScanError, ScanMethod};
+use tree::arena::FileTree;
+
+/// Scan `path` with default options and build the size-sorted tree, as the GUI does before
+/// its first layout. Progress reports are discarded.
+pub fn scan_and_build(path: &Path, method: ScanMethod) -> Result<FileTree, ScanError> {
+    let (progress_tx, _progress_rx) = std::sync::mpsc::channel();
+    let entries = scanner::scan(path, method, ScanOptions::default(), progress_tx)?;
+    Ok(tree::build_tree(&entries))
+}
+
+/// Lay `tree` out over a `width` x `height` canvas and rasterize its cushions on the CPU,
+/// with default layout and color settings. Returns RGBA8 pixels, row-major.
+pub fn layout_and_rasterize(tree: &FileTree, width: u32, height: u32, config: &CushionConfig) -> Vec<u8> {
+    let layout = layout::compute_layout_in_rect(
+        tree,
+        tree.root,
+        0.0,
+        0.0,
+        width as f32,
+        height as f32,
+        &layout::LayoutConfig::default(),
+    );
+    render::cushion::rasterize_cushions(
+        width,
+        height,
+        &layout.rects,
+        tree,
+        config,
+        &ColorSettings::default(),
+        &HashMap::new(),
+    )
+}
@@ -14,21 +14,28 @@ use std::sync::Arc;
 use anyhow::Result;
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::{Key, NamedKey};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
 use winit::window::{CursorIcon, Window, WindowAttributes, WindowId};
 
 use app::App;
-use app::AppPhase;
 use render::RenderState;
 use ui::input;
 use ui::overlay::SidebarHitId;
 
+/// How often to check whether a requested screenshot has come back from the GPU.
+const SCREENSHOT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(16);
+
 /// Main application handler for winit's event loop.
 struct SilvaViewApp {
     app: App,
     render_state: Option<RenderState>,
+    // Declared before `window` so it is dropped while the display handle is still valid.
+    clipboard: Option<window_clipboard::Clipboard>,
     window: Option<Arc<Window>>,
+    modifiers: ModifiersState,
+    /// Reports back from worker threads that need the event loop, e.g. a saved screenshot.
+    proxy: Option<EventLoopProxy<AppEvent>>,
 }
 
 impl SilvaViewApp {
@@ -36,7 +43,10 @@ impl SilvaViewApp {
         Self {
             app: App::new(scan_path),
             render_state: None,
+            clipboard: None,
             window: None,
+            modifiers: ModifiersState::empty(),
+            proxy: None,
         }
     }
 
@@ -46,14 +56,230 @@ impl SilvaViewApp {
         };
         if let (Some(tree), Some(nav)) = (&self.app.tree, &self.app.navigation) {
             let path = ui::tooltip::build_path(tree, nav.current_root);
-            window.set_title(&format!("SilvaView-rs — {}", path));
+            match &self.app.split_view {
+                Some(split) => window.set_title(&format!(
+                    "SilvaView-rs — {} | {}",
+                    path,
+                    ui::tooltip::build_path(tree, split.current_root)
+                )),
+                None => window.set_title(&format!("SilvaView-rs — {}", path)),
+            }
         } else {
             window.set_title("SilvaView-rs — Disk Space Visualizer");
         }
     }
+
+    /// Typing into the open search prompt: Enter runs the query / steps to the next match,
+    /// Backspace deletes, Escape closes. Returns true if the key was consumed.
+    fn handle_search_key(&mut self, key: &Key) -> bool {
+        if self.app.search_query.is_none() {
+            return false;
+        }
+        match key.as_ref() {
+            Key::Named(NamedKey::Enter) => self.app.search_next(),
+            Key::Named(NamedKey::Escape) => self.app.close_search(),
+            Key::Named(NamedKey::Backspace) => self.app.edit_search(None),
+            Key::Named(NamedKey::Space) => self.app.edit_search(Some(" ")),
+            Key::Character(text) => self.app.edit_search(Some(text)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Typing into the open "go to path" prompt, like the search prompt; Ctrl+V pastes.
+    /// Returns true if the key was consumed.
+    fn handle_path_key(&mut self, key: &Key) -> bool {
+        if self.app.path_query.is_none() {
+            return false;
+        }
+        match key.as_ref() {
+            Key::Character(c) if self.modifiers.control_key() && c.eq_ignore_ascii_case("v") => {
+                match self.clipboard.as_ref().map(|clipboard| clipboard.read()) {
+                    Some(Ok(text)) => self.app.edit_path_prompt(Some(text.trim())),
+                    Some(Err(e)) => tracing::warn!("Failed to paste from clipboard: {}", e),
+                    None => {}
+                }
+            }
+            Key::Named(NamedKey::Enter) => self.app.submit_path_prompt(),
+            Key::Named(NamedKey::Escape) => self.app.close_path_prompt(),
+            Key::Named(NamedKey::Backspace) => self.app.edit_path_prompt(None),
+            Key::Named(NamedKey::Space) => self.app.edit_path_prompt(Some(" ")),
+            Key::Character(text) => self.app.edit_path_prompt(Some(text)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keyboard access to the sidebar: Tab / Shift-Tab move focus, Enter / Space activate
+    /// the focused control, Left / Right step its slider, Escape closes the pin menu or else
+    /// drops focus. Returns true if the key was consumed.
+    fn handle_sidebar_key(&mut self, key: &Key) -> bool {
+        if matches!(key.as_ref(), Key::Named(NamedKey::Escape)) && self.app.close_pin_menu() {
+            return true;
+        }
+        if matches!(key.as_ref(), Key::Named(NamedKey::Tab)) {
+            self.app.cycle_sidebar_focus(self.modifiers.shift_key());
+            return true;
+        }
+        let Some(focus) = self.app.sidebar_focus.clone() else {
+            return false;
+        };
+        match key.as_ref() {
+            Key::Named(NamedKey::Enter) | Key::Named(NamedKey::Space) => self.activate_sidebar(focus),
+            Key::Named(NamedKey::ArrowLeft) | Key::Named(NamedKey::ArrowRight) => {
+                let up = matches!(key.as_ref(), Key::Named(NamedKey::ArrowRight));
+                let step = match (focus, up) {
+                    (SidebarHitId::VibrancyDown | SidebarHitId::VibrancyTrack | SidebarHitId::VibrancyUp, false) => {
+                        SidebarHitId::VibrancyDown
+                    }
+                    (SidebarHitId::VibrancyDown | SidebarHitId::VibrancyTrack | SidebarHitId::VibrancyUp, true) => {
+                        SidebarHitId::VibrancyUp
+                    }
+                    (SidebarHitId::BrightnessDown | SidebarHitId::BrightnessTrack | SidebarHitId::BrightnessUp, false) => {
+                        SidebarHitId::BrightnessDown
+                    }
+                    (SidebarHitId::BrightnessDown | SidebarHitId::BrightnessTrack | SidebarHitId::BrightnessUp, true) => {
+                        SidebarHitId::BrightnessUp
+                    }
+                    (SidebarHitId::DepthDown | SidebarHitId::DepthTrack | SidebarHitId::DepthUp, false) => {
+                        SidebarHitId::DepthDown
+                    }
+                    (SidebarHitId::DepthDown | SidebarHitId::DepthTrack | SidebarHitId::DepthUp, true) => {
+                        SidebarHitId::DepthUp
+                    }
+                    _ => return false,
+                };
+                self.activate_sidebar(step);
+            }
+            Key::Named(NamedKey::Escape) => self.app.sidebar_focus = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Run a sidebar control, from a click or keyboard activation. The slider tracks are
+    /// driven by pointer position or arrow keys instead and do nothing here.
+    fn activate_sidebar(&mut self, hit: SidebarHitId) {
+        match hit {
+            SidebarHitId::SelectDrive(path) => {
+                self.app.start_scan_path(path);
+                self.update_window_title();
+            }
+            SidebarHitId::OpenFolder => {
+                if let Some(paths) = rfd::FileDialog::new().pick_folders() {
+                    self.app.start_scan_paths(paths);
+                    self.update_window_title();
+                }
+            }
+            SidebarHitId::CycleColorMode => {
+                use crate::render::colors::ColorMode;
+                self.app.color_settings.mode = match self.app.color_settings.mode {
+                    ColorMode::Category => ColorMode::CategoryExtension,
+                    ColorMode::CategoryExtension => ColorMode::ExtensionHash,
+                    ColorMode::ExtensionHash => ColorMode::ExtensionPalette,
+                    ColorMode::ExtensionPalette => ColorMode::Category,
+                };
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::VibrancyDown => {
+                self.app.color_settings.vibrancy =
+                    (self.app.color_settings.vibrancy - 0.08).clamp(0.6, 2.0);
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::VibrancyUp => {
+                self.app.color_settings.vibrancy =
+                    (self.app.color_settings.vibrancy + 0.08).clamp(0.6, 2.0);
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::BrightnessDown => {
+                self.app.cushion_config.min_brightness =
+                    (self.app.cushion_config.min_brightness - 0.05).clamp(0.0, ui::overlay::MIN_BRIGHTNESS_MAX);
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::BrightnessUp => {
+                self.app.cushion_config.min_brightness =
+                    (self.app.cushion_config.min_brightness + 0.05).clamp(0.0, ui::overlay::MIN_BRIGHTNESS_MAX);
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::DepthDown => {
+                self.app.depth_limit =
+                    ui::overlay::step_depth_limit(self.app.depth_limit, -1);
+                self.app.needs_relayout = true;
+            }
+            SidebarHitId::DepthUp => {
+                self.app.depth_limit =
+                    ui::overlay::step_depth_limit(self.app.depth_limit, 1);
+                self.app.needs_relayout = true;
+            }
+            SidebarHitId::ToggleHoverInfo => {
+                self.app.show_hover_info = !self.app.show_hover_info;
+            }
+            SidebarHitId::ToggleSizeUnits => {
+                self.app.size_units = self.app.size_units.toggled();
+            }
+            SidebarHitId::ToggleSizeBasis => self.app.toggle_size_basis(),
+            SidebarHitId::ToggleCategoryPatterns => {
+                self.app.show_category_patterns = !self.app.show_category_patterns;
+            }
+            SidebarHitId::ToggleDirectoryTint => {
+                self.app.color_settings.tint_directories =
+                    !self.app.color_settings.tint_directories;
+                self.app.recolor_treemap();
+            }
+            SidebarHitId::ToggleHiddenFiles => {
+                self.app.layout_config.show_hidden = !self.app.layout_config.show_hidden;
+                self.app.root_analytics = None;
+                self.app.needs_relayout = true;
+            }
+            SidebarHitId::ToggleFreezeLayout => self.app.toggle_freeze_layout(),
+            SidebarHitId::CopyTree => {
+                if let (Some(text), Some(clipboard)) =
+                    (self.app.current_view_text(), &mut self.clipboard)
+                {
+                    let lines = text.lines().count();
+                    match clipboard.write(text) {
+                        Ok(()) => tracing::info!("Copied {} tree lines to clipboard", lines),
+                        Err(e) => tracing::warn!("Failed to copy tree to clipboard: {}", e),
+                    }
+                }
+            }
+            SidebarHitId::RestoreExcluded(id) => self.app.restore_excluded(Some(id)),
+            SidebarHitId::PinColor(slot) => self.app.choose_pinned_color(slot),
+            SidebarHitId::ToggleScanPause => {
+                if self.app.toggle_scan_pause() {
+                    tracing::info!("Scan {}", if self.app.scan_paused() { "paused" } else { "resumed" });
+                }
+            }
+            SidebarHitId::NavigateUp => {
+                self.app.navigate_up();
+                self.update_window_title();
+            }
+            SidebarHitId::NavigateForward => {
+                self.app.navigate_forward();
+                self.update_window_title();
+            }
+            SidebarHitId::ToggleSidebar => {
+                self.app.sidebar_collapsed = !self.app.sidebar_collapsed;
+                self.app.needs_relayout = true;
+            }
+            SidebarHitId::VibrancyTrack | SidebarHitId::BrightnessTrack | SidebarHitId::DepthTrack => {}
+        }
+    }
+}
+
+/// Sent to the event loop from background threads.
+#[derive(Debug, Clone)]
+enum AppEvent {
+    /// A scan reported progress, finished or streamed in a preview tree.
+    ScanUpdate,
+    /// A background layout finished; `poll_layout` swaps it in on the next frame.
+    LayoutReady,
+    /// A screenshot was written to this path but couldn't go on the clipboard as an image;
+    /// its path goes there instead.
+    ScreenshotNotCopied(PathBuf),
 }
 
-impl ApplicationHandler for SilvaViewApp {
+impl ApplicationHandler<AppEvent> for SilvaViewApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.window.is_some() {
             return;
@@ -70,6 +296,12 @@ impl ApplicationHandler for SilvaViewApp {
         );
         self.window = Some(window.clone());
 
+        // SAFETY: the window is owned by `self` and outlives the clipboard (see field order).
+        match unsafe { window_clipboard::Clipboard::connect(window.as_ref()) } {
+            Ok(clipboard) => self.clipboard = Some(clipboard),
+            Err(e) => tracing::warn!("Clipboard unavailable: {}", e),
+        }
+
         // Initialize GPU rendering
         let render_state = pollster::block_on(RenderState::new(window.clone()));
         match render_state {
@@ -84,7 +316,7 @@ impl ApplicationHandler for SilvaViewApp {
                 );
                 self.app.viewport_width = size.width as f32;
                 self.app.viewport_height = size.height as f32;
-                self.app.cached_treemap_image = Some(state.treemap_image().clone());
+                self.app.scale_factor = scale as f32;
                 self.render_state = Some(state);
                 window.request_redraw();
             }
@@ -104,25 +336,38 @@ impl ApplicationHandler for SilvaViewApp {
             WindowEvent::Resized(size) => {
                 if let Some(render) = &mut self.render_state {
                     render.resize(size.width, size.height);
-                    self.app.cached_treemap_image = Some(render.treemap_image().clone());
                     self.app.resize(size.width, size.height);
                 }
             }
 
             WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 tracing::info!("Scale factor changed: {:.3}", scale_factor);
+                self.app.scale_factor = scale_factor as f32;
+                self.app.needs_relayout = true;
                 if let (Some(render), Some(window)) = (&mut self.render_state, &self.window) {
                     let size = window.inner_size();
                     render.resize(size.width, size.height);
-                    self.app.cached_treemap_image = Some(render.treemap_image().clone());
                     self.app.resize(size.width, size.height);
                     window.request_redraw();
                 }
             }
 
             WindowEvent::CursorMoved { position, .. } => {
+                let (prev_x, prev_y) = (self.app.mouse.x, self.app.mouse.y);
                 self.app.mouse.x = position.x as f32;
                 self.app.mouse.y = position.y as f32;
+                if self.app.mouse.panning {
+                    self.app.pan_view(self.app.mouse.x - prev_x, self.app.mouse.y - prev_y);
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                if self.app.inspector_dragging {
+                    self.app.set_inspector_width(self.app.viewport_width - self.app.mouse.x);
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
                 if self.app.vibrancy_dragging {
                     if let Some(track) = self
                         .app
@@ -133,65 +378,120 @@ impl ApplicationHandler for SilvaViewApp {
                     {
                         self.app.color_settings.vibrancy =
                             ui::overlay::vibrancy_value_from_track_x(self.app.mouse.x, track);
-                        self.app.needs_relayout = true;
+                        self.app.recolor_treemap();
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                    }
+                }
+                if self.app.brightness_dragging {
+                    if let Some(track) = self
+                        .app
+                        .sidebar_hit_regions
+                        .iter()
+                        .find(|r| matches!(r.id, SidebarHitId::BrightnessTrack))
+                        .map(|r| r.bounds)
+                    {
+                        self.app.cushion_config.min_brightness =
+                            ui::overlay::min_brightness_from_track_x(self.app.mouse.x, track);
+                        self.app.recolor_treemap();
                         if let Some(window) = &self.window {
                             window.request_redraw();
                         }
                     }
                 }
+                if self.app.depth_dragging {
+                    if let Some(track) = self
+                        .app
+                        .sidebar_hit_regions
+                        .iter()
+                        .find(|r| matches!(r.id, SidebarHitId::DepthTrack))
+                        .map(|r| r.bounds)
+                    {
+                        let limit = ui::overlay::depth_limit_from_track_x(self.app.mouse.x, track);
+                        if limit != self.app.depth_limit {
+                            self.app.depth_limit = limit;
+                            self.app.needs_relayout = true;
+                            if let Some(window) = &self.window {
+                                window.request_redraw();
+                            }
+                        }
+                    }
+                }
 
                 // Update hover state
-                let new_hover = if let Some(layout) = &self.app.layout {
-                    input::hit_test(
-                        &layout.rects,
-                        self.app.mouse.x,
-                        self.app.mouse.y,
-                    )
-                } else {
+                let over_inspector = self.app.over_inspector(self.app.mouse.x, self.app.mouse.y);
+                let new_hover = if over_inspector {
                     None
+                } else {
+                    self.app.hit_test_treemap(self.app.mouse.x, self.app.mouse.y)
                 };
-                if new_hover != self.app.hover_node {
-                    self.app.hover_node = new_hover;
+                if self.app.set_hover_node(new_hover) {
                     if let Some(window) = &self.window {
                         window.request_redraw();
                     }
                 }
             }
 
+            WindowEvent::MouseWheel { delta, .. } => {
+                let over_sidebar = self.app.hit_test_sidebar(self.app.mouse.x, self.app.mouse.y).is_some();
+                let over_inspector = self.app.over_inspector(self.app.mouse.x, self.app.mouse.y);
+                let [x1, y1, x2, y2] = self.app.sidebar_exclusion_rect();
+                let (x, y) = (self.app.mouse.x, self.app.mouse.y);
+                if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
+                    self.app.scroll_sidebar(input::scroll_pixels(delta));
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                } else if !over_sidebar && !over_inspector {
+                    let action = input::process_scroll(
+                        delta,
+                        &self.app.mouse,
+                        self.app.is_zoomed(),
+                        self.modifiers.control_key(),
+                    );
+                    self.handle_action(action);
+                }
+            }
+
+            // Only delivered on macOS/iOS; other platforms send pinches as ctrl+wheel above.
+            WindowEvent::PinchGesture { delta, .. } => {
+                let over_sidebar = self.app.hit_test_sidebar(self.app.mouse.x, self.app.mouse.y).is_some();
+                let over_inspector = self.app.over_inspector(self.app.mouse.x, self.app.mouse.y);
+                if !over_sidebar && !over_inspector {
+                    let action = input::process_pinch(delta, &self.app.mouse);
+                    self.handle_action(action);
+                }
+            }
+
             WindowEvent::MouseInput { state, button, .. } => {
+                if button == winit::event::MouseButton::Middle {
+                    self.app.mouse.panning = state == ElementState::Pressed;
+                    return;
+                }
                 if button == winit::event::MouseButton::Left {
                     self.app.mouse.left_pressed = state == ElementState::Pressed;
                     if state == ElementState::Released {
                         self.app.vibrancy_dragging = false;
+                        self.app.brightness_dragging = false;
+                        self.app.depth_dragging = false;
+                        self.app.inspector_dragging = false;
                     }
                 }
 
+                if state == ElementState::Pressed
+                    && button == winit::event::MouseButton::Left
+                    && self.app.over_inspector(self.app.mouse.x, self.app.mouse.y)
+                {
+                    // The panel itself is read-only; only its left edge reacts (drag to resize).
+                    self.app.inspector_dragging =
+                        self.app.hit_test_inspector_grip(self.app.mouse.x, self.app.mouse.y);
+                    return;
+                }
+
                 if state == ElementState::Pressed && button == winit::event::MouseButton::Left {
                     if let Some(hit) = self.app.hit_test_sidebar(self.app.mouse.x, self.app.mouse.y) {
                         match hit {
-                            SidebarHitId::SelectDrive(path) => {
-                                self.app.start_scan_path(path);
-                                self.update_window_title();
-                            }
-                            SidebarHitId::CycleColorMode => {
-                                use crate::render::colors::ColorMode;
-                                self.app.color_settings.mode = match self.app.color_settings.mode {
-                                    ColorMode::Category => ColorMode::CategoryExtension,
-                                    ColorMode::CategoryExtension => ColorMode::ExtensionHash,
-                                    ColorMode::ExtensionHash => ColorMode::Category,
-                                };
-                                self.app.needs_relayout = true;
-                            }
-                            SidebarHitId::VibrancyDown => {
-                                self.app.color_settings.vibrancy =
-                                    (self.app.color_settings.vibrancy - 0.08).clamp(0.6, 2.0);
-                                self.app.needs_relayout = true;
-                            }
-                            SidebarHitId::VibrancyUp => {
-                                self.app.color_settings.vibrancy =
-                                    (self.app.color_settings.vibrancy + 0.08).clamp(0.6, 2.0);
-                                self.app.needs_relayout = true;
-                            }
                             SidebarHitId::VibrancyTrack => {
                                 if let Some(track) = self
                                     .app
@@ -203,12 +503,38 @@ impl ApplicationHandler for SilvaViewApp {
                                     self.app.color_settings.vibrancy =
                                         ui::overlay::vibrancy_value_from_track_x(self.app.mouse.x, track);
                                     self.app.vibrancy_dragging = true;
-                                    self.app.needs_relayout = true;
+                                    self.app.recolor_treemap();
                                 }
                             }
-                            SidebarHitId::ToggleHoverInfo => {
-                                self.app.show_hover_info = !self.app.show_hover_info;
+                            SidebarHitId::BrightnessTrack => {
+                                if let Some(track) = self
+                                    .app
+                                    .sidebar_hit_regions
+                                    .iter()
+                                    .find(|r| matches!(r.id, SidebarHitId::BrightnessTrack))
+                                    .map(|r| r.bounds)
+                                {
+                                    self.app.cushion_config.min_brightness =
+                                        ui::overlay::min_brightness_from_track_x(self.app.mouse.x, track);
+                                    self.app.brightness_dragging = true;
+                                    self.app.recolor_treemap();
+                                }
                             }
+                            SidebarHitId::DepthTrack => {
+                                if let Some(track) = self
+                                    .app
+                                    .sidebar_hit_regions
+                                    .iter()
+                                    .find(|r| matches!(r.id, SidebarHitId::DepthTrack))
+                                    .map(|r| r.bounds)
+                                {
+                                    self.app.depth_limit =
+                                        ui::overlay::depth_limit_from_track_x(self.app.mouse.x, track);
+                                    self.app.depth_dragging = true;
+                                    self.app.needs_relayout = true;
+                                }
+                            }
+                            hit => self.activate_sidebar(hit),
                         }
                         if let Some(window) = &self.window {
                             window.request_redraw();
@@ -216,6 +542,14 @@ impl ApplicationHandler for SilvaViewApp {
                         return;
                     }
 
+                    // A click outside the pin menu only closes it.
+                    if self.app.close_pin_menu() {
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                        return;
+                    }
+
                     if let Some(node) = self.app.hit_test_label(self.app.mouse.x, self.app.mouse.y) {
                         self.app.drill_down(node);
                         self.update_window_title();
@@ -225,16 +559,24 @@ impl ApplicationHandler for SilvaViewApp {
                         return;
                     }
 
-                    // Fallback: allow clicking a directory rectangle to drill down.
-                    // Sidebar hit-testing already returned above, so this only applies to treemap tiles.
-                    if let (Some(layout), Some(tree)) = (&self.app.layout, &self.app.tree) {
-                        if let Some(node) = input::hit_test(&layout.rects, self.app.mouse.x, self.app.mouse.y) {
+                    // Fallback: clicking a directory rectangle drills down, a file selects it
+                    // (double-click opens it). Sidebar hit-testing already returned above, so
+                    // this only applies to treemap tiles.
+                    if let Some(tree) = &self.app.tree {
+                        if let Some(node) = self.app.hit_test_treemap(self.app.mouse.x, self.app.mouse.y) {
                             if tree.get(node).is_dir {
                                 self.app.drill_down(node);
                                 self.update_window_title();
                                 if let Some(window) = &self.window {
                                     window.request_redraw();
                                 }
+                            } else {
+                                if !self.app.open_file_group(node) {
+                                    self.app.click_file(node);
+                                }
+                                if let Some(window) = &self.window {
+                                    window.request_redraw();
+                                }
                             }
                         }
                     }
@@ -245,6 +587,7 @@ impl ApplicationHandler for SilvaViewApp {
                     input::process_mouse_button(
                         button,
                         state,
+                        self.modifiers,
                         &self.app.mouse,
                         &layout.rects,
                     )
@@ -254,6 +597,31 @@ impl ApplicationHandler for SilvaViewApp {
                 self.handle_action(action);
             }
 
+            WindowEvent::HoveredFile(path) => {
+                self.app.drop_target = Some(path);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+
+            WindowEvent::HoveredFileCancelled => {
+                self.app.drop_target = None;
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+
+            WindowEvent::DroppedFile(path) => {
+                self.app.scan_dropped_path(&path);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+
             WindowEvent::KeyboardInput { event, .. } => {
                 if event.state == ElementState::Pressed {
                     if matches!(event.logical_key.as_ref(), Key::Named(NamedKey::F2)) {
@@ -263,16 +631,54 @@ impl ApplicationHandler for SilvaViewApp {
                                 scan_path: self.app.scan_path.clone(),
                                 layout: self.app.layout_config.clone(),
                                 cushion: self.app.cushion_config,
+                                color: self.app.color_settings.clone(),
                                 show_labels: self.app.show_text_labels,
+                                label_top_level: self.app.label_top_level,
+                                show_file_labels: self.app.show_file_labels,
+                                show_dir_watermarks: self.app.show_dir_watermarks,
+                                focus_hovered_folder: self.app.focus_hovered_folder,
+                                lshape_layout: self.app.lshape_layout,
+                                highlight_recent_days: self.app.highlight_recent_days,
                                 label_font_scale: self.app.label_font_scale,
                                 label_font_path: self.app.label_font_path.clone(),
+                                include_system_metadata: self.app.include_system_metadata,
+                                scan_depth_limit: self.app.scan_depth_limit,
+                                auto_refresh_interval: self.app.auto_refresh_interval,
+                                hover_delay_ms: self.app.hover_delay.as_millis() as f32,
+                                max_animation_fps: self.app.max_animation_fps as f32,
+                                poll_scan_progress: self.app.poll_scan_progress,
+                                background_layout: self.app.background_layout,
+                                background_style: self.app.background_style,
+                                background_image_path: self.app.background_image_path.clone(),
+                                percent_basis: self.app.percent_basis,
                             },
                             false,
                         );
                         if let Some(settings) = settings {
-                            self.app.layout_config = settings.layout;
+                            self.app.hover_delay =
+                                std::time::Duration::from_millis(settings.hover_delay_ms as u64);
+                            self.app.max_animation_fps = settings.max_animation_fps.round() as u32;
+                            self.app.poll_scan_progress = settings.poll_scan_progress;
+                            self.app.background_layout = settings.background_layout;
+                            self.app.set_background(settings.background_style, settings.background_image_path.clone());
+                            // Take only the user-tunable fields; internal ones (e.g. `dpi_scale`)
+                            // stay as the app set them. The dialog has no hidden-files control,
+                            // so the sidebar's choice is kept.
+                            let chosen = layout::LayoutSettings {
+                                show_hidden: self.app.layout_config.show_hidden,
+                                ..settings.layout.settings()
+                            };
+                            self.app.layout_config.apply_settings(&chosen);
                             self.app.cushion_config = settings.cushion;
+                            self.app.color_settings = settings.color;
                             self.app.show_text_labels = settings.show_labels;
+                            self.app.label_top_level = settings.label_top_level;
+                            self.app.show_file_labels = settings.show_file_labels;
+                            self.app.show_dir_watermarks = settings.show_dir_watermarks;
+                            self.app.focus_hovered_folder = settings.focus_hovered_folder;
+                            self.app.lshape_layout = settings.lshape_layout;
+                            self.app.highlight_recent_days = settings.highlight_recent_days;
+                            self.app.percent_basis = settings.percent_basis;
                             self.app.label_font_scale = settings.label_font_scale;
                             self.app.label_font_path = settings.label_font_path.clone();
                             if !settings.label_font_path.trim().is_empty() {
@@ -287,7 +693,17 @@ impl ApplicationHandler for SilvaViewApp {
                                     );
                                 }
                             }
+                            self.app.auto_refresh_interval = settings.auto_refresh_interval;
                             self.app.needs_relayout = true;
+                            if settings.include_system_metadata != self.app.include_system_metadata
+                                || settings.scan_depth_limit != self.app.scan_depth_limit
+                            {
+                                // Metafiles and the depth limit only apply during the scan itself.
+                                self.app.include_system_metadata = settings.include_system_metadata;
+                                self.app.scan_depth_limit = settings.scan_depth_limit;
+                                self.app.start_scan_paths(self.app.scan_paths());
+                                self.update_window_title();
+                            }
                             if let Some(window) = &self.window {
                                 window.request_redraw();
                             }
@@ -295,7 +711,22 @@ impl ApplicationHandler for SilvaViewApp {
                         return;
                     }
 
-                    let action = input::process_key(event.logical_key.clone(), event.state);
+                    if self.handle_search_key(&event.logical_key) || self.handle_path_key(&event.logical_key) {
+                        self.update_window_title();
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                        return;
+                    }
+
+                    if self.handle_sidebar_key(&event.logical_key) {
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                        return;
+                    }
+
+                    let action = input::process_key(event.logical_key.clone(), event.state, self.modifiers);
                     self.handle_action(action);
                 }
             }
@@ -309,31 +740,73 @@ impl ApplicationHandler for SilvaViewApp {
                     }
                 }
 
+                if self.app.refresh_deadline().is_some_and(|due| due <= std::time::Instant::now()) {
+                    self.app.start_refresh();
+                }
+
                 // Poll for scan completion
-                if self.app.phase == app::AppPhase::Scanning {
+                if self.app.phase == app::AppPhase::Scanning || self.app.is_refreshing() {
                     if self.app.poll_scan() {
                         self.update_window_title();
                     }
                 }
+                if self.app.poll_duplicates() {
+                    self.update_window_title();
+                }
+                self.app.poll_path_check();
+                if self.app.poll_dropped_path() {
+                    self.update_window_title();
+                }
+                if self.app.poll_expansion() {
+                    self.update_window_title();
+                }
 
-                // Recompute layout if needed
-                if self.app.needs_relayout && self.app.phase == AppPhase::Ready {
-                    self.app.relayout();
+                // Recompute layout if needed, holding off while a resize drag is in progress
+                // (also during scanning, once a partial tree has streamed in). The end of the
+                // debounce is scheduled as a redraw deadline in `about_to_wait`.
+                let relayout_due = self.app.needs_relayout && self.app.tree.is_some();
+                let relayout_now = relayout_due && self.app.resize_settled();
+                // A pending relayout repaints everything, so partial redraws wait for it.
+                let dirty = if relayout_due { None } else { self.app.cushion_dirty.take() };
+                // A background layout that arrived counts as a relayout; one that was
+                // superseded is dropped, and the next starts here.
+                let mut relaid_out = self.app.poll_layout();
+                if relayout_now {
+                    if self.app.background_layout {
+                        self.app.start_relayout();
+                    } else {
+                        self.app.relayout();
+                        relaid_out = true;
+                    }
+                }
+                if relaid_out {
+                    self.app.cushion_dirty = None;
+                }
+                if relaid_out || dirty.is_some() {
                     if let (Some(render), Some(layout), Some(tree)) =
                         (&mut self.render_state, &self.app.layout, &self.app.tree)
                     {
+                        let (layout_w, layout_h) = self.app.layout_viewport;
+                        let pinned_colors = self.app.pinned_colors();
                         render.update_cushion_treemap(
+                            (layout_w as u32, layout_h as u32),
                             &layout.rects,
                             tree,
                             &self.app.cushion_config,
-                            &self.app.color_settings,
-                            self.app.sidebar_exclusion_rect(),
+                            &render::cushion_gpu::CushionFrame {
+                                color_settings: &self.app.color_settings,
+                                pinned_colors: &pinned_colors,
+                                exclusion_rect: self.app.layout_exclusion_rect(),
+                                dirty,
+                            },
                         );
                         self.app.cached_treemap_image = Some(render.treemap_image().clone());
+                        self.app.cushion_instances = render.cushion_instances();
                         tracing::info!(
-                            "Cushion treemap rasterized (WGSL): {}x{}",
-                            render.surface_config.width,
-                            render.surface_config.height
+                            "Cushion treemap rasterized (WGSL): {}x{}{}",
+                            layout_w,
+                            layout_h,
+                            if dirty.is_some() { " (partial)" } else { "" }
                         );
                     }
                 }
@@ -346,21 +819,148 @@ impl ApplicationHandler for SilvaViewApp {
                         tracing::error!("Render error: {}", e);
                     }
                 }
+                self.app.last_frame = Some(std::time::Instant::now());
+            }
 
-                // Request continuous redraws during scanning for progress updates
-                if self.app.phase == app::AppPhase::Scanning {
-                    if let Some(window) = &self.window {
-                        window.request_redraw();
+            _ => {}
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: AppEvent) {
+        match event {
+            // Picked up by `poll_scan` on the next frame, which `about_to_wait` paces.
+            AppEvent::ScanUpdate => self.app.scan_update_pending = true,
+            AppEvent::LayoutReady => {
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            AppEvent::ScreenshotNotCopied(path) => {
+                if let Some(clipboard) = &mut self.clipboard {
+                    if let Err(e) = clipboard.write(path.to_string_lossy().into_owned()) {
+                        tracing::warn!("Failed to copy screenshot path to clipboard: {}", e);
                     }
                 }
             }
+        }
+    }
 
-            _ => {}
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let capture = self.render_state.as_mut().and_then(|render| render.poll_capture());
+        if let Some(capture) = capture {
+            self.deliver_screenshot(capture);
+        }
+        let capture_pending = self.render_state.as_ref().is_some_and(|render| render.capture_pending());
+
+        // Timed redraws (scan progress, resize debounce, hover delay) all wake up through
+        // here instead of requesting a redraw every frame.
+        let now = std::time::Instant::now();
+        match self.app.next_redraw_deadline() {
+            Some(deadline) if deadline <= now => {
+                if self.app.hover_info_deadline().is_some_and(|due| due <= now) {
+                    self.app.hover_info_shown = true;
+                }
+                event_loop.set_control_flow(ControlFlow::Wait);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            Some(deadline) => event_loop.set_control_flow(ControlFlow::WaitUntil(deadline)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
+        // Check back for a frame readback the GPU hasn't finished.
+        if capture_pending {
+            let poll_at = now + SCREENSHOT_POLL_INTERVAL;
+            if !matches!(event_loop.control_flow(), ControlFlow::WaitUntil(deadline) if deadline <= poll_at) {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(poll_at));
+            }
         }
     }
 }
 
+/// Put the PNG at `path` on the clipboard as an image, through the platform's own clipboard
+/// tool, and wait for it to take the image.
+fn copy_png_to_clipboard(path: &Path) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        let mut command = std::process::Command::new("powershell");
+        command
+            .args(["-NoProfile", "-STA", "-Command"])
+            .arg(
+                "Add-Type -AssemblyName System.Windows.Forms, System.Drawing; \
+                 [System.Windows.Forms.Clipboard]::SetImage([System.Drawing.Image]::FromFile($env:SILVAVIEW_SCREENSHOT))",
+            )
+            .env("SILVAVIEW_SCREENSHOT", path)
+            .creation_flags(0x08000000); // CREATE_NO_WINDOW
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("osascript");
+        command
+            .args(["-e", "on run argv"])
+            .args(["-e", "set the clipboard to (read (POSIX file (item 1 of argv)) as «class PNGf»)"])
+            .args(["-e", "end run"])
+            .arg(path);
+        command
+    };
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = std::process::Command::new("wl-copy");
+        command.args(["--type", "image/png"]).stdin(std::fs::File::open(path)?);
+        command
+    } else {
+        let mut command = std::process::Command::new("xclip");
+        command.args(["-selection", "clipboard", "-t", "image/png", "-i"]).arg(path);
+        command
+    };
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("{:?} exited with {}", command.get_program(), status)))
+    }
+}
+
 impl SilvaViewApp {
+    /// Hand a captured frame to the clipboard as an image. The frame is saved to a PNG in the
+    /// temp dir, which the platform's clipboard tool then loads; if that fails, the PNG's path
+    /// is copied instead.
+    fn deliver_screenshot(&mut self, capture: anyhow::Result<render::FrameCapture>) {
+        let capture = match capture {
+            Ok(capture) => capture,
+            Err(e) => {
+                tracing::warn!("Failed to capture the window: {:#}", e);
+                return;
+            }
+        };
+        // Milliseconds keep two screenshots taken within a second apart.
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("silvaview-{}.png", stamp));
+        let proxy = self.proxy.clone();
+        // Encoding a full-window PNG takes long enough to drop frames, so it runs off-thread,
+        // and so does the clipboard tool.
+        std::thread::spawn(move || {
+            if let Err(e) = capture.save_png(&path) {
+                tracing::warn!("Failed to save screenshot to {:?}: {:#}", path, e);
+                return;
+            }
+            match copy_png_to_clipboard(&path) {
+                Ok(()) => tracing::info!("Copied {}x{} screenshot to clipboard", capture.width, capture.height),
+                Err(e) => {
+                    tracing::warn!("Failed to copy screenshot image, copying {:?} instead: {}", path, e);
+                    if let Some(proxy) = proxy {
+                        let _ = proxy.send_event(AppEvent::ScreenshotNotCopied(path));
+                    }
+                }
+            }
+        });
+    }
+
     fn handle_action(&mut self, action: input::InputAction) {
         match action {
             input::InputAction::DrillDown { node } => {
@@ -377,6 +977,154 @@ impl SilvaViewApp {
                     window.request_redraw();
                 }
             }
+            input::InputAction::RevealLargest => {
+                self.app.reveal_largest();
+                self.update_window_title();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::OpenPathPrompt => {
+                self.app.close_search();
+                self.app.open_path_prompt();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::OpenSearch => {
+                self.app.close_path_prompt();
+                self.app.open_search();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::FindDuplicates => {
+                self.app.find_duplicates();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::CycleDuplicates(delta) => {
+                self.app.cycle_duplicate_group(delta);
+                self.update_window_title();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::OpenPinMenu { x, y } => {
+                self.app.open_pin_menu(x, y);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::CyclePinnedColor => {
+                self.app.cycle_pinned_color();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleSplitView => {
+                self.app.toggle_split_view();
+                self.update_window_title();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleLegend => {
+                self.app.show_legend = !self.app.show_legend;
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::OpenFolders => {
+                self.activate_sidebar(SidebarHitId::OpenFolder);
+            }
+            input::InputAction::AddFolders => {
+                // The picker multi-selects within one folder; run it again for another drive.
+                if let Some(paths) = rfd::FileDialog::new().set_title("Add folders to this scan").pick_folders() {
+                    self.app.add_scan_paths(paths);
+                    self.update_window_title();
+                }
+            }
+            input::InputAction::CopyScreenshot => {
+                if let (Some(render), Some(window)) = (&mut self.render_state, &self.window) {
+                    render.request_capture();
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::CycleColorMode => {
+                self.activate_sidebar(SidebarHitId::CycleColorMode);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::AdjustVibrancy(step) => {
+                self.activate_sidebar(if step < 0 {
+                    SidebarHitId::VibrancyDown
+                } else {
+                    SidebarHitId::VibrancyUp
+                });
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ExcludeHovered => {
+                self.app.exclude_hovered();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::RestoreAllExcluded => {
+                self.app.restore_excluded(None);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleStats => {
+                self.app.show_stats = !self.app.show_stats;
+                if self.app.show_stats {
+                    tracing::info!("Stats: {}", self.app.stats_lines().join(", "));
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleScanPause => {
+                self.activate_sidebar(SidebarHitId::ToggleScanPause);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleFileGrouping => {
+                self.app.toggle_file_grouping();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleFlatLayout => {
+                self.app.toggle_flat_layout();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::ToggleInspector => {
+                self.app.toggle_inspector();
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::Zoom { delta, x, y } => {
+                self.app.zoom_view(delta, x, y);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            input::InputAction::Pan { dx, dy } => {
+                self.app.pan_view(dx, dy);
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
             input::InputAction::Resize { width, height } => {
                 self.app.resize(width, height);
                 if let Some(window) = &self.window {
@@ -388,6 +1136,18 @@ impl SilvaViewApp {
     }
 }
 
+/// On Windows the app is built for the GUI subsystem and starts without a console, so
+/// whatever a headless run prints would be lost. Borrow the console of the shell it was
+/// launched from, if any.
+fn attach_parent_console() {
+    #[cfg(windows)]
+    // SAFETY: plain Win32 call; failing just means there is no parent console.
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 fn main() -> Result<()> {
     // Initialize logging
     tracing_subscriber::fmt()
@@ -398,11 +1158,108 @@ fn main() -> Result<()> {
         )
         .init();
 
-    // Parse command line: optional path argument, defaults to C:\
-    let scan_path = std::env::args()
-        .nth(1)
-        .map(PathBuf::from)
-        .unwrap_or_else(|| {
+    // Parse command line: optional path arguments (defaults to C:\; several are scanned
+    // and merged under one root), plus
+    // `--export-tree <file> [--export-depth <n>]` for a headless du-style dump,
+    // `--export-analytics-csv <file> [--csv-extensions]` for headless category totals,
+    // `--export-files-csv <file> [--min-size <bytes>]` for a headless list of large files,
+    // `--export-svg <file>` for a flat vector image of the treemap,
+    // `--diagnostics <file>` to write a JSON report of scan internals for bug reports
+    // (an array of them, one per folder, for a merged scan),
+    // `--scan-depth <n>` to scan only n levels deep and load deeper folders on drill-down,
+    // `--render-size <w>x<h>` to lay out and render at a fixed size scaled to the window, and
+    // `--dry-run` to only scan and print how much was found and how long it took.
+    let mut scan_args: Vec<PathBuf> = Vec::new();
+    let mut export_tree: Option<PathBuf> = None;
+    let mut export_depth: Option<u16> = None;
+    let mut export_csv: Option<PathBuf> = None;
+    let mut csv_extensions = false;
+    let mut export_files: Option<PathBuf> = None;
+    let mut min_file_size = 0u64;
+    let mut export_svg: Option<PathBuf> = None;
+    let mut diagnostics: Option<PathBuf> = None;
+    let mut scan_depth: Option<usize> = None;
+    let mut refresh_minutes: Option<u64> = None;
+    let mut render_size: Option<(u32, u32)> = None;
+    let mut dry_run = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export-tree" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--export-tree requires an output path"))?;
+                export_tree = Some(PathBuf::from(out));
+            }
+            "--export-analytics-csv" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--export-analytics-csv requires an output path"))?;
+                export_csv = Some(PathBuf::from(out));
+            }
+            "--csv-extensions" => csv_extensions = true,
+            "--export-files-csv" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--export-files-csv requires an output path"))?;
+                export_files = Some(PathBuf::from(out));
+            }
+            "--min-size" => {
+                let bytes = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--min-size requires a number of bytes"))?;
+                min_file_size = bytes.parse()?;
+            }
+            "--export-svg" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--export-svg requires an output path"))?;
+                export_svg = Some(PathBuf::from(out));
+            }
+            "--diagnostics" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--diagnostics requires an output path"))?;
+                diagnostics = Some(PathBuf::from(out));
+            }
+            "--export-depth" => {
+                let depth = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--export-depth requires a number"))?;
+                export_depth = Some(depth.parse()?);
+            }
+            "--scan-depth" => {
+                let depth = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--scan-depth requires a number"))?;
+                scan_depth = Some(depth.parse()?).filter(|&d| d > 0);
+            }
+            "--refresh-minutes" => {
+                let minutes = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--refresh-minutes requires a number"))?;
+                refresh_minutes = Some(minutes.parse()?).filter(|&m| m > 0);
+            }
+            "--render-size" => {
+                let size = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--render-size requires a size like 2560x1440"))?;
+                let (w, h) = size
+                    .split_once(['x', 'X'])
+                    .ok_or_else(|| anyhow::anyhow!("--render-size expects <width>x<height>, got {}", size))?;
+                render_size = Some((w.parse()?, h.parse()?)).filter(|&(w, h)| w > 0 && h > 0);
+            }
+            "--dry-run" => dry_run = true,
+            _ => scan_args.push(PathBuf::from(arg)),
+        }
+    }
+
+    let merged = scan_args.len() > 1;
+    if merged && (dry_run || export_tree.is_some() || export_csv.is_some() || export_files.is_some() || export_svg.is_some()) {
+        anyhow::bail!("--dry-run and the exports take a single path");
+    }
+    let mut scan_args = scan_args.into_iter();
+    let scan_path = scan_args.next().unwrap_or_else(|| {
             #[cfg(windows)]
             {
                 PathBuf::from("C:\\")
@@ -426,13 +1283,127 @@ fn main() -> Result<()> {
         }
     }
 
+    let headless =
+        export_tree.is_some() || export_csv.is_some() || export_files.is_some() || export_svg.is_some();
+    if dry_run || headless {
+        attach_parent_console();
+    }
+
+    if dry_run {
+        let estimate = scanner::estimate(&scan_path, scanner::ScanMethod::Auto)?;
+        println!(
+            "{}: {} entries ({} files, {} folders), {} in {:.2}s via {}",
+            scan_path.display(),
+            estimate.entries,
+            estimate.files,
+            estimate.dirs,
+            ui::tooltip::format_size(estimate.total_bytes, ui::tooltip::SizeUnits::default()),
+            estimate.elapsed.as_secs_f64(),
+            estimate.method
+        );
+        return Ok(());
+    }
+
+    if headless {
+        return export_headless(
+            &scan_path,
+            HeadlessExports {
+                tree: export_tree.as_deref(),
+                tree_depth: export_depth,
+                analytics_csv: export_csv.as_deref(),
+                csv_extensions,
+                files_csv: export_files.as_deref(),
+                min_file_size,
+                svg: export_svg.as_deref(),
+                diagnostics: diagnostics.as_deref(),
+            },
+        );
+    }
+
     tracing::info!("SilvaView-rs starting, scan path: {:?}", scan_path);
 
-    let event_loop = EventLoop::new()?;
+    let event_loop = EventLoop::<AppEvent>::with_user_event().build()?;
     event_loop.set_control_flow(ControlFlow::Wait);
+    let proxy = event_loop.create_proxy();
 
     let mut app = SilvaViewApp::new(scan_path);
+    app.app.diagnostics_path = diagnostics;
+    app.app.scan_depth_limit = scan_depth;
+    app.app.fixed_resolution = render_size;
+    app.app.auto_refresh_interval = refresh_minutes.map(|m| std::time::Duration::from_secs(m * 60));
+    app.proxy = Some(proxy.clone());
+    let layout_proxy = proxy.clone();
+    app.app.scan_waker = Some(Arc::new(move || {
+        let _ = proxy.send_event(AppEvent::ScanUpdate);
+    }));
+    app.app.layout_waker = Some(Arc::new(move || {
+        let _ = layout_proxy.send_event(AppEvent::LayoutReady);
+    }));
+    if merged {
+        let paths = std::iter::once(app.app.scan_path.clone()).chain(scan_args).collect();
+        app.app.start_scan_paths(paths);
+    }
     event_loop.run_app(&mut app)?;
 
     Ok(())
 }
+
+/// Output files requested for a headless run.
+struct HeadlessExports<'a> {
+    tree: Option<&'a Path>,
+    tree_depth: Option<u16>,
+    analytics_csv: Option<&'a Path>,
+    csv_extensions: bool,
+    files_csv: Option<&'a Path>,
+    min_file_size: u64,
+    svg: Option<&'a Path>,
+    diagnostics: Option<&'a Path>,
+}
+
+/// Scan without opening a window and write the requested exports: a du-style text tree
+/// analytics CSV, a CSV of files over a size threshold and/or a flat SVG treemap.
+fn export_headless(scan_path: &Path, exports: HeadlessExports) -> Result<()> {
+    let diagnostics_out = exports.diagnostics;
+    let (tx, _rx) = std::sync::mpsc::channel();
+    let options = scanner::types::ScanOptions {
+        diagnostics: diagnostics_out.map(|_| Default::default()),
+        ..Default::default()
+    };
+    let diagnostics = options.diagnostics.clone();
+    let result = scanner::scan(scan_path, scanner::ScanMethod::Auto, options, tx);
+    if let (Some(path), Some(diagnostics)) = (diagnostics_out, diagnostics) {
+        diagnostics.lock().unwrap().write_json(path)?;
+    }
+    let entries = result?;
+    let tree = tree::build_tree(&entries);
+    if let Some(out) = exports.tree {
+        let text = tree::export::to_text(
+            &tree,
+            tree.root,
+            Some(exports.tree_depth.unwrap_or(tree::export::DEFAULT_EXPORT_DEPTH)),
+            ui::tooltip::SizeUnits::default(),
+        );
+        std::fs::write(out, text)?;
+        tracing::info!("Exported tree of {:?} to {:?}", scan_path, out);
+    }
+    if let Some(out) = exports.analytics_csv {
+        let analytics = tree::analytics::compute_analytics(&tree, tree.root, true, &Default::default());
+        let csv = tree::export::analytics_csv(&tree, tree.root, &analytics, exports.csv_extensions);
+        std::fs::write(out, csv)?;
+        tracing::info!("Exported analytics of {:?} to {:?}", scan_path, out);
+    }
+    if let Some(out) = exports.files_csv {
+        let csv = tree::export::files_csv(&tree, tree.root, exports.min_file_size);
+        std::fs::write(out, csv)?;
+        tracing::info!("Exported files of {:?} over {} bytes to {:?}", scan_path, exports.min_file_size, out);
+    }
+    if let Some(out) = exports.svg {
+        let (w, h) = render::svg::DEFAULT_SVG_SIZE;
+        let layout =
+            layout::compute_layout_in_rect(&tree, tree.root, 0.0, 0.0, w, h, &layout::LayoutConfig::default());
+        let svg = render::svg::layout_to_svg(&layout, &tree, w, h, &render::colors::ColorSettings::default());
+        std::fs::write(out, svg)?;
+        tracing::info!("Exported treemap of {:?} to {:?}", scan_path, out);
+    }
+    Ok(())
+}
@@ -1,18 +1,30 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::tree::arena::{FileTree, NodeId};
 use crate::tree::extensions::FileCategory;
+use serde::{Deserialize, Serialize};
 use vello::peniko::color::{DynamicColor, Srgb};
 use vello::peniko::Color;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ColorMode {
     Category,
     CategoryExtension,
     ExtensionHash,
+    /// Every extension gets its own palette slot, spaced evenly around the hue circle.
+    ExtensionPalette,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorSettings {
     pub mode: ColorMode,
     pub vibrancy: f32,
+    /// Tint directories toward the category that dominates their bytes instead of a name hash.
+    pub tint_directories: bool,
+    /// Colors pinned to folders, by full path so they carry over to rescans.
+    pub pinned: BTreeMap<PathBuf, AppColor>,
 }
 
 impl Default for ColorSettings {
@@ -20,12 +32,45 @@ impl Default for ColorSettings {
         Self {
             mode: ColorMode::CategoryExtension,
             vibrancy: 1.20,
+            tint_directories: false,
+            pinned: BTreeMap::new(),
+        }
+    }
+}
+
+impl ColorSettings {
+    /// Pin `color` to `folder`, or unpin it with `None`.
+    pub fn pin(&mut self, folder: PathBuf, color: Option<AppColor>) {
+        match color {
+            Some(color) => self.pinned.insert(folder, color),
+            None => self.pinned.remove(&folder),
+        };
+    }
+
+    /// The color after `folder`'s current pin in `PIN_COLORS`: the first for an unpinned
+    /// folder, `None` past the last.
+    pub fn next_pin(&self, folder: &Path) -> Option<AppColor> {
+        match self.pinned.get(folder) {
+            None => Some(PIN_COLORS[0]),
+            Some(current) => PIN_COLORS
+                .iter()
+                .position(|c| c == current)
+                .and_then(|i| PIN_COLORS.get(i + 1))
+                .copied(),
         }
     }
+
+    /// Pinned colors resolved to the nodes of `tree`; pins on folders it lacks are skipped.
+    pub fn pinned_nodes(&self, tree: &FileTree, scan_path: &Path) -> HashMap<NodeId, AppColor> {
+        self.pinned
+            .iter()
+            .filter_map(|(path, &color)| Some((crate::tree::find_by_path(tree, path, scan_path)?, color)))
+            .collect()
+    }
 }
 
 /// Our custom color representation for easy manipulation.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct AppColor {
     pub r: f32,
     pub g: f32,
@@ -69,6 +114,16 @@ impl AppColor {
     }
 }
 
+/// Colors a folder can be pinned to, in the order the pin key cycles through them.
+pub const PIN_COLORS: [AppColor; 6] = [
+    AppColor::new(0.20, 0.45, 0.95),
+    AppColor::new(0.20, 0.75, 0.35),
+    AppColor::new(0.95, 0.60, 0.15),
+    AppColor::new(0.90, 0.25, 0.25),
+    AppColor::new(0.65, 0.35, 0.90),
+    AppColor::new(0.95, 0.85, 0.20),
+];
+
 /// Dark mode color palette for file categories.
 /// Vibrant colors on dark background for modern aesthetic.
 pub fn category_color(category: FileCategory) -> AppColor {
@@ -88,12 +143,29 @@ pub fn category_color(category: FileCategory) -> AppColor {
         FileCategory::Backup => hsv_to_rgb(25.0 / 360.0, 0.40, 0.70),
         FileCategory::Database => hsv_to_rgb(245.0 / 360.0, 0.45, 0.82),
         FileCategory::DiskImage => hsv_to_rgb(205.0 / 360.0, 0.64, 0.82),
+        FileCategory::SystemFile => AppColor::new(0.30, 0.32, 0.36),
         FileCategory::Other => AppColor::new(0.50, 0.50, 0.55),
     }
 }
 
-/// Get color for a node based on its extension.
-pub fn extension_color(ext: &str, settings: &ColorSettings) -> AppColor {
+/// A category's color at the current vibrancy, before any per-extension shift: what a
+/// legend swatch should show.
+pub fn category_swatch(category: FileCategory, settings: &ColorSettings) -> AppColor {
+    if category == FileCategory::SystemFile {
+        return system_file_color(settings);
+    }
+    apply_vibrancy(category_color(category), settings.vibrancy)
+}
+
+/// Flat slate for unreclaimable system files, independent of color mode so they never
+/// blend in with user data of the same extension.
+pub fn system_file_color(settings: &ColorSettings) -> AppColor {
+    apply_vibrancy(category_color(FileCategory::SystemFile), settings.vibrancy * 0.85)
+}
+
+/// Get color for a node based on its extension; `slot`, its place in the tree's
+/// `palette_slots`, picks its color under `ExtensionPalette`.
+pub fn extension_color(ext: &str, slot: u16, settings: &ColorSettings) -> AppColor {
     let category = crate::tree::extensions::categorize_extension(ext);
     let base = category_color(category);
     let ext_norm = ext.trim_start_matches('.').to_ascii_lowercase();
@@ -107,10 +179,35 @@ pub fn extension_color(ext: &str, settings: &ColorSettings) -> AppColor {
             let h = hash01(&ext_norm);
             hsv_to_rgb(h, 0.72, 0.84)
         }
+        ColorMode::ExtensionPalette => palette_color(slot),
     };
     apply_vibrancy(adjusted, settings.vibrancy)
 }
 
+/// Base (unshaded) color of a laid-out node at `depth`: tinted or hashed for directories,
+/// slate for system files, by extension otherwise. Pinned colors are the caller's concern.
+pub fn node_color(tree: &FileTree, id: NodeId, depth: u16, settings: &ColorSettings) -> AppColor {
+    let node = tree.get(id);
+    if node.is_dir {
+        match node.dominant_category {
+            Some(dominant) if settings.tint_directories => {
+                dominant_directory_color(&node.name, depth, dominant, settings)
+            }
+            _ => directory_color(&node.name, depth, settings),
+        }
+    } else if !node.reclaimable {
+        system_file_color(settings)
+    } else {
+        let ext = tree
+            .extensions
+            .get(node.extension_id as usize)
+            .map(|s| s.as_str())
+            .unwrap_or("");
+        let slot = tree.palette_slots.get(node.extension_id as usize).copied();
+        extension_color(ext, slot.unwrap_or(node.extension_id), settings)
+    }
+}
+
 /// Directory colors are intentionally muted but varied by name hash.
 /// This keeps hierarchy readable without making directories all identical gray.
 pub fn directory_color(name: &str, depth: u16, settings: &ColorSettings) -> AppColor {
@@ -129,11 +226,45 @@ pub fn directory_color(name: &str, depth: u16, settings: &ColorSettings) -> AppC
     )
 }
 
+/// Directory color pulled toward the category that dominates its contents,
+/// so "mostly video" reads at the container level.
+pub fn dominant_directory_color(
+    name: &str,
+    depth: u16,
+    dominant: FileCategory,
+    settings: &ColorSettings,
+) -> AppColor {
+    let base = directory_color(name, depth, settings);
+    let tint = apply_vibrancy(category_color(dominant), settings.vibrancy).darken(0.12);
+    let t = 0.65;
+    AppColor::new(
+        base.r + (tint.r - base.r) * t,
+        base.g + (tint.g - base.g) * t,
+        base.b + (tint.b - base.b) * t,
+    )
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 4] = [
+        Self::Category,
+        Self::CategoryExtension,
+        Self::ExtensionHash,
+        Self::ExtensionPalette,
+    ];
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(mode_name(*self))
+    }
+}
+
 pub fn mode_name(mode: ColorMode) -> &'static str {
     match mode {
         ColorMode::Category => "Category",
         ColorMode::CategoryExtension => "Cat+Ext",
         ColorMode::ExtensionHash => "Ext Hash",
+        ColorMode::ExtensionPalette => "Ext Palette",
     }
 }
 
@@ -186,6 +317,19 @@ fn hsv_to_rgb(h: f32, s: f32, v: f32) -> AppColor {
     AppColor { r, g, b, a: 1.0 }
 }
 
+/// Color of palette slot `slot`. Hues step by the golden ratio, so each new slot lands in
+/// the widest gap left by the ones before it; brightness cycles over three levels to
+/// separate the slots whose hues still end up close.
+fn palette_color(slot: u16) -> AppColor {
+    let value = [0.86, 0.68, 0.96][slot as usize % 3];
+    hsv_to_rgb(palette_hue(slot), 0.72, value)
+}
+
+fn palette_hue(slot: u16) -> f32 {
+    const GOLDEN_RATIO_CONJUGATE: f32 = 0.618_034;
+    (slot as f32 * GOLDEN_RATIO_CONJUGATE).fract()
+}
+
 fn hash01(s: &str) -> f32 {
     let mut h: u32 = 2166136261;
     for &b in s.as_bytes() {
@@ -194,3 +338,61 @@ fn hash01(s: &str) -> f32 {
     }
     ((h >> 8) as f32) / ((u32::MAX >> 8) as f32)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{palette_hue, ColorMode, ColorSettings, PIN_COLORS};
+    use crate::tree::arena::{FileNode, FileTree};
+
+    #[test]
+    fn color_settings_round_trip_and_fill_missing_fields() {
+        let settings = ColorSettings {
+            mode: ColorMode::ExtensionHash,
+            vibrancy: 1.5,
+            tint_directories: true,
+            pinned: [(PathBuf::from("/data/backup"), PIN_COLORS[2])].into(),
+        };
+        let text = toml::to_string(&settings).unwrap();
+        assert_eq!(toml::from_str::<ColorSettings>(&text).unwrap(), settings);
+
+        let partial: ColorSettings = toml::from_str("mode = \"Category\"").unwrap();
+        assert_eq!(partial.mode, ColorMode::Category);
+        assert_eq!(partial.vibrancy, ColorSettings::default().vibrancy);
+    }
+
+    #[test]
+    fn pins_cycle_through_the_colors_and_resolve_by_path() {
+        let mut settings = ColorSettings::default();
+        let backup = PathBuf::from("/data/backup");
+        assert_eq!(settings.next_pin(&backup), Some(PIN_COLORS[0]));
+        settings.pin(backup.clone(), Some(PIN_COLORS[0]));
+        assert_eq!(settings.next_pin(&backup), Some(PIN_COLORS[1]));
+        settings.pin(backup.clone(), PIN_COLORS.last().copied());
+        assert_eq!(settings.next_pin(&backup), None);
+
+        // Found by path in any tree that has the folder, whatever its node id there.
+        let mut tree = FileTree::new("data");
+        tree.add_child(tree.root, FileNode::test_dir("music", 10));
+        let folder = tree.add_child(tree.root, FileNode::test_dir("backup", 10));
+        let pinned = settings.pinned_nodes(&tree, Path::new("/data"));
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned.get(&folder), PIN_COLORS.last());
+
+        settings.pin(backup, None);
+        assert!(settings.pinned.is_empty());
+        assert!(settings.pinned_nodes(&tree, Path::new("/data")).is_empty());
+    }
+
+    #[test]
+    fn palette_slots_are_spread_around_the_hue_circle() {
+        for count in [4u16, 8, 16] {
+            let mut hues: Vec<f32> = (1..=count).map(palette_hue).collect();
+            hues.sort_by(f32::total_cmp);
+            let gaps = hues.windows(2).map(|w| w[1] - w[0]).chain([1.0 + hues[0] - hues[hues.len() - 1]]);
+            let smallest = gaps.fold(f32::MAX, f32::min);
+            assert!(smallest > 0.3 / count as f32, "{} slots leave a gap of {}", count, smallest);
+        }
+    }
+}
@@ -1,11 +1,15 @@
+use std::collections::HashMap;
+
 use crate::layout::LayoutRect;
 use crate::render::colors;
-use crate::render::colors::ColorSettings;
-use crate::tree::arena::FileTree;
+use crate::render::colors::{AppColor, ColorSettings};
+use crate::tree::arena::{FileTree, NodeId};
+use serde::{Deserialize, Serialize};
 use vello::kurbo::Rect;
 
 /// Cushion shading parameters (van Wijk & van de Wetering 1999).
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CushionConfig {
     /// Ambient light intensity (paper default: ~0.16 = 40/255)
     pub ambient: f32,
@@ -15,6 +19,12 @@ pub struct CushionConfig {
     pub light: [f32; 3],
     /// Fast approximate lighting mode (avoids per-pixel normal normalization).
     pub fast_lighting: bool,
+    /// Lowest shading intensity a pixel may get, so steep cushion edges on deeply nested
+    /// tiles don't go black against the background (0.0 = no floor).
+    pub min_brightness: f32,
+    /// How much each nesting level darkens a tile, so deeper tiles read as further in
+    /// without frames (0.0 = off, 1.0 = strongest).
+    pub depth_shading: f32,
 }
 
 impl Default for CushionConfig {
@@ -29,10 +39,19 @@ impl Default for CushionConfig {
             light: [lx / len, ly / len, lz / len],
             // Prioritize visual fidelity by default; fast mode remains optional.
             fast_lighting: false,
+            min_brightness: 0.0,
+            depth_shading: 0.0,
         }
     }
 }
 
+/// Brightness multiplier for a tile at `depth` (its `LayoutRect::depth`): 1.0 for top-level
+/// tiles, dropping by up to 8% per level below, never under 0.4.
+pub fn depth_shade(depth: u16, strength: f32) -> f32 {
+    let levels = depth.saturating_sub(1) as f32;
+    (1.0 - strength.clamp(0.0, 1.0) * 0.08 * levels).max(0.4)
+}
+
 /// CPU-rasterize the cushion treemap into an RGBA pixel buffer.
 ///
 /// Each pixel's color is determined by the deepest (last-drawn) rectangle
@@ -45,6 +64,7 @@ pub fn rasterize_cushions(
     tree: &FileTree,
     config: &CushionConfig,
     color_settings: &ColorSettings,
+    pinned_colors: &HashMap<NodeId, AppColor>,
 ) -> Vec<u8> {
     let w = width as usize;
     let h = height as usize;
@@ -74,22 +94,13 @@ pub fn rasterize_cushions(
     // Iterate rects in order: parents before children.
     // Children overwrite parent pixels, so deeper structure shows through.
     for rect in layout_rects {
-        let node = tree.get(rect.node);
-
         // Base color
-        let base = if node.is_dir {
-            colors::directory_color(&node.name, rect.depth, color_settings)
-        } else {
-            let ext = if node.extension_id > 0 {
-                tree.extensions
-                    .get(node.extension_id as usize)
-                    .map(|s| s.as_str())
-                    .unwrap_or("")
-            } else {
-                ""
-            };
-            colors::extension_color(ext, color_settings)
+        let base = match pinned_colors.get(&rect.node) {
+            Some(&pinned) => pinned,
+            None => colors::node_color(tree, rect.node, rect.depth, color_settings),
         };
+        let shade = depth_shade(rect.depth, config.depth_shading);
+        let base = AppColor::new(base.r * shade, base.g * shade, base.b * shade);
 
         let [sx1, sx2, sy1, sy2] = rect.surface;
 
@@ -116,7 +127,8 @@ pub fn rasterize_cushions(
                     let ndotl = lambert * inv_len;
                     let intensity = (config.ambient + config.diffuse * ndotl)
                         .clamp(0.0, 1.0)
-                        .powf(1.22);
+                        .powf(1.22)
+                        .max(config.min_brightness);
 
                     let idx = (row_offset + px) * 4;
                     buf[idx] = (base.r * intensity * 255.0) as u8;
@@ -140,7 +152,8 @@ pub fn rasterize_cushions(
                     let cos_theta = (dot / n_len).max(0.0);
                     let intensity = (config.ambient + config.diffuse * cos_theta)
                         .clamp(0.0, 1.0)
-                        .powf(1.22);
+                        .powf(1.22)
+                        .max(config.min_brightness);
 
                     let idx = (row_offset + px) * 4;
                     buf[idx] = (base.r * intensity * 255.0) as u8;
@@ -163,3 +176,27 @@ pub fn layout_to_rect(rect: &LayoutRect) -> Rect {
         (rect.y + rect.h) as f64,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{depth_shade, CushionConfig};
+
+    #[test]
+    fn depth_shading_darkens_deeper_tiles() {
+        assert_eq!(depth_shade(7, 0.0), 1.0);
+        assert_eq!(depth_shade(1, 1.0), 1.0);
+        assert!(depth_shade(3, 1.0) < depth_shade(2, 1.0));
+        assert_eq!(depth_shade(40, 1.0), 0.4);
+    }
+
+    #[test]
+    fn cushion_config_round_trips() {
+        let config = CushionConfig {
+            ambient: 0.4,
+            fast_lighting: true,
+            ..CushionConfig::default()
+        };
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<CushionConfig>(&text).unwrap(), config);
+    }
+}
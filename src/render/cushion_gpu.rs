@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
 use vello::peniko::ImageData;
@@ -6,13 +8,25 @@ use vello::Renderer;
 
 use crate::layout::LayoutRect;
 use crate::render::colors;
-use crate::render::colors::ColorSettings;
-use crate::render::cushion::CushionConfig;
-use crate::tree::arena::FileTree;
+use crate::render::colors::{AppColor, ColorSettings};
+use crate::render::cushion::{self, CushionConfig};
+use crate::tree::arena::{FileTree, NodeId};
 
 const INITIAL_INSTANCE_CAPACITY: usize = 16_384;
 const CUSHION_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 
+/// What a cushion render draws besides the layout and tree: tile colors, the region
+/// kept clear, and how much of the previous render to redraw.
+pub struct CushionFrame<'a> {
+    pub color_settings: &'a ColorSettings,
+    /// Colors the user pinned to nodes, overriding `color_settings`
+    pub pinned_colors: &'a HashMap<NodeId, AppColor>,
+    /// `[x1, y1, x2, y2]` in pixels where no tile is drawn (the sidebar)
+    pub exclusion_rect: [f32; 4],
+    /// Pixel region to redraw over the previous render; `None` redraws everything
+    pub dirty: Option<[f32; 4]>,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct Uniforms {
@@ -22,6 +36,8 @@ struct Uniforms {
     light_dir: [f32; 3],
     fast_mode: u32,
     exclusion_rect: [f32; 4], // x1,y1,x2,y2 in pixels; treemap is skipped inside this region
+    min_brightness: f32,
+    _pad: [f32; 3],
 }
 
 #[repr(C)]
@@ -30,10 +46,16 @@ struct RectInstance {
     rect: [f32; 4],
     color: [f32; 4],
     coeffs: [f32; 4],
-    info: [f32; 2], // reserved for future visual channels: size_log_norm, age_norm
+    info: [f32; 2], // size_log_norm, depth shade (brightness multiplier)
     _pad: [f32; 2],
 }
 
+/// GPU rasterizer for the cushion treemap.
+///
+/// The render target is registered with Vello's renderer so scenes can draw it as an
+/// image. Exactly one registration is live at a time: `resize_target` unregisters the old
+/// target before registering its replacement, and the owner must call `release` with the
+/// same renderer before dropping this (`RenderState` does so in its `Drop`).
 pub struct CushionGpu {
     pipeline: wgpu::RenderPipeline,
     uniform_buffer: wgpu::Buffer,
@@ -49,6 +71,8 @@ pub struct CushionGpu {
     target_image: ImageData,
     target_width: u32,
     target_height: u32,
+    /// Whether the target holds a complete render; partial updates need one to patch.
+    has_content: bool,
 }
 
 impl CushionGpu {
@@ -182,6 +206,7 @@ impl CushionGpu {
             target_image,
             target_width: width.max(1),
             target_height: height.max(1),
+            has_content: false,
         })
     }
 
@@ -189,6 +214,11 @@ impl CushionGpu {
         &self.target_image
     }
 
+    /// Tiles drawn by the last render.
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
     pub fn resize_target(
         &mut self,
         device: &wgpu::Device,
@@ -210,6 +240,26 @@ impl CushionGpu {
         self.target_image = image;
         self.target_width = width;
         self.target_height = height;
+        self.has_content = false;
+    }
+
+    /// Unregister the render target from `renderer`. Safe to call more than once; the
+    /// target must not be drawn through Vello afterwards.
+    pub fn release(&mut self, renderer: &mut Renderer) {
+        renderer.unregister_texture(self.target_image.clone());
+        self.has_content = false;
+    }
+
+    /// Clamp a pixel-space `[x1, y1, x2, y2]` region to whole target pixels as a
+    /// scissor `(x, y, w, h)`; `None` when it covers nothing.
+    fn scissor_for(&self, region: [f32; 4]) -> Option<(u32, u32, u32, u32)> {
+        let clamp_x = |v: f32| v.clamp(0.0, self.target_width as f32);
+        let clamp_y = |v: f32| v.clamp(0.0, self.target_height as f32);
+        let x1 = clamp_x(region[0]).floor() as u32;
+        let y1 = clamp_y(region[1]).floor() as u32;
+        let x2 = clamp_x(region[2]).ceil() as u32;
+        let y2 = clamp_y(region[3]).ceil() as u32;
+        (x2 > x1 && y2 > y1).then_some((x1, y1, x2 - x1, y2 - y1))
     }
 
     pub fn update_and_render(
@@ -219,11 +269,47 @@ impl CushionGpu {
         layout_rects: &[LayoutRect],
         tree: &FileTree,
         config: &CushionConfig,
-        color_settings: &ColorSettings,
-        exclusion_rect: [f32; 4],
+        frame: &CushionFrame,
     ) {
-        let mut instances = Vec::with_capacity(layout_rects.len());
+        let CushionFrame {
+            color_settings,
+            pinned_colors,
+            exclusion_rect,
+            dirty,
+        } = *frame;
+        // With a dirty region, only that part of the previous render is redrawn:
+        // the pass keeps the old contents and is scissored to the region.
+        let scissor = match dirty {
+            Some(region) if self.has_content => match self.scissor_for(region) {
+                Some(scissor) => Some(scissor),
+                None => return,
+            },
+            _ => None,
+        };
+
+        let mut instances = Vec::with_capacity(layout_rects.len() + 1);
+        if let Some((sx, sy, sw, sh)) = scissor {
+            // `Load` keeps stale pixels wherever no tile covers the region now, so the
+            // first instance wipes it: zero alpha reads as transparent (unblended write).
+            instances.push(RectInstance {
+                rect: [sx as f32, sy as f32, sw as f32, sh as f32],
+                color: [0.0; 4],
+                coeffs: [0.0; 4],
+                info: [0.0; 2],
+                _pad: [0.0; 2],
+            });
+        }
         for rect in layout_rects {
+            if let Some((sx, sy, sw, sh)) = scissor {
+                let (sx, sy, sw, sh) = (sx as f32, sy as f32, sw as f32, sh as f32);
+                let outside = rect.x >= sx + sw
+                    || rect.y >= sy + sh
+                    || rect.x + rect.w <= sx
+                    || rect.y + rect.h <= sy;
+                if outside {
+                    continue;
+                }
+            }
             let node = tree.get(rect.node);
             let x = rect.x.max(0.0);
             let y = rect.y.max(0.0);
@@ -234,25 +320,19 @@ impl CushionGpu {
             if w < 0.5 || h < 0.5 {
                 continue;
             }
-            let base = if node.is_dir {
-                colors::directory_color(&node.name, rect.depth, color_settings)
-            } else {
-                let ext = if node.extension_id > 0 {
-                    tree.extensions
-                        .get(node.extension_id as usize)
-                        .map(|s| s.as_str())
-                        .unwrap_or("")
-                } else {
-                    ""
-                };
-                colors::extension_color(ext, color_settings)
+            let base = match pinned_colors.get(&rect.node) {
+                Some(&pinned) => pinned,
+                None => colors::node_color(tree, rect.node, rect.depth, color_settings),
             };
 
             instances.push(RectInstance {
                 rect: [x, y, w, h],
                 color: [base.r, base.g, base.b, 1.0],
                 coeffs: rect.surface,
-                info: [((node.size as f32 + 1.0).log10() / 12.0).clamp(0.0, 1.0), 0.0],
+                info: [
+                    ((node.size as f32 + 1.0).log10() / 12.0).clamp(0.0, 1.0),
+                    cushion::depth_shade(rect.depth, config.depth_shading),
+                ],
                 _pad: [0.0, 0.0],
             });
         }
@@ -280,6 +360,8 @@ impl CushionGpu {
             light_dir: light,
             fast_mode: if config.fast_lighting { 1 } else { 0 },
             exclusion_rect,
+            min_brightness: config.min_brightness,
+            _pad: [0.0; 3],
         };
         queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
 
@@ -295,12 +377,12 @@ impl CushionGpu {
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.078,
-                            g: 0.086,
-                            b: 0.11,
-                            a: 1.0,
-                        }),
+                        // Transparent so the scene background shows through gaps.
+                        load: if scissor.is_some() {
+                            wgpu::LoadOp::Load
+                        } else {
+                            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                        },
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -309,6 +391,9 @@ impl CushionGpu {
                 occlusion_query_set: None,
             });
 
+            if let Some((x, y, w, h)) = scissor {
+                pass.set_scissor_rect(x, y, w, h);
+            }
             if self.instance_count > 0 {
                 pass.set_pipeline(&self.pipeline);
                 pass.set_bind_group(0, &self.uniform_bind_group, &[]);
@@ -318,6 +403,7 @@ impl CushionGpu {
         }
 
         queue.submit(Some(encoder.finish()));
+        self.has_content = true;
     }
 
     fn ensure_instance_capacity(&mut self, device: &wgpu::Device, required: usize) {
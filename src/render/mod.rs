@@ -2,9 +2,11 @@ pub mod colors;
 pub mod cushion;
 pub mod cushion_gpu;
 pub mod scene;
+pub mod svg;
 pub mod text;
 
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{mpsc, Arc};
 
 use anyhow::Result;
 use vello::wgpu;
@@ -12,10 +14,9 @@ use vello::{AaConfig, RenderParams, Renderer, RendererOptions, Scene};
 use winit::window::Window;
 
 use crate::layout::LayoutRect;
-use crate::render::colors::ColorSettings;
 use crate::tree::arena::FileTree;
 use cushion::CushionConfig;
-use cushion_gpu::CushionGpu;
+use cushion_gpu::{CushionFrame, CushionGpu};
 
 /// Holds all GPU rendering state.
 pub struct RenderState {
@@ -28,6 +29,40 @@ pub struct RenderState {
     scene_target_view: wgpu::TextureView,
     blitter: wgpu::util::TextureBlitter,
     cushion_gpu: CushionGpu,
+    /// Copy the next rendered frame back from the GPU (see `request_capture`).
+    capture_requested: bool,
+    pending_capture: Option<PendingCapture>,
+}
+
+/// A frame copied into a readback buffer, waiting for the buffer to map.
+struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    /// Row pitch in the buffer, padded to `COPY_BYTES_PER_ROW_ALIGNMENT`
+    padded_row: u32,
+    mapped: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// RGBA pixels of one rendered frame.
+pub struct FrameCapture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl FrameCapture {
+    /// Encode the frame as a PNG file.
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
 }
 
 impl RenderState {
@@ -110,6 +145,8 @@ impl RenderState {
             scene_target_view,
             blitter,
             cushion_gpu,
+            capture_requested: false,
+            pending_capture: None,
         })
     }
 
@@ -123,26 +160,36 @@ impl RenderState {
         self.scene_target_view = self
             .scene_target
             .create_view(&wgpu::TextureViewDescriptor::default());
-        self.cushion_gpu.resize_target(
-            &self.device,
-            &mut self.renderer,
-            self.surface_config.width,
-            self.surface_config.height,
-        );
+        // The cushion target is resized lazily in `update_cushion_treemap`, so the
+        // previous treemap image stays valid until replacement content exists.
     }
 
     pub fn treemap_image(&self) -> &vello::peniko::ImageData {
         self.cushion_gpu.image()
     }
 
+    /// Cushion tiles drawn by the last treemap render.
+    pub fn cushion_instances(&self) -> u32 {
+        self.cushion_gpu.instance_count()
+    }
+
+    /// Rasterize the cushion treemap. With `frame.dirty`, only that pixel region of the
+    /// previous render is redrawn (falls back to a full redraw after a resize). `target_size` is the
+    /// pixel size the layout was computed for, which lags the surface while it is frozen.
     pub fn update_cushion_treemap(
         &mut self,
+        target_size: (u32, u32),
         layout_rects: &[LayoutRect],
         tree: &FileTree,
         config: &CushionConfig,
-        color_settings: &ColorSettings,
-        exclusion_rect: [f32; 4],
+        frame: &CushionFrame,
     ) {
+        self.cushion_gpu.resize_target(
+            &self.device,
+            &mut self.renderer,
+            target_size.0,
+            target_size.1,
+        );
         self.cushion_gpu
             .update_and_render(
                 &self.device,
@@ -150,14 +197,41 @@ impl RenderState {
                 layout_rects,
                 tree,
                 config,
-                color_settings,
-                exclusion_rect,
+                frame,
             );
     }
 
+    /// Acquire the next swapchain texture. A lost or outdated surface (sleep/resume, RDP
+    /// reconnects, GPU resets) is reconfigured and retried once; `None` skips the frame.
+    fn acquire_surface_texture(&mut self) -> Result<Option<wgpu::SurfaceTexture>> {
+        for attempt in 0..2 {
+            match self.surface.get_current_texture() {
+                Ok(texture) => return Ok(Some(texture)),
+                Err(e @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                    tracing::warn!("Surface unavailable ({}), reconfiguring (attempt {})", e, attempt + 1);
+                    self.surface.configure(&self.device, &self.surface_config);
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
+                    tracing::debug!("Timed out acquiring the next frame, skipping it");
+                    return Ok(None);
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    return Err(wgpu::SurfaceError::OutOfMemory.into());
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to acquire the next frame: {}", e);
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Render a scene to the surface.
     pub fn render(&mut self, scene: &Scene) -> Result<()> {
-        let surface_texture = self.surface.get_current_texture()?;
+        let Some(surface_texture) = self.acquire_surface_texture()? else {
+            return Ok(());
+        };
 
         let render_params = RenderParams {
             base_color: vello::peniko::Color::BLACK,
@@ -186,11 +260,98 @@ impl RenderState {
             });
         self.blitter
             .copy(&self.device, &mut encoder, &self.scene_target_view, &surface_view);
+        let capture = std::mem::take(&mut self.capture_requested).then(|| self.copy_scene_target(&mut encoder));
         self.queue.submit(Some(encoder.finish()));
 
+        if let Some((buffer, width, height, padded_row)) = capture {
+            let (tx, rx) = mpsc::channel();
+            buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+                let _ = tx.send(result);
+            });
+            self.pending_capture = Some(PendingCapture {
+                buffer,
+                width,
+                height,
+                padded_row,
+                mapped: rx,
+            });
+        }
+
         surface_texture.present();
         Ok(())
     }
+
+    /// Copy the next rendered frame back to the CPU. The copy is queued with that frame and
+    /// collected later through `poll_capture`, so rendering never waits on it.
+    pub fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Whether a captured frame is still being copied back from the GPU.
+    pub fn capture_pending(&self) -> bool {
+        self.pending_capture.is_some()
+    }
+
+    /// Collect a requested frame once the GPU has finished copying it, without blocking.
+    pub fn poll_capture(&mut self) -> Option<Result<FrameCapture>> {
+        let pending = self.pending_capture.as_ref()?;
+        let _ = self.device.poll(wgpu::PollType::Poll);
+        let mapped = match pending.mapped.try_recv() {
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Ok(result) => result.map_err(anyhow::Error::from),
+            Err(mpsc::TryRecvError::Disconnected) => Err(anyhow::anyhow!("frame readback was dropped")),
+        };
+        let pending = self.pending_capture.take()?;
+        Some(mapped.map(|()| {
+            let row = pending.width as usize * 4;
+            let pixels = {
+                let data = pending.buffer.get_mapped_range(..);
+                data.chunks_exact(pending.padded_row as usize)
+                    .flat_map(|padded| &padded[..row])
+                    .copied()
+                    .collect()
+            };
+            pending.buffer.unmap();
+            FrameCapture {
+                width: pending.width,
+                height: pending.height,
+                pixels,
+            }
+        }))
+    }
+
+    /// Record a copy of the scene target into a new readback buffer.
+    fn copy_scene_target(&self, encoder: &mut wgpu::CommandEncoder) -> (wgpu::Buffer, u32, u32, u32) {
+        let size = self.scene_target.size();
+        let padded_row = (size.width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame capture readback"),
+            size: padded_row as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.scene_target.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        (buffer, size.width, size.height, padded_row)
+    }
+}
+
+impl Drop for RenderState {
+    fn drop(&mut self) {
+        // The renderer's texture registry holds the cushion target; release it while both
+        // are still alive.
+        self.cushion_gpu.release(&mut self.renderer);
+    }
 }
 
 fn create_scene_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
@@ -207,7 +368,8 @@ fn create_scene_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::
         format: wgpu::TextureFormat::Rgba8Unorm,
         usage: wgpu::TextureUsages::STORAGE_BINDING
             | wgpu::TextureUsages::TEXTURE_BINDING
-            | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            | wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     })
 }
@@ -1,14 +1,18 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use vello::kurbo::{Affine, Rect};
-use vello::peniko::{Blob, Color, Fill, ImageAlphaType, ImageData, ImageFormat};
+use vello::kurbo::{Affine, BezPath, Circle, Rect, Shape, Stroke};
+use vello::peniko::{Blob, Color, Fill, Gradient, ImageAlphaType, ImageData, ImageFormat};
 use vello::Scene;
 
 use super::cushion;
 use super::text::{TextRenderer, TextRenderResult};
-use crate::layout::LayoutRect;
+use crate::layout::{grouped_files, DirInsets, Layout, LayoutConfig, LayoutRect};
 use crate::tree::arena::{FileTree, NodeId};
-use crate::ui::tooltip::format_size;
+use crate::tree::extensions::FileCategory;
+use crate::ui::tooltip::{
+    format_count, format_share, format_size, format_size_opts, PercentBasis, SizeFormat, SizeUnits,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct LabelHitRegion {
@@ -16,20 +20,146 @@ pub struct LabelHitRegion {
     pub bounds: [f32; 4], // [x1, y1, x2, y2]
 }
 
+/// What `build_scene` draws over the treemap image, and how.
+#[derive(Debug, Clone, Copy)]
+pub struct SceneOptions {
+    pub show_text_labels: bool,
+    /// Label the view root's direct children even when their headers are small
+    pub label_top_level: bool,
+    pub show_file_labels: bool,
+    pub show_dir_watermarks: bool,
+    pub label_font_scale: f32,
+    /// Name and size of the hovered tile next to it
+    pub show_hover_info: bool,
+    /// Dim everything outside the hovered tile's folder
+    pub focus_hovered_folder: bool,
+    pub size_units: SizeUnits,
+    /// Size that shares in the hover info are measured against, and what it is
+    pub share_of: (u64, PercentBasis),
+    pub show_category_patterns: bool,
+}
+
+/// Color of the empty area around and between treemap tiles.
+pub const BACKGROUND_COLOR: Color = Color::from_rgb8(20, 22, 28);
+
+/// What to paint behind the treemap image (the exclusion region and tile gaps).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackgroundStyle {
+    /// Flat `BACKGROUND_COLOR`
+    #[default]
+    None,
+    /// Radial vignette, lighter in the middle
+    Vignette,
+    /// User-supplied PNG scaled to cover the window
+    Image,
+}
+
+impl BackgroundStyle {
+    pub const ALL: [BackgroundStyle; 3] = [Self::None, Self::Vignette, Self::Image];
+}
+
+impl std::fmt::Display for BackgroundStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::None => "None",
+            Self::Vignette => "Vignette",
+            Self::Image => "Image",
+        })
+    }
+}
+
+/// Paint the window background in screen space. Call before appending the treemap,
+/// which is transparent wherever no tile was drawn. `Image` without a loaded image
+/// falls back to the flat color.
+pub fn draw_background(
+    scene: &mut Scene,
+    style: BackgroundStyle,
+    image: Option<&ImageData>,
+    viewport_width: f32,
+    viewport_height: f32,
+) {
+    let (w, h) = (viewport_width as f64, viewport_height as f64);
+    let bounds = Rect::new(0.0, 0.0, w, h);
+    scene.fill(Fill::NonZero, Affine::IDENTITY, BACKGROUND_COLOR, None, &bounds);
+
+    match (style, image) {
+        (BackgroundStyle::Vignette, _) => {
+            let radius = (w.hypot(h) * 0.5).max(1.0) as f32;
+            let gradient = Gradient::new_radial((w * 0.5, h * 0.5), radius).with_stops([
+                (0.0, Color::from_rgb8(34, 38, 48)),
+                (0.6, BACKGROUND_COLOR),
+                (1.0, Color::from_rgb8(6, 7, 10)),
+            ]);
+            scene.fill(Fill::NonZero, Affine::IDENTITY, &gradient, None, &bounds);
+        }
+        (BackgroundStyle::Image, Some(image)) if image.width > 0 && image.height > 0 => {
+            // Cover the viewport, cropping the longer axis, centered.
+            let (iw, ih) = (image.width as f64, image.height as f64);
+            let scale = (w / iw).max(h / ih);
+            let transform = Affine::translate(((w - iw * scale) * 0.5, (h - ih * scale) * 0.5))
+                * Affine::scale(scale);
+            scene.push_clip_layer(Fill::NonZero, Affine::IDENTITY, &bounds);
+            scene.draw_image(image, transform);
+            scene.pop_layer();
+        }
+        _ => {}
+    }
+}
+
+/// `*.ext` for a group tile's files, or "(no extension)".
+fn extension_pattern(tree: &FileTree, node: NodeId) -> String {
+    match tree.extensions[tree.get(node).extension_id as usize].as_str() {
+        "" => "(no extension)".to_string(),
+        ext => format!("*.{}", ext),
+    }
+}
+
+/// Decode a PNG file into an RGBA image for `BackgroundStyle::Image`.
+pub fn load_background_image(path: &std::path::Path) -> anyhow::Result<ImageData> {
+    let mut decoder = png::Decoder::new(std::fs::File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buf,
+        png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => anyhow::bail!("unexpanded indexed PNG"),
+    };
+    Ok(image_from_rgba(rgba, info.width, info.height))
+}
+
 /// Build a Vello scene from the cached treemap image + overlays.
 pub fn build_scene(
     scene: &mut Scene,
     treemap_image: Option<&ImageData>,
     layout_rects: &[LayoutRect],
+    layout_config: &LayoutConfig,
     tree: &FileTree,
-    hover_node: Option<NodeId>,
+    hover_rect: Option<usize>,
     text_renderer: &mut TextRenderer,
-    show_text_labels: bool,
-    label_font_scale: f32,
-    show_hover_info: bool,
+    options: &SceneOptions,
 ) -> Vec<LabelHitRegion> {
+    let SceneOptions {
+        show_text_labels,
+        label_top_level,
+        show_file_labels,
+        show_dir_watermarks,
+        label_font_scale,
+        show_hover_info,
+        focus_hovered_folder,
+        size_units,
+        share_of,
+        show_category_patterns,
+    } = *options;
     scene.reset();
     let mut label_hit_regions = Vec::new();
+    // An index rather than a node: in the split view a node can have a tile in each half.
+    let hovered = hover_rect.and_then(|idx| layout_rects.get(idx));
 
     // Draw the cached CPU-rasterized treemap as a single image
     if let Some(image) = treemap_image {
@@ -44,16 +174,36 @@ pub fn build_scene(
         tracing::debug!("No treemap image to draw yet");
     }
 
+    // Category patterns keep file types distinguishable without relying on color alone.
+    if show_category_patterns {
+        for rect in layout_rects {
+            let node = tree.get(rect.node);
+            if node.is_dir || rect.w < 24.0 || rect.h < 24.0 || rect.w * rect.h < 1_500.0 {
+                continue;
+            }
+            if let Some(pattern) = category_pattern(tree.category(rect.node)) {
+                draw_category_pattern(scene, rect, pattern);
+            }
+        }
+    }
+
+    // Faint folder names behind the frames and labels, for folders whose header band
+    // doesn't show or is easy to lose among their children.
+    if show_dir_watermarks {
+        draw_dir_watermarks(scene, layout_rects, tree, text_renderer);
+    }
+
     // Draw lightweight directory frame/header overlays so hierarchy reads as nested containers.
+    // The insets are the ones layout reserved, so headers sit exactly above the children.
     for rect in layout_rects {
         let node = tree.get(rect.node);
-        if !node.is_dir || rect.depth == 0 || rect.w < 24.0 || rect.h < 20.0 {
+        if !node.is_dir || !layout_config.shows_dir_frame(rect) {
             continue;
         }
 
-        let (frame, header) = directory_frame_params(rect.depth);
-        let inner_w = rect.w - frame * 2.0;
-        let inner_h = rect.h - frame * 2.0;
+        let DirInsets { pad, frame, header } = layout_config.dir_insets(rect.depth, rect.h);
+        let inner_w = rect.w - (pad + frame) * 2.0;
+        let inner_h = rect.h - (pad + frame) * 2.0;
         if inner_w <= 2.0 || inner_h <= 2.0 {
             continue;
         }
@@ -62,10 +212,10 @@ pub fn build_scene(
         let header_h = header.min((inner_h - 1.0).max(0.0));
         if header_h > 1.0 {
             let header_rect = Rect::new(
-                (rect.x + frame) as f64,
-                (rect.y + frame) as f64,
-                (rect.x + rect.w - frame) as f64,
-                (rect.y + frame + header_h) as f64,
+                (rect.x + pad + frame) as f64,
+                (rect.y + pad + frame) as f64,
+                (rect.x + rect.w - pad - frame) as f64,
+                (rect.y + pad + frame + header_h) as f64,
             );
             scene.fill(
                 Fill::NonZero,
@@ -78,10 +228,10 @@ pub fn build_scene(
 
         // Frame border strips.
         let border = Color::new([1.0, 1.0, 1.0, 0.08]);
-        let x0 = rect.x;
-        let y0 = rect.y;
-        let x1 = rect.x + rect.w;
-        let y1 = rect.y + rect.h;
+        let x0 = rect.x + pad;
+        let y0 = rect.y + pad;
+        let x1 = rect.x + rect.w - pad;
+        let y1 = rect.y + rect.h - pad;
 
         let top = Rect::new(x0 as f64, y0 as f64, x1 as f64, (y0 + frame) as f64);
         let bottom = Rect::new(x0 as f64, (y1 - frame) as f64, x1 as f64, y1 as f64);
@@ -93,35 +243,64 @@ pub fn build_scene(
         scene.fill(Fill::NonZero, Affine::IDENTITY, border, None, &right);
     }
 
+    let mut placed_bounds: Vec<[f32; 4]> = Vec::new();
     if show_text_labels {
         let viewport_area = layout_rects
             .first()
             .map(|r| (r.w * r.h).max(1.0))
             .unwrap_or(1.0);
         let min_label_area = (viewport_area * 0.0004).max(1_200.0);
+        // Top-level = no laid-out ancestor below the view root (collapsed chains included).
+        let top_level: HashSet<NodeId> = if label_top_level {
+            let laid_out: HashSet<NodeId> = layout_rects.iter().map(|r| r.node).collect();
+            let view_root = layout_rects.first().map(|r| r.node);
+            layout_rects
+                .iter()
+                .skip(1)
+                .filter(|r| {
+                    tree.ancestors(r.node)
+                        .take_while(|&a| Some(a) != view_root)
+                        .all(|a| !laid_out.contains(&a))
+                })
+                .map(|r| r.node)
+                .collect()
+        } else {
+            HashSet::new()
+        };
         let mut candidates: Vec<&LayoutRect> = layout_rects
             .iter()
             .filter(|r| {
                 let node = tree.get(r.node);
                 let area = r.w * r.h;
-                node.is_dir && r.depth >= 1 && area >= min_label_area && r.w >= 64.0 && r.h >= 18.0 && r.depth <= 10
+                let big_enough = area >= min_label_area || top_level.contains(&r.node);
+                node.is_dir && r.depth >= 1 && big_enough && r.w >= 64.0 && r.h >= 18.0 && r.depth <= 10
             })
             .collect();
-        candidates.sort_by(|a, b| (b.w * b.h).partial_cmp(&(a.w * a.h)).unwrap());
+        // Pinned top-level labels go first, in name order so placement is stable between
+        // rescans; the rest follow by area.
+        candidates.sort_by(|a, b| {
+            let (a_top, b_top) = (top_level.contains(&a.node), top_level.contains(&b.node));
+            b_top.cmp(&a_top).then_with(|| {
+                if a_top {
+                    tree.get(a.node).name.cmp(&tree.get(b.node).name)
+                } else {
+                    (b.w * b.h).partial_cmp(&(a.w * a.h)).unwrap()
+                }
+            })
+        });
 
         let max_labels = 80;
         let candidate_count = candidates.len();
-        let mut placed_bounds: Vec<[f32; 4]> = Vec::with_capacity(max_labels);
         let mut drawn = 0usize;
 
         for rect in candidates {
-            if drawn >= max_labels {
+            if drawn >= max_labels && !top_level.contains(&rect.node) {
                 break;
             }
             let node = tree.get(rect.node);
-            let (frame, header) = directory_frame_params(rect.depth);
-            let pad_x = (frame + 3.0).max(4.0);
-            let pad_y = (frame + 2.0).max(3.0);
+            let DirInsets { pad, frame, header } = layout_config.dir_insets(rect.depth, rect.h);
+            let pad_x = (pad + frame + 3.0).max(4.0);
+            let pad_y = (pad + frame + 2.0).max(3.0);
             let max_text_w = rect.w - pad_x * 2.0;
             if max_text_w <= 24.0 {
                 continue;
@@ -134,7 +313,11 @@ pub fn build_scene(
 
             let scale = label_font_scale.clamp(0.6, 2.5);
             let font_size = ((label_band_h * 0.62).clamp(9.0, 14.0) * scale).clamp(8.0, 28.0);
-            let base = format!("{}  {}", node.name, format_size(node.size));
+            let base = format!(
+                "{}  {}",
+                node.name,
+                format_size_opts(layout_config.size_of(tree, rect.node), &SizeFormat::compact(size_units))
+            );
             let label = truncate_label(&base, max_text_w, font_size);
             if label.is_empty() {
                 continue;
@@ -183,72 +366,97 @@ pub fn build_scene(
             "Text overlays: candidates={}, drawn={}, hover={:?}",
             candidate_count,
             drawn,
-            hover_node
+            hovered.map(|rect| rect.node)
         );
     }
 
+    // Big file tiles get their name, or just the extension when the name doesn't fit, so
+    // file types can be told apart without relying on color.
+    if show_file_labels {
+        draw_file_labels(scene, layout_rects, tree, text_renderer, label_font_scale, &mut placed_bounds);
+    }
+
+    // Dim everything outside the hovered folder so its extent stands out.
+    if let (true, Some(hovered)) = (focus_hovered_folder, hovered) {
+        draw_folder_focus_mask(scene, layout_rects, tree, hovered);
+    }
+
     // Hover highlight helps orient which rectangle is under the cursor.
-    if let Some(hover_id) = hover_node {
-        let mut hovered_rect: Option<&LayoutRect> = None;
-        for rect in layout_rects {
-            if rect.node == hover_id {
-                let shape = cushion::layout_to_rect(rect);
-                let highlight = Color::new([1.0f32, 1.0, 1.0, 0.20]);
-                scene.fill(Fill::NonZero, Affine::IDENTITY, highlight, None, &shape);
-                hovered_rect = Some(rect);
-                break;
-            }
-        }
+    if let Some(rect) = hovered {
+        let hover_id = rect.node;
+        let shape = cushion::layout_to_rect(rect);
+        let highlight = Color::new([1.0f32, 1.0, 1.0, 0.20]);
+        scene.fill(Fill::NonZero, Affine::IDENTITY, highlight, None, &shape);
 
         if show_hover_info {
-            if let (Some(rect), Some(tree)) = (hovered_rect, Some(tree)) {
-                let node = tree.get(hover_id);
-                let text = format!("{}  {}", node.name, format_size(node.size));
-                let in_rect = rect.w >= 180.0 && rect.h >= 32.0;
-                if let Some(rendered) = text_renderer.render_text(&text, "default", 13.0, Some(320.0)) {
-                    if in_rect {
-                        let x = rect.x + 6.0;
-                        let y = rect.y + (rect.h - rendered.height as f32).max(2.0) * 0.5;
-                        let bg = Rect::new(
-                            x as f64 - 2.0,
-                            y as f64 - 1.0,
-                            (x + rendered.width as f32 + 4.0) as f64,
-                            (y + rendered.height as f32 + 2.0) as f64,
-                        );
-                        scene.fill(
-                            Fill::NonZero,
-                            Affine::IDENTITY,
-                            Color::new([0.0, 0.0, 0.0, 0.42]),
-                            None,
-                            &bg,
-                        );
-                        draw_text_to_scene(scene, rendered, x, y);
+            let node = tree.get(hover_id);
+            let (total, basis) = share_of;
+            let text = if rect.grouped > 0 {
+                let bytes: u64 = grouped_files(tree, hover_id, layout_config).map(|id| tree.get(id).size).sum();
+                format!(
+                    "{}: {} files, {}",
+                    extension_pattern(tree, hover_id),
+                    format_count(rect.grouped as u64),
+                    format_size(bytes, size_units)
+                )
+            } else {
+                // Bytes of folders hidden from the layout don't count towards the shares.
+                let size = layout_config.size_of(tree, hover_id);
+                match format_share(size, total) {
+                    Some(share) => format!(
+                        "{}  {}  ·  {} {}",
+                        node.name,
+                        format_size(size, size_units),
+                        share,
+                        basis.label()
+                    ),
+                    None => format!("{}  {}", node.name, format_size(size, size_units)),
+                }
+            };
+            let in_rect = rect.w >= 180.0 && rect.h >= 32.0;
+            if let Some(rendered) = text_renderer.render_text(&text, "default", 13.0, Some(320.0)) {
+                if in_rect {
+                    let x = rect.x + 6.0;
+                    let y = rect.y + (rect.h - rendered.height as f32).max(2.0) * 0.5;
+                    let bg = Rect::new(
+                        x as f64 - 2.0,
+                        y as f64 - 1.0,
+                        (x + rendered.width as f32 + 4.0) as f64,
+                        (y + rendered.height as f32 + 2.0) as f64,
+                    );
+                    scene.fill(
+                        Fill::NonZero,
+                        Affine::IDENTITY,
+                        Color::new([0.0, 0.0, 0.0, 0.42]),
+                        None,
+                        &bg,
+                    );
+                    draw_text_to_scene(scene, rendered, x, y);
+                } else {
+                    let place_right = rect.x + rect.w + 180.0 < layout_rects
+                        .iter()
+                        .map(|r| r.x + r.w)
+                        .fold(0.0_f32, f32::max);
+                    let bx = if place_right {
+                        rect.x + rect.w + 8.0
                     } else {
-                        let place_right = rect.x + rect.w + 180.0 < layout_rects
-                            .iter()
-                            .map(|r| r.x + r.w)
-                            .fold(0.0_f32, f32::max);
-                        let bx = if place_right {
-                            rect.x + rect.w + 8.0
-                        } else {
-                            (rect.x - rendered.width as f32 - 14.0).max(240.0)
-                        };
-                        let by = rect.y.clamp(8.0, 720.0);
-                        let bg = Rect::new(
-                            bx as f64,
-                            by as f64,
-                            (bx + rendered.width as f32 + 10.0) as f64,
-                            (by + rendered.height as f32 + 8.0) as f64,
-                        );
-                        scene.fill(
-                            Fill::NonZero,
-                            Affine::IDENTITY,
-                            Color::new([0.05, 0.06, 0.07, 0.90]),
-                            None,
-                            &bg,
-                        );
-                        draw_text_to_scene(scene, rendered, bx + 5.0, by + 4.0);
-                    }
+                        (rect.x - rendered.width as f32 - 14.0).max(240.0)
+                    };
+                    let by = rect.y.clamp(8.0, 720.0);
+                    let bg = Rect::new(
+                        bx as f64,
+                        by as f64,
+                        (bx + rendered.width as f32 + 10.0) as f64,
+                        (by + rendered.height as f32 + 8.0) as f64,
+                    );
+                    scene.fill(
+                        Fill::NonZero,
+                        Affine::IDENTITY,
+                        Color::new([0.05, 0.06, 0.07, 0.90]),
+                        None,
+                        &bg,
+                    );
+                    draw_text_to_scene(scene, rendered, bx + 5.0, by + 4.0);
                 }
             }
         }
@@ -257,6 +465,226 @@ pub fn build_scene(
     label_hit_regions
 }
 
+/// Darken the treemap outside the folder containing the `hovered` tile (the tile itself
+/// when it is a folder). Layout nests descendants inside their folder's rect, so one
+/// even-odd fill of the treemap bounds minus that rect covers exactly the non-descendants.
+fn draw_folder_focus_mask(scene: &mut Scene, layout_rects: &[LayoutRect], tree: &FileTree, hovered: &LayoutRect) {
+    // Nearest folder at or above the hovered node that has a tile of its own, in the same
+    // half of the split view as the hovered tile.
+    let folders: Vec<NodeId> = std::iter::successors(Some(hovered.node), |&id| tree.get(id).parent)
+        .filter(|&id| tree.get(id).is_dir)
+        .collect();
+    let inner = cushion::layout_to_rect(hovered);
+    let Some((_, focus)) = layout_rects
+        .iter()
+        .filter(|rect| cushion::layout_to_rect(rect).contains_rect(inner))
+        .filter_map(|rect| folders.iter().position(|&id| id == rect.node).map(|rank| (rank, rect)))
+        .min_by_key(|&(rank, _)| rank)
+    else {
+        return;
+    };
+
+    let Some(bounds) = layout_rects
+        .iter()
+        .map(cushion::layout_to_rect)
+        .reduce(|a, b| a.union(b))
+    else {
+        return;
+    };
+    let focus = cushion::layout_to_rect(focus);
+    if focus.contains_rect(bounds) {
+        return;
+    }
+
+    let mut mask = bounds.to_path(0.1);
+    mask.extend(focus.to_path(0.1));
+    scene.fill(
+        Fill::EvenOdd,
+        Affine::IDENTITY,
+        Color::new([0.0, 0.0, 0.0, 0.45]),
+        None,
+        &mask,
+    );
+}
+
+/// Outline the tile of a selected node so it stands out from the hover highlight.
+pub fn draw_selection_outline(scene: &mut Scene, layout: &Layout, node: NodeId) {
+    if let Some(rect) = layout.rect_of(node) {
+        let shape = cushion::layout_to_rect(rect).inset(-1.0);
+        scene.stroke(
+            &Stroke::new(2.0),
+            Affine::IDENTITY,
+            Color::new([1.0, 0.86, 0.25, 0.95]),
+            None,
+            &shape,
+        );
+    }
+}
+
+/// Outline visible tiles modified at or after `cutoff` (Unix seconds): files, and folders
+/// whose contents weren't laid out, since their `modified` is their newest descendant's.
+pub fn draw_recent_outlines(scene: &mut Scene, layout: &Layout, tree: &FileTree, cutoff: u64) {
+    for rect in &layout.rects {
+        let node = tree.get(rect.node);
+        if node.modified == 0 || node.modified < cutoff {
+            continue;
+        }
+        // Children are sorted largest first, so an unlaid first child means none were laid out.
+        if node.is_dir && node.first_child.is_some_and(|child| layout.visible(child)) {
+            continue;
+        }
+        let shape = cushion::layout_to_rect(rect).inset(-0.5);
+        scene.stroke(
+            &Stroke::new(1.5),
+            Affine::IDENTITY,
+            Color::new([1.0, 0.78, 0.25, 0.95]),
+            None,
+            &shape,
+        );
+    }
+}
+
+/// Outline every visible member of a duplicate-file group.
+pub fn draw_duplicate_outlines(scene: &mut Scene, layout: &Layout, nodes: &[NodeId]) {
+    for rect in nodes.iter().filter_map(|&node| layout.rect_of(node)) {
+        let shape = cushion::layout_to_rect(rect).inset(-1.0);
+        scene.stroke(
+            &Stroke::new(2.0),
+            Affine::IDENTITY,
+            Color::new([0.95, 0.35, 0.85, 0.95]),
+            None,
+            &shape,
+        );
+    }
+}
+
+/// Tag directories that had children culled from the layout with a "+N more" badge in
+/// their bottom-right corner, so a heavily truncated wide folder doesn't look complete.
+pub fn draw_overflow_badges(scene: &mut Scene, text_renderer: &mut TextRenderer, layout: &Layout) {
+    // By rect rather than by node, so a folder shown in both halves of the split view gets
+    // a badge in each.
+    for rect in &layout.rects {
+        let hidden = layout.hidden_children(rect.node);
+        if hidden == 0 || rect.w < 72.0 || rect.h < 40.0 {
+            continue;
+        }
+        let label = format!("+{} more", format_count(hidden as u64));
+        let Some(rendered) = text_renderer.render_text(&label, "default", 11.0, Some(rect.w - 12.0)) else {
+            continue;
+        };
+        let (w, h) = (rendered.width as f32 + 8.0, rendered.height as f32 + 4.0);
+        if w > rect.w - 8.0 || h > rect.h - 8.0 {
+            continue;
+        }
+        let x = rect.x + rect.w - w - 4.0;
+        let y = rect.y + rect.h - h - 4.0;
+        let badge = Rect::new(x as f64, y as f64, (x + w) as f64, (y + h) as f64).to_rounded_rect(3.0);
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::new([0.0, 0.0, 0.0, 0.55]),
+            None,
+            &badge,
+        );
+        draw_text_to_scene(scene, rendered, x + 4.0, y + 2.0);
+    }
+}
+
+/// Non-color overlay used to tell file categories apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CategoryPattern {
+    Diagonal,
+    BackDiagonal,
+    Crosshatch,
+    Horizontal,
+    Vertical,
+    Grid,
+    Dots,
+}
+
+fn category_pattern(category: FileCategory) -> Option<CategoryPattern> {
+    match category {
+        FileCategory::Archive => Some(CategoryPattern::Diagonal),
+        FileCategory::Image => Some(CategoryPattern::Dots),
+        FileCategory::Video => Some(CategoryPattern::Horizontal),
+        FileCategory::Audio => Some(CategoryPattern::Vertical),
+        FileCategory::Document => Some(CategoryPattern::BackDiagonal),
+        FileCategory::Code => Some(CategoryPattern::Grid),
+        FileCategory::Executable => Some(CategoryPattern::Crosshatch),
+        _ => None,
+    }
+}
+
+fn draw_category_pattern(scene: &mut Scene, rect: &LayoutRect, pattern: CategoryPattern) {
+    const SPACING: f64 = 7.0;
+    let x0 = rect.x as f64;
+    let y0 = rect.y as f64;
+    let x1 = (rect.x + rect.w) as f64;
+    let y1 = (rect.y + rect.h) as f64;
+    let w = x1 - x0;
+    let h = y1 - y0;
+    let ink = Color::new([0.0, 0.0, 0.0, 0.30]);
+
+    let bounds = Rect::new(x0, y0, x1, y1);
+    scene.push_clip_layer(Fill::NonZero, Affine::IDENTITY, &bounds);
+
+    if pattern == CategoryPattern::Dots {
+        let mut dots = BezPath::new();
+        let mut y = y0 + SPACING * 0.5;
+        while y < y1 {
+            let mut x = x0 + SPACING * 0.5;
+            while x < x1 {
+                dots.extend(Circle::new((x, y), 1.3).path_elements(0.1));
+                x += SPACING;
+            }
+            y += SPACING;
+        }
+        scene.fill(Fill::NonZero, Affine::IDENTITY, ink, None, &dots);
+    } else {
+        let mut lines = BezPath::new();
+        let horizontal = matches!(pattern, CategoryPattern::Horizontal | CategoryPattern::Grid);
+        let vertical = matches!(pattern, CategoryPattern::Vertical | CategoryPattern::Grid);
+        let diagonal = matches!(pattern, CategoryPattern::Diagonal | CategoryPattern::Crosshatch);
+        let back_diagonal =
+            matches!(pattern, CategoryPattern::BackDiagonal | CategoryPattern::Crosshatch);
+
+        if horizontal {
+            let mut y = y0 + SPACING * 0.5;
+            while y < y1 {
+                lines.move_to((x0, y));
+                lines.line_to((x1, y));
+                y += SPACING;
+            }
+        }
+        if vertical {
+            let mut x = x0 + SPACING * 0.5;
+            while x < x1 {
+                lines.move_to((x, y0));
+                lines.line_to((x, y1));
+                x += SPACING;
+            }
+        }
+        if diagonal || back_diagonal {
+            // Sweep far enough that diagonals cover the whole (clipped) rect.
+            let mut offset = 0.0;
+            while offset < w + h {
+                if diagonal {
+                    lines.move_to((x0 + offset, y0));
+                    lines.line_to((x0 + offset - h, y1));
+                }
+                if back_diagonal {
+                    lines.move_to((x1 - offset, y0));
+                    lines.line_to((x1 - offset + h, y1));
+                }
+                offset += SPACING;
+            }
+        }
+        scene.stroke(&Stroke::new(1.2), Affine::IDENTITY, ink, None, &lines);
+    }
+
+    scene.pop_layer();
+}
+
 /// Draw rendered text to a Vello scene.
 fn draw_text_to_scene(scene: &mut Scene, text_result: TextRenderResult, x: f32, y: f32) {
     let tx = x.round();
@@ -278,18 +706,141 @@ fn draw_text_to_scene(scene: &mut Scene, text_result: TextRenderResult, x: f32,
         );
 }
 
+/// Smallest folder tile (px) that gets a watermark.
+const WATERMARK_MIN_W: f32 = 160.0;
+const WATERMARK_MIN_H: f32 = 80.0;
+const WATERMARK_MIN_AREA: f32 = 40_000.0;
+const MAX_WATERMARKS: usize = 24;
+
+/// Center each large folder's name across its tile in big, low-alpha type. Bigger folders
+/// go first and a watermark overlapping one already drawn is skipped, so a parent's name
+/// doesn't smear into its children's.
+fn draw_dir_watermarks(scene: &mut Scene, layout_rects: &[LayoutRect], tree: &FileTree, text_renderer: &mut TextRenderer) {
+    let mut candidates: Vec<&LayoutRect> = layout_rects
+        .iter()
+        .filter(|r| {
+            tree.get(r.node).is_dir
+                && r.depth >= 1
+                && r.w >= WATERMARK_MIN_W
+                && r.h >= WATERMARK_MIN_H
+                && r.w * r.h >= WATERMARK_MIN_AREA
+        })
+        .collect();
+    candidates.sort_by(|a, b| (b.w * b.h).partial_cmp(&(a.w * a.h)).unwrap());
+
+    let mut placed: Vec<[f32; 4]> = Vec::new();
+    for rect in candidates {
+        if placed.len() >= MAX_WATERMARKS {
+            break;
+        }
+        let name = &tree.get(rect.node).name;
+        let max_text_w = rect.w * 0.8;
+        let font_size = (rect.h * 0.25).clamp(18.0, 72.0);
+        if truncate_label(name, max_text_w, font_size) != name.as_str() {
+            continue;
+        }
+        let Some(rendered) = text_renderer.render_text(name, "default", font_size, Some(max_text_w)) else {
+            continue;
+        };
+        let (text_w, text_h) = (rendered.width as f32, rendered.height as f32);
+        if text_w <= 1.0 || text_h <= 1.0 || text_w > rect.w || text_h > rect.h {
+            continue;
+        }
+        let tx = rect.x + (rect.w - text_w) * 0.5;
+        let ty = rect.y + (rect.h - text_h) * 0.5;
+        let bounds = [tx, ty, tx + text_w, ty + text_h];
+        if placed.iter().any(|b| rects_overlap(*b, bounds)) {
+            continue;
+        }
+        scene
+            .draw_glyphs(&rendered.font)
+            .font_size(rendered.font_size)
+            .transform(Affine::translate((tx.round() as f64, ty.round() as f64)))
+            .brush(Color::new([1.0, 1.0, 1.0, 0.16]))
+            .draw(Fill::NonZero, rendered.glyphs.into_iter());
+        placed.push(bounds);
+    }
+}
+
 fn rects_overlap(a: [f32; 4], b: [f32; 4]) -> bool {
     a[0] < b[2] && a[2] > b[0] && a[1] < b[3] && a[3] > b[1]
 }
 
-fn directory_frame_params(depth: u16) -> (f32, f32) {
-    if depth == 0 {
-        return (0.0, 0.0);
+/// Smallest file tile (px) that gets an in-tile label.
+const FILE_LABEL_MIN_W: f32 = 56.0;
+const FILE_LABEL_MIN_H: f32 = 24.0;
+const FILE_LABEL_MIN_AREA: f32 = 4_000.0;
+const MAX_FILE_LABELS: usize = 120;
+
+/// Center a name-or-extension label in the largest file tiles, skipping any that would
+/// overlap an already placed label.
+fn draw_file_labels(
+    scene: &mut Scene,
+    layout_rects: &[LayoutRect],
+    tree: &FileTree,
+    text_renderer: &mut TextRenderer,
+    label_font_scale: f32,
+    placed_bounds: &mut Vec<[f32; 4]>,
+) {
+    let mut candidates: Vec<&LayoutRect> = layout_rects
+        .iter()
+        .filter(|r| {
+            !tree.get(r.node).is_dir
+                && r.w >= FILE_LABEL_MIN_W
+                && r.h >= FILE_LABEL_MIN_H
+                && r.w * r.h >= FILE_LABEL_MIN_AREA
+        })
+        .collect();
+    candidates.sort_by(|a, b| (b.w * b.h).partial_cmp(&(a.w * a.h)).unwrap());
+
+    let font_size = (11.0 * label_font_scale.clamp(0.6, 2.5)).clamp(8.0, 24.0);
+    let mut drawn = 0usize;
+    for rect in candidates {
+        if drawn >= MAX_FILE_LABELS {
+            break;
+        }
+        let node = tree.get(rect.node);
+        let max_text_w = rect.w - 8.0;
+        let text = if rect.grouped > 0 {
+            let text = format!("{} ({})", extension_pattern(tree, rect.node), format_count(rect.grouped as u64));
+            if truncate_label(&text, max_text_w, font_size) != text {
+                continue;
+            }
+            text
+        } else if truncate_label(&node.name, max_text_w, font_size) == node.name.as_str() {
+            node.name.to_string()
+        } else {
+            let ext = &tree.extensions[node.extension_id as usize];
+            if ext.is_empty() {
+                continue;
+            }
+            let ext = format!(".{}", ext);
+            if truncate_label(&ext, max_text_w, font_size) != ext {
+                continue;
+            }
+            ext
+        };
+
+        let Some(rendered) = text_renderer.render_text(&text, "default", font_size, Some(max_text_w)) else {
+            continue;
+        };
+        let (text_w, text_h) = (rendered.width as f32, rendered.height as f32);
+        if text_w <= 1.0 || text_h <= 1.0 || text_w + 4.0 > rect.w || text_h + 4.0 > rect.h {
+            continue;
+        }
+        let tx = rect.x + (rect.w - text_w) * 0.5;
+        let ty = rect.y + (rect.h - text_h) * 0.5;
+        let bounds = [tx - 1.0, ty - 1.0, tx + text_w + 1.0, ty + text_h + 1.0];
+        if placed_bounds.iter().any(|b| rects_overlap(*b, bounds)) {
+            continue;
+        }
+
+        let bg = Rect::new(bounds[0] as f64, bounds[1] as f64, bounds[2] as f64, bounds[3] as f64);
+        scene.fill(Fill::NonZero, Affine::IDENTITY, Color::new([0.0, 0.0, 0.0, 0.30]), None, &bg);
+        draw_text_to_scene(scene, rendered, tx, ty);
+        placed_bounds.push(bounds);
+        drawn += 1;
     }
-    let scale = 0.92_f32.powi((depth as i32 - 1).max(0));
-    let frame = (2.0 * scale).max(1.0);
-    let header = (16.0 * scale).max(8.0);
-    (frame, header)
 }
 
 fn truncate_label(name: &str, max_width: f32, font_size: f32) -> String {
@@ -319,3 +870,71 @@ pub fn image_from_rgba(buf: Vec<u8>, width: u32, height: u32) -> ImageData {
         height,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use vello::Scene;
+
+    use super::{build_scene, SceneOptions};
+    use crate::layout::{compute_layout_in_rect, LayoutConfig};
+    use crate::render::text::TextRenderer;
+    use crate::tree::arena::{FileNode, FileTree, NodeId};
+    use crate::ui::tooltip::{PercentBasis, SizeUnits};
+
+    /// Roboto from the vendored vello examples, so the test doesn't hinge on system fonts.
+    const TEST_FONT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/vello/examples/assets/roboto/Roboto-Regular.ttf");
+
+    /// Height of the folder label drawn for `dir` at `label_font_scale`.
+    fn label_height(tree: &FileTree, dir: NodeId, config: &LayoutConfig, label_font_scale: f32) -> Option<f32> {
+        let mut text_renderer = TextRenderer::new();
+        text_renderer
+            .load_font_from_path("default", Path::new(TEST_FONT))
+            .expect("bundled test font");
+        let layout = compute_layout_in_rect(tree, tree.root, 0.0, 0.0, 800.0, 600.0, config);
+        let regions = build_scene(
+            &mut Scene::new(),
+            None,
+            &layout.rects,
+            config,
+            tree,
+            None,
+            &mut text_renderer,
+            &SceneOptions {
+                show_text_labels: true,
+                label_top_level: true,
+                show_file_labels: false,
+                show_dir_watermarks: false,
+                label_font_scale,
+                show_hover_info: false,
+                focus_hovered_folder: false,
+                size_units: SizeUnits::default(),
+                share_of: (tree.get(tree.root).size, PercentBasis::default()),
+                show_category_patterns: false,
+            },
+        );
+        let region = regions.iter().find(|r| r.node == dir)?;
+        Some(region.bounds[3] - region.bounds[1])
+    }
+
+    #[test]
+    fn label_font_scale_enlarges_folder_labels() {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        let photos = tree.add_child(root, FileNode::test_dir("photos", 900));
+        tree.add_child(photos, FileNode::test_file("a.jpg", 600));
+        tree.add_child(photos, FileNode::test_file("b.jpg", 300));
+        tree.add_child(root, FileNode::test_file("notes.txt", 100));
+        tree.get_mut(root).size = 1_000;
+
+        // A header band tall enough for the scaled text.
+        let config = LayoutConfig {
+            dir_header_px: 48.0,
+            ..LayoutConfig::default()
+        };
+        let normal = label_height(&tree, photos, &config, 1.0).expect("label was dropped");
+        let scaled = label_height(&tree, photos, &config, 1.5).expect("scaled label was dropped");
+        assert!(scaled > normal * 1.3, "scaled label {} vs {}", scaled, normal);
+    }
+}
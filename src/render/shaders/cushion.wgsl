@@ -5,13 +5,14 @@ struct Uniforms {
     light_dir: vec3<f32>,
     fast_mode: u32,
     exclusion_rect: vec4<f32>,
+    min_brightness: f32,
 };
 
 struct RectInstance {
     rect: vec4<f32>,     // x, y, w, h in pixel space
     color: vec4<f32>,    // linear RGBA
     coeffs: vec4<f32>,   // sx1, sx2, sy1, sy2 in world/pixel space
-    info: vec2<f32>,     // size_log_norm, age_norm (reserved)
+    info: vec2<f32>,     // size_log_norm, depth shade
     _pad: vec2<f32>,
 };
 
@@ -27,6 +28,7 @@ struct VSOut {
     @location(1) local_pos: vec2<f32>,
     @location(2) color: vec4<f32>,
     @location(3) coeffs: vec4<f32>,
+    @location(4) shade: f32,
 };
 
 fn quad_vertex(i: u32) -> vec2<f32> {
@@ -62,6 +64,7 @@ fn vs_main(
     out.local_pos = local;
     out.color = inst.color;
     out.coeffs = inst.coeffs;
+    out.shade = inst.info.y;
     return out;
 }
 
@@ -88,7 +91,7 @@ fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
     }
 
     // Preserve the cushion's 3D feel while keeping color saturation.
-    let brightness = pow(clamp(u.ambient + u.diffuse * ndotl, 0.0, 1.0), 1.16);
+    let brightness = max(pow(clamp(u.ambient + u.diffuse * ndotl, 0.0, 1.0), 1.16), u.min_brightness);
     let shadow = in.color.rgb * 0.55;
     let highlight = in.color.rgb * 0.85 + vec3<f32>(0.12, 0.12, 0.12);
     var rgb = mix(shadow, highlight, brightness);
@@ -100,5 +103,8 @@ fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
     let border = smoothstep(0.0, 0.02, edge);
     rgb *= mix(0.82, 1.0, border);
 
+    // Optional per-depth dimming, so nesting reads without frames.
+    rgb *= in.shade;
+
     return vec4<f32>(rgb, in.color.a);
 }
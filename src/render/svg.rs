@@ -0,0 +1,157 @@
+use std::fmt::Write;
+
+use crate::layout::Layout;
+use crate::render::colors::{self, AppColor, ColorSettings};
+use crate::tree::arena::FileTree;
+
+/// Canvas size for headless exports, which have no window to match.
+pub const DEFAULT_SVG_SIZE: (f32, f32) = (1600.0, 1000.0);
+
+/// Folder labels need at least this much room to be drawn.
+const LABEL_MIN_W: f32 = 48.0;
+const LABEL_MIN_H: f32 = 16.0;
+const LABEL_FONT_SIZE: f32 = 10.0;
+/// Rough advance of one label character, used to truncate names to their rect.
+const LABEL_CHAR_W: f32 = 6.0;
+
+/// Render a layout as a flat (unshaded) SVG document of `width` x `height`.
+///
+/// One `<rect>` per layout rect, parents first so children paint over them, filled with the
+/// same base colors the cushion renderer starts from; folders big enough get a name label.
+pub fn layout_to_svg(
+    layout: &Layout,
+    tree: &FileTree,
+    width: f32,
+    height: f32,
+    color_settings: &ColorSettings,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(out, r##"<rect width="100%" height="100%" fill="#14161c"/>"##);
+
+    for rect in &layout.rects {
+        if rect.w <= 0.0 || rect.h <= 0.0 {
+            continue;
+        }
+        let color = colors::node_color(tree, rect.node, rect.depth, color_settings);
+        let _ = writeln!(
+            out,
+            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" stroke="black" stroke-opacity="0.35" stroke-width="0.5"/>"#,
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+            hex(color)
+        );
+    }
+
+    // Labels go last so no later tile covers them.
+    let _ = writeln!(
+        out,
+        r#"<g font-family="sans-serif" font-size="{}" fill="white" fill-opacity="0.85">"#,
+        LABEL_FONT_SIZE
+    );
+    for rect in &layout.rects {
+        let node = tree.get(rect.node);
+        if !node.is_dir || rect.w < LABEL_MIN_W || rect.h < LABEL_MIN_H {
+            continue;
+        }
+        let max_chars = ((rect.w - 6.0) / LABEL_CHAR_W) as usize;
+        let name: String = if node.name.chars().count() > max_chars {
+            let mut name: String = node.name.chars().take(max_chars.saturating_sub(1)).collect();
+            name.push('…');
+            name
+        } else {
+            node.name.to_string()
+        };
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+            rect.x + 3.0,
+            rect.y + LABEL_FONT_SIZE + 1.0,
+            xml_escape(&name)
+        );
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn hex(color: AppColor) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(color.r), channel(color.g), channel(color.b))
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::layout::LayoutRect;
+    use crate::tree::arena::{FileNode, NodeId};
+
+    fn rect(node: NodeId, x: f32, y: f32, w: f32, h: f32, depth: u16) -> LayoutRect {
+        LayoutRect { node, x, y, w, h, depth, surface: [0.0; 4], grouped: 0 }
+    }
+
+    #[test]
+    fn markup_characters_are_escaped() {
+        assert_eq!(xml_escape(r#"a&b <c> "d""#), "a&amp;b &lt;c&gt; &quot;d&quot;");
+        assert_eq!(xml_escape("plain"), "plain");
+    }
+
+    #[test]
+    fn tiny_layout_becomes_colored_rects_and_labels() {
+        let mut tree = FileTree::new("root");
+        let dir = tree.add_child(tree.root, FileNode::test_dir("R&D <old>", 100));
+        let file = tree.add_child(dir, FileNode::test_file("notes", 100));
+        let layout = Layout {
+            rects: vec![
+                rect(dir, 0.0, 0.0, 200.0, 100.0, 1),
+                rect(file, 4.0, 16.0, 192.0, 80.0, 2),
+                rect(file, 0.0, 0.0, 0.0, 5.0, 2),
+            ],
+            node_to_rect: HashMap::from([(dir, 0), (file, 1)]),
+            overflow: HashMap::new(),
+            coarsening: 0,
+        };
+        let settings = ColorSettings::default();
+
+        let svg = layout_to_svg(&layout, &tree, 200.0, 100.0, &settings);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100""#));
+        assert!(svg.ends_with("</svg>\n"));
+        // Background plus the two rects with an area; the zero-width one is skipped.
+        assert_eq!(svg.matches("<rect ").count(), 3);
+        let dir_fill = hex(colors::node_color(&tree, dir, 1, &settings));
+        assert!(svg.contains(&format!(
+            r#"<rect x="0.00" y="0.00" width="200.00" height="100.00" fill="{dir_fill}""#
+        )));
+        assert!(svg.contains(r#"<rect x="4.00" y="16.00" width="192.00" height="80.00""#));
+        // Only the folder is labelled, with its name escaped.
+        assert_eq!(svg.matches("<text ").count(), 1);
+        assert!(svg.contains(r#"<text x="3.00" y="11.00">R&amp;D &lt;old&gt;</text>"#));
+    }
+
+    #[test]
+    fn colors_are_written_as_hex() {
+        assert_eq!(hex(AppColor { r: 1.0, g: 0.5, b: 0.0, a: 1.0 }), "#ff8000");
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// NTFS volume geometry as reported by `FSCTL_GET_NTFS_VOLUME_DATA`, plus the MFT layout.
+#[derive(Debug, Clone, Default)]
+pub struct VolumeDiagnostics {
+    pub bytes_per_sector: u32,
+    pub bytes_per_cluster: u32,
+    pub bytes_per_record: u32,
+    pub total_clusters: i64,
+    pub mft_start_lcn: i64,
+    pub mft_valid_data_length: i64,
+    pub mft_extent_count: usize,
+    pub mft_extent_bytes: u64,
+}
+
+/// Everything a scan otherwise only reports through `tracing`, collected for bug reports
+/// (`--diagnostics <path>`). Filled in by `scanner::scan` and the scanner it dispatches to.
+#[derive(Debug, Clone, Default)]
+pub struct ScanDiagnostics {
+    pub scan_path: String,
+    /// "mft" or "walkdir"
+    pub method: String,
+    /// File system reported by the volume (Windows drive scans only)
+    pub filesystem: Option<String>,
+    /// Only set by the MFT scanner
+    pub volume: Option<VolumeDiagnostics>,
+    pub records_processed: u64,
+    pub records_skipped: u64,
+    /// Records whose parent wasn't seen yet during the main pass
+    pub records_deferred: u64,
+    /// Deferred records that never resolved and were attached to the root
+    pub records_orphaned: u64,
+    pub attribute_lists_resolved: u64,
+    pub attribute_list_bytes_recovered: u64,
+    pub mft_bytes_read: u64,
+    pub files: u64,
+    pub dirs: u64,
+    pub total_bytes: u64,
+    pub entries: usize,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+impl ScanDiagnostics {
+    /// Render as a pretty-printed JSON object.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n");
+        let _ = writeln!(out, "  \"scan_path\": {},", json_string(&self.scan_path));
+        let _ = writeln!(out, "  \"method\": {},", json_string(&self.method));
+        let _ = writeln!(out, "  \"filesystem\": {},", json_opt_string(self.filesystem.as_deref()));
+        match &self.volume {
+            Some(v) => {
+                out.push_str("  \"volume\": {\n");
+                let _ = writeln!(out, "    \"bytes_per_sector\": {},", v.bytes_per_sector);
+                let _ = writeln!(out, "    \"bytes_per_cluster\": {},", v.bytes_per_cluster);
+                let _ = writeln!(out, "    \"bytes_per_record\": {},", v.bytes_per_record);
+                let _ = writeln!(out, "    \"total_clusters\": {},", v.total_clusters);
+                let _ = writeln!(out, "    \"mft_start_lcn\": {},", v.mft_start_lcn);
+                let _ = writeln!(out, "    \"mft_valid_data_length\": {},", v.mft_valid_data_length);
+                let _ = writeln!(out, "    \"mft_extent_count\": {},", v.mft_extent_count);
+                let _ = writeln!(out, "    \"mft_extent_bytes\": {}", v.mft_extent_bytes);
+                out.push_str("  },\n");
+            }
+            None => out.push_str("  \"volume\": null,\n"),
+        }
+        let counters = [
+            ("records_processed", self.records_processed),
+            ("records_skipped", self.records_skipped),
+            ("records_deferred", self.records_deferred),
+            ("records_orphaned", self.records_orphaned),
+            ("attribute_lists_resolved", self.attribute_lists_resolved),
+            ("attribute_list_bytes_recovered", self.attribute_list_bytes_recovered),
+            ("mft_bytes_read", self.mft_bytes_read),
+            ("files", self.files),
+            ("dirs", self.dirs),
+            ("total_bytes", self.total_bytes),
+            ("entries", self.entries as u64),
+            ("elapsed_ms", self.elapsed_ms),
+        ];
+        for (name, value) in counters {
+            let _ = writeln!(out, "  \"{}\": {},", name, value);
+        }
+        let _ = writeln!(out, "  \"error\": {}", json_opt_string(self.error.as_deref()));
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json())
+            .with_context(|| format!("Failed to write scan diagnostics to {:?}", path))
+    }
+}
+
+/// Render one report per scanned folder as a JSON array, for merged scans.
+pub fn list_to_json(reports: &[ScanDiagnostics]) -> String {
+    let items: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let json = report.to_json();
+            json.lines().map(|line| format!("  {}", line)).collect::<Vec<_>>().join("\n")
+        })
+        .collect();
+    if items.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n{}\n]\n", items.join(",\n"))
+}
+
+pub fn write_json_list(reports: &[ScanDiagnostics], path: &Path) -> Result<()> {
+    std::fs::write(path, list_to_json(reports))
+        .with_context(|| format!("Failed to write scan diagnostics to {:?}", path))
+}
+
+fn json_opt_string(s: Option<&str>) -> String {
+    s.map(json_string).unwrap_or_else(|| "null".to_string())
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_parses_back_with_every_field() {
+        let report = ScanDiagnostics {
+            scan_path: "C:\\Users\\\"me\"\n\u{1}".to_string(),
+            method: "mft".to_string(),
+            filesystem: Some("NTFS".to_string()),
+            volume: Some(VolumeDiagnostics {
+                bytes_per_sector: 512,
+                bytes_per_cluster: 4096,
+                bytes_per_record: 1024,
+                total_clusters: 1 << 40,
+                mft_start_lcn: -1,
+                mft_valid_data_length: 262_144,
+                mft_extent_count: 3,
+                mft_extent_bytes: 262_144,
+            }),
+            records_processed: 250,
+            records_deferred: 4,
+            records_orphaned: 1,
+            total_bytes: u64::MAX,
+            entries: 249,
+            ..Default::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(json["scan_path"], report.scan_path.as_str());
+        assert_eq!(json["filesystem"], "NTFS");
+        assert_eq!(json["volume"]["total_clusters"], 1i64 << 40);
+        assert_eq!(json["volume"]["mft_start_lcn"], -1);
+        assert_eq!(json["records_deferred"], 4);
+        assert_eq!(json["total_bytes"], u64::MAX);
+        assert_eq!(json["error"], serde_json::Value::Null);
+        assert_eq!(json.as_object().unwrap().len(), 17);
+        assert_eq!(json["volume"].as_object().unwrap().len(), 8);
+
+        // A failed walkdir scan has no volume but says why it stopped.
+        let failed = ScanDiagnostics {
+            method: "walkdir".to_string(),
+            error: Some("Access denied".to_string()),
+            ..Default::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&failed.to_json()).unwrap();
+        assert_eq!(json["volume"], serde_json::Value::Null);
+        assert_eq!(json["filesystem"], serde_json::Value::Null);
+        assert_eq!(json["error"], "Access denied");
+    }
+
+    #[test]
+    fn merged_scans_report_each_folder_in_order() {
+        let report = |path: &str| ScanDiagnostics {
+            scan_path: path.to_string(),
+            method: "walkdir".to_string(),
+            ..Default::default()
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&list_to_json(&[report("/home"), report("/srv")])).unwrap();
+        let reports = json.as_array().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0]["scan_path"], "/home");
+        assert_eq!(reports[1]["scan_path"], "/srv");
+        assert_eq!(reports[1]["volume"], serde_json::Value::Null);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&list_to_json(&[])).unwrap(), serde_json::json!([]));
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::elevation;
+
+/// Why a scan could not run. `Display` is the technical description (for logs and
+/// diagnostics); `user_message` adds what the user can do about it.
+#[derive(Debug)]
+pub enum ScanError {
+    /// Opening the raw volume to read its MFT needs Administrator rights.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    NotElevated { drive: char },
+    /// The volume has no MFT to read (FAT32, exFAT, ...).
+    #[cfg_attr(not(windows), allow(dead_code))]
+    NotNtfs { drive: char, filesystem: Option<String> },
+    PathNotFound(PathBuf),
+    /// The path exists but is a file.
+    NotAFolder(PathBuf),
+    AccessDenied(PathBuf),
+    /// The raw volume couldn't be opened or queried for another reason.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    VolumeOpenFailed { drive: char, source: io::Error },
+    /// Reading failed partway, or what was read didn't make sense.
+    Io { path: PathBuf, source: io::Error },
+    /// Stopped through its `ScanControl` before it finished.
+    Cancelled,
+}
+
+impl ScanError {
+    /// Classify an I/O error on `path`, picking out missing paths and denied access.
+    pub fn from_io(path: &Path, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::NotFound => ScanError::PathNotFound(path.to_path_buf()),
+            io::ErrorKind::PermissionDenied => ScanError::AccessDenied(path.to_path_buf()),
+            _ => ScanError::Io {
+                path: path.to_path_buf(),
+                source,
+            },
+        }
+    }
+
+    /// One-line explanation for the status banner, with the fix when there is one.
+    pub fn user_message(&self) -> String {
+        match self {
+            ScanError::NotElevated { drive } => format!(
+                "Reading the MFT of {}: needs Administrator rights. Restart SilvaView as \
+                 Administrator, or scan a folder to use file-walk mode.",
+                drive
+            ),
+            ScanError::NotNtfs { drive, filesystem } => format!(
+                "{}: is {}, which has no MFT. Scan it in file-walk mode instead.",
+                drive,
+                filesystem.as_deref().unwrap_or("not NTFS")
+            ),
+            ScanError::PathNotFound(path) => format!("{} does not exist", path.display()),
+            ScanError::NotAFolder(path) => format!("{} is a file. Pick a folder to scan.", path.display()),
+            ScanError::AccessDenied(path) if cfg!(windows) && !elevation::is_elevated() => format!(
+                "Access to {} was denied. Restart SilvaView as Administrator to scan it.",
+                path.display()
+            ),
+            ScanError::AccessDenied(path) => {
+                format!("Access to {} was denied. Check its permissions.", path.display())
+            }
+            ScanError::VolumeOpenFailed { .. } | ScanError::Io { .. } => format!("Could not scan: {}", self),
+            ScanError::Cancelled => "Scan cancelled".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::NotElevated { drive } => {
+                write!(f, "opening volume {}: requires Administrator privileges", drive)
+            }
+            ScanError::NotNtfs { drive, filesystem } => write!(
+                f,
+                "{}: is {} rather than NTFS",
+                drive,
+                filesystem.as_deref().unwrap_or("not identifiable")
+            ),
+            ScanError::PathNotFound(path) => write!(f, "{} does not exist", path.display()),
+            ScanError::NotAFolder(path) => write!(f, "{} is not a folder", path.display()),
+            ScanError::AccessDenied(path) => write!(f, "access to {} denied", path.display()),
+            ScanError::VolumeOpenFailed { drive, source } => {
+                write!(f, "failed to open volume {}: {}", drive, source)
+            }
+            ScanError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ScanError::Cancelled => write!(f, "scan cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScanError::VolumeOpenFailed { source, .. } | ScanError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_are_classified_by_kind() {
+        let path = Path::new("/data");
+        let classify = |kind| ScanError::from_io(path, io::Error::from(kind));
+        assert!(matches!(classify(io::ErrorKind::NotFound), ScanError::PathNotFound(p) if p == path));
+        assert!(matches!(classify(io::ErrorKind::PermissionDenied), ScanError::AccessDenied(p) if p == path));
+        assert!(matches!(classify(io::ErrorKind::InvalidData), ScanError::Io { .. }));
+    }
+
+    #[test]
+    fn every_variant_has_a_user_message() {
+        let path = PathBuf::from("/data");
+        let io_error = || io::Error::new(io::ErrorKind::InvalidData, "bad record");
+        assert!(ScanError::NotElevated { drive: 'C' }.user_message().contains("Administrator"));
+        assert_eq!(
+            ScanError::NotNtfs { drive: 'E', filesystem: Some("exFAT".into()) }.user_message(),
+            "E: is exFAT, which has no MFT. Scan it in file-walk mode instead."
+        );
+        assert!(ScanError::NotNtfs { drive: 'E', filesystem: None }.user_message().contains("not NTFS"));
+        assert_eq!(ScanError::PathNotFound(path.clone()).user_message(), "/data does not exist");
+        assert_eq!(ScanError::NotAFolder(path.clone()).user_message(), "/data is a file. Pick a folder to scan.");
+        assert!(ScanError::AccessDenied(path.clone()).user_message().starts_with("Access to /data was denied."));
+        assert_eq!(
+            ScanError::VolumeOpenFailed { drive: 'C', source: io_error() }.user_message(),
+            "Could not scan: failed to open volume C: bad record"
+        );
+        assert_eq!(
+            ScanError::Io { path, source: io_error() }.user_message(),
+            "Could not scan: /data: bad record"
+        );
+        assert_eq!(ScanError::Cancelled.user_message(), "Scan cancelled");
+    }
+}
@@ -1,18 +1,25 @@
 use std::collections::HashMap;
+#[cfg(any(windows, test))]
+use std::collections::HashSet;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 
 use anyhow::{Context, Result};
 
-use super::types::{RawFileEntry, ScanProgress};
+#[cfg(windows)]
+use super::diagnostics::VolumeDiagnostics;
+use super::error::ScanError;
+use super::types::{RawFileEntry, ScanOptions, ScanProgress};
+#[cfg(any(windows, test))]
+use super::types::ATTRIBUTE_SHORT_NAME_ONLY;
 
 #[cfg(windows)]
 use windows::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
 #[cfg(windows)]
 use windows::Win32::Storage::FileSystem::{
-    CreateFileW, ReadFile, SetFilePointerEx, FILE_BEGIN, FILE_FLAG_BACKUP_SEMANTICS,
-    FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    CreateFileW, GetVolumeInformationW, ReadFile, SetFilePointerEx, FILE_BEGIN,
+    FILE_FLAG_BACKUP_SEMANTICS, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
 };
 #[cfg(windows)]
 use windows::Win32::System::IO::DeviceIoControl;
@@ -51,22 +58,39 @@ struct MftExtent {
     length: u64,
 }
 
+#[cfg(windows)]
+const ATTR_TYPE_STANDARD_INFORMATION: u32 = 0x10;
 const ATTR_TYPE_FILE_NAME: u32 = 0x30;
 const ATTR_TYPE_DATA: u32 = 0x80;
 const ATTR_TYPE_ATTRIBUTE_LIST: u32 = 0x20;
 const ATTR_TYPE_END: u32 = 0xFFFFFFFF;
 
+/// Name of the synthetic top-level node that holds NTFS system metafiles.
+#[cfg(windows)]
+const SYSTEM_METADATA_NODE: &str = "System (NTFS metadata)";
+
+/// Name of the synthetic top-level node that holds records whose parent never resolved.
+#[cfg(any(windows, test))]
+const ORPHANED_NODE: &str = "(orphaned)";
+
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch.
+#[cfg(windows)]
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
 /// Filename namespace constants
 const FILENAME_NAMESPACE_POSIX: u8 = 0;
 const FILENAME_NAMESPACE_WIN32: u8 = 1;
 const FILENAME_NAMESPACE_WIN32_AND_DOS: u8 = 3;
 
-/// Scan an NTFS volume by directly parsing the Master File Table.
+/// Scan an NTFS volume by directly parsing the Master File Table. With a `scope` folder
+/// only its subtree is reported, rooted there (see `in_scope`).
 #[cfg(windows)]
 pub fn scan_mft(
     drive_letter: char,
+    scope: Option<&Path>,
+    options: ScanOptions,
     progress_tx: mpsc::Sender<ScanProgress>,
-) -> Result<Vec<RawFileEntry>> {
+) -> Result<Vec<RawFileEntry>, ScanError> {
     use windows::Win32::Foundation::GENERIC_READ;
 
     let volume_path = format!("\\\\.\\{}:", drive_letter);
@@ -89,14 +113,15 @@ pub fn scan_mft(
             OPEN_EXISTING,
             FILE_FLAG_BACKUP_SEMANTICS,
             None,
-        )?
-    };
+        )
+    }
+    .map_err(|e| volume_open_error(drive_letter, e))?;
 
     if handle == INVALID_HANDLE_VALUE {
-        anyhow::bail!("Failed to open volume. Administrator privileges required.");
+        return Err(ScanError::NotElevated { drive: drive_letter });
     }
 
-    let result = scan_mft_with_handle(handle, root_path, progress_tx);
+    let result = scan_mft_with_handle(handle, drive_letter, root_path, scope, options, progress_tx);
 
     unsafe {
         let _ = CloseHandle(handle);
@@ -105,12 +130,28 @@ pub fn scan_mft(
     result
 }
 
+/// Opening `\\.\X:` is refused with access denied unless the process is elevated.
+#[cfg(windows)]
+fn volume_open_error(drive: char, error: windows::core::Error) -> ScanError {
+    if error.code() == windows::Win32::Foundation::E_ACCESSDENIED {
+        ScanError::NotElevated { drive }
+    } else {
+        ScanError::VolumeOpenFailed {
+            drive,
+            source: std::io::Error::other(error),
+        }
+    }
+}
+
 #[cfg(windows)]
 fn scan_mft_with_handle(
     handle: HANDLE,
+    drive_letter: char,
     root_path: PathBuf,
+    scope: Option<&Path>,
+    options: ScanOptions,
     progress_tx: mpsc::Sender<ScanProgress>,
-) -> Result<Vec<RawFileEntry>> {
+) -> Result<Vec<RawFileEntry>, ScanError> {
     let start = std::time::Instant::now();
 
     // Get NTFS volume data to find MFT location
@@ -129,11 +170,15 @@ fn scan_mft_with_handle(
             None,
         )
     }
-    .context("Failed to get NTFS volume data. Not an NTFS volume?")?;
+    .map_err(|_| ScanError::NotNtfs {
+        drive: drive_letter,
+        filesystem: volume_filesystem(drive_letter),
+    })?;
 
     let bytes_per_record = volume_data.bytes_per_file_record_segment as usize;
     let bytes_per_cluster = volume_data.bytes_per_cluster as u64;
     let mft_start_offset = volume_data.mft_start_lcn * bytes_per_cluster as i64;
+    let volume_size = (volume_data.number_sectors.max(0) as u64).saturating_mul(volume_data.bytes_per_sector as u64);
 
     tracing::info!(
         "MFT start: cluster {}, offset {}, record size: {} bytes, cluster size: {} bytes",
@@ -156,13 +201,30 @@ fn scan_mft_with_handle(
     // The MFT is a file and can be fragmented. We must parse its data runs
     // to know where all the MFT fragments are on disk.
     // ========================================================================
-    let mft_extents = read_mft_extents(handle, mft_start_offset, bytes_per_record, bytes_per_cluster)?;
+    let mft_extents = read_mft_extents(handle, mft_start_offset, bytes_per_record, bytes_per_cluster, volume_size)
+        .map_err(|e| ScanError::Io {
+            path: root_path.clone(),
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:#}", e)),
+        })?;
 
     tracing::info!(
         "MFT has {} extents covering {} bytes",
         mft_extents.len(),
         mft_extents.iter().map(|e| e.length).sum::<u64>()
     );
+    options.diagnose(|d| {
+        d.method = "mft".to_string();
+        d.volume = Some(VolumeDiagnostics {
+            bytes_per_sector: volume_data.bytes_per_sector,
+            bytes_per_cluster: volume_data.bytes_per_cluster,
+            bytes_per_record: volume_data.bytes_per_file_record_segment,
+            total_clusters: volume_data.total_clusters,
+            mft_start_lcn: volume_data.mft_start_lcn,
+            mft_valid_data_length: volume_data.mft_valid_data_length,
+            mft_extent_count: mft_extents.len(),
+            mft_extent_bytes: mft_extents.iter().map(|e| e.length).sum(),
+        });
+    });
 
     // ========================================================================
     // PHASE 2: Read all MFT records – single-pass incremental path building
@@ -172,59 +234,78 @@ fn scan_mft_with_handle(
     let mut record_paths: HashMap<u64, PathBuf> = HashMap::new();
     record_paths.insert(5, root_path.clone());
 
-    let mut entries: Vec<RawFileEntry> = Vec::new();
-    // Track entries that need $DATA size resolved from extension records
-    let mut needs_size_resolution: HashMap<u64, usize> = HashMap::new();
-
-    let mut files_scanned: u64 = 0;
-    let mut dirs_scanned: u64 = 0;
-    let mut total_bytes: u64 = 0;
+    let mut kept = ScopedEntries::new(scope);
+    // Synthetic parent for system metafiles when they are included in the scan
+    let metadata_root = root_path.join(SYSTEM_METADATA_NODE);
+    let mut metadata_bytes: u64 = 0;
+    if options.include_system_metadata && scope.is_none() {
+        kept.entries.push(RawFileEntry {
+            path: metadata_root.clone(),
+            size: 0,
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir: true,
+            parent: Some(root_path.clone()),
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        });
+    }
     let mut records_processed: u64 = 0;
     let mut records_skipped: u64 = 0;
 
-    // Storage for ATTRIBUTE_LIST extension record resolution
-    let mut base_to_extensions: HashMap<u64, Vec<Vec<u8>>> = HashMap::new();
+    // Storage for ATTRIBUTE_LIST extension record resolution: base record → (record number, bytes)
+    let mut base_to_extensions: HashMap<u64, Vec<(u64, Vec<u8>)>> = HashMap::new();
+    // $ATTRIBUTE_LIST of each base record whose $DATA lives in an extension record
+    let mut attribute_lists: HashMap<u64, AttributeList> = HashMap::new();
 
     // Deferred records: records whose parent path isn't known yet
-    // (parent record number, record number, name, size, is_dir, has_attribute_list)
-    let mut deferred: Vec<(u64, u64, String, u64, bool, bool)> = Vec::new();
+    let mut deferred: Vec<DeferredRecord> = Vec::new();
 
-    let mut global_record_number: u64 = 0;
     let mft_valid_bytes = volume_data.mft_valid_data_length as u64;
+    // On-disk $MFT size; the single-extent fallback has no real length, so use the valid length
+    let mft_on_disk_bytes = if mft_extents.iter().any(|e| e.length == u64::MAX) {
+        mft_valid_bytes
+    } else {
+        mft_extents.iter().map(|e| e.length).sum::<u64>()
+    };
     let mut mft_bytes_read_total: u64 = 0;
 
     const BATCH_SIZE: usize = 1024;
+    const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+    let mut last_progress = std::time::Instant::now();
+    let mut entries_streamed: usize = 0;
     let batch_bytes = bytes_per_record * BATCH_SIZE;
     let mut buffer = vec![0u8; batch_bytes];
 
-    for extent in &mft_extents {
-        let mut extent_bytes_read: u64 = 0;
-
-        while extent_bytes_read < extent.length {
-            if mft_bytes_read_total >= mft_valid_bytes {
-                break;
-            }
-
-            let disk_pos = extent.disk_offset + extent_bytes_read as i64;
-            let remaining_in_extent = extent.length - extent_bytes_read;
-            let remaining_valid = mft_valid_bytes - mft_bytes_read_total;
-            let to_read = (batch_bytes as u64)
-                .min(remaining_in_extent)
-                .min(remaining_valid) as usize;
+    // Records are numbered by their position in the logical $MFT stream, so the read plan
+    // stitches records that straddle an extent boundary instead of dropping them.
+    let mft_reads = plan_mft_reads(
+        &mft_extents,
+        bytes_per_record as u64,
+        mft_valid_bytes,
+        BATCH_SIZE as u64,
+    );
 
-            if to_read < bytes_per_record {
-                break;
-            }
+    'reads: for read in &mft_reads {
+        options.checkpoint()?;
+        let mut filled: usize = 0;
+        for &(disk_pos, length) in &read.pieces {
+            let length = length as usize;
 
             unsafe {
-                SetFilePointerEx(handle, disk_pos, None, FILE_BEGIN)?;
+                SetFilePointerEx(handle, disk_pos, None, FILE_BEGIN).map_err(|e| ScanError::Io {
+                    path: root_path.clone(),
+                    source: std::io::Error::other(e),
+                })?;
             }
 
             let mut bytes_read: u32 = 0;
             let read_result = unsafe {
                 ReadFile(
                     handle,
-                    Some(&mut buffer[..to_read]),
+                    Some(&mut buffer[filled..filled + length]),
                     Some(&mut bytes_read),
                     None,
                 )
@@ -232,257 +313,323 @@ fn scan_mft_with_handle(
 
             if read_result.is_err() || bytes_read == 0 {
                 tracing::warn!(
-                    "Read failed at disk offset {}, extent_offset {}, skipping rest of extent",
+                    "Read failed at disk offset {} (records from {}), skipping batch",
                     disk_pos,
-                    extent_bytes_read
+                    read.first_record
                 );
+                continue 'reads;
+            }
+            filled += bytes_read as usize;
+            if (bytes_read as usize) < length {
                 break;
             }
+        }
 
-            let records_in_batch = (bytes_read as usize) / bytes_per_record;
+        let records_in_batch = filled / bytes_per_record;
 
-            for i in 0..records_in_batch {
-                let record_data = &buffer[i * bytes_per_record..(i + 1) * bytes_per_record];
-                records_processed += 1;
-                let record_number = global_record_number;
-                global_record_number += 1;
+        for i in 0..records_in_batch {
+            let record_data = &buffer[i * bytes_per_record..(i + 1) * bytes_per_record];
+            records_processed += 1;
+            let record_number = read.first_record + i as u64;
 
-                // Quick header checks
-                if record_data.len() < 42 || &record_data[0..4] != b"FILE" {
-                    records_skipped += 1;
-                    continue;
-                }
+            // Quick header checks
+            if record_data.len() < 42 || &record_data[0..4] != b"FILE" {
+                records_skipped += 1;
+                continue;
+            }
 
-                let base_record_ref = read_u48_le(record_data, 0x20);
+            let base_record_ref = read_u48_le(record_data, 0x20);
 
-                if base_record_ref != 0 {
-                    // EXTENSION RECORD → store for later $ATTRIBUTE_LIST resolution
-                    base_to_extensions
-                        .entry(base_record_ref)
-                        .or_insert_with(Vec::new)
-                        .push(record_data.to_vec());
-                    records_skipped += 1;
-                    continue;
-                }
+            if base_record_ref != 0 {
+                // EXTENSION RECORD → store for later $ATTRIBUTE_LIST resolution
+                base_to_extensions
+                    .entry(base_record_ref)
+                    .or_insert_with(Vec::new)
+                    .push((record_number, record_data.to_vec()));
+                records_skipped += 1;
+                continue;
+            }
 
-                // BASE RECORD
-                let mut record_copy = record_data.to_vec();
-                apply_fixups(&mut record_copy);
-                let record = &record_copy;
+            // BASE RECORD
+            let mut record_copy = record_data.to_vec();
+            apply_fixups(&mut record_copy);
+            let record = &record_copy;
 
-                let flags = read_u16_le(record, 22);
-                let in_use = (flags & 0x01) != 0;
-                if !in_use {
-                    records_skipped += 1;
-                    continue;
-                }
+            let flags = read_u16_le(record, 22);
+            let in_use = (flags & 0x01) != 0;
+            if !in_use {
+                records_skipped += 1;
+                continue;
+            }
 
-                let is_directory = (flags & 0x02) != 0;
+            let is_directory = (flags & 0x02) != 0;
 
-                let (best_name, any_name, parent_record, data_size, has_attribute_list, file_name_size) =
-                    parse_mft_attributes(record, is_directory);
+            let (best_name, any_name, parent_record, data_size, has_attribute_list, file_name_size) =
+                parse_mft_attributes(record, is_directory);
+            let mut meta = parse_record_metadata(record, is_directory);
+            if best_name.is_none() && any_name.is_some() {
+                // The long name may still turn up in an extension record; see below.
+                meta.attributes |= ATTRIBUTE_SHORT_NAME_ONLY;
+            }
 
-                // Use best_name, falling back to any_name (which includes DOS names)
-                let name = best_name.or(any_name);
+            // Use best_name, falling back to any_name (which includes DOS names)
+            let name = best_name.or(any_name);
 
-                if let (Some(name), Some(parent)) = (name, parent_record) {
-                    // Skip system metafiles
-                    if name.starts_with('$') && record_number < 24 {
+            if !is_directory && data_size.is_none() && has_attribute_list {
+                if let Some(list) = find_attribute_list(record, bytes_per_cluster, volume_size) {
+                    attribute_lists.insert(record_number, list);
+                }
+            }
+
+            if let (Some(name), Some(parent)) = (name, parent_record) {
+                // System metafiles are skipped unless the caller asked to see the overhead
+                if name.starts_with('$') && record_number < 24 {
+                    if !options.include_system_metadata {
                         records_skipped += 1;
                         continue;
                     }
 
-                    let final_size = if is_directory {
+                    let full_path = metadata_root.join(&name);
+                    let size = if is_directory {
+                        // $Extend: its children ($UsnJrnl, $ObjId, ...) resolve through it
+                        record_paths.insert(record_number, full_path.clone());
                         0
+                    } else if record_number == 0 {
+                        // Record 0's extent list is the authoritative on-disk size of $MFT
+                        mft_on_disk_bytes
                     } else {
                         data_size.unwrap_or(file_name_size)
                     };
+                    if scope.is_some() {
+                        continue;
+                    }
+                    metadata_bytes += size;
+                    let entry = RawFileEntry {
+                        path: full_path,
+                        size,
+                        allocated: if record_number == 0 { Some(mft_on_disk_bytes) } else { meta.allocated },
+                        modified: meta.modified,
+                        attributes: meta.attributes,
+                        is_dir: is_directory,
+                        parent: Some(metadata_root.clone()),
+                        mft_record: Some(record_number),
+                        orphaned: false,
+                        unexpanded: false,
+                    };
+                    kept.push(entry, false);
+                    continue;
+                }
 
-                    // Build path incrementally from parent
-                    if let Some(parent_path) = record_paths.get(&parent).cloned() {
-                        let full_path = parent_path.join(&name);
-
-                        // Register directory paths so children can find them
-                        if is_directory {
-                            record_paths.insert(record_number, full_path.clone());
-                        }
-
-                        let entry = RawFileEntry {
-                            path: full_path,
-                            size: final_size,
-                            is_dir: is_directory,
-                            parent: Some(parent_path),
-                            mft_record: Some(record_number),
-                        };
+                let final_size = if is_directory {
+                    0
+                } else {
+                    data_size.unwrap_or(file_name_size)
+                };
 
-                        let entry_idx = entries.len();
-                        entries.push(entry);
-
-                        // Track for $ATTRIBUTE_LIST resolution
-                        if !is_directory && data_size.is_none() && has_attribute_list {
-                            needs_size_resolution.insert(record_number, entry_idx);
-                        }
-                    } else {
-                        // Parent not yet seen — defer for later
-                        deferred.push((parent, record_number, name, final_size, is_directory, !is_directory && data_size.is_none() && has_attribute_list));
-                    }
+                // Build path incrementally from parent
+                if let Some(parent_path) = record_paths.get(&parent).cloned() {
+                    let full_path = parent_path.join(&name);
 
+                    // Register directory paths so children can find them
                     if is_directory {
-                        dirs_scanned += 1;
-                    } else {
-                        files_scanned += 1;
-                        total_bytes += final_size;
+                        record_paths.insert(record_number, full_path.clone());
                     }
+
+                    let entry = RawFileEntry {
+                        path: full_path,
+                        size: final_size,
+                        allocated: meta.allocated,
+                        modified: meta.modified,
+                        attributes: meta.attributes,
+                        is_dir: is_directory,
+                        parent: Some(parent_path),
+                        mft_record: Some(record_number),
+                        orphaned: false,
+                        unexpanded: false,
+                    };
+                    kept.push(entry, !is_directory && data_size.is_none() && has_attribute_list);
                 } else {
-                    records_skipped += 1;
+                    // Parent not yet seen — defer for later
+                    deferred.push(DeferredRecord {
+                        parent,
+                        record_number,
+                        name,
+                        size: final_size,
+                        is_dir: is_directory,
+                        needs_attr_resolve: !is_directory && data_size.is_none() && has_attribute_list,
+                        meta,
+                    });
                 }
-            }
-
-            let actual_read = (records_in_batch * bytes_per_record) as u64;
-            extent_bytes_read += actual_read;
-            mft_bytes_read_total += actual_read;
-
-            // Progress updates
-            if records_processed % 50_000 == 0 {
-                let _ = progress_tx.send(ScanProgress::Progress {
-                    files_scanned,
-                    dirs_scanned,
-                    total_bytes,
-                });
+            } else {
+                records_skipped += 1;
             }
         }
 
-        if mft_bytes_read_total >= mft_valid_bytes {
-            break;
+        mft_bytes_read_total += (records_in_batch * bytes_per_record) as u64;
+
+        // Time-based progress updates so counters climb smoothly regardless of record density
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = std::time::Instant::now();
+            // Records arrive in MFT order, not by folder, so there's no path to show.
+            let _ = progress_tx.send(ScanProgress::Progress {
+                files_scanned: kept.totals.files,
+                dirs_scanned: kept.totals.dirs,
+                total_bytes: kept.totals.bytes,
+                current_path: None,
+            });
+            options.send_partial(&kept.entries, &mut entries_streamed);
         }
     }
 
     // ==================== Resolve deferred records ====================
-    // Records whose parents weren't seen yet during the main scan pass.
-    // Iterate multiple times until no more progress is made.
-    let mut prev_deferred_count = deferred.len() + 1;
-    while !deferred.is_empty() && deferred.len() < prev_deferred_count {
-        prev_deferred_count = deferred.len();
-        let mut still_deferred = Vec::new();
-
-        for (parent, record_number, name, final_size, is_directory, needs_attr_resolve) in deferred {
-            if let Some(parent_path) = record_paths.get(&parent).cloned() {
-                let full_path = parent_path.join(&name);
-
-                if is_directory {
-                    record_paths.insert(record_number, full_path.clone());
-                }
-
-                let entry = RawFileEntry {
-                    path: full_path,
-                    size: final_size,
-                    is_dir: is_directory,
-                    parent: Some(parent_path),
-                    mft_record: Some(record_number),
-                };
-
-                let entry_idx = entries.len();
-                entries.push(entry);
-
-                if needs_attr_resolve {
-                    needs_size_resolution.insert(record_number, entry_idx);
-                }
-            } else {
-                still_deferred.push((parent, record_number, name, final_size, is_directory, needs_attr_resolve));
-            }
-        }
-
-        deferred = still_deferred;
+    // Records whose parents weren't seen yet during the main scan pass. They are counted
+    // once resolved, when their path says whether they are in scope.
+    let records_deferred = deferred.len() as u64;
+    let resolved = resolve_deferred(deferred, &mut record_paths, &root_path);
+    let records_orphaned = resolved.iter().filter(|(e, _)| e.orphaned && e.mft_record.is_some()).count() as u64;
+    for (entry, needs_attr_resolve) in resolved {
+        kept.push(entry, needs_attr_resolve);
     }
 
-    if !deferred.is_empty() {
-        tracing::warn!(
-            "{} records could not be resolved (orphaned parent references), attaching to root",
-            deferred.len()
+    // ==================== Long names for 8.3-only records ====================
+    // A record with many names or attributes can keep its Win32 $FILE_NAME in an
+    // extension record, leaving only the DOS alias in the base record.
+    let long_names: Vec<(usize, String)> = kept
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.attributes & ATTRIBUTE_SHORT_NAME_ONLY != 0)
+        .filter_map(|(idx, e)| {
+            let extensions = base_to_extensions.get(&e.mft_record?)?;
+            extensions.iter().find_map(|(_, data)| {
+                let mut ext_copy = data.clone();
+                apply_fixups(&mut ext_copy);
+                parse_mft_attributes(&ext_copy, e.is_dir).0.map(|name| (idx, name))
+            })
+        })
+        .collect();
+    let long_names_restored = long_names.len();
+    restore_long_names(&mut kept.entries, long_names);
+    let short_names_left = kept.entries.iter().filter(|e| e.attributes & ATTRIBUTE_SHORT_NAME_ONLY != 0).count();
+    if long_names_restored > 0 || short_names_left > 0 {
+        tracing::info!(
+            "Restored long names for {} 8.3-only records; {} keep their short name",
+            long_names_restored,
+            short_names_left
         );
-        for (_, record_number, name, final_size, is_directory, needs_attr_resolve) in deferred {
-            let full_path = root_path.join(&name);
-
-            if is_directory {
-                record_paths.insert(record_number, full_path.clone());
-            }
-
-            let entry = RawFileEntry {
-                path: full_path,
-                size: final_size,
-                is_dir: is_directory,
-                parent: Some(root_path.clone()),
-                mft_record: Some(record_number),
-            };
-
-            let entry_idx = entries.len();
-            entries.push(entry);
-
-            if needs_attr_resolve {
-                needs_size_resolution.insert(record_number, entry_idx);
-            }
-        }
     }
 
     // ==================== $ATTRIBUTE_LIST extension resolution ====================
     let mut resolved_count = 0u64;
     let mut recovered_bytes: u64 = 0;
 
-    for (base_ref, extensions) in &base_to_extensions {
-        if let Some(&idx) = needs_size_resolution.get(base_ref) {
-            // Look for $DATA in any of the extension records
-            let mut data_size_from_ext: Option<u64> = None;
-            for ext_data in extensions {
-                let mut ext_copy = ext_data.clone();
-                apply_fixups(&mut ext_copy);
-                if let Some(size) = parse_data_size_from_record(&ext_copy) {
-                    data_size_from_ext = Some(size);
-                    break;
+    let mut records_fetched = 0u64;
+    let no_extensions = Vec::new();
+
+    for (base_ref, &idx) in &kept.needs_size_resolution {
+        let extensions = base_to_extensions.get(base_ref).unwrap_or(&no_extensions);
+
+        // The list names the record holding the first $DATA segment, the only one that
+        // carries the stream size. Fetch it if the pass above never saw it.
+        let listed = attribute_lists
+            .get(base_ref)
+            .and_then(|list| match list {
+                AttributeList::Resident(bytes) => attribute_list_data_record(bytes),
+                AttributeList::NonResident { extents, size } => {
+                    // Volume reads must cover whole sectors, so read the list's clusters
+                    // whole and drop the slack past its end.
+                    let clusters = size.next_multiple_of(bytes_per_cluster);
+                    let mut bytes = read_pieces(handle, &extent_pieces(extents, 0, clusters))?;
+                    bytes.truncate(*size as usize);
+                    attribute_list_data_record(&bytes)
                 }
+            })
+            .filter(|&n| n != *base_ref);
+        let listed_record = listed.and_then(|n| match extensions.iter().find(|(r, _)| *r == n) {
+            Some((_, data)) => Some(data.clone()),
+            None => {
+                let pieces = extent_pieces(&mft_extents, n * bytes_per_record as u64, bytes_per_record as u64);
+                let data = read_pieces(handle, &pieces).filter(|d| d.starts_with(b"FILE"))?;
+                records_fetched += 1;
+                Some(data)
             }
+        });
 
-            if let Some(new_size) = data_size_from_ext {
-                let entry = &mut entries[idx];
-                let old_size = entry.size;
-                if new_size > old_size {
-                    recovered_bytes += new_size - old_size;
-                    entry.size = new_size;
-                    total_bytes = total_bytes - old_size + new_size;
-                    resolved_count += 1;
-                }
+        // Without a usable list, take $DATA from whichever extension record has one
+        let data_size_from_ext = listed_record
+            .iter()
+            .chain(extensions.iter().map(|(_, data)| data))
+            .find_map(|ext_data| {
+                let mut ext_copy = ext_data.clone();
+                apply_fixups(&mut ext_copy);
+                parse_data_size_from_record(&ext_copy)
+            });
+
+        if let Some(new_size) = data_size_from_ext {
+            let entry = &mut kept.entries[idx];
+            let old_size = entry.size;
+            if new_size > old_size {
+                recovered_bytes += new_size - old_size;
+                entry.size = new_size;
+                kept.totals.bytes += new_size - old_size;
+                resolved_count += 1;
             }
         }
     }
 
     tracing::info!(
-        "Resolved $ATTRIBUTE_LIST for {} files → recovered {:.2} GB",
+        "Resolved $ATTRIBUTE_LIST for {} files ({} extension records fetched) → recovered {:.2} GiB",
         resolved_count,
+        records_fetched,
         recovered_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
     );
 
+    if options.include_system_metadata {
+        tracing::info!(
+            "NTFS system metadata: {:.2} GiB reported under '{}'",
+            metadata_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+            SYSTEM_METADATA_NODE
+        );
+    }
+
+    let (entries, totals) = kept.finish();
     let elapsed = start.elapsed();
-    let total_gb = total_bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+    let total_gib = totals.bytes as f64 / (1024.0 * 1024.0 * 1024.0);
 
     tracing::info!(
-        "MFT scan complete: {} files, {} dirs, {:.2} GB in {:.2}s",
-        files_scanned,
-        dirs_scanned,
-        total_gb,
+        "MFT scan complete: {} files, {} dirs, {:.2} GiB in {:.2}s",
+        totals.files,
+        totals.dirs,
+        total_gib,
         elapsed.as_secs_f64()
     );
 
     tracing::info!(
-        "MFT statistics: processed {} records, skipped {}, yielded {} entries, read {} bytes of MFT",
+        "MFT statistics: processed {} records, skipped {}, yielded {} entries ({} orphaned), read {} bytes of MFT",
         records_processed,
         records_skipped,
-        files_scanned + dirs_scanned,
+        entries.len(),
+        records_orphaned,
         mft_bytes_read_total
     );
 
+    options.diagnose(|d| {
+        d.records_processed = records_processed;
+        d.records_skipped = records_skipped;
+        d.records_deferred = records_deferred;
+        d.records_orphaned = records_orphaned;
+        d.attribute_lists_resolved = resolved_count;
+        d.attribute_list_bytes_recovered = recovered_bytes;
+        d.mft_bytes_read = mft_bytes_read_total;
+        d.files = totals.files;
+        d.dirs = totals.dirs;
+        d.total_bytes = totals.bytes;
+    });
+
     let _ = progress_tx.send(ScanProgress::Completed {
-        total_files: files_scanned,
-        total_dirs: dirs_scanned,
-        total_bytes,
+        total_files: totals.files,
+        total_dirs: totals.dirs,
+        total_bytes: totals.bytes,
         elapsed_ms: elapsed.as_millis() as u64,
     });
 
@@ -498,6 +645,7 @@ fn read_mft_extents(
     mft_start_offset: i64,
     bytes_per_record: usize,
     bytes_per_cluster: u64,
+    volume_size: u64,
 ) -> Result<Vec<MftExtent>> {
     // Read MFT record 0 (the $MFT file itself)
     let mut record0 = vec![0u8; bytes_per_record];
@@ -553,7 +701,7 @@ fn read_mft_extents(
             let data_runs_offset_in_attr = read_u16_le(&record0, offset + 32) as usize;
             let data_runs_start = offset + data_runs_offset_in_attr;
 
-            let extents = parse_data_runs(&record0[data_runs_start..], bytes_per_cluster)?;
+            let extents = parse_data_runs(&record0[data_runs_start..], bytes_per_cluster, volume_size)?;
             tracing::info!("Parsed {} data runs from $MFT record 0", extents.len());
             return Ok(extents);
         }
@@ -575,7 +723,11 @@ fn read_mft_extents(
 /// Data runs encode a series of (length, offset) pairs in a compact variable-length format.
 /// Each run starts with a header byte: low nibble = bytes for length, high nibble = bytes for offset.
 /// A header byte of 0x00 terminates the list.
-fn parse_data_runs(data: &[u8], bytes_per_cluster: u64) -> Result<Vec<MftExtent>> {
+///
+/// Every extent must lie inside the `volume_size`-byte volume. A run list that leaves it is
+/// corrupt, and since MFT records are numbered by their position in the stream, dropping the
+/// bad extent would misnumber everything after it, so the whole list is rejected.
+fn parse_data_runs(data: &[u8], bytes_per_cluster: u64, volume_size: u64) -> Result<Vec<MftExtent>> {
     let mut extents = Vec::new();
     let mut pos = 0;
     let mut current_lcn: i64 = 0; // Running LCN (offsets are relative to previous)
@@ -620,12 +772,28 @@ fn parse_data_runs(data: &[u8], bytes_per_cluster: u64) -> Result<Vec<MftExtent>
         }
         pos += offset_bytes;
 
-        current_lcn += run_offset;
-
-        extents.push(MftExtent {
-            disk_offset: current_lcn * bytes_per_cluster as i64,
-            length: run_length * bytes_per_cluster,
-        });
+        current_lcn = current_lcn
+            .checked_add(run_offset)
+            .ok_or_else(|| anyhow::anyhow!("MFT data run offset overflows at run {}", extents.len()))?;
+
+        let extent = current_lcn
+            .checked_mul(bytes_per_cluster as i64)
+            .zip(run_length.checked_mul(bytes_per_cluster))
+            .map(|(disk_offset, length)| MftExtent { disk_offset, length })
+            .filter(|e| {
+                e.disk_offset >= 0
+                    && (e.disk_offset as u64).checked_add(e.length).is_some_and(|end| end <= volume_size)
+            });
+        let Some(extent) = extent else {
+            anyhow::bail!(
+                "MFT data run {} (cluster {}, {} clusters) lies outside the {}-byte volume",
+                extents.len(),
+                current_lcn,
+                run_length,
+                volume_size
+            );
+        };
+        extents.push(extent);
     }
 
     if extents.is_empty() {
@@ -635,6 +803,105 @@ fn parse_data_runs(data: &[u8], bytes_per_cluster: u64) -> Result<Vec<MftExtent>
     Ok(extents)
 }
 
+/// One read of whole MFT records.
+///
+/// Records are numbered by their position in the logical `$MFT` $DATA stream. A record whose
+/// bytes straddle an extent boundary is read as several pieces and stitched back together.
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MftRead {
+    /// Record number of the first record covered by this read
+    first_record: u64,
+    /// (disk offset, byte length) pieces, concatenated in order
+    pieces: Vec<(i64, u64)>,
+}
+
+/// Split the MFT extent list into record-aligned reads of at most `batch_records` records.
+///
+/// Extents whose lengths are not multiples of `bytes_per_record` (odd cluster/record size
+/// combinations) would otherwise shift every later record number away from the parent
+/// references stored in $FILE_NAME, so the straddling record is stitched across extents.
+#[cfg(any(windows, test))]
+fn plan_mft_reads(
+    extents: &[MftExtent],
+    bytes_per_record: u64,
+    valid_bytes: u64,
+    batch_records: u64,
+) -> Vec<MftRead> {
+    let mut reads = Vec::new();
+    if bytes_per_record == 0 {
+        return reads;
+    }
+    let batch_records = batch_records.max(1);
+
+    let mut logical: u64 = 0;
+    let mut pending: Option<MftRead> = None;
+    let mut pending_bytes: u64 = 0;
+
+    for extent in extents {
+        if logical >= valid_bytes {
+            break;
+        }
+        let extent_len = extent.length.min(valid_bytes - logical);
+        if extent_len % bytes_per_record != 0 {
+            tracing::debug!(
+                "MFT extent at disk offset {} is not record-aligned ({} bytes, record size {})",
+                extent.disk_offset,
+                extent_len,
+                bytes_per_record
+            );
+        }
+
+        let mut pos: u64 = 0;
+
+        // Finish a record that started at the tail of the previous extent.
+        if let Some(read) = pending.as_mut() {
+            let take = (bytes_per_record - pending_bytes).min(extent_len);
+            read.pieces.push((extent.disk_offset, take));
+            pending_bytes += take;
+            pos += take;
+            if pending_bytes == bytes_per_record {
+                reads.extend(pending.take());
+                pending_bytes = 0;
+            }
+        }
+
+        while pos < extent_len {
+            let remaining = extent_len - pos;
+            let first_record = (logical + pos) / bytes_per_record;
+            let whole = remaining / bytes_per_record;
+            if whole == 0 {
+                pending = Some(MftRead {
+                    first_record,
+                    pieces: vec![(extent.disk_offset + pos as i64, remaining)],
+                });
+                pending_bytes = remaining;
+                break;
+            }
+
+            let count = whole.min(batch_records);
+            reads.push(MftRead {
+                first_record,
+                pieces: vec![(extent.disk_offset + pos as i64, count * bytes_per_record)],
+            });
+            pos += count * bytes_per_record;
+        }
+
+        logical += extent_len;
+    }
+
+    if let Some(read) = pending {
+        tracing::warn!(
+            "Dropping truncated MFT record {} ({} of {} bytes available)",
+            read.first_record,
+            pending_bytes,
+            bytes_per_record
+        );
+    }
+
+    reads
+}
+
 /// Apply Update Sequence Array fixups to an MFT record.
 /// NTFS stores fixup values at sector boundaries to detect corruption.
 fn apply_fixups(record: &mut [u8]) -> bool {
@@ -673,6 +940,51 @@ fn apply_fixups(record: &mut [u8]) -> bool {
 
 // ─── ATTRIBUTE_LIST helper functions ───────────────────────────────────────
 
+/// Rename entries to the long names found for them (`(entry index, name)`), clearing
+/// `ATTRIBUTE_SHORT_NAME_ONLY`, and move everything under a renamed folder along with it.
+#[cfg(any(windows, test))]
+fn restore_long_names(entries: &mut [RawFileEntry], long_names: Vec<(usize, String)>) {
+    // Keyed by the old (short-name) path, so each path is rewritten in one walk no matter
+    // how many of its ancestors were renamed.
+    let mut renamed: HashMap<PathBuf, String> = HashMap::with_capacity(long_names.len());
+    for (idx, name) in long_names {
+        let entry = &mut entries[idx];
+        entry.attributes &= !ATTRIBUTE_SHORT_NAME_ONLY;
+        renamed.insert(entry.path.clone(), name);
+    }
+    if renamed.is_empty() {
+        return;
+    }
+
+    for entry in entries.iter_mut() {
+        for path in std::iter::once(&mut entry.path).chain(entry.parent.as_mut()) {
+            if let Some(restored) = apply_long_names(path, &renamed) {
+                *path = restored;
+            }
+        }
+    }
+}
+
+/// `path` with every component renamed in `renamed` replaced, parents first, or `None` if
+/// nothing along it changed.
+#[cfg(any(windows, test))]
+fn apply_long_names(path: &Path, renamed: &HashMap<PathBuf, String>) -> Option<PathBuf> {
+    let mut old = PathBuf::new();
+    let mut new = PathBuf::new();
+    let mut changed = false;
+    for component in path.components() {
+        old.push(component);
+        match renamed.get(&old) {
+            Some(name) => {
+                new.push(name);
+                changed = true;
+            }
+            None => new.push(component),
+        }
+    }
+    changed.then_some(new)
+}
+
 /// Parse MFT attributes to extract file information, detecting $ATTRIBUTE_LIST presence.
 /// Returns: (best_name, any_name, parent_record, data_size, has_attribute_list, file_name_size)
 ///
@@ -777,6 +1089,183 @@ fn parse_mft_attributes(
     (best_file_name, any_name, parent_record, data_size, has_attribute_list, file_name_size)
 }
 
+/// Per-record details carried alongside the name and size.
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, Copy, Default)]
+struct RecordMetadata {
+    allocated: Option<u64>,
+    modified: Option<u64>,
+    /// FILE_ATTRIBUTE_* bitmask from $STANDARD_INFORMATION
+    attributes: u32,
+}
+
+/// Parse the on-disk allocation of the unnamed $DATA stream, plus the last-modified time
+/// and FILE_ATTRIBUTE flags from $STANDARD_INFORMATION.
+///
+/// Resident data lives inside the record itself and allocates no clusters.
+#[cfg(windows)]
+fn parse_record_metadata(record: &[u8], is_directory: bool) -> RecordMetadata {
+    let mut meta = RecordMetadata::default();
+
+    let first_attr_offset = read_u16_le(record, 20) as usize;
+    let mut offset = first_attr_offset;
+
+    while offset + 16 <= record.len() {
+        let attr_type = read_u32_le(record, offset);
+        if attr_type == ATTR_TYPE_END {
+            break;
+        }
+
+        let attr_length = read_u32_le(record, offset + 4) as usize;
+        if attr_length == 0 || attr_length < 16 || offset + attr_length > record.len() {
+            break;
+        }
+
+        let non_resident = record[offset + 8];
+        let attr_name_length = record[offset + 9] as usize;
+
+        if attr_type == ATTR_TYPE_STANDARD_INFORMATION && non_resident == 0 {
+            let value_offset = offset + read_u16_le(record, offset + 20) as usize;
+            if value_offset + 16 <= record.len() {
+                // Altered time, 100ns ticks since 1601
+                let filetime = read_u64_le(record, value_offset + 8);
+                meta.modified = (filetime / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET_SECS);
+            }
+            if value_offset + 36 <= record.len() {
+                meta.attributes = read_u32_le(record, value_offset + 32);
+            }
+        } else if attr_type == ATTR_TYPE_DATA && attr_name_length == 0 && !is_directory {
+            if non_resident != 0 {
+                if offset + 48 <= record.len() {
+                    meta.allocated = Some(read_u64_le(record, offset + 40));
+                }
+            } else {
+                meta.allocated = Some(0);
+            }
+        }
+
+        offset += attr_length;
+    }
+
+    meta
+}
+
+/// A base record's $ATTRIBUTE_LIST, which names the records holding each of its attributes.
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone)]
+enum AttributeList {
+    /// List entries stored inside the base record
+    Resident(Vec<u8>),
+    /// List entries stored in clusters elsewhere on the volume
+    NonResident { extents: Vec<MftExtent>, size: u64 },
+}
+
+/// Find the $ATTRIBUTE_LIST attribute in a base record.
+#[cfg(any(windows, test))]
+fn find_attribute_list(record: &[u8], bytes_per_cluster: u64, volume_size: u64) -> Option<AttributeList> {
+    let mut offset = read_u16_le(record, 20) as usize;
+
+    while offset + 16 <= record.len() {
+        let attr_type = read_u32_le(record, offset);
+        if attr_type == ATTR_TYPE_END {
+            break;
+        }
+
+        let attr_length = read_u32_le(record, offset + 4) as usize;
+        if attr_length == 0 || attr_length < 16 || offset + attr_length > record.len() {
+            break;
+        }
+
+        if attr_type == ATTR_TYPE_ATTRIBUTE_LIST {
+            let attr = &record[offset..offset + attr_length];
+            if attr[8] == 0 {
+                if attr.len() < 24 {
+                    return None;
+                }
+                let value_length = read_u32_le(attr, 16) as usize;
+                let value_offset = read_u16_le(attr, 20) as usize;
+                return attr
+                    .get(value_offset..value_offset.checked_add(value_length)?)
+                    .map(|value| AttributeList::Resident(value.to_vec()));
+            }
+            if attr.len() < 56 {
+                return None;
+            }
+            let runs_offset = read_u16_le(attr, 32) as usize;
+            let size = read_u64_le(attr, 48);
+            let extents = parse_data_runs(attr.get(runs_offset..)?, bytes_per_cluster, volume_size).ok()?;
+            return Some(AttributeList::NonResident { extents, size });
+        }
+
+        offset += attr_length;
+    }
+    None
+}
+
+/// Record number holding the first segment (starting VCN 0) of the unnamed $DATA stream,
+/// read from $ATTRIBUTE_LIST entries.
+#[cfg(any(windows, test))]
+fn attribute_list_data_record(list: &[u8]) -> Option<u64> {
+    let mut offset = 0;
+    while offset + 0x1A <= list.len() {
+        let attr_type = read_u32_le(list, offset);
+        let entry_length = read_u16_le(list, offset + 4) as usize;
+        if attr_type == ATTR_TYPE_END || entry_length < 0x1A {
+            break;
+        }
+
+        let name_length = list[offset + 6];
+        let starting_vcn = read_u64_le(list, offset + 8);
+        if attr_type == ATTR_TYPE_DATA && name_length == 0 && starting_vcn == 0 {
+            return Some(read_u48_le(list, offset + 0x10));
+        }
+
+        offset += entry_length;
+    }
+    None
+}
+
+/// Disk pieces covering `length` bytes from logical offset `start` of a stream laid out
+/// in `extents`. Returns nothing if the stream ends first.
+#[cfg(any(windows, test))]
+fn extent_pieces(extents: &[MftExtent], start: u64, length: u64) -> Vec<(i64, u64)> {
+    let mut pieces = Vec::new();
+    let (mut logical, mut pos, end) = (0u64, start, start.saturating_add(length));
+    for extent in extents {
+        let extent_end = logical.saturating_add(extent.length);
+        if pos < extent_end && pos < end {
+            let take = extent_end.min(end) - pos;
+            pieces.push((extent.disk_offset + (pos - logical) as i64, take));
+            pos += take;
+        }
+        if pos >= end {
+            return pieces;
+        }
+        logical = extent_end;
+    }
+    Vec::new()
+}
+
+/// Read and concatenate disk pieces, or `None` on an empty plan or any failed read.
+#[cfg(windows)]
+fn read_pieces(handle: HANDLE, pieces: &[(i64, u64)]) -> Option<Vec<u8>> {
+    if pieces.is_empty() {
+        return None;
+    }
+    let mut data = Vec::new();
+    for &(disk_pos, length) in pieces {
+        let start = data.len();
+        data.resize(start + length as usize, 0);
+        unsafe { SetFilePointerEx(handle, disk_pos, None, FILE_BEGIN) }.ok()?;
+        let mut bytes_read: u32 = 0;
+        unsafe { ReadFile(handle, Some(&mut data[start..]), Some(&mut bytes_read), None) }.ok()?;
+        if bytes_read as u64 != length {
+            return None;
+        }
+    }
+    Some(data)
+}
+
 /// Parse $DATA size from a record (used for extension records in Pass 2).
 fn parse_data_size_from_record(record: &[u8]) -> Option<u64> {
     if record.len() < 42 {
@@ -864,17 +1353,59 @@ fn read_u64_le(data: &[u8], offset: usize) -> u64 {
 #[cfg(not(windows))]
 pub fn scan_mft(
     drive_letter: char,
+    scope: Option<&Path>,
+    options: ScanOptions,
     progress_tx: mpsc::Sender<ScanProgress>,
-) -> Result<Vec<RawFileEntry>> {
+) -> Result<Vec<RawFileEntry>, ScanError> {
     let root = PathBuf::from(format!("{}:\\", drive_letter));
     tracing::warn!("MFT scanning only available on Windows, falling back to jwalk");
-    super::walk::scan_walkdir(&root, progress_tx)
+    super::walk::scan_walkdir(scope.unwrap_or(&root), options, progress_tx)
+}
+
+/// File system name of a drive as reported by the volume ("NTFS", "exFAT", "FAT32", ...).
+#[cfg(windows)]
+pub fn volume_filesystem(drive_letter: char) -> Option<String> {
+    let root: Vec<u16> = format!("{}:\\", drive_letter)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut fs_name = [0u16; 32];
+
+    unsafe {
+        GetVolumeInformationW(
+            PCWSTR(root.as_ptr()),
+            None,
+            None,
+            None,
+            None,
+            Some(&mut fs_name),
+        )
+    }
+    .ok()?;
+
+    let len = fs_name.iter().position(|&c| c == 0).unwrap_or(fs_name.len());
+    Some(String::from_utf16_lossy(&fs_name[..len]))
+}
+
+#[cfg(not(windows))]
+pub fn volume_filesystem(_drive_letter: char) -> Option<String> {
+    None
+}
+
+/// Whether the drive is NTFS; the MFT scanner cannot read anything else.
+pub fn is_ntfs_volume(drive_letter: char) -> bool {
+    volume_filesystem(drive_letter).is_some_and(|fs| fs.eq_ignore_ascii_case("NTFS"))
 }
 
 #[cfg(windows)]
 pub fn is_mft_available(drive_letter: char) -> bool {
     use windows::Win32::Foundation::GENERIC_READ;
 
+    // FAT32/exFAT volumes open fine but have no MFT to read
+    if !is_ntfs_volume(drive_letter) {
+        return false;
+    }
+
     let volume_path = format!("\\\\.\\{}:", drive_letter);
     let wide_path: Vec<u16> = volume_path.encode_utf16().chain(std::iter::once(0)).collect();
 
@@ -905,3 +1436,647 @@ pub fn is_mft_available(drive_letter: char) -> bool {
 pub fn is_mft_available(_drive_letter: char) -> bool {
     false
 }
+
+/// A record read before its parent directory, waiting for the parent's path.
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone)]
+struct DeferredRecord {
+    parent: u64,
+    record_number: u64,
+    name: String,
+    size: u64,
+    is_dir: bool,
+    /// Its $DATA size lives in an $ATTRIBUTE_LIST extension record.
+    needs_attr_resolve: bool,
+    meta: RecordMetadata,
+}
+
+/// Turn deferred records into entries once their parents' paths are known. Passes repeat
+/// while they make progress, so forward references of any depth resolve, and directories
+/// are added to `record_paths` as they resolve. What is left (a parent that never showed
+/// up, or a parent chain that loops) is attached flat under an `(orphaned)` node at
+/// `root_path`. Each entry comes with its record's `needs_attr_resolve`.
+#[cfg(any(windows, test))]
+fn resolve_deferred(
+    mut deferred: Vec<DeferredRecord>,
+    record_paths: &mut HashMap<u64, PathBuf>,
+    root_path: &Path,
+) -> Vec<(RawFileEntry, bool)> {
+    let mut resolved = Vec::new();
+    let entry = |record: &DeferredRecord, path: PathBuf, parent: &Path, orphaned: bool| RawFileEntry {
+        path,
+        size: record.size,
+        allocated: record.meta.allocated,
+        modified: record.meta.modified,
+        attributes: record.meta.attributes,
+        is_dir: record.is_dir,
+        parent: Some(parent.to_path_buf()),
+        mft_record: Some(record.record_number),
+        orphaned,
+        unexpanded: false,
+    };
+
+    let mut prev_deferred_count = deferred.len() + 1;
+    while !deferred.is_empty() && deferred.len() < prev_deferred_count {
+        prev_deferred_count = deferred.len();
+        let mut still_deferred = Vec::new();
+        for record in deferred {
+            let Some(parent_path) = record_paths.get(&record.parent).cloned() else {
+                still_deferred.push(record);
+                continue;
+            };
+            let full_path = parent_path.join(&record.name);
+            if record.is_dir {
+                record_paths.insert(record.record_number, full_path.clone());
+            }
+            resolved.push((entry(&record, full_path, &parent_path, false), record.needs_attr_resolve));
+        }
+        deferred = still_deferred;
+    }
+
+    if deferred.is_empty() {
+        return resolved;
+    }
+    tracing::warn!(
+        "{} records could not be resolved (orphaned parent references), attaching under '{}'",
+        deferred.len(),
+        ORPHANED_NODE
+    );
+    // Keep orphans out of the root's namespace so they can't collide with (and
+    // be merged into or confused with) real top-level entries.
+    let orphan_root = root_path.join(ORPHANED_NODE);
+    resolved.push((
+        RawFileEntry {
+            path: orphan_root.clone(),
+            size: 0,
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir: true,
+            parent: Some(root_path.to_path_buf()),
+            mft_record: None,
+            orphaned: true,
+            unexpanded: false,
+        },
+        false,
+    ));
+    let mut used_names = HashSet::new();
+    for record in deferred {
+        let full_path = orphan_root.join(unique_orphan_name(&record.name, record.record_number, &mut used_names));
+        if record.is_dir {
+            record_paths.insert(record.record_number, full_path.clone());
+        }
+        resolved.push((entry(&record, full_path, &orphan_root, true), record.needs_attr_resolve));
+    }
+    resolved
+}
+
+/// Name for an orphaned record inside the `(orphaned)` node. Records from different
+/// directories can share a name; later ones get their record number appended so every
+/// orphan stays a separate node. Compared case-insensitively, as NTFS does.
+#[cfg(any(windows, test))]
+fn unique_orphan_name(name: &str, record_number: u64, used: &mut HashSet<String>) -> String {
+    if used.insert(name.to_lowercase()) {
+        return name.to_string();
+    }
+    let unique = format!("{} (record {})", name, record_number);
+    used.insert(unique.to_lowercase());
+    unique
+}
+
+/// Whether `path` is `scope` or lies beneath it. NTFS names are case-insensitive, so
+/// components are compared ignoring ASCII case.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn path_is_within(path: &Path, scope: &Path) -> bool {
+    let mut path_components = path.components();
+    scope.components().all(|scope_part| {
+        path_components.next().is_some_and(|part| {
+            part.as_os_str()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(&scope_part.as_os_str().to_string_lossy())
+        })
+    })
+}
+
+/// Whether a record at `path` is kept by a scan narrowed to `scope`, while the records are
+/// read. The MFT is parsed globally, so a folder scan still resolves every record's path,
+/// but only those under `scope` are kept. A path through an 8.3 alias (`PROGRA~1`) may
+/// turn out to be inside once long names are restored, so it is kept until
+/// `root_at_scope` checks again. Orphaned records live under the synthetic `(orphaned)`
+/// node at the drive root and are dropped with the rest.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn in_scope(path: &Path, scope: Option<&Path>) -> bool {
+    scope.is_none_or(|scope| path_is_within(path, scope) || path.to_string_lossy().contains('~'))
+}
+
+/// Finish narrowing a scan to `scope`: drop what `in_scope` kept on account of 8.3 names
+/// and detach the scope directory from its parent so `build_tree` roots the tree there
+/// rather than at the drive.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn root_at_scope(entries: &mut Vec<RawFileEntry>, scope: &Path) {
+    entries.retain(|e| path_is_within(&e.path, scope));
+
+    // Reparent the scope directory itself; synthesize it when the MFT never yielded it.
+    match entries
+        .iter_mut()
+        .find(|e| e.is_dir && e.path.components().count() == scope.components().count())
+    {
+        Some(scope_entry) => scope_entry.parent = None,
+        None => entries.push(RawFileEntry {
+            path: scope.to_path_buf(),
+            size: 0,
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir: true,
+            parent: None,
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        }),
+    }
+
+    tracing::info!("Scoped MFT scan to {}: kept {} entries", scope.display(), entries.len());
+}
+
+/// Files, folders and file bytes a scan reports, for its progress and final totals.
+#[cfg(any(windows, test))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct ScanTotals {
+    files: u64,
+    dirs: u64,
+    bytes: u64,
+}
+
+#[cfg(any(windows, test))]
+impl ScanTotals {
+    fn add(&mut self, entry: &RawFileEntry) {
+        if entry.is_dir {
+            self.dirs += 1;
+        } else {
+            self.files += 1;
+            self.bytes += entry.size;
+        }
+    }
+
+    /// Totals of `entries` below their root, which (like a tree's root) isn't counted.
+    fn of(entries: &[RawFileEntry]) -> Self {
+        let mut totals = Self::default();
+        for entry in entries.iter().filter(|e| e.parent.is_some()) {
+            totals.add(entry);
+        }
+        totals
+    }
+}
+
+/// The entries an MFT scan keeps as it reads records, narrowed to `scope`, and the
+/// running totals of just those. The MFT is read whole, so records outside the scope
+/// are never counted.
+#[cfg(any(windows, test))]
+struct ScopedEntries<'a> {
+    scope: Option<&'a Path>,
+    entries: Vec<RawFileEntry>,
+    /// Entry index of each kept file whose $DATA size lives in an extension record
+    needs_size_resolution: HashMap<u64, usize>,
+    totals: ScanTotals,
+}
+
+#[cfg(any(windows, test))]
+impl<'a> ScopedEntries<'a> {
+    fn new(scope: Option<&'a Path>) -> Self {
+        Self {
+            scope,
+            entries: Vec::new(),
+            needs_size_resolution: HashMap::new(),
+            totals: ScanTotals::default(),
+        }
+    }
+
+    /// Keep and count `entry` if it is in scope (see `in_scope`).
+    fn push(&mut self, entry: RawFileEntry, needs_attr_resolve: bool) {
+        if !in_scope(&entry.path, self.scope) {
+            return;
+        }
+        if let (true, Some(record_number)) = (needs_attr_resolve, entry.mft_record) {
+            self.needs_size_resolution.insert(record_number, self.entries.len());
+        }
+        self.totals.add(&entry);
+        self.entries.push(entry);
+    }
+
+    /// The entries, rooted at the scope when there is one, and their totals.
+    fn finish(mut self) -> (Vec<RawFileEntry>, ScanTotals) {
+        let Some(scope) = self.scope else {
+            return (self.entries, self.totals);
+        };
+        root_at_scope(&mut self.entries, scope);
+        let totals = ScanTotals::of(&self.entries);
+        (self.entries, totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        attribute_list_data_record, extent_pieces, find_attribute_list, parse_data_runs, parse_mft_attributes, plan_mft_reads, resolve_deferred, restore_long_names, root_at_scope,
+        in_scope, unique_orphan_name, AttributeList, DeferredRecord, MftExtent, MftRead, RecordMetadata, ScanTotals, ScopedEntries,
+        ORPHANED_NODE,
+    };
+    use crate::scanner::types::{RawFileEntry, ATTRIBUTE_SHORT_NAME_ONLY};
+
+    fn entry(path: PathBuf, is_dir: bool) -> RawFileEntry {
+        RawFileEntry {
+            parent: path.parent().map(Path::to_path_buf),
+            path,
+            size: if is_dir { 0 } else { 10 },
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir,
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        }
+    }
+
+    fn deferred(record_number: u64, parent: u64, name: &str, is_dir: bool) -> DeferredRecord {
+        DeferredRecord {
+            parent,
+            record_number,
+            name: name.to_string(),
+            size: if is_dir { 0 } else { 10 },
+            is_dir,
+            needs_attr_resolve: false,
+            meta: RecordMetadata::default(),
+        }
+    }
+
+    /// `record_paths` seeded with the root directory, record 5, at `vol`.
+    fn root_paths() -> HashMap<u64, PathBuf> {
+        HashMap::from([(5, PathBuf::from("vol"))])
+    }
+
+    #[test]
+    fn deferred_forward_references_resolve_in_any_order() {
+        let vol = Path::new("vol");
+        let mut record_paths = root_paths();
+        // A file before its folder, and that folder before its own parent.
+        let records = vec![
+            deferred(30, 20, "a.txt", false),
+            deferred(20, 10, "inner", true),
+            deferred(10, 5, "outer", true),
+        ];
+
+        let resolved = resolve_deferred(records, &mut record_paths, vol);
+
+        assert_eq!(resolved.len(), 3);
+        assert!(resolved.iter().all(|(e, _)| !e.orphaned));
+        let file = resolved.iter().find(|(e, _)| e.mft_record == Some(30)).unwrap();
+        assert_eq!(file.0.path, vol.join("outer").join("inner").join("a.txt"));
+        assert_eq!(file.0.parent.as_deref(), Some(vol.join("outer").join("inner").as_path()));
+        assert_eq!(record_paths.get(&20), Some(&vol.join("outer").join("inner")));
+    }
+
+    #[test]
+    fn deferred_cycles_and_missing_parents_become_orphans() {
+        let vol = Path::new("vol");
+        let orphans = vol.join(ORPHANED_NODE);
+        let mut record_paths = root_paths();
+        let records = vec![
+            // 40 and 41 are each other's parent.
+            deferred(40, 41, "loop", true),
+            deferred(41, 40, "Loop", true),
+            // Record 99 never appeared.
+            deferred(50, 99, "lost.bin", false),
+            deferred(60, 5, "fine.txt", false),
+        ];
+
+        let resolved = resolve_deferred(records, &mut record_paths, vol);
+
+        let fine = resolved.iter().find(|(e, _)| e.mft_record == Some(60)).unwrap();
+        assert!(!fine.0.orphaned);
+        let orphan_root = resolved.iter().find(|(e, _)| e.path == orphans).unwrap();
+        assert!(orphan_root.0.orphaned && orphan_root.0.is_dir);
+        assert_eq!(orphan_root.0.parent.as_deref(), Some(vol));
+
+        let mut orphaned: Vec<PathBuf> = resolved
+            .iter()
+            .filter(|(e, _)| e.orphaned && e.mft_record.is_some())
+            .map(|(e, _)| e.path.clone())
+            .collect();
+        orphaned.sort();
+        assert_eq!(
+            orphaned,
+            vec![orphans.join("Loop (record 41)"), orphans.join("loop"), orphans.join("lost.bin")]
+        );
+        assert!(resolved
+            .iter()
+            .filter(|(e, _)| e.orphaned && e.mft_record.is_some())
+            .all(|(e, _)| e.parent.as_deref() == Some(orphans.as_path())));
+    }
+
+    #[test]
+    fn scoped_scans_keep_the_scope_and_detach_it() {
+        let vol = Path::new("vol");
+        let entries = vec![
+            entry(vol.join("Users"), true),
+            entry(vol.join("Users").join("me"), true),
+            entry(vol.join("Users").join("me").join("Projects"), true),
+            entry(vol.join("Users").join("me").join("Projects").join("a.rs"), false),
+            entry(vol.join("Users").join("me").join("notes.txt"), false),
+            entry(vol.join("Windows").join("explorer.exe"), false),
+            entry(vol.join("PROGRA~1").join("app.exe"), false),
+        ];
+
+        // Scope given in different case than the MFT reports it.
+        let scope = vol.join("users").join("ME").join("projects");
+        let mut kept: Vec<_> = entries.into_iter().filter(|e| in_scope(&e.path, Some(&scope))).collect();
+        // 8.3 paths are held back until their long names are known.
+        assert_eq!(kept.len(), 3);
+        root_at_scope(&mut kept, &scope);
+
+        assert_eq!(kept.len(), 2);
+        let scope = kept.iter().find(|e| e.is_dir).unwrap();
+        assert_eq!(scope.path, vol.join("Users").join("me").join("Projects"));
+        assert_eq!(scope.parent, None);
+        assert!(kept.iter().any(|e| e.path.ends_with("a.rs")));
+    }
+
+    #[test]
+    fn scoped_scans_count_only_the_subtree() {
+        let vol = Path::new("vol");
+        let projects = vol.join("Users").join("me").join("Projects");
+        let read_in_order = [
+            entry(vol.join("Users"), true),
+            entry(vol.join("Users").join("me"), true),
+            entry(projects.clone(), true),
+            entry(projects.join("a.rs"), false),
+            entry(vol.join("Users").join("me").join("notes.txt"), false),
+            entry(vol.join("Windows"), true),
+            entry(vol.join("Windows").join("explorer.exe"), false),
+            entry(vol.join("PROGRA~1").join("app.exe"), false),
+        ];
+        // Records read before their parents: one subtree inside the scope, one outside.
+        let mut record_paths = root_paths();
+        record_paths.insert(30, projects.clone());
+        record_paths.insert(40, vol.join("Windows"));
+        let late = vec![
+            deferred(51, 50, "b.rs", false),
+            deferred(50, 30, "src", true),
+            deferred(52, 40, "notepad.exe", false),
+        ];
+        let resolved = resolve_deferred(late, &mut record_paths, vol);
+
+        let scope = vol.join("users").join("me").join("projects");
+        let mut kept = ScopedEntries::new(Some(&scope));
+        for entry in read_in_order {
+            kept.push(entry, false);
+        }
+        for (entry, needs_attr_resolve) in resolved {
+            kept.push(entry, needs_attr_resolve);
+        }
+        let (entries, totals) = kept.finish();
+
+        let tree = crate::tree::build_tree(&entries);
+        let root = tree.get(tree.root);
+        assert_eq!(totals, ScanTotals { files: 2, dirs: 1, bytes: 20 });
+        assert_eq!((root.file_count as u64, root.dir_count as u64, root.size), (totals.files, totals.dirs, totals.bytes));
+    }
+
+    #[test]
+    fn colliding_orphan_names_get_record_suffix() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_orphan_name("Temp", 40, &mut used), "Temp");
+        assert_eq!(unique_orphan_name("TEMP", 41, &mut used), "TEMP (record 41)");
+        assert_eq!(unique_orphan_name("notes.txt", 42, &mut used), "notes.txt");
+    }
+
+    #[test]
+    fn non_record_aligned_extent_is_stitched_and_numbering_stays_aligned() {
+        // 1024-byte records; the first extent ends halfway through record 1.
+        let extents = [
+            MftExtent {
+                disk_offset: 10_000,
+                length: 1536,
+            },
+            MftExtent {
+                disk_offset: 50_000,
+                length: 2560,
+            },
+        ];
+        let reads = plan_mft_reads(&extents, 1024, u64::MAX, 1024);
+        assert_eq!(
+            reads,
+            vec![
+                MftRead {
+                    first_record: 0,
+                    pieces: vec![(10_000, 1024)],
+                },
+                MftRead {
+                    first_record: 1,
+                    pieces: vec![(11_024, 512), (50_000, 512)],
+                },
+                MftRead {
+                    first_record: 2,
+                    pieces: vec![(50_512, 2048)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_are_batched_and_clamped_to_valid_length() {
+        let extents = [MftExtent {
+            disk_offset: 0,
+            length: u64::MAX,
+        }];
+        let reads = plan_mft_reads(&extents, 1024, 5 * 1024 + 100, 2);
+        let firsts: Vec<u64> = reads.iter().map(|r| r.first_record).collect();
+        assert_eq!(firsts, vec![0, 2, 4]);
+        assert_eq!(reads[2].pieces, vec![(4096, 1024)]);
+    }
+
+    #[test]
+    fn data_runs_inside_the_volume_parse() {
+        // 0x21: 1 length byte, 2 offset bytes. 16 clusters at LCN 0x100, then 8 clusters
+        // 0x40 clusters back (negative offset 0xFFC0).
+        let runs = [0x21, 0x10, 0x00, 0x01, 0x21, 0x08, 0xC0, 0xFF, 0x00];
+        let extents = parse_data_runs(&runs, 4096, 1 << 30).unwrap();
+        let got: Vec<(i64, u64)> = extents.iter().map(|e| (e.disk_offset, e.length)).collect();
+        assert_eq!(got, vec![(0x100 * 4096, 16 * 4096), (0xC0 * 4096, 8 * 4096)]);
+    }
+
+    #[test]
+    fn data_runs_outside_the_volume_are_rejected() {
+        let volume = 1 << 30;
+        // A negative first offset would put the extent before the start of the volume.
+        assert!(parse_data_runs(&[0x11, 0x04, 0xF0, 0x00], 4096, volume).is_err());
+        // LCN 0x7FFF_FFFF is far past the end of a 1 GiB volume.
+        assert!(parse_data_runs(&[0x41, 0x04, 0xFF, 0xFF, 0xFF, 0x7F, 0x00], 4096, volume).is_err());
+        // The run starts inside the volume but runs off its end.
+        assert!(parse_data_runs(&[0x31, 0xFF, 0xF0, 0xFF, 0x03, 0x00], 4096, volume).is_err());
+        // Offsets large enough to overflow the running LCN or the byte offset.
+        let huge = [0x81, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00];
+        assert!(parse_data_runs(&huge, 4096, u64::MAX).is_err());
+    }
+
+    /// One $ATTRIBUTE_LIST entry: type, starting VCN and the record holding it.
+    fn list_entry(attr_type: u32, name_length: u8, starting_vcn: u64, record: u64) -> Vec<u8> {
+        let mut entry = vec![0u8; 0x20];
+        entry[0..4].copy_from_slice(&attr_type.to_le_bytes());
+        entry[4..6].copy_from_slice(&0x20u16.to_le_bytes());
+        entry[6] = name_length;
+        entry[8..16].copy_from_slice(&starting_vcn.to_le_bytes());
+        // Sequence number in the top two bytes of the reference.
+        entry[16..24].copy_from_slice(&(record | 0x0003_0000_0000_0000).to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn attribute_list_names_the_first_data_segment() {
+        let list = [
+            list_entry(0x10, 0, 0, 40),
+            // A named stream and a later segment of $DATA don't carry the file size.
+            list_entry(0x80, 4, 0, 41),
+            list_entry(0x80, 0, 512, 43),
+            list_entry(0x80, 0, 0, 42),
+        ]
+        .concat();
+        assert_eq!(attribute_list_data_record(&list), Some(42));
+        assert_eq!(attribute_list_data_record(&list[..0x60]), None);
+    }
+
+    /// A record holding one resident $FILE_NAME per `(namespace, name)`, all under `parent`.
+    fn record_with_names(parent: u64, names: &[(u8, &str)]) -> Vec<u8> {
+        let mut record = vec![0u8; 56];
+        record[20] = 56;
+        for &(namespace, name) in names {
+            let utf16: Vec<u16> = name.encode_utf16().collect();
+            let mut value = vec![0u8; 0x42];
+            value[..6].copy_from_slice(&parent.to_le_bytes()[..6]);
+            value[0x40] = utf16.len() as u8;
+            value[0x41] = namespace;
+            value.extend(utf16.iter().flat_map(|c| c.to_le_bytes()));
+            let length = (24 + value.len()).next_multiple_of(8);
+            let mut attr = vec![0u8; 24];
+            attr[..4].copy_from_slice(&0x30u32.to_le_bytes());
+            attr[4..8].copy_from_slice(&(length as u32).to_le_bytes());
+            attr[20] = 24;
+            attr.extend(value);
+            attr.resize(length, 0);
+            record.extend(attr);
+        }
+        record.extend(0xFFFF_FFFFu32.to_le_bytes());
+        record.resize(record.len() + 8, 0);
+        record
+    }
+
+    #[test]
+    fn dos_only_names_are_kept_but_not_preferred() {
+        // Only a DOS (namespace 2) name: no display name, but the record isn't dropped.
+        let record = record_with_names(5, &[(2, "LONGFI~1.TXT")]);
+        let (best, any, parent, ..) = parse_mft_attributes(&record, false);
+        assert_eq!((best, any.as_deref(), parent), (None, Some("LONGFI~1.TXT"), Some(5)));
+
+        // With a Win32 name alongside, that one wins whichever comes first.
+        let record = record_with_names(5, &[(2, "LONGFI~1.TXT"), (1, "long file name.txt")]);
+        let (best, any, ..) = parse_mft_attributes(&record, false);
+        assert_eq!(best.as_deref(), Some("long file name.txt"));
+        assert_eq!(any.as_deref(), Some("long file name.txt"));
+    }
+
+    #[test]
+    fn restored_long_names_move_folder_contents() {
+        let vol = Path::new("vol");
+        let mut entries = vec![
+            entry(vol.join("PROGRA~1"), true),
+            entry(vol.join("PROGRA~1").join("MYAPPL~1"), true),
+            entry(vol.join("PROGRA~1").join("MYAPPL~1").join("app.exe"), false),
+            entry(vol.join("PROGRA~1").join("README~1.TXT"), false),
+        ];
+        for e in &mut entries {
+            if e.path.file_name().unwrap().to_string_lossy().contains('~') {
+                e.attributes |= ATTRIBUTE_SHORT_NAME_ONLY;
+            }
+        }
+        let long_names = vec![
+            (0, "Program Files".to_string()),
+            (1, "My Application".to_string()),
+            (3, "Read me first.txt".to_string()),
+        ];
+
+        restore_long_names(&mut entries, long_names);
+
+        let app_dir = vol.join("Program Files").join("My Application");
+        assert_eq!(entries[0].path, vol.join("Program Files"));
+        assert_eq!(entries[1].path, app_dir);
+        assert_eq!(entries[1].parent.as_deref(), Some(vol.join("Program Files").as_path()));
+        assert_eq!(entries[2].path, app_dir.join("app.exe"));
+        assert_eq!(entries[2].parent.as_deref(), Some(app_dir.as_path()));
+        assert_eq!(entries[3].path, vol.join("Program Files").join("Read me first.txt"));
+        assert!(entries.iter().all(|e| e.attributes & ATTRIBUTE_SHORT_NAME_ONLY == 0));
+    }
+
+    #[test]
+    fn extent_pieces_follow_the_stream_across_extents() {
+        let extents = [
+            MftExtent { disk_offset: 8192, length: 3000 },
+            MftExtent { disk_offset: 40960, length: 4096 },
+        ];
+        assert_eq!(extent_pieces(&extents, 1024, 1024), vec![(9216, 1024)]);
+        assert_eq!(extent_pieces(&extents, 2048, 1024), vec![(10240, 952), (40960, 72)]);
+        // Past the end of the stream.
+        assert!(extent_pieces(&extents, 6144, 1024).is_empty());
+    }
+
+    /// A FILE record whose only attribute, at offset 56, is `attr`.
+    fn record_with(attr: &[u8]) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[..4].copy_from_slice(b"FILE");
+        record[20..22].copy_from_slice(&56u16.to_le_bytes());
+        record[56..56 + attr.len()].copy_from_slice(attr);
+        record[56 + attr.len()..60 + attr.len()].copy_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn resident_attribute_lists_name_the_first_data_record() {
+        let list = [list_entry(0x10, 0, 0, 40), list_entry(0x80, 0, 16, 42), list_entry(0x80, 0, 0, 41)].concat();
+        let mut attr = vec![0u8; 24];
+        attr[..4].copy_from_slice(&0x20u32.to_le_bytes());
+        attr[4..8].copy_from_slice(&(24 + list.len() as u32).to_le_bytes());
+        attr[16..20].copy_from_slice(&(list.len() as u32).to_le_bytes());
+        attr[20..22].copy_from_slice(&24u16.to_le_bytes());
+        attr.extend_from_slice(&list);
+
+        let Some(AttributeList::Resident(found)) = find_attribute_list(&record_with(&attr), 4096, 1 << 30) else {
+            panic!("resident $ATTRIBUTE_LIST not found");
+        };
+        assert_eq!(found, list);
+        // The $DATA segment at VCN 16 comes first but isn't the one holding the size.
+        assert_eq!(attribute_list_data_record(&found), Some(41));
+    }
+
+    #[test]
+    fn non_resident_attribute_lists_are_found_by_their_runs() {
+        let mut attr = vec![0u8; 72];
+        attr[..4].copy_from_slice(&0x20u32.to_le_bytes());
+        attr[4..8].copy_from_slice(&72u32.to_le_bytes());
+        attr[8] = 1;
+        attr[32..34].copy_from_slice(&64u16.to_le_bytes());
+        attr[48..56].copy_from_slice(&300u64.to_le_bytes());
+        // One cluster at LCN 100.
+        attr[64..67].copy_from_slice(&[0x11, 0x01, 0x64]);
+
+        let Some(AttributeList::NonResident { extents, size }) = find_attribute_list(&record_with(&attr), 4096, 1 << 30)
+        else {
+            panic!("non-resident $ATTRIBUTE_LIST not found");
+        };
+        assert_eq!(size, 300);
+        // The list is read as the whole cluster, which the runs must cover.
+        assert_eq!(extent_pieces(&extents, 0, size.next_multiple_of(4096)), vec![(409_600, 4096)]);
+    }
+}
@@ -1,14 +1,17 @@
+pub mod diagnostics;
 pub mod elevation;
+pub mod error;
 pub mod mft;
 pub mod types;
 pub mod walk;
 
 use std::path::Path;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-
-use self::types::{RawFileEntry, ScanProgress};
+pub use self::error::ScanError;
+use self::diagnostics::ScanDiagnostics;
+use self::types::{RawFileEntry, ScanOptions, ScanProgress};
 
 /// The scanning strategy to use.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,28 +28,134 @@ pub enum ScanMethod {
 pub fn scan(
     path: &Path,
     method: ScanMethod,
+    options: ScanOptions,
+    progress_tx: mpsc::Sender<ScanProgress>,
+) -> Result<Vec<RawFileEntry>, ScanError> {
+    let start = std::time::Instant::now();
+    options.diagnose(|d| {
+        d.scan_path = path.display().to_string();
+        d.filesystem = try_extract_drive_letter(path).and_then(mft::volume_filesystem);
+    });
+
+    let result = scan_with_method(path, method, options.clone(), progress_tx);
+
+    options.diagnose(|d| {
+        d.elapsed_ms = start.elapsed().as_millis() as u64;
+        match &result {
+            Ok(entries) => d.entries = entries.len(),
+            Err(e) => d.error = Some(format!("{:#}", e)),
+        }
+    });
+    result
+}
+
+/// What a scan found, without the tree. See [`estimate`].
+#[derive(Debug, Clone)]
+pub struct ScanEstimate {
+    /// "mft" or "walkdir", whichever actually ran
+    pub method: String,
+    pub entries: usize,
+    pub files: u64,
+    pub dirs: u64,
+    pub total_bytes: u64,
+    pub elapsed: Duration,
+}
+
+/// Scan `path` and count what it holds, without building a tree or layout (`--dry-run`).
+/// The scan is most of a full run's cost, so this tells how long one would take.
+pub fn estimate(path: &Path, method: ScanMethod) -> Result<ScanEstimate, ScanError> {
+    let (progress_tx, _progress_rx) = mpsc::channel();
+    let diagnostics = Arc::new(Mutex::new(ScanDiagnostics::default()));
+    let options = ScanOptions { diagnostics: Some(diagnostics.clone()), ..ScanOptions::default() };
+    let start = Instant::now();
+    let entries = scan(path, method, options, progress_tx)?;
+    let elapsed = start.elapsed();
+
+    let dirs = entries.iter().filter(|e| e.is_dir).count() as u64;
+    let total_bytes = entries.iter().filter(|e| !e.is_dir).map(|e| e.size).sum();
+    let method = diagnostics.lock().map(|d| d.method.clone()).unwrap_or_default();
+    Ok(ScanEstimate {
+        method,
+        entries: entries.len(),
+        files: entries.len() as u64 - dirs,
+        dirs,
+        total_bytes,
+        elapsed,
+    })
+}
+
+/// Check that `path` is a folder we can list before starting a scan, so a bad drop or
+/// stale drive fails with a readable message instead of an empty treemap.
+pub fn check_scan_path(path: &Path) -> Result<(), ScanError> {
+    let meta = std::fs::metadata(path).map_err(|e| ScanError::from_io(path, e))?;
+    if !meta.is_dir() {
+        return Err(ScanError::NotAFolder(path.to_path_buf()));
+    }
+    std::fs::read_dir(path)
+        .map(|_| ())
+        .map_err(|e| ScanError::from_io(path, e))
+}
+
+fn scan_with_method(
+    path: &Path,
+    method: ScanMethod,
+    options: ScanOptions,
     progress_tx: mpsc::Sender<ScanProgress>,
-) -> Result<Vec<RawFileEntry>> {
+) -> Result<Vec<RawFileEntry>, ScanError> {
     match method {
         ScanMethod::Mft => {
             let drive_letter = extract_drive_letter(path)?;
-            mft::scan_mft(drive_letter, progress_tx)
+            if !mft::is_ntfs_volume(drive_letter) {
+                tracing::warn!(
+                    "{}: is {} rather than NTFS, scanning {} with walkdir instead of the MFT",
+                    drive_letter,
+                    mft::volume_filesystem(drive_letter).as_deref().unwrap_or("not identifiable"),
+                    path.display()
+                );
+                return walk::scan_walkdir(path, options, progress_tx);
+            }
+            scan_mft_scoped(drive_letter, path, options, progress_tx)
         }
-        ScanMethod::WalkDir => walk::scan_walkdir(path, progress_tx),
+        ScanMethod::WalkDir => walk::scan_walkdir(path, options, progress_tx),
         ScanMethod::Auto => {
             if let Some(letter) = try_extract_drive_letter(path) {
                 if mft::is_mft_available(letter) {
-                    return mft::scan_mft(letter, progress_tx);
+                    return scan_mft_scoped(letter, path, options, progress_tx);
+                }
+                if let Some(fs) = mft::volume_filesystem(letter).filter(|fs| !fs.eq_ignore_ascii_case("NTFS")) {
+                    tracing::info!("{}: is {}, MFT not applicable", letter, fs);
                 }
             }
-            walk::scan_walkdir(path, progress_tx)
+            tracing::info!("Scanning {} via walkdir", path.display());
+            walk::scan_walkdir(path, options, progress_tx)
         }
     }
 }
 
-fn extract_drive_letter(path: &Path) -> Result<char> {
-    try_extract_drive_letter(path)
-        .ok_or_else(|| anyhow::anyhow!("Cannot extract drive letter from path: {:?}", path))
+/// MFT scan of `path`'s volume, narrowed to `path` when it is a folder rather than the
+/// drive root.
+fn scan_mft_scoped(
+    drive_letter: char,
+    path: &Path,
+    mut options: ScanOptions,
+    progress_tx: mpsc::Sender<ScanProgress>,
+) -> Result<Vec<RawFileEntry>, ScanError> {
+    tracing::info!("Scanning {} via MFT", path.display());
+    if path.parent().is_none() {
+        return mft::scan_mft(drive_letter, None, options, progress_tx);
+    }
+
+    // Entries under 8.3 aliases are only sorted in or out at the end, so partial batches
+    // could preview folders outside the requested one.
+    options.partial_tx = None;
+    mft::scan_mft(drive_letter, Some(path), options, progress_tx)
+}
+
+fn extract_drive_letter(path: &Path) -> Result<char, ScanError> {
+    try_extract_drive_letter(path).ok_or_else(|| ScanError::Io {
+        path: path.to_path_buf(),
+        source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "no drive letter to read an MFT from"),
+    })
 }
 
 fn try_extract_drive_letter(path: &Path) -> Option<char> {
@@ -59,3 +168,22 @@ fn try_extract_drive_letter(path: &Path) -> Option<char> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_paths_must_be_existing_folders() {
+        let dir = std::env::temp_dir().join(format!("silvaview-check-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, b"x").unwrap();
+
+        assert!(check_scan_path(&dir).is_ok());
+        assert!(matches!(check_scan_path(&file), Err(ScanError::NotAFolder(p)) if p == file));
+        let missing = dir.join("missing");
+        assert!(matches!(check_scan_path(&missing), Err(ScanError::PathNotFound(p)) if p == missing));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,18 @@
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+
+use super::diagnostics::ScanDiagnostics;
+use super::error::ScanError;
+
+/// FILE_ATTRIBUTE_* bits kept on entries (same values as the Win32 constants).
+pub const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+pub const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+pub const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+pub const FILE_ATTRIBUTE_TEMPORARY: u32 = 0x100;
+/// Not a Win32 bit: set by the MFT scanner on records whose only name is a DOS 8.3 alias
+/// (`LONGFI~1.TXT`) because no long name could be found for them.
+pub const ATTRIBUTE_SHORT_NAME_ONLY: u32 = 0x8000_0000;
 
 /// Raw file entry collected during scanning, before tree construction.
 #[derive(Debug, Clone)]
@@ -7,6 +21,12 @@ pub struct RawFileEntry {
     pub path: PathBuf,
     /// File size in bytes (0 for directories)
     pub size: u64,
+    /// Bytes allocated on disk, when the scanner can tell (None for directories)
+    pub allocated: Option<u64>,
+    /// Last modification time as Unix seconds, when the scanner can tell
+    pub modified: Option<u64>,
+    /// FILE_ATTRIBUTE_* bitmask (0 when unknown). Off Windows, dotfiles get `FILE_ATTRIBUTE_HIDDEN`.
+    pub attributes: u32,
     /// Whether this entry is a directory
     pub is_dir: bool,
     /// Parent directory path
@@ -15,6 +35,117 @@ pub struct RawFileEntry {
     /// internally to resolve $ATTRIBUTE_LIST attributes that live in
     /// extension records.
     pub mft_record: Option<u64>,
+    /// Set by the MFT scanner when the entry's parent record never resolved and the
+    /// entry was attached to the scan root instead.
+    pub orphaned: bool,
+    /// A directory at a depth-limited scan's cutoff, so its contents weren't listed.
+    pub unexpanded: bool,
+}
+
+/// Options that change what a scan reports.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Report NTFS system metafiles ($MFT, $LogFile, $Bitmap, ...) under a synthetic
+    /// top-level node instead of skipping them. Only honored by the MFT scanner.
+    pub include_system_metadata: bool,
+    /// Receives batches of entries while the scan is still running, so a partial
+    /// tree can be previewed before the final result.
+    pub partial_tx: Option<mpsc::Sender<Vec<RawFileEntry>>>,
+    /// Collects volume and record statistics for `--diagnostics` bug reports.
+    pub diagnostics: Option<Arc<Mutex<ScanDiagnostics>>>,
+    /// Don't list directories more than this many levels below the scan root; the ones at
+    /// the cutoff are reported as `unexpanded`. Only honored by the walkdir scanner (an MFT
+    /// scan reads the whole volume either way).
+    pub max_depth: Option<usize>,
+    /// Lets the UI pause, resume or cancel the scan while it runs.
+    pub control: Option<Arc<ScanControl>>,
+}
+
+impl ScanOptions {
+    /// Update the diagnostics report, if one was requested.
+    pub fn diagnose(&self, f: impl FnOnce(&mut ScanDiagnostics)) {
+        if let Some(diagnostics) = &self.diagnostics {
+            if let Ok(mut d) = diagnostics.lock() {
+                f(&mut d);
+            }
+        }
+    }
+
+    /// Block while the scan is paused; fails once it is cancelled. Scanners call this
+    /// between units of work.
+    pub fn checkpoint(&self) -> Result<(), ScanError> {
+        match &self.control {
+            Some(control) => control.checkpoint(),
+            None => Ok(()),
+        }
+    }
+
+    /// Forward entries added since the last call to the streaming consumer, if any.
+    pub fn send_partial(&self, entries: &[RawFileEntry], sent: &mut usize) {
+        if let Some(tx) = &self.partial_tx {
+            if *sent < entries.len() {
+                let _ = tx.send(entries[*sent..].to_vec());
+                *sent = entries.len();
+            }
+        }
+    }
+}
+
+const SCAN_RUNNING: u8 = 0;
+const SCAN_PAUSED: u8 = 1;
+const SCAN_CANCELLED: u8 = 2;
+
+/// Run state shared between a scan thread and the UI. Checking it is a single atomic
+/// load while the scan runs; a paused scan sleeps on a condition variable until it is
+/// resumed or cancelled.
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    state: AtomicU8,
+    lock: Mutex<()>,
+    changed: Condvar,
+}
+
+impl ScanControl {
+    /// Pause a running scan at its next checkpoint.
+    pub fn pause(&self) {
+        self.transition(SCAN_RUNNING, SCAN_PAUSED);
+    }
+
+    pub fn resume(&self) {
+        self.transition(SCAN_PAUSED, SCAN_RUNNING);
+    }
+
+    /// Stop the scan at its next checkpoint, paused or not. Final.
+    pub fn cancel(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.state.store(SCAN_CANCELLED, Ordering::SeqCst);
+        self.changed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == SCAN_PAUSED
+    }
+
+    fn transition(&self, from: u8, to: u8) {
+        let _guard = self.lock.lock().unwrap();
+        if self.state.compare_exchange(from, to, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            self.changed.notify_all();
+        }
+    }
+
+    fn checkpoint(&self) -> Result<(), ScanError> {
+        if self.state.load(Ordering::Relaxed) == SCAN_RUNNING {
+            return Ok(());
+        }
+        let mut guard = self.lock.lock().unwrap();
+        while self.state.load(Ordering::SeqCst) == SCAN_PAUSED {
+            guard = self.changed.wait(guard).unwrap();
+        }
+        match self.state.load(Ordering::SeqCst) {
+            SCAN_CANCELLED => Err(ScanError::Cancelled),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Progress updates emitted during scanning.
@@ -27,6 +158,8 @@ pub enum ScanProgress {
         files_scanned: u64,
         dirs_scanned: u64,
         total_bytes: u64,
+        /// Directory the scanner is working through, when it walks folder by folder.
+        current_path: Option<PathBuf>,
     },
     /// Scan completed
     Completed {
@@ -37,4 +170,6 @@ pub enum ScanProgress {
     },
     /// Error encountered (non-fatal)
     Error { path: PathBuf, message: String },
+    /// The scan could not run at all
+    Failed { error: Arc<ScanError> },
 }
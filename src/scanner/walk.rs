@@ -1,17 +1,18 @@
 use std::path::Path;
 use std::sync::mpsc;
 
-use anyhow::Result;
 use jwalk::WalkDir;
 
-use super::types::{RawFileEntry, ScanProgress};
+use super::error::ScanError;
+use super::types::{RawFileEntry, ScanOptions, ScanProgress};
 
 /// Scan a directory tree using jwalk (parallel filesystem walker).
 /// This is the fallback scanner that works on any filesystem without admin privileges.
 pub fn scan_walkdir(
     root: &Path,
+    options: ScanOptions,
     progress_tx: mpsc::Sender<ScanProgress>,
-) -> Result<Vec<RawFileEntry>> {
+) -> Result<Vec<RawFileEntry>, ScanError> {
     let _ = progress_tx.send(ScanProgress::Started {
         root: root.to_path_buf(),
     });
@@ -21,8 +22,18 @@ pub fn scan_walkdir(
     let mut files_scanned: u64 = 0;
     let mut dirs_scanned: u64 = 0;
     let mut total_bytes: u64 = 0;
+    let mut entries_streamed: usize = 0;
+    // Slow walks (network shares, permission-heavy trees) still report progress this often.
+    const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+    let mut last_progress = std::time::Instant::now();
 
-    for entry in WalkDir::new(root).skip_hidden(false).sort(false) {
+    let mut walker = WalkDir::new(root).skip_hidden(false).sort(false);
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    for entry in walker {
+        options.checkpoint()?;
         let entry = match entry {
             Ok(e) => e,
             Err(e) => {
@@ -36,20 +47,34 @@ pub fn scan_walkdir(
 
         let path = entry.path();
         let is_dir = entry.file_type().is_dir();
-        let size = if is_dir {
-            0
-        } else {
-            entry.metadata().map(|m| m.len()).unwrap_or(0)
+        let metadata = entry.metadata().ok();
+        let (size, allocated, modified) = match &metadata {
+            Some(m) if !is_dir => (
+                m.len(),
+                allocated_bytes(m),
+                m.modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+            ),
+            _ => (0, None, None),
         };
 
+        let unexpanded = is_dir && options.max_depth == Some(entry.depth);
+        let attributes = file_attributes(&path, metadata.as_ref());
         let parent = path.parent().map(|p| p.to_path_buf());
 
         entries.push(RawFileEntry {
             path,
             size,
+            allocated,
+            modified,
+            attributes,
             is_dir,
             parent,
             mft_record: None,
+            orphaned: false,
+            unexpanded,
         });
 
         if is_dir {
@@ -59,16 +84,33 @@ pub fn scan_walkdir(
             total_bytes += size;
         }
 
-        // Send progress every 10,000 entries
-        if (files_scanned + dirs_scanned) % 10_000 == 0 {
+        // Send progress every 10,000 entries, or sooner when the walk is slow
+        let batch_done = (files_scanned + dirs_scanned) % 10_000 == 0;
+        if batch_done || last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = std::time::Instant::now();
+            let current_path = entries.last().and_then(|e| match e.is_dir {
+                true => Some(e.path.clone()),
+                false => e.parent.clone(),
+            });
             let _ = progress_tx.send(ScanProgress::Progress {
                 files_scanned,
                 dirs_scanned,
                 total_bytes,
+                current_path,
             });
+            if batch_done {
+                options.send_partial(&entries, &mut entries_streamed);
+            }
         }
     }
 
+    options.diagnose(|d| {
+        d.method = "walkdir".to_string();
+        d.files = files_scanned;
+        d.dirs = dirs_scanned;
+        d.total_bytes = total_bytes;
+    });
+
     let elapsed = start.elapsed();
     let _ = progress_tx.send(ScanProgress::Completed {
         total_files: files_scanned,
@@ -79,3 +121,35 @@ pub fn scan_walkdir(
 
     Ok(entries)
 }
+
+/// Size on disk from the block count; not exposed by std on other platforms.
+#[cfg(unix)]
+fn allocated_bytes(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// FILE_ATTRIBUTE_* flags for the entry.
+#[cfg(windows)]
+fn file_attributes(_path: &Path, metadata: Option<&std::fs::Metadata>) -> u32 {
+    use std::os::windows::fs::MetadataExt;
+    metadata.map_or(0, |m| m.file_attributes())
+}
+
+/// Elsewhere only dotfiles map onto a flag: FILE_ATTRIBUTE_HIDDEN.
+#[cfg(not(windows))]
+fn file_attributes(path: &Path, _metadata: Option<&std::fs::Metadata>) -> u32 {
+    let dotfile = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    if dotfile {
+        super::types::FILE_ATTRIBUTE_HIDDEN
+    } else {
+        0
+    }
+}
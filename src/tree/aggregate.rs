@@ -1,26 +1,114 @@
-use super::arena::{FileTree, NodeId};
+use std::collections::HashMap;
+
+use super::arena::{FileTree, NodeId, SizeBasis};
+use super::extensions::FileCategory;
 
 /// Compute aggregated sizes for all directory nodes (bottom-up).
-/// After this, each directory's `size` field equals the sum of all descendant file sizes.
+/// Each file's `size` is first taken from its `logical` or `allocated` size, per the tree's
+/// `size_basis`. After this, each directory's `size` field equals the sum of all descendant
+/// file sizes; `logical`, `allocated`, `modified`, `file_count` and `dir_count` are rolled
+/// up the same way.
 pub fn aggregate_sizes(tree: &mut FileTree) {
     // Process nodes in reverse order (children before parents) since
     // children always have higher indices than their parents in our arena.
     // This is guaranteed by the add_child insertion order.
+    let basis = tree.size_basis;
     let len = tree.nodes.len();
     for i in (0..len).rev() {
-        let node = &tree.nodes[i];
+        let node = &mut tree.nodes[i];
         if !node.is_dir {
+            node.size = match basis {
+                SizeBasis::Logical => node.logical,
+                SizeBasis::OnDisk => node.allocated,
+            };
             continue;
         }
 
         // Sum up all direct children
         let mut total: u64 = 0;
+        let mut logical: u64 = 0;
+        let mut allocated: u64 = 0;
+        let mut modified: u64 = 0;
+        let mut file_count: u32 = 0;
+        let mut dir_count: u32 = 0;
         let mut child = node.first_child;
         while let Some(child_id) = child {
-            total += tree.nodes[child_id.index()].size;
-            child = tree.nodes[child_id.index()].next_sibling;
+            let c = &tree.nodes[child_id.index()];
+            total += c.size;
+            logical += c.logical;
+            allocated += c.allocated;
+            modified = modified.max(c.modified);
+            if c.is_dir {
+                file_count += c.file_count;
+                dir_count += c.dir_count + 1;
+            } else {
+                file_count += 1;
+            }
+            child = c.next_sibling;
+        }
+        let node = &mut tree.nodes[i];
+        node.size = total;
+        node.logical = logical;
+        node.allocated = allocated;
+        node.modified = modified;
+        node.file_count = file_count;
+        node.dir_count = dir_count;
+    }
+}
+
+/// Record the file category holding the most bytes under each directory, and its share of
+/// the directory's bytes (bottom-up). Per-category byte totals are only kept until the
+/// parent directory has absorbed them.
+pub fn aggregate_dominant_categories(tree: &mut FileTree) {
+    const N: usize = FileCategory::ALL.len();
+    let mut pending: HashMap<u32, [u64; N]> = HashMap::new();
+
+    for i in (0..tree.nodes.len()).rev() {
+        if !tree.nodes[i].is_dir {
+            continue;
+        }
+
+        let mut totals = [0u64; N];
+        let mut child = tree.nodes[i].first_child;
+        while let Some(child_id) = child {
+            let c = &tree.nodes[child_id.index()];
+            if !c.is_dir {
+                totals[tree.category(child_id) as usize] += c.size;
+            } else if let Some(sub) = pending.remove(&child_id.0) {
+                for (total, bytes) in totals.iter_mut().zip(sub) {
+                    *total += bytes;
+                }
+            }
+            child = c.next_sibling;
+        }
+
+        let dominant = totals
+            .iter()
+            .enumerate()
+            .filter(|(_, &bytes)| bytes > 0)
+            .max_by_key(|(_, &bytes)| bytes);
+        let sum: u64 = totals.iter().sum();
+        let node = &mut tree.nodes[i];
+        node.dominant_category = dominant.map(|(k, _)| FileCategory::ALL[k]);
+        node.dominant_share = dominant.map_or(0.0, |(_, &bytes)| (bytes as f64 / sum as f64) as f32);
+        if tree.nodes[i].parent.is_some() {
+            pending.insert(i as u32, totals);
         }
-        tree.nodes[i].size = total;
+    }
+}
+
+/// Rank the tree's extensions for `palette_slots`: most bytes first, ties by name. Logical
+/// bytes are used, so switching the size basis doesn't shuffle the colors.
+pub fn rank_extensions(tree: &mut FileTree) {
+    let mut bytes = vec![0u64; tree.extensions.len()];
+    for node in tree.nodes.iter().filter(|n| !n.is_dir) {
+        bytes[node.extension_id as usize] += node.logical;
+    }
+    let mut ranked: Vec<usize> = (0..tree.extensions.len()).collect();
+    ranked.sort_by(|&a, &b| bytes[b].cmp(&bytes[a]).then_with(|| tree.extensions[a].cmp(&tree.extensions[b])));
+    tree.palette_slots = vec![0; ranked.len()];
+    for (rank, id) in ranked.into_iter().enumerate() {
+        tree.palette_slots[id] = rank as u16;
     }
 }
 
@@ -0,0 +1,124 @@
+use std::collections::{HashMap, HashSet};
+
+use super::arena::{FileTree, NodeId};
+use super::extensions::FileCategory;
+
+/// Analytics data for the file type breakdown panel.
+#[derive(Debug, Default)]
+pub struct Analytics {
+    /// Total bytes per category
+    pub category_sizes: Vec<(FileCategory, u64)>,
+    /// Total size of all files
+    pub total_size: u64,
+    /// Part of `total_size` taken by unreclaimable system files (pagefile, hiberfil, ...)
+    pub unreclaimable_size: u64,
+    /// Number of files per category
+    pub category_file_counts: HashMap<FileCategory, u64>,
+    /// Folders with no files anywhere below them
+    pub empty_dirs: u64,
+    /// Files of size 0
+    pub zero_byte_files: u64,
+}
+
+/// Compute analytics for the current view. Without `include_hidden`, files and folders
+/// flagged hidden or system are skipped along with everything beneath them, as are the
+/// subtrees in `excluded` (hidden from the layout by the user) either way.
+pub fn compute_analytics(
+    tree: &FileTree,
+    root: NodeId,
+    include_hidden: bool,
+    excluded: &HashSet<NodeId>,
+) -> Analytics {
+    let mut category_map = HashMap::new();
+    let mut category_file_counts = HashMap::new();
+    let mut total_size = 0u64;
+    let mut unreclaimable_size = 0u64;
+    let (mut empty_dirs, mut zero_byte_files) = (0u64, 0u64);
+
+    let mut stack: Vec<NodeId> = tree.children(root).collect();
+    while let Some(node_id) = stack.pop() {
+        let node = tree.get(node_id);
+        if (!include_hidden && node.is_hidden()) || excluded.contains(&node_id) {
+            continue;
+        }
+
+        if node.is_dir {
+            // A depth-limited scan's unexpanded folders only look empty.
+            if node.file_count == 0 && !tree.unexpanded.contains(&node_id) {
+                empty_dirs += 1;
+            }
+            stack.extend(tree.children(node_id));
+        } else {
+            // Logical size: tiny files stored in the MFT take 0 bytes on disk but aren't empty.
+            if node.logical == 0 {
+                zero_byte_files += 1;
+            }
+            // It's a file - categorize it
+            let category = tree.category(node_id);
+            *category_map.entry(category).or_insert(0u64) += node.size;
+            *category_file_counts.entry(category).or_insert(0u64) += 1;
+            total_size += node.size;
+            if !node.reclaimable {
+                unreclaimable_size += node.size;
+            }
+        }
+    }
+
+    // Sort by size descending
+    let mut category_sizes: Vec<_> = category_map.into_iter().collect();
+    category_sizes.sort_by(|a, b| b.1.cmp(&a.1));
+
+    Analytics {
+        category_sizes,
+        total_size,
+        unreclaimable_size,
+        category_file_counts,
+        empty_dirs,
+        zero_byte_files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    use super::compute_analytics;
+    use crate::scanner::types::RawFileEntry;
+    use crate::tree::arena::SizeBasis;
+
+    fn entry(path: &str, is_dir: bool, size: u64, allocated: u64) -> RawFileEntry {
+        RawFileEntry {
+            path: PathBuf::from(path),
+            size,
+            allocated: Some(allocated),
+            modified: None,
+            attributes: 0,
+            is_dir,
+            parent: None,
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        }
+    }
+
+    #[test]
+    fn analytics_count_empty_folders_and_zero_byte_files() {
+        let mut tree = crate::tree::build_tree(&[
+            entry("/data/full/a.bin", false, 4096, 4096),
+            entry("/data/full/blank.txt", false, 0, 0),
+            entry("/data/empty", true, 0, 0),
+            entry("/data/empty/nested", true, 0, 0),
+            // Small enough to live in the MFT: no clusters, but not empty.
+            entry("/data/full/tiny.ini", false, 300, 0),
+        ]);
+        crate::tree::set_size_basis(&mut tree, SizeBasis::OnDisk);
+        let analytics = compute_analytics(&tree, tree.root, true, &HashSet::new());
+        assert_eq!((analytics.empty_dirs, analytics.zero_byte_files), (2, 1));
+
+        // Folders the user hid from the layout drop out of the totals.
+        let full = crate::tree::find_by_path(&tree, Path::new("/data/full"), Path::new("/data")).unwrap();
+        let analytics = compute_analytics(&tree, tree.root, true, &HashSet::from([full]));
+        assert_eq!((analytics.total_size, analytics.zero_byte_files), (0, 0));
+    }
+}
@@ -1,7 +1,13 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+
 use compact_str::CompactString;
 
+use super::extensions::{categorize_extension, FileCategory};
+use crate::scanner::types::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM};
+
 /// Index into the arena `Vec<FileNode>`. Uses u32 to save memory (supports up to ~4 billion nodes).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NodeId(pub u32);
 
 impl NodeId {
@@ -12,14 +18,43 @@ impl NodeId {
     }
 }
 
+/// Which of a file's sizes the tree is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeBasis {
+    /// What the files claim to hold (the size Explorer lists).
+    #[default]
+    Logical,
+    /// Bytes allocated on disk: compressed and sparse files shrink, small files round up.
+    OnDisk,
+}
+
+impl SizeBasis {
+    pub fn toggled(self) -> Self {
+        match self {
+            SizeBasis::Logical => SizeBasis::OnDisk,
+            SizeBasis::OnDisk => SizeBasis::Logical,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeBasis::Logical => "Logical",
+            SizeBasis::OnDisk => "On Disk",
+        }
+    }
+}
+
 /// A single node in the file tree, stored in a flat arena.
 /// Uses sibling-list representation: each node has `first_child` and `next_sibling`.
 #[derive(Debug, Clone)]
 pub struct FileNode {
     /// File or directory name (not full path)
     pub name: CompactString,
-    /// Size in bytes. For files: actual size. For dirs: aggregated sum of children.
+    /// Size in bytes on the tree's `size_basis`: `logical` or `allocated`.
+    /// For dirs: aggregated sum of children.
     pub size: u64,
+    /// Size the file claims to hold, whatever the size basis. For dirs: aggregated sum.
+    pub logical: u64,
     /// Whether this node is a directory
     pub is_dir: bool,
     /// Index into the global extension table (0 = no extension / directory)
@@ -32,9 +67,79 @@ pub struct FileNode {
     pub next_sibling: Option<NodeId>,
     /// Depth in the tree (root = 0)
     pub depth: u16,
+    /// False for OS-managed files that can't be deleted to free space (pagefile, hiberfil, ...)
+    pub reclaimable: bool,
+    /// Bytes allocated on disk. For files: from the scanner, falling back to `size`.
+    /// For dirs: aggregated sum of children.
+    pub allocated: u64,
+    /// Last modification time as Unix seconds (0 = unknown). For dirs: newest descendant.
+    pub modified: u64,
+    /// FILE_ATTRIBUTE_* bitmask from the scanner (0 = none / unknown)
+    pub attributes: u32,
+    /// Number of files below a directory (0 for files)
+    pub file_count: u32,
+    /// Number of directories below a directory, not counting itself (0 for files)
+    pub dir_count: u32,
+    /// File category holding the most bytes below a directory (None for files / empty dirs)
+    pub dominant_category: Option<FileCategory>,
+    /// Fraction of a directory's bytes in `dominant_category` (0.0 for files / empty dirs)
+    pub dominant_share: f32,
+}
+
+impl FileNode {
+    /// Whether the node is flagged hidden or system, e.g. to leave it out of the view.
+    pub fn is_hidden(&self) -> bool {
+        self.attributes & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0
+    }
+
+    /// A detached node of size 0 with nothing filled in but its name.
+    fn empty(name: &str, is_dir: bool) -> Self {
+        Self {
+            name: CompactString::new(name),
+            size: 0,
+            logical: 0,
+            is_dir,
+            extension_id: 0,
+            parent: None,
+            first_child: None,
+            next_sibling: None,
+            depth: 0,
+            reclaimable: true,
+            allocated: 0,
+            modified: 0,
+            attributes: 0,
+            file_count: 0,
+            dir_count: 0,
+            dominant_category: None,
+            dominant_share: 0.0,
+        }
+    }
+}
+
+/// Detached nodes for building trees by hand in tests, with `add_child`. Sizes are set
+/// as given on files and folders alike; folder totals are the test's to keep.
+#[cfg(test)]
+impl FileNode {
+    pub fn test_file(name: &str, size: u64) -> Self {
+        Self::test_node(name, false, size)
+    }
+
+    pub fn test_dir(name: &str, size: u64) -> Self {
+        Self::test_node(name, true, size)
+    }
+
+    fn test_node(name: &str, is_dir: bool, size: u64) -> Self {
+        Self {
+            size,
+            logical: size,
+            allocated: size,
+            ..Self::empty(name, is_dir)
+        }
+    }
 }
 
 /// The file tree stored as a flat arena of nodes.
+#[derive(Clone)]
 pub struct FileTree {
     /// All nodes in contiguous memory
     pub nodes: Vec<FileNode>,
@@ -42,41 +147,112 @@ pub struct FileTree {
     pub root: NodeId,
     /// Deduplicated extension table: index → extension string (e.g., "pdf", "rs", "exe")
     pub extensions: Vec<CompactString>,
+    /// Reverse of `extensions`, for interning
+    extension_ids: HashMap<CompactString, u16>,
+    /// Palette slot of each extension, by index into `extensions`: their rank by bytes,
+    /// so the same files get the same colors whatever order the scan met them in (set by
+    /// `aggregate::rank_extensions`).
+    pub palette_slots: Vec<u16>,
+    /// Directories that have children but aggregated to size 0 (set by `build_tree`).
+    pub empty_dir_anomalies: usize,
+    /// Scanner records whose parent never resolved, filed under `(orphaned)` (set by `build_tree`).
+    pub orphaned_entries: usize,
+    /// Directories a depth-limited scan stopped at, so their contents are missing. Add to
+    /// it with `mark_unexpanded`.
+    pub unexpanded: HashSet<NodeId>,
+    /// `unexpanded` directories and all their ancestors, for `needs_expansion`.
+    expansion_pending: HashSet<NodeId>,
+    /// Slots of nodes cut out by `clear_children`, refilled by `add_child_reusing`.
+    free_slots: BTreeSet<NodeId>,
+    /// Which size `FileNode::size` holds; change it with `tree::set_size_basis`.
+    pub size_basis: SizeBasis,
 }
 
 impl FileTree {
     /// Create an empty tree with a root node.
     pub fn new(root_name: &str) -> Self {
-        let root_node = FileNode {
-            name: CompactString::new(root_name),
-            size: 0,
-            is_dir: true,
-            extension_id: 0,
-            parent: None,
-            first_child: None,
-            next_sibling: None,
-            depth: 0,
-        };
+        let root_node = FileNode::empty(root_name, true);
 
         FileTree {
             nodes: vec![root_node],
             root: NodeId(0),
             extensions: vec![CompactString::new("")], // index 0 = no extension
+            extension_ids: HashMap::from([(CompactString::new(""), 0)]),
+            palette_slots: Vec::new(),
+            empty_dir_anomalies: 0,
+            orphaned_entries: 0,
+            unexpanded: HashSet::new(),
+            expansion_pending: HashSet::new(),
+            free_slots: BTreeSet::new(),
+            size_basis: SizeBasis::Logical,
         }
     }
 
     /// Add a child node under the given parent. Returns the new node's ID.
-    pub fn add_child(&mut self, parent: NodeId, mut node: FileNode) -> NodeId {
+    pub fn add_child(&mut self, parent: NodeId, node: FileNode) -> NodeId {
         let new_id = NodeId(self.nodes.len() as u32);
-        node.parent = Some(parent);
-        node.depth = self.nodes[parent.index()].depth + 1;
+        self.nodes.push(node);
+        self.link_child(parent, new_id);
+        new_id
+    }
+
+    /// Like `add_child`, but into the lowest free slot past `parent`, so children still
+    /// come after their parents in the arena.
+    pub fn add_child_reusing(&mut self, parent: NodeId, node: FileNode) -> NodeId {
+        let Some(slot) = self.free_slots.range(NodeId(parent.0 + 1)..).next().copied() else {
+            return self.add_child(parent, node);
+        };
+        self.free_slots.remove(&slot);
+        self.nodes[slot.index()] = node;
+        self.link_child(parent, slot);
+        slot
+    }
 
+    fn link_child(&mut self, parent: NodeId, id: NodeId) {
+        let depth = self.nodes[parent.index()].depth + 1;
         // Prepend to parent's child list (O(1))
-        node.next_sibling = self.nodes[parent.index()].first_child;
-        self.nodes[parent.index()].first_child = Some(new_id);
+        let next_sibling = self.nodes[parent.index()].first_child;
+        self.nodes[parent.index()].first_child = Some(id);
+        let node = &mut self.nodes[id.index()];
+        node.parent = Some(parent);
+        node.depth = depth;
+        node.next_sibling = next_sibling;
+    }
 
-        self.nodes.push(node);
-        new_id
+    /// Cut everything below `at` out of the tree. Their slots are left as empty files for
+    /// `add_child_reusing` to fill, so ids below `at` go stale and all others stay valid.
+    pub fn clear_children(&mut self, at: NodeId) {
+        let below: Vec<NodeId> = self.descendants(at).collect();
+        for id in below {
+            self.unexpanded.remove(&id);
+            self.expansion_pending.remove(&id);
+            self.nodes[id.index()] = FileNode::empty("", false);
+            self.free_slots.insert(id);
+        }
+        self.nodes[at.index()].first_child = None;
+        self.unexpanded.remove(&at);
+
+        // `at` and its ancestors stay pending only through what's still below them.
+        let mut current = Some(at);
+        while let Some(id) = current {
+            let pending = self.unexpanded.contains(&id) || self.children(id).any(|c| self.expansion_pending.contains(&c));
+            if pending || !self.expansion_pending.remove(&id) {
+                break;
+            }
+            current = self.nodes[id.index()].parent;
+        }
+    }
+
+    /// Record that a depth-limited scan stopped at directory `id`.
+    pub fn mark_unexpanded(&mut self, id: NodeId) {
+        self.unexpanded.insert(id);
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if !self.expansion_pending.insert(id) {
+                break;
+            }
+            current = self.nodes[id.index()].parent;
+        }
     }
 
     /// Get a node by ID.
@@ -89,14 +265,24 @@ impl FileTree {
         &mut self.nodes[id.index()]
     }
 
-    /// Total number of nodes.
+    /// Total number of nodes, not counting free slots.
     pub fn len(&self) -> usize {
-        self.nodes.len()
+        self.nodes.len() - self.free_slots.len()
     }
 
     /// Whether the tree is empty (only root).
     pub fn is_empty(&self) -> bool {
-        self.nodes.len() <= 1
+        self.len() <= 1
+    }
+
+    /// Rough heap footprint in bytes: the node array plus names and extensions too long
+    /// to be stored inline.
+    pub fn memory_estimate(&self) -> usize {
+        let spilled = |s: &CompactString| if s.is_heap_allocated() { s.capacity() } else { 0 };
+        self.nodes.capacity() * std::mem::size_of::<FileNode>()
+            + self.nodes.iter().map(|n| spilled(&n.name)).sum::<usize>()
+            + self.extensions.capacity() * std::mem::size_of::<CompactString>()
+            + self.extensions.iter().map(spilled).sum::<usize>()
     }
 
     /// Iterate over children of a node.
@@ -107,16 +293,87 @@ impl FileTree {
         }
     }
 
-    /// Get or create an extension ID for the given extension string.
+    /// File category of a node, with unreclaimable system files reported as `SystemFile`.
+    pub fn category(&self, id: NodeId) -> FileCategory {
+        let node = self.get(id);
+        if !node.reclaimable {
+            return FileCategory::SystemFile;
+        }
+        let ext = self
+            .extensions
+            .get(node.extension_id as usize)
+            .map(|s| s.as_str())
+            .unwrap_or("");
+        categorize_extension(ext)
+    }
+
+    /// Iterate over all descendants of a node in pre-order (the node itself excluded).
+    pub fn descendants(&self, node: NodeId) -> Descendants<'_> {
+        Descendants {
+            tree: self,
+            stack: self.nodes[node.index()].first_child.into_iter().collect(),
+        }
+    }
+
+    /// Iterate from a node's parent up to the root (the node itself excluded).
+    pub fn ancestors(&self, node: NodeId) -> Ancestors<'_> {
+        Ancestors {
+            tree: self,
+            current: self.nodes[node.index()].parent,
+        }
+    }
+
+    /// Whether `node` or anything below it still has to be scanned past a depth limit.
+    pub fn needs_expansion(&self, node: NodeId) -> bool {
+        self.expansion_pending.contains(&node)
+    }
+
+    /// The `n` largest files under `root`, largest first.
+    pub fn largest_files(&self, root: NodeId, n: usize) -> Vec<NodeId> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // Min-heap of the best candidates seen so far
+        let mut heap: BinaryHeap<Reverse<(u64, u32)>> = BinaryHeap::with_capacity(n + 1);
+        for id in self.descendants(root) {
+            let node = self.get(id);
+            if node.is_dir {
+                continue;
+            }
+            heap.push(Reverse((node.size, id.0)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        let mut files: Vec<(u64, u32)> = heap.into_iter().map(|Reverse(entry)| entry).collect();
+        files.sort_by(|a, b| b.cmp(a));
+        files.into_iter().map(|(_, id)| NodeId(id)).collect()
+    }
+
+    /// Get or create an extension ID for the given extension string, case-folded.
+    /// Extensions are short, so the lowercased key stays inline and this doesn't allocate
+    /// for ASCII extensions already seen. Once all `u16` IDs are taken, further new
+    /// extensions share ID 0 (no extension) rather than wrapping onto existing ones.
     pub fn intern_extension(&mut self, ext: &str) -> u16 {
-        let lower = ext.to_ascii_lowercase();
-        if let Some(pos) = self.extensions.iter().position(|e| e.as_str() == lower) {
-            pos as u16
+        let lower = if ext.is_ascii() {
+            let mut lower = CompactString::new(ext);
+            lower.make_ascii_lowercase();
+            lower
         } else {
-            let id = self.extensions.len() as u16;
-            self.extensions.push(CompactString::new(&lower));
-            id
+            CompactString::from(ext.to_lowercase())
+        };
+        if let Some(&id) = self.extension_ids.get(&lower) {
+            return id;
+        }
+        if self.extensions.len() > u16::MAX as usize {
+            return 0;
         }
+        let id = self.extensions.len() as u16;
+        self.extensions.push(lower.clone());
+        self.extension_ids.insert(lower, id);
+        id
     }
 }
 
@@ -135,3 +392,114 @@ impl<'a> Iterator for ChildIter<'a> {
         Some(id)
     }
 }
+
+/// Pre-order iterator over the descendants of a node.
+pub struct Descendants<'a> {
+    tree: &'a FileTree,
+    /// Next node to visit on top; siblings wait underneath their subtree
+    stack: Vec<NodeId>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+        let node = &self.tree.nodes[id.index()];
+        if let Some(sibling) = node.next_sibling {
+            self.stack.push(sibling);
+        }
+        if let Some(child) = node.first_child {
+            self.stack.push(child);
+        }
+        Some(id)
+    }
+}
+
+/// Iterator from a node's parent up to the root.
+pub struct Ancestors<'a> {
+    tree: &'a FileTree,
+    current: Option<NodeId>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.current?;
+        self.current = self.tree.nodes[id.index()].parent;
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// root
+    /// ├── a/
+    /// │   ├── a1
+    /// │   └── a2/
+    /// │       └── a2x
+    /// └── b
+    fn sample() -> (FileTree, [NodeId; 6]) {
+        let mut tree = FileTree::new("root");
+        let root = tree.root;
+        // add_child prepends, so add siblings in reverse display order.
+        let b = tree.add_child(root, FileNode::test_file("b", 5));
+        let a = tree.add_child(root, FileNode::test_dir("a", 0));
+        let a2 = tree.add_child(a, FileNode::test_dir("a2", 0));
+        let a1 = tree.add_child(a, FileNode::test_file("a1", 3));
+        let a2x = tree.add_child(a2, FileNode::test_file("a2x", 7));
+        (tree, [root, a, a1, a2, a2x, b])
+    }
+
+    #[test]
+    fn descendants_are_pre_order_and_complete() {
+        let (tree, [root, a, a1, a2, a2x, b]) = sample();
+        let order: Vec<NodeId> = tree.descendants(root).collect();
+        assert_eq!(order, vec![a, a1, a2, a2x, b]);
+        assert_eq!(order.len(), tree.len() - 1);
+    }
+
+    #[test]
+    fn descendants_stay_inside_the_subtree() {
+        let (tree, [_, a, a1, a2, a2x, _]) = sample();
+        assert_eq!(tree.descendants(a).collect::<Vec<_>>(), vec![a1, a2, a2x]);
+        assert_eq!(tree.descendants(a1).count(), 0);
+    }
+
+    #[test]
+    fn ancestors_walk_up_to_the_root() {
+        let (tree, [root, a, _, a2, a2x, _]) = sample();
+        assert_eq!(tree.ancestors(a2x).collect::<Vec<_>>(), vec![a2, a, root]);
+        assert_eq!(tree.ancestors(root).count(), 0);
+    }
+
+    #[test]
+    fn extensions_intern_case_insensitively() {
+        let mut tree = FileTree::new("root");
+        let jpg = tree.intern_extension("JPG");
+        assert_eq!(tree.intern_extension("jpg"), jpg);
+        let umlaut = tree.intern_extension("ÄRZ");
+        assert_eq!(tree.intern_extension("ärz"), umlaut);
+        assert_eq!(tree.extensions[umlaut as usize], "ärz");
+        assert_eq!(tree.intern_extension(""), 0);
+    }
+
+    #[test]
+    fn largest_files_uses_all_descendants() {
+        let (tree, [root, _, a1, _, a2x, b]) = sample();
+        assert_eq!(tree.largest_files(root, 2), vec![a2x, b]);
+        assert_eq!(tree.largest_files(root, 10), vec![a2x, b, a1]);
+    }
+
+    #[test]
+    fn memory_estimate_counts_long_names() {
+        let (mut tree, [root, ..]) = sample();
+        let short = tree.memory_estimate();
+        assert!(short >= tree.len() * std::mem::size_of::<FileNode>());
+        tree.get_mut(root).name = CompactString::new("a folder name far too long to be stored inline");
+        assert!(tree.memory_estimate() > short);
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::arena::{FileTree, NodeId};
+use super::node_path;
+
+/// Bytes hashed from each end of a candidate file.
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Files smaller than this aren't worth the I/O of confirming.
+pub const DEFAULT_MIN_DUPLICATE_SIZE: u64 = 1024 * 1024;
+
+/// A file that shares its size with at least one other file.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub node: NodeId,
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+/// Files confirmed to share size and sampled content.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub files: Vec<NodeId>,
+}
+
+impl DuplicateGroup {
+    /// Bytes freed by keeping one copy.
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.files.len() as u64).saturating_sub(1)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateReport {
+    /// Groups, most wasted bytes first
+    pub groups: Vec<DuplicateGroup>,
+    /// Sum of `wasted()` over all groups
+    pub reclaimable: u64,
+}
+
+/// Group files under `root` by size and keep only sizes shared by two or more files.
+/// Cheap: only walks the tree, so it can run on the UI thread before hashing starts.
+pub fn size_candidates(tree: &FileTree, root: NodeId, scan_path: &Path, min_size: u64) -> Vec<Candidate> {
+    let mut by_size: HashMap<u64, Vec<NodeId>> = HashMap::new();
+    for id in tree.descendants(root) {
+        let node = tree.get(id);
+        // Contents can only match at the same logical size, whatever the size basis.
+        if !node.is_dir && node.reclaimable && node.logical >= min_size.max(1) {
+            by_size.entry(node.logical).or_default().push(id);
+        }
+    }
+    by_size
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .flat_map(|(size, ids)| ids.into_iter().map(move |id| (size, id)))
+        .map(|(size, id)| Candidate {
+            node: id,
+            size,
+            path: node_path(tree, id, scan_path),
+        })
+        .collect()
+}
+
+/// Confirm size collisions by hashing the first and last 64 KiB of each file.
+/// Unreadable files are skipped. This does the I/O, so run it off the UI thread.
+pub fn confirm_duplicates(candidates: Vec<Candidate>) -> DuplicateReport {
+    let mut by_key: HashMap<(u64, u64), Vec<NodeId>> = HashMap::new();
+    for candidate in candidates {
+        match sample_hash(&candidate.path, candidate.size) {
+            Ok(hash) => by_key.entry((candidate.size, hash)).or_default().push(candidate.node),
+            Err(e) => tracing::debug!("Skipping {:?} for duplicate check: {}", candidate.path, e),
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_key
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|((size, _), files)| DuplicateGroup { size, files })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.wasted()));
+    let reclaimable = groups.iter().map(DuplicateGroup::wasted).sum();
+
+    DuplicateReport { groups, reclaimable }
+}
+
+/// FNV-1a over the first and last `SAMPLE_BYTES` of the file. A fixed function, unlike
+/// `DefaultHasher`, so the same contents hash the same on every build.
+fn sample_hash(path: &Path, size: u64) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; SAMPLE_BYTES.min(size) as usize];
+
+    file.read_exact(&mut buf)?;
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, &buf);
+    if size > SAMPLE_BYTES {
+        let tail = SAMPLE_BYTES.min(size - SAMPLE_BYTES);
+        file.seek(SeekFrom::Start(size - tail))?;
+        buf.truncate(tail as usize);
+        file.read_exact(&mut buf)?;
+        hash = fnv1a(hash, &buf);
+    }
+    Ok(hash)
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::arena::FileNode;
+
+    #[test]
+    fn size_candidates_keep_only_shared_sizes() {
+        let mut tree = FileTree::new("data");
+        let root = tree.root;
+        let sub = tree.add_child(root, FileNode::test_dir("sub", 0));
+        let a = tree.add_child(root, FileNode::test_file("a.bin", 4096));
+        let b = tree.add_child(sub, FileNode::test_file("b.bin", 4096));
+        tree.add_child(root, FileNode::test_file("c.bin", 8192));
+        tree.add_child(root, FileNode::test_file("tiny1", 10));
+        tree.add_child(root, FileNode::test_file("tiny2", 10));
+
+        let mut found = size_candidates(&tree, root, Path::new("/data"), 1024);
+        found.sort_by_key(|c| c.node.0);
+        let nodes: Vec<NodeId> = found.iter().map(|c| c.node).collect();
+        assert_eq!(nodes, [a, b]);
+        assert!(found.iter().all(|c| c.size == 4096));
+        assert_eq!(found[1].path, Path::new("/data/sub/b.bin"));
+    }
+
+    #[test]
+    fn confirmed_groups_need_matching_samples() {
+        let dir = std::env::temp_dir().join(format!("silvaview-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Bigger than two samples, so a difference in the middle goes unseen.
+        let size = SAMPLE_BYTES * 3;
+        let same = vec![7u8; size as usize];
+        let mut middle = same.clone();
+        middle[(SAMPLE_BYTES + 10) as usize] = 1;
+        let mut tail = same.clone();
+        *tail.last_mut().unwrap() = 1;
+        let files = [("a", &same), ("b", &same), ("c", &middle), ("d", &tail)];
+        let candidates = files
+            .iter()
+            .enumerate()
+            .map(|(i, (name, bytes))| {
+                let path = dir.join(name);
+                std::fs::write(&path, bytes).unwrap();
+                Candidate {
+                    node: NodeId(i as u32 + 1),
+                    size,
+                    path,
+                }
+            })
+            .chain([Candidate {
+                node: NodeId(9),
+                size,
+                path: dir.join("missing"),
+            }])
+            .collect();
+
+        let report = confirm_duplicates(candidates);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        let mut group = report.groups[0].files.clone();
+        group.sort_by_key(|id| id.0);
+        assert_eq!(group, [NodeId(1), NodeId(2), NodeId(3)]);
+        assert_eq!(report.reclaimable, size * 2);
+    }
+
+    #[test]
+    fn sample_hash_is_fnv1a() {
+        // Published FNV-1a 64 test vectors.
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
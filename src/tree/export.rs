@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::analytics::Analytics;
+use super::arena::{FileTree, NodeId};
+use crate::ui::tooltip::{build_path, format_size, format_timestamp, SizeUnits};
+
+/// Depth used when no explicit limit is given by the caller.
+pub const DEFAULT_EXPORT_DEPTH: u16 = 3;
+
+/// Render the subtree under `root` as `du -h --max-depth` style text.
+///
+/// One line per node, indented two spaces per level below `root`, directories suffixed
+/// with `/`. Children are emitted in tree order, which `build_tree` sorts by size.
+pub fn to_text(
+    tree: &FileTree,
+    root: NodeId,
+    depth_limit: Option<u16>,
+    units: SizeUnits,
+) -> String {
+    let mut out = String::new();
+
+    // Explicit stack (deep paths would overflow a recursive walk); children pushed in
+    // reverse so they pop in size order.
+    let mut stack = vec![(root, 0u16)];
+    while let Some((id, level)) = stack.pop() {
+        let node = tree.get(id);
+        let _ = writeln!(
+            out,
+            "{:>10}  {}{}{}",
+            format_size(node.size, units),
+            "  ".repeat(level as usize),
+            node.name,
+            if node.is_dir { "/" } else { "" }
+        );
+
+        if depth_limit.is_some_and(|limit| level >= limit) {
+            continue;
+        }
+        let children: Vec<NodeId> = tree.children(id).collect();
+        stack.extend(children.into_iter().rev().map(|child| (child, level + 1)));
+    }
+
+    out
+}
+
+/// Render category totals as CSV (`category,bytes,percent,file_count`), largest first.
+///
+/// With `include_extensions`, per-extension rows follow the categories, keyed by the
+/// extension with its leading dot (`(none)` for files without one) so they can't be
+/// confused with category names.
+pub fn analytics_csv(
+    tree: &FileTree,
+    root: NodeId,
+    analytics: &Analytics,
+    include_extensions: bool,
+) -> String {
+    let total = analytics.total_size;
+    let percent = |bytes: u64| {
+        if total == 0 {
+            0.0
+        } else {
+            bytes as f64 / total as f64 * 100.0
+        }
+    };
+
+    let mut out = String::from("category,bytes,percent,file_count\n");
+    for (category, bytes) in &analytics.category_sizes {
+        let files = analytics.category_file_counts.get(category).copied().unwrap_or(0);
+        let _ = writeln!(out, "{:?},{},{:.2},{}", category, bytes, percent(*bytes), files);
+    }
+
+    if include_extensions {
+        let mut by_ext: HashMap<u16, (u64, u64)> = HashMap::new();
+        for id in tree.descendants(root) {
+            let node = tree.get(id);
+            if !node.is_dir {
+                let totals = by_ext.entry(node.extension_id).or_default();
+                totals.0 += node.size;
+                totals.1 += 1;
+            }
+        }
+        let mut rows: Vec<_> = by_ext.into_iter().collect();
+        rows.sort_by_key(|(_, (bytes, _))| std::cmp::Reverse(*bytes));
+        for (ext_id, (bytes, files)) in rows {
+            let ext = tree.extensions[ext_id as usize].as_str();
+            let label = if ext.is_empty() {
+                "(none)".to_string()
+            } else {
+                format!(".{}", ext)
+            };
+            let _ = writeln!(out, "{},{},{:.2},{}", csv_field(&label), bytes, percent(bytes), files);
+        }
+    }
+
+    out
+}
+
+/// One CSV row per file under `root` of at least `min_size` bytes, in tree order.
+///
+/// `modified` is left empty when the scanner had no timestamp for the file.
+pub fn files_csv(tree: &FileTree, root: NodeId, min_size: u64) -> String {
+    let mut out = String::from("path,bytes,allocated,extension,category,modified\n");
+    for id in tree.descendants(root) {
+        let node = tree.get(id);
+        if node.is_dir || node.size < min_size {
+            continue;
+        }
+        let modified = if node.modified == 0 {
+            String::new()
+        } else {
+            format_timestamp(node.modified)
+        };
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{:?},{}",
+            csv_field(&build_path(tree, id)),
+            node.size,
+            node.allocated,
+            csv_field(&tree.extensions[node.extension_id as usize]),
+            tree.category(id),
+            modified,
+        );
+    }
+    out
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline.
+fn csv_field(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\"")).into()
+    } else {
+        s.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::tree::analytics::compute_analytics;
+    use crate::tree::arena::FileNode;
+
+    fn file_with_ext(tree: &mut FileTree, name: &str, ext: &str, size: u64) -> FileNode {
+        FileNode {
+            extension_id: tree.intern_extension(ext),
+            ..FileNode::test_file(name, size)
+        }
+    }
+
+    #[test]
+    fn files_csv_quotes_awkward_names_and_skips_small_files() {
+        let mut tree = FileTree::new("data");
+        let root = tree.root;
+        let sub = tree.add_child(root, FileNode::test_dir("a,b", 0));
+        let quoted = file_with_ext(&mut tree, "say \"hi\".txt", "txt", 4096);
+        tree.add_child(sub, quoted);
+        tree.add_child(root, FileNode::test_file("plain.bin", 2048));
+        tree.add_child(root, FileNode::test_file("small.bin", 100));
+
+        let csv = files_csv(&tree, root, 1024);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], "path,bytes,allocated,extension,category,modified");
+        assert_eq!(rows.len(), 3, "{csv}");
+        let quoted = rows.iter().find(|row| row.contains("hi")).unwrap();
+        assert_eq!(*quoted, "\"data\\a,b\\say \"\"hi\"\".txt\",4096,4096,txt,Document,");
+        assert!(rows.iter().any(|row| row.starts_with("data\\plain.bin,2048,2048,")));
+        assert!(rows.iter().all(|row| !row.contains("small.bin")));
+    }
+
+    #[test]
+    fn csv_field_only_quotes_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn analytics_csv_lists_categories_then_extensions() {
+        let mut tree = FileTree::new("data");
+        let root = tree.root;
+        for (name, ext, size) in [("a.txt", "txt", 600), ("b.md", "md", 150), ("c", "", 250)] {
+            let node = file_with_ext(&mut tree, name, ext, size);
+            tree.add_child(root, node);
+        }
+        let analytics = compute_analytics(&tree, root, true, &HashSet::new());
+
+        assert_eq!(
+            analytics_csv(&tree, root, &analytics, false),
+            "category,bytes,percent,file_count\nDocument,750,75.00,2\nOther,250,25.00,1\n"
+        );
+        let with_extensions = analytics_csv(&tree, root, &analytics, true);
+        let rows: Vec<&str> = with_extensions.lines().skip(3).collect();
+        assert_eq!(rows, [".txt,600,60.00,1", "(none),250,25.00,1", ".md,150,15.00,1"]);
+    }
+}
@@ -31,10 +31,68 @@ pub enum FileCategory {
     Database,
     /// Disk images / VM: iso, img, vhd, vmdk
     DiskImage,
+    /// Unreclaimable OS files at the drive root: pagefile.sys, hiberfil.sys, swapfile.sys
+    SystemFile,
     /// Unknown / no extension
     Other,
 }
 
+impl FileCategory {
+    /// Every category, in declaration order (`ALL[c as usize] == c`).
+    pub const ALL: [FileCategory; 17] = [
+        FileCategory::Image,
+        FileCategory::Video,
+        FileCategory::Audio,
+        FileCategory::Document,
+        FileCategory::Ebook,
+        FileCategory::Archive,
+        FileCategory::Code,
+        FileCategory::Executable,
+        FileCategory::Config,
+        FileCategory::Font,
+        FileCategory::Installer,
+        FileCategory::Asset3D,
+        FileCategory::Backup,
+        FileCategory::Database,
+        FileCategory::DiskImage,
+        FileCategory::SystemFile,
+        FileCategory::Other,
+    ];
+
+    /// Name shown in the UI, e.g. in the color legend.
+    pub fn label(self) -> &'static str {
+        match self {
+            FileCategory::Image => "Images",
+            FileCategory::Video => "Video",
+            FileCategory::Audio => "Audio",
+            FileCategory::Document => "Documents",
+            FileCategory::Ebook => "Ebooks",
+            FileCategory::Archive => "Archives",
+            FileCategory::Code => "Code",
+            FileCategory::Executable => "Executables",
+            FileCategory::Config => "Config",
+            FileCategory::Font => "Fonts",
+            FileCategory::Installer => "Installers",
+            FileCategory::Asset3D => "3D assets",
+            FileCategory::Backup => "Backups",
+            FileCategory::Database => "Databases",
+            FileCategory::DiskImage => "Disk images",
+            FileCategory::SystemFile => "System files",
+            FileCategory::Other => "Other",
+        }
+    }
+}
+
+/// Files Windows keeps at the drive root that can't be deleted to free space directly.
+const UNRECLAIMABLE_ROOT_FILES: &[&str] = &["pagefile.sys", "hiberfil.sys", "swapfile.sys"];
+
+/// Whether a file directly under a drive root is an OS-managed paging/hibernation file.
+pub fn is_unreclaimable_root_file(name: &str) -> bool {
+    UNRECLAIMABLE_ROOT_FILES
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(name))
+}
+
 /// Classify a file extension into a category.
 pub fn categorize_extension(ext: &str) -> FileCategory {
     match ext.to_ascii_lowercase().as_str() {
@@ -1,35 +1,151 @@
 pub mod aggregate;
+pub mod analytics;
 pub mod arena;
+pub mod dedupe;
+pub mod export;
 pub mod extensions;
+pub mod search;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use compact_str::CompactString;
 
-use self::arena::{FileNode, FileTree, NodeId};
+use self::arena::{FileNode, FileTree, NodeId, SizeBasis};
 use crate::scanner::types::RawFileEntry;
 
+/// Extract the `\\server\share\` root of a UNC path (also accepts `\\?\UNC\server\share`).
+fn unc_share_root(path: &str) -> Option<String> {
+    let rest = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        rest
+    } else if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    } else {
+        path.strip_prefix(r"\\")?
+    };
+
+    let mut parts = rest.split('\\');
+    let server = parts.next().filter(|s| !s.is_empty())?;
+    let share = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!(r"\\{}\{}\", server, share))
+}
+
+/// `path.starts_with(base)`, ignoring case on Windows, whose file systems do: the same
+/// folder can reach us spelled as typed by the user and as stored on disk.
+pub fn path_starts_with(path: &Path, base: &Path) -> bool {
+    strip_path_prefix(path, base).is_some()
+}
+
+/// `path.strip_prefix(base)`, ignoring case on Windows like `path_starts_with`.
+pub fn strip_path_prefix<'a>(path: &'a Path, base: &Path) -> Option<&'a Path> {
+    if !cfg!(windows) {
+        return path.strip_prefix(base).ok();
+    }
+    let mut parts = path.components();
+    base.components()
+        .all(|b| parts.next().is_some_and(|p| same_name(&p.as_os_str().to_string_lossy(), &b.as_os_str().to_string_lossy())))
+        .then_some(parts.as_path())
+}
+
+/// Whether two file names are the same, ignoring case on Windows.
+pub fn same_name(a: &str, b: &str) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+/// On-disk path of `node`. The tree root's name is the scan root when it is absolute
+/// (`C:\`, a share, `/`); otherwise the tree is rooted at `scan_path`.
+pub fn node_path(tree: &FileTree, node: NodeId, scan_path: &Path) -> PathBuf {
+    let root_name = Path::new(tree.get(tree.root).name.as_str());
+    let mut path = if root_name.has_root() {
+        root_name.to_path_buf()
+    } else {
+        scan_path.to_path_buf()
+    };
+    let mut parts: Vec<&str> = std::iter::once(node)
+        .chain(tree.ancestors(node))
+        .take_while(|&id| id != tree.root)
+        .map(|id| tree.get(id).name.as_str())
+        .collect();
+    parts.reverse();
+    path.extend(parts);
+    path
+}
+
+/// Inverse of `node_path`: the node at `path`, if the tree has one there. Names are
+/// matched ignoring case on Windows, so a path typed in any case is found.
+pub fn find_by_path(tree: &FileTree, path: &Path, scan_path: &Path) -> Option<NodeId> {
+    let root_name = Path::new(tree.get(tree.root).name.as_str());
+    let base = if root_name.has_root() { root_name } else { scan_path };
+    let (mut node, rest) = match strip_path_prefix(path, base) {
+        Some(rest) => (tree.root, rest),
+        // Merged scans name their top-level folders by absolute path (see `merge_trees`).
+        None => tree.children(tree.root).find_map(|id| {
+            let top = Path::new(tree.get(id).name.as_str());
+            top.has_root().then(|| strip_path_prefix(path, top)).flatten().map(|rest| (id, rest))
+        })?,
+    };
+    for part in rest.components() {
+        let name = part.as_os_str().to_str()?;
+        node = tree.children(node).find(|&id| same_name(&tree.get(id).name, name))?;
+    }
+    Some(node)
+}
+
+/// Whether `path` is a bare drive root such as `C:\`.
+fn is_drive_root(path: &Path) -> bool {
+    let Some(s) = path.to_str() else {
+        return false;
+    };
+    let bytes = s.as_bytes();
+    bytes.len() == 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
 /// Find the common root path for all entries.
 /// For drive scans (C:\), returns the drive root.
+/// For network scans (\\server\share\...), returns the share root.
 /// For subdirectory scans, returns the deepest common ancestor.
+/// A parentless directory entry (a scoped MFT scan) is taken as the root outright.
 fn find_common_root(entries: &[RawFileEntry]) -> PathBuf {
     if entries.is_empty() {
         return PathBuf::from("");
     }
 
+    if let Some(scope) = entries.iter().find(|e| e.is_dir && e.parent.is_none()) {
+        if entries.iter().all(|e| path_starts_with(&e.path, &scope.path)) {
+            return scope.path.clone();
+        }
+    }
+
     // Check if this is a drive root scan (all paths start with same drive letter)
     let first_path = &entries[0].path;
 
+    // UNC paths: `\\server\share` is the root unit, the way `X:\` is for drives.
+    // Compared as strings so the leading `\\server` is never split into components.
+    if let Some(share_root) = first_path.to_str().and_then(unc_share_root) {
+        let share_unit = share_root.trim_end_matches('\\');
+        let all_match = entries.iter().all(|e| {
+            e.path.to_str().is_some_and(|p| {
+                p == share_unit || p.starts_with(share_root.as_str())
+            })
+        });
+
+        if all_match {
+            tracing::info!("Detected network share scan: {}", share_root);
+            return PathBuf::from(share_root);
+        }
+    }
+
     // Try to get the drive root (e.g., "C:\")
     if let Some(path_str) = first_path.to_str() {
         if path_str.len() >= 2 && path_str.chars().nth(1) == Some(':') {
             let drive_root = PathBuf::from(format!("{}:\\", path_str.chars().next().unwrap()));
 
             // Verify all entries start with this drive root
-            let all_match = entries.iter().all(|e| {
-                e.path.starts_with(&drive_root)
-            });
+            let all_match = entries.iter().all(|e| path_starts_with(&e.path, &drive_root));
 
             if all_match {
                 tracing::info!("Detected drive root scan: {}", drive_root.display());
@@ -38,10 +154,11 @@ fn find_common_root(entries: &[RawFileEntry]) -> PathBuf {
         }
     }
 
-    // Fallback: find shortest common path
+    // Fallback: find shortest common path. Every entry is checked: one left outside the
+    // root would be grafted under it by its own path or dropped, so sampling isn't safe.
     let mut root = first_path.clone();
-    for entry in entries.iter().skip(1).take(100) {  // Sample first 100
-        while !entry.path.starts_with(&root) {
+    for entry in entries.iter().skip(1) {
+        while !path_starts_with(&entry.path, &root) {
             match root.parent() {
                 Some(parent) => root = parent.to_path_buf(),
                 None => return PathBuf::from(""),
@@ -95,59 +212,70 @@ pub fn build_tree(entries: &[RawFileEntry]) -> FileTree {
 
     let mut tree = FileTree::new(&root_name);
 
-    // Map from path → NodeId for parent lookups
-    let mut path_map: HashMap<std::path::PathBuf, NodeId> = HashMap::new();
-    path_map.insert(root_path.clone(), tree.root);
+    // Map from path → NodeId for parent lookups. Keys borrow from `entries` (and
+    // `root_path`), so building it doesn't copy a path per entry.
+    let mut path_map: HashMap<&Path, NodeId> = HashMap::with_capacity(entries.len());
+    path_map.insert(root_path.as_path(), tree.root);
 
     // First pass: create all directory nodes
     for entry in entries.iter().filter(|e| e.is_dir) {
         if entry.path == root_path {
             continue;
         }
-        ensure_node(&mut tree, &mut path_map, &entry.path, true, 0);
+        let id = ensure_node(&mut tree, &mut path_map, &entry.path, true, 0);
+        tree.get_mut(id).attributes = entry.attributes;
+        if entry.unexpanded {
+            tree.mark_unexpanded(id);
+        }
     }
 
+    // Paging/hibernation files only mean anything directly under a drive root.
+    let root_is_drive = is_drive_root(&root_path);
+    let mut unreclaimable_bytes = 0u64;
+
     // Second pass: create all file nodes
     for entry in entries.iter().filter(|e| !e.is_dir) {
-        let ext = entry
-            .path
-            .extension()
-            .map(|e| e.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let ext_id = tree.intern_extension(&ext);
-
-        let name = entry
-            .path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        let parent_path = entry
-            .path
-            .parent()
-            .unwrap_or(Path::new(""))
-            .to_path_buf();
-        let parent_id = ensure_node(&mut tree, &mut path_map, &parent_path, true, 0);
-
-        let node = FileNode {
-            name: CompactString::new(&name),
-            size: entry.size,
-            is_dir: false,
-            extension_id: ext_id,
-            parent: Some(parent_id),
-            first_child: None,
-            next_sibling: None,
-            depth: 0, // will be set by add_child
-        };
-
+        let parent_id = ensure_node(&mut tree, &mut path_map, parent_path(entry), true, 0);
+        let node = file_node(&mut tree, entry, parent_id, root_is_drive);
+        if !node.reclaimable {
+            unreclaimable_bytes += entry.size;
+        }
         let id = tree.add_child(parent_id, node);
-        path_map.insert(entry.path.clone(), id);
+        path_map.insert(&entry.path, id);
+    }
+
+    if unreclaimable_bytes > 0 {
+        tracing::info!(
+            "Unreclaimable system files (pagefile/hiberfil/swapfile): {:.2} GiB",
+            unreclaimable_bytes as f64 / 1_073_741_824.0
+        );
     }
 
     // Aggregate directory sizes
     aggregate::aggregate_sizes(&mut tree);
+    aggregate::aggregate_dominant_categories(&mut tree);
     // Sort children by size for squarified layout
     aggregate::sort_children_by_size(&mut tree);
+    aggregate::rank_extensions(&mut tree);
+
+    // Directories with children but no size usually mean a parent-linking or aggregation
+    // bug; orphaned MFT entries (unresolved parents) are the usual suspect.
+    let orphan_ids: HashSet<NodeId> = entries
+        .iter()
+        .filter(|e| e.orphaned)
+        .filter_map(|e| path_map.get(e.path.as_path()).copied())
+        .collect();
+    // Synthetic container nodes carry no MFT record and aren't counted.
+    tree.orphaned_entries = entries.iter().filter(|e| e.orphaned && e.mft_record.is_some()).count();
+    let (anomalies, from_orphans) = count_empty_dir_anomalies(&tree, &orphan_ids);
+    tree.empty_dir_anomalies = anomalies;
+    if anomalies > 0 {
+        tracing::warn!(
+            "{} directories have children but size 0 ({} under orphaned entries)",
+            anomalies,
+            from_orphans
+        );
+    }
 
     // Debug: count direct children of root
     let root_child_count = tree.children(tree.root).count();
@@ -162,7 +290,7 @@ pub fn build_tree(entries: &[RawFileEntry]) -> FileTree {
     for (i, child_id) in tree.children(tree.root).take(10).enumerate() {
         let child = tree.get(child_id);
         tracing::debug!(
-            "  [{}] '{}' (dir={}, size={:.2} GB)",
+            "  [{}] '{}' (dir={}, size={:.2} GiB)",
             i,
             child.name,
             child.is_dir,
@@ -173,12 +301,235 @@ pub fn build_tree(entries: &[RawFileEntry]) -> FileTree {
     tree
 }
 
+/// The directory a file entry goes in. Scanners report the parent they resolved; it is
+/// only recomputed from the path when they didn't.
+fn parent_path(entry: &RawFileEntry) -> &Path {
+    entry
+        .parent
+        .as_deref()
+        .or_else(|| entry.path.parent())
+        .unwrap_or(Path::new(""))
+}
+
+/// The node for file `entry` in directory `parent`, its extension interned in `tree`.
+fn file_node(tree: &mut FileTree, entry: &RawFileEntry, parent: NodeId, root_is_drive: bool) -> FileNode {
+    let ext = entry.path.extension().map(|e| e.to_string_lossy()).unwrap_or_default();
+    let ext_id = tree.intern_extension(&ext);
+    let name = entry.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let reclaimable = !(root_is_drive && parent == tree.root && extensions::is_unreclaimable_root_file(&name));
+    FileNode {
+        name: CompactString::new(&name),
+        size: entry.size,
+        logical: entry.size,
+        is_dir: false,
+        extension_id: ext_id,
+        parent: Some(parent),
+        first_child: None,
+        next_sibling: None,
+        depth: 0, // will be set by add_child
+        reclaimable,
+        allocated: entry.allocated.unwrap_or(entry.size),
+        modified: entry.modified.unwrap_or(0),
+        attributes: entry.attributes,
+        file_count: 0,
+        dir_count: 0,
+        dominant_category: None,
+        dominant_share: 0.0,
+    }
+}
+
+/// Builds a tree from entries that arrive in batches, e.g. streamed from a running scan,
+/// without going over earlier batches again. Nodes are only ever appended, so a `NodeId`
+/// names the same node in every `snapshot`.
+pub struct TreeBuilder {
+    tree: FileTree,
+    root_is_drive: bool,
+    /// Directories created so far, by path.
+    dirs: HashMap<PathBuf, NodeId>,
+}
+
+impl TreeBuilder {
+    /// Start an empty tree for a scan of `root_path`.
+    pub fn new(root_path: &Path) -> Self {
+        let root_name = root_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root_path.to_string_lossy().to_string());
+        let tree = FileTree::new(&root_name);
+        let dirs = HashMap::from([(root_path.to_path_buf(), tree.root)]);
+        Self {
+            tree,
+            root_is_drive: is_drive_root(root_path),
+            dirs,
+        }
+    }
+
+    /// Add a batch of entries. A file may come before its folder's own entry; the folder
+    /// is created for it and filled in when its entry arrives.
+    pub fn add(&mut self, entries: &[RawFileEntry]) {
+        for entry in entries {
+            if entry.is_dir {
+                let id = self.dir(&entry.path);
+                self.tree.get_mut(id).attributes = entry.attributes;
+                if entry.unexpanded {
+                    self.tree.mark_unexpanded(id);
+                }
+            } else {
+                let parent = self.dir(parent_path(entry));
+                let node = file_node(&mut self.tree, entry, parent, self.root_is_drive);
+                self.tree.add_child(parent, node);
+            }
+        }
+    }
+
+    /// The tree so far, with folder sizes aggregated.
+    pub fn snapshot(&self) -> FileTree {
+        let mut tree = self.tree.clone();
+        aggregate::aggregate_sizes(&mut tree);
+        aggregate::aggregate_dominant_categories(&mut tree);
+        aggregate::sort_children_by_size(&mut tree);
+        aggregate::rank_extensions(&mut tree);
+        tree
+    }
+
+    /// The node for directory `path`, creating it and any missing ancestors. Paths outside
+    /// the scan root hang off the root.
+    fn dir(&mut self, path: &Path) -> NodeId {
+        if let Some(&id) = self.dirs.get(path) {
+            return id;
+        }
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.dir(parent),
+            _ => self.tree.root,
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |n| n.to_string_lossy());
+        let node = FileNode {
+            name: CompactString::new(&name),
+            size: 0,
+            logical: 0,
+            is_dir: true,
+            extension_id: 0,
+            parent: Some(parent),
+            first_child: None,
+            next_sibling: None,
+            depth: 0,
+            reclaimable: true,
+            allocated: 0,
+            modified: 0,
+            attributes: 0,
+            file_count: 0,
+            dir_count: 0,
+            dominant_category: None,
+            dominant_share: 0.0,
+        };
+        let id = self.tree.add_child(parent, node);
+        self.dirs.insert(path.to_path_buf(), id);
+        id
+    }
+}
+
+/// Replace the contents of directory `at` with the children of `subtree`'s root, e.g. a
+/// deeper rescan of that folder, and re-aggregate. Node ids outside `at` stay valid; ids
+/// below it go stale, and their slots are reused for the new contents.
+pub fn replace_subtree(tree: &mut FileTree, at: NodeId, subtree: &FileTree) {
+    tree.clear_children(at);
+    copy_children(tree, at, subtree);
+
+    aggregate::aggregate_sizes(tree);
+    aggregate::aggregate_dominant_categories(tree);
+    aggregate::sort_children_by_size(tree);
+    aggregate::rank_extensions(tree);
+}
+
+/// Combine separately scanned folders under one synthetic root named `name`. Each scan
+/// becomes a top-level directory named by its full path, so `node_path` still
+/// resolves files to where they live on disk.
+pub fn merge_trees(name: &str, scans: &[(PathBuf, FileTree)]) -> FileTree {
+    let mut tree = FileTree::new(name);
+    let root = tree.root;
+    for (path, scan) in scans {
+        let mut top = scan.get(scan.root).clone();
+        top.name = CompactString::new(path.to_string_lossy());
+        top.first_child = None;
+        let id = tree.add_child(root, top);
+        if scan.unexpanded.contains(&scan.root) {
+            tree.mark_unexpanded(id);
+        }
+        copy_children(&mut tree, id, scan);
+        tree.empty_dir_anomalies += scan.empty_dir_anomalies;
+        tree.orphaned_entries += scan.orphaned_entries;
+    }
+
+    aggregate::aggregate_sizes(&mut tree);
+    aggregate::aggregate_dominant_categories(&mut tree);
+    aggregate::sort_children_by_size(&mut tree);
+    aggregate::rank_extensions(&mut tree);
+    tree
+}
+
+/// Copy everything below `subtree`'s root under `at`, without re-aggregating.
+fn copy_children(tree: &mut FileTree, at: NodeId, subtree: &FileTree) {
+    // Parents are copied before their children, which keeps the arena ordered the way
+    // the aggregation passes expect.
+    let mut stack = vec![(subtree.root, at)];
+    while let Some((from, to)) = stack.pop() {
+        for child in subtree.children(from) {
+            let mut node = subtree.get(child).clone();
+            node.extension_id = tree.intern_extension(&subtree.extensions[node.extension_id as usize]);
+            node.first_child = None;
+            let id = tree.add_child_reusing(to, node);
+            if subtree.unexpanded.contains(&child) {
+                tree.mark_unexpanded(id);
+            }
+            if subtree.get(child).is_dir {
+                stack.push((child, id));
+            }
+        }
+    }
+}
+
+/// Re-measure the tree in `basis` (logical size or size on disk) without rescanning.
+/// Node ids stay valid; only sizes and the order of siblings change.
+pub fn set_size_basis(tree: &mut FileTree, basis: SizeBasis) {
+    if tree.size_basis == basis {
+        return;
+    }
+    tree.size_basis = basis;
+    aggregate::aggregate_sizes(tree);
+    aggregate::aggregate_dominant_categories(tree);
+    aggregate::sort_children_by_size(tree);
+}
+
+/// Count directories that have children but aggregated to size 0.
+/// Returns (total, how many of those sit at or under an orphaned entry).
+fn count_empty_dir_anomalies(tree: &FileTree, orphan_ids: &HashSet<NodeId>) -> (usize, usize) {
+    let mut total = 0;
+    let mut from_orphans = 0;
+    for (i, node) in tree.nodes.iter().enumerate() {
+        if !node.is_dir || node.first_child.is_none() || node.size != 0 {
+            continue;
+        }
+        total += 1;
+
+        let id = NodeId(i as u32);
+        if std::iter::once(id)
+            .chain(tree.ancestors(id))
+            .any(|a| orphan_ids.contains(&a))
+        {
+            from_orphans += 1;
+        }
+    }
+    (total, from_orphans)
+}
+
 /// Ensure a directory node exists at the given path, creating intermediate nodes as needed.
 /// Uses an iterative approach to avoid stack overflow on deep paths.
-fn ensure_node(
+fn ensure_node<'a>(
     tree: &mut FileTree,
-    path_map: &mut HashMap<std::path::PathBuf, NodeId>,
-    path: &Path,
+    path_map: &mut HashMap<&'a Path, NodeId>,
+    path: &'a Path,
     is_dir: bool,
     size: u64,
 ) -> NodeId {
@@ -187,41 +538,22 @@ fn ensure_node(
         return id;
     }
 
-    // Build list of missing ancestors from root to target
-    let mut missing = Vec::new();
-    let mut current = path.to_path_buf();
-
-    loop {
-        if path_map.contains_key(&current) {
-            break;
-        }
-        missing.push(current.clone());
-
-        match current.parent() {
-            Some(parent) if !parent.as_os_str().is_empty() => {
-                current = parent.to_path_buf();
-            }
-            _ => break,
-        }
-    }
-
-    // Reverse to create from root downward
-    missing.reverse();
+    // Missing ancestors, target first, up to the first one already known
+    let missing: Vec<&'a Path> = path
+        .ancestors()
+        .take_while(|p| !p.as_os_str().is_empty() && !path_map.contains_key(p))
+        .collect();
 
-    // Create each missing ancestor
+    // Create each missing ancestor from the root downward
     let mut last_id = tree.root;
-    for ancestor in missing {
-        let parent_path = ancestor
+    for &ancestor in missing.iter().rev() {
+        let parent_id = ancestor
             .parent()
-            .unwrap_or(Path::new(""))
-            .to_path_buf();
+            .and_then(|parent| path_map.get(parent))
+            .copied()
+            .unwrap_or(tree.root);
 
-        let parent_id = path_map.get(&parent_path).copied().unwrap_or(tree.root);
-
-        let name = ancestor
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
+        let name = ancestor.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
 
         // Determine if this is the target node or an intermediate directory
         let (is_this_dir, this_size) = if ancestor == path {
@@ -233,18 +565,289 @@ fn ensure_node(
         let node = FileNode {
             name: CompactString::new(&name),
             size: this_size,
+            logical: this_size,
             is_dir: is_this_dir,
             extension_id: 0,
             parent: Some(parent_id),
             first_child: None,
             next_sibling: None,
             depth: 0,
+            reclaimable: true,
+            allocated: 0,
+            modified: 0,
+            attributes: 0,
+            file_count: 0,
+            dir_count: 0,
+            dominant_category: None,
+            dominant_share: 0.0,
         };
 
         let id = tree.add_child(parent_id, node);
-        path_map.insert(ancestor.clone(), id);
+        path_map.insert(ancestor, id);
         last_id = id;
     }
 
     last_id
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::extensions::FileCategory;
+
+    fn entry(path: &str, is_dir: bool) -> RawFileEntry {
+        RawFileEntry {
+            path: PathBuf::from(path),
+            size: if is_dir { 0 } else { 10 },
+            allocated: None,
+            modified: None,
+            attributes: 0,
+            is_dir,
+            parent: None,
+            mft_record: None,
+            orphaned: false,
+            unexpanded: false,
+        }
+    }
+
+    #[test]
+    fn unc_share_root_parses_server_and_share() {
+        assert_eq!(
+            unc_share_root(r"\\nas\media\Videos\a.mkv").as_deref(),
+            Some(r"\\nas\media\")
+        );
+        assert_eq!(unc_share_root(r"\\nas\media").as_deref(), Some(r"\\nas\media\"));
+        assert_eq!(
+            unc_share_root(r"\\?\UNC\nas\media\Videos").as_deref(),
+            Some(r"\\nas\media\")
+        );
+        assert_eq!(unc_share_root(r"\\nas"), None);
+        assert_eq!(unc_share_root(r"\\?\C:\Users"), None);
+        assert_eq!(unc_share_root(r"C:\Users"), None);
+    }
+
+    #[test]
+    fn common_root_of_unc_entries_is_the_share() {
+        let entries = vec![
+            entry(r"\\nas\media\Videos", true),
+            entry(r"\\nas\media\Videos\a.mkv", false),
+            entry(r"\\nas\media\Music\b.flac", false),
+        ];
+        assert_eq!(find_common_root(&entries), PathBuf::from(r"\\nas\media\"));
+    }
+
+    #[test]
+    fn common_root_covers_entries_past_the_first_hundred() {
+        let mut entries: Vec<_> = (0..150).map(|i| entry(&format!("/data/a/{i}.bin"), false)).collect();
+        entries.push(entry("/data/b", true));
+        entries.push(entry("/data/b/late.bin", false));
+        assert_eq!(find_common_root(&entries), PathBuf::from("/data"));
+
+        let tree = build_tree(&entries);
+        assert_eq!(tree.get(tree.root).file_count, 151);
+        assert!(find_by_path(&tree, Path::new("/data/b/late.bin"), Path::new("/data")).is_some());
+    }
+
+    #[test]
+    fn palette_slots_follow_bytes_not_scan_order() {
+        let entries = vec![
+            entry("/data/notes.txt", false),
+            entry("/data/a.bin", false),
+            entry("/data/b.bin", false),
+            entry("/data/c.log", false),
+        ];
+        let slots = |tree: &FileTree| {
+            let slot = |ext: &str| {
+                let id = tree.extensions.iter().position(|e| e == ext).unwrap();
+                tree.palette_slots[id]
+            };
+            (slot("bin"), slot("log"), slot("txt"))
+        };
+        let tree = build_tree(&entries);
+        // Most bytes first, then by name.
+        assert_eq!(slots(&tree), (0, 1, 2));
+        let reversed: Vec<_> = entries.into_iter().rev().collect();
+        assert_eq!(slots(&build_tree(&reversed)), (0, 1, 2));
+    }
+
+    #[test]
+    fn tree_builder_keeps_ids_across_snapshots() {
+        let mut builder = TreeBuilder::new(Path::new("/data"));
+        builder.add(&[entry("/data/a/x.bin", false), entry("/data/a/y.bin", false)]);
+        let first = builder.snapshot();
+        let a = first.children(first.root).next().unwrap();
+        assert_eq!((first.get(a).name.as_str(), first.get(first.root).size), ("a", 20));
+
+        // The folder's own entry comes after its files; later files land next to them.
+        builder.add(&[entry("/data/a", true), entry("/data/b/z.bin", false)]);
+        let second = builder.snapshot();
+        assert_eq!(second.get(a).name, "a");
+        assert_eq!(second.get(a).file_count, 2);
+        assert_eq!(second.get(second.root).size, 30);
+        assert_eq!(second.children(second.root).count(), 2);
+    }
+
+    #[test]
+    fn unc_entries_from_different_shares_fall_back() {
+        let entries = vec![
+            entry(r"\\nas\media\a.mkv", false),
+            entry(r"\\nas\backup\b.zip", false),
+        ];
+        assert_ne!(find_common_root(&entries), PathBuf::from(r"\\nas\media\"));
+    }
+
+    #[test]
+    fn only_bare_drive_roots_count_as_drive_roots() {
+        assert!(is_drive_root(Path::new(r"C:\")));
+        assert!(!is_drive_root(Path::new(r"C:\Windows")));
+        assert!(!is_drive_root(Path::new(r"\\nas\media\")));
+        assert!(extensions::is_unreclaimable_root_file("PAGEFILE.SYS"));
+        assert!(!extensions::is_unreclaimable_root_file("pagefile.sys.bak"));
+    }
+
+    #[test]
+    fn replace_subtree_fills_in_an_unexpanded_folder() {
+        let mut shallow = vec![entry("/data", true), entry("/data/a", true), entry("/data/b.bin", false)];
+        shallow[1].unexpanded = true;
+        let mut tree = build_tree(&shallow);
+        let a = tree.children(tree.root).find(|&id| tree.get(id).name == "a").unwrap();
+        assert!(tree.needs_expansion(tree.root));
+        assert_eq!(tree.get(tree.root).size, 10);
+
+        let mut deeper = vec![entry("/data/a", true), entry("/data/a/x.bin", false), entry("/data/a/deep", true)];
+        deeper[2].unexpanded = true;
+        let subtree = build_tree(&deeper);
+        replace_subtree(&mut tree, a, &subtree);
+
+        assert_eq!(tree.get(a).size, 10);
+        assert_eq!(tree.get(tree.root).size, 20);
+        assert_eq!(tree.get(tree.root).file_count, 2);
+        let deep = tree.children(a).find(|&id| tree.get(id).name == "deep").unwrap();
+        assert_eq!(tree.unexpanded, HashSet::from([deep]));
+        assert_eq!(tree.get(deep).depth, 2);
+    }
+
+    #[test]
+    fn node_paths_and_find_by_path_are_inverses() {
+        // Rooted at the scan folder: the tree root is named by its last component.
+        let tree = build_tree(&[entry("/data/a/x.bin", false), entry("/data/b/y.bin", false)]);
+        let scan_path = Path::new("/data");
+        for id in tree.descendants(tree.root) {
+            let path = node_path(&tree, id, scan_path);
+            assert!(path.starts_with(scan_path), "{:?}", path);
+            assert_eq!(find_by_path(&tree, &path, scan_path), Some(id));
+        }
+        assert_eq!(node_path(&tree, tree.root, scan_path), scan_path);
+        assert_eq!(find_by_path(&tree, scan_path, scan_path), Some(tree.root));
+        assert_eq!(find_by_path(&tree, Path::new("/data/a/missing"), scan_path), None);
+        assert_eq!(find_by_path(&tree, Path::new("/elsewhere/a"), scan_path), None);
+
+        // Merged scans resolve through their top-level folders, named by absolute path.
+        let merged = merge_trees(
+            "Selected folders",
+            &[
+                (PathBuf::from("/data"), tree.clone()),
+                (
+                    PathBuf::from("/media"),
+                    build_tree(&[entry("/media/v/y.mkv", false), entry("/media/w/z.mkv", false)]),
+                ),
+            ],
+        );
+        let y = find_by_path(&merged, Path::new("/media/v/y.mkv"), Path::new("/data")).unwrap();
+        assert_eq!(merged.get(y).name, "y.mkv");
+        assert_eq!(node_path(&merged, y, Path::new("/data")), Path::new("/media/v/y.mkv"));
+    }
+
+    #[test]
+    fn repeated_expansions_reuse_the_replaced_slots() {
+        let mut shallow = vec![entry("/data", true), entry("/data/a", true), entry("/data/b.bin", false)];
+        shallow[1].unexpanded = true;
+        let mut tree = build_tree(&shallow);
+        let a = tree.children(tree.root).find(|&id| tree.get(id).name == "a").unwrap();
+        let b = tree.children(tree.root).find(|&id| tree.get(id).name == "b.bin").unwrap();
+
+        let mut deeper: Vec<RawFileEntry> = vec![entry("/data/a", true), entry("/data/a/deep", true)];
+        deeper.extend((0..50).map(|i| entry(&format!("/data/a/deep/f{i}.bin"), false)));
+        deeper[1].unexpanded = true;
+        let subtree = build_tree(&deeper);
+
+        replace_subtree(&mut tree, a, &subtree);
+        let arena = tree.nodes.len();
+        for _ in 0..20 {
+            replace_subtree(&mut tree, a, &subtree);
+        }
+        assert_eq!(tree.nodes.len(), arena);
+        assert_eq!(tree.len(), arena);
+        assert_eq!(tree.get(tree.root).file_count, 51);
+        assert_eq!(tree.get(b).name, "b.bin");
+        assert!(tree.needs_expansion(a));
+
+        // A smaller rescan leaves free slots, which the counts and totals skip.
+        let flat = build_tree(&[entry("/data/a", true), entry("/data/a/x.bin", false)]);
+        replace_subtree(&mut tree, a, &flat);
+        assert_eq!(tree.nodes.len(), arena);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.get(tree.root).size, 20);
+        assert!(!tree.needs_expansion(a));
+        assert!(!tree.needs_expansion(tree.root));
+    }
+
+    #[test]
+    fn dominant_category_and_share_roll_up_to_parents() {
+        let mut entries = vec![
+            entry("/data", true),
+            entry("/data/media", true),
+            entry("/data/media/a.mkv", false),
+            entry("/data/media/b.mkv", false),
+            entry("/data/media/c.txt", false),
+            entry("/data/docs", true),
+            entry("/data/docs/d.txt", false),
+            entry("/data/empty", true),
+        ];
+        entries[6].size = 40;
+        let tree = build_tree(&entries);
+        let child = |name: &str| tree.children(tree.root).find(|&id| tree.get(id).name == name).unwrap();
+
+        let media = tree.get(child("media"));
+        assert_eq!(media.dominant_category, Some(FileCategory::Video));
+        assert!((media.dominant_share - 2.0 / 3.0).abs() < 1e-6);
+        // 50 bytes of text against 20 of video.
+        let root = tree.get(tree.root);
+        assert_eq!(root.dominant_category, Some(FileCategory::Document));
+        assert!((root.dominant_share - 50.0 / 70.0).abs() < 1e-6);
+        assert_eq!(tree.get(child("empty")).dominant_category, None);
+        assert_eq!(tree.get(child("empty")).dominant_share, 0.0);
+    }
+
+    #[test]
+    fn size_basis_switches_between_logical_and_allocated() {
+        let mut entries = vec![entry("/data", true), entry("/data/small.txt", false), entry("/data/sparse.img", false)];
+        entries[1].size = 100;
+        entries[1].allocated = Some(4096);
+        entries[2].size = 1_000_000;
+        entries[2].allocated = Some(0);
+        let mut tree = build_tree(&entries);
+        let first = |tree: &FileTree| tree.get(tree.children(tree.root).next().unwrap()).name.to_string();
+        assert_eq!(tree.get(tree.root).size, 1_000_100);
+        assert_eq!(first(&tree), "sparse.img");
+
+        set_size_basis(&mut tree, SizeBasis::OnDisk);
+        assert_eq!(tree.get(tree.root).size, 4096);
+        assert_eq!(tree.get(tree.root).logical, 1_000_100);
+        assert_eq!(first(&tree), "small.txt");
+
+        set_size_basis(&mut tree, SizeBasis::Logical);
+        assert_eq!(tree.get(tree.root).size, 1_000_100);
+    }
+
+    #[test]
+    fn build_path_does_not_double_root_separator() {
+        let mut tree = FileTree::new(r"\\nas\media\");
+        let dir = tree.add_child(tree.root, FileNode::test_dir("Videos", 0));
+        let file = tree.add_child(dir, FileNode::test_file("a.mkv", 10));
+        assert_eq!(
+            crate::ui::tooltip::build_path(&tree, file),
+            r"\\nas\media\Videos\a.mkv"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use super::arena::{FileTree, NodeId};
+
+/// Default cap on returned matches; a search box never shows more than this.
+pub const DEFAULT_SEARCH_LIMIT: usize = 200;
+
+/// Points per matched character.
+const MATCH_SCORE: i32 = 16;
+/// Bonus when a match directly follows the previous one.
+const CONSECUTIVE_BONUS: i32 = 24;
+/// Bonus when a run of matches starts a word (`foo_bar`, `foo.rs`, `FooBar`).
+const BOUNDARY_BONUS: i32 = 30;
+/// Penalty per name character skipped between matches (capped per gap).
+const GAP_PENALTY: i32 = 2;
+const MAX_GAP_PENALTY: i32 = 12;
+/// Penalty for the one pattern character a longer term may fail to match (a typo).
+const TYPO_PENALTY: i32 = 48;
+/// Terms at least this long tolerate a single unmatched character.
+const TYPO_MIN_TERM_LEN: usize = 4;
+/// Bonus for a contiguous substring match, and extra when it is a prefix or the whole name.
+const SUBSTRING_BONUS: i32 = 32;
+const PREFIX_BONUS: i32 = 32;
+const EXACT_BONUS: i32 = 64;
+
+/// Search the subtree under `root` (excluding `root`) and return up to `limit` matches,
+/// best first; ties go to the larger node.
+///
+/// The query is split on whitespace. `ext:rs` terms restrict results to files with that
+/// extension (exact, case-insensitive; several are OR'd). Every other term must fuzzy-match
+/// the node name, and their scores add up. A query of only `ext:` terms lists those files
+/// by size.
+pub fn search(tree: &FileTree, root: NodeId, query: &str, limit: usize) -> Vec<(NodeId, u32)> {
+    let mut extensions = Vec::new();
+    let mut terms = Vec::new();
+    for token in query.split_whitespace() {
+        match token.strip_prefix("ext:") {
+            Some(ext) => extensions.push(ext.trim_start_matches('.').to_ascii_lowercase()),
+            None => terms.push(token.to_lowercase()),
+        }
+    }
+    if extensions.is_empty() && terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<(NodeId, u32)> = tree
+        .descendants(root)
+        .filter(|&id| id != root)
+        .filter_map(|id| {
+            let node = tree.get(id);
+            if !extensions.is_empty()
+                && (node.is_dir || !extensions.iter().any(|e| *e == tree.extensions[node.extension_id as usize]))
+            {
+                return None;
+            }
+            let mut score = 0u32;
+            for term in &terms {
+                score += fuzzy_score(term, &node.name)?;
+            }
+            Some((id, score))
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| tree.get(b.0).size.cmp(&tree.get(a.0).size))
+    });
+    results.truncate(limit);
+    results
+}
+
+/// Score how well `term` matches `name` as an in-order subsequence, ignoring case.
+/// `None` when it doesn't match; terms of four or more characters may miss one
+/// character (a typo) at a penalty. Higher is better and any match scores at least 1.
+pub fn fuzzy_score(term: &str, name: &str) -> Option<u32> {
+    let term: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    if term.is_empty() {
+        return Some(1);
+    }
+    let original: Vec<char> = name.chars().collect();
+    let lower: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    // Case folding can change lengths (rare); skip boundary detection then.
+    let aligned = original.len() == lower.len();
+
+    let mut score = 0i32;
+    let mut pos = 0usize;
+    let mut prev: Option<usize> = None;
+    let mut typo_used = false;
+    for &c in &term {
+        match lower[pos..].iter().position(|&n| n == c) {
+            Some(offset) => {
+                let at = pos + offset;
+                score += MATCH_SCORE;
+                match prev {
+                    Some(p) if at == p + 1 => score += CONSECUTIVE_BONUS,
+                    gap_from => {
+                        let gap = at - gap_from.map_or(0, |p| p + 1);
+                        score -= (gap as i32 * GAP_PENALTY).min(MAX_GAP_PENALTY);
+                        if aligned && is_word_start(&original, at) {
+                            score += BOUNDARY_BONUS;
+                        }
+                    }
+                }
+                prev = Some(at);
+                pos = at + 1;
+            }
+            None if !typo_used && term.len() >= TYPO_MIN_TERM_LEN => {
+                typo_used = true;
+                score -= TYPO_PENALTY;
+            }
+            None => return None,
+        }
+    }
+
+    if !typo_used {
+        let needle: String = term.iter().collect();
+        let haystack: String = lower.iter().collect();
+        if let Some(at) = haystack.find(&needle) {
+            score += SUBSTRING_BONUS;
+            if at == 0 {
+                score += PREFIX_BONUS;
+            }
+            if needle.len() == haystack.len() {
+                score += EXACT_BONUS;
+            }
+        }
+    }
+    // Shorter names are the more specific match.
+    score -= (lower.len() as i32 - term.len() as i32).clamp(0, MAX_GAP_PENALTY);
+    Some(score.max(1) as u32)
+}
+
+fn is_word_start(name: &[char], at: usize) -> bool {
+    if at == 0 {
+        return true;
+    }
+    let (before, here) = (name[at - 1], name[at]);
+    matches!(before, '.' | '_' | '-' | ' ' | '(' | '[')
+        || (before.is_lowercase() && here.is_uppercase())
+        || (before.is_alphabetic() && here.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_prefers_tighter_matches() {
+        assert!(fuzzy_score("fo.rs", "foo.rs").is_some());
+        assert!(fuzzy_score("xyz", "foo.rs").is_none());
+        assert!(fuzzy_score("main", "main.rs") > fuzzy_score("main", "domain_model.rs"));
+        assert!(fuzzy_score("fb", "foo_bar.txt") > fuzzy_score("fb", "offbeat.txt"));
+        assert!(fuzzy_score("readme", "README.md") > fuzzy_score("readme", "ReadMeNot.md"));
+        // One typo is tolerated in longer terms, at a cost.
+        assert!(fuzzy_score("cargo", "Cargo.toml") > fuzzy_score("carho", "Cargo.toml"));
+        assert!(fuzzy_score("carho", "Cargo.toml").is_some());
+        assert!(fuzzy_score("cx", "Cargo.toml").is_none());
+    }
+
+    #[test]
+    fn search_filters_by_extension_and_ranks_by_score_then_size() {
+        use crate::tree::arena::FileNode;
+
+        let mut tree = FileTree::new("main");
+        let root = tree.root;
+        let src = tree.add_child(root, FileNode::test_dir("src", 0));
+        let rs = FileNode { extension_id: tree.intern_extension("rs"), ..FileNode::test_file("main.rs", 10) };
+        let main_rs = tree.add_child(src, rs);
+        let rs = FileNode { extension_id: tree.intern_extension("rs"), ..FileNode::test_file("lib.rs", 500) };
+        let lib_rs = tree.add_child(src, rs);
+        let md = FileNode { extension_id: tree.intern_extension("md"), ..FileNode::test_file("domain.md", 900) };
+        let domain_md = tree.add_child(root, md);
+
+        let ids = |query: &str, limit| -> Vec<NodeId> {
+            search(&tree, root, query, limit).into_iter().map(|(id, _)| id).collect()
+        };
+        assert!(ids("", 10).is_empty());
+        // The root itself never matches, even though its name does.
+        assert_eq!(ids("main", 10), vec![main_rs, domain_md]);
+        assert_eq!(ids("main ext:rs", 10), vec![main_rs]);
+        // Only extensions: every such file, largest first; folders never match.
+        assert_eq!(ids("ext:.RS", 10), vec![lib_rs, main_rs]);
+        assert_eq!(ids("ext:rs ext:md", 2), vec![domain_md, lib_rs]);
+        assert!(ids("ext:toml", 10).is_empty());
+    }
+}
@@ -1,20 +1,43 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use iced::widget::{button, checkbox, column, container, row, slider, text, text_input};
+use iced::widget::{button, checkbox, column, container, pick_list, row, slider, text, text_input};
 use iced::{application, window, Element, Length, Task, Theme};
 
 use crate::layout::LayoutConfig;
+use crate::render::colors::{AppColor, ColorMode, ColorSettings};
 use crate::render::cushion::CushionConfig;
+use crate::render::scene::BackgroundStyle;
+use crate::ui::overlay::MIN_BRIGHTNESS_MAX;
+use crate::ui::tooltip::PercentBasis;
 
 #[derive(Clone)]
 pub struct DialogResult {
     pub scan_path: PathBuf,
     pub layout: LayoutConfig,
     pub cushion: CushionConfig,
+    pub color: ColorSettings,
     pub show_labels: bool,
+    pub label_top_level: bool,
+    pub show_file_labels: bool,
+    pub show_dir_watermarks: bool,
+    pub focus_hovered_folder: bool,
+    pub lshape_layout: bool,
+    pub highlight_recent_days: Option<u32>,
     pub label_font_scale: f32,
     pub label_font_path: String,
+    pub include_system_metadata: bool,
+    pub scan_depth_limit: Option<usize>,
+    pub auto_refresh_interval: Option<Duration>,
+    pub hover_delay_ms: f32,
+    pub max_animation_fps: f32,
+    pub poll_scan_progress: bool,
+    pub background_layout: bool,
+    pub background_style: BackgroundStyle,
+    pub percent_basis: PercentBasis,
+    pub background_image_path: String,
 }
 
 pub fn run_config_dialog(
@@ -54,13 +77,43 @@ enum Message {
     MinAreaChanged(f32),
     MinSideChanged(f32),
     RecurseSideChanged(f32),
+    RecurseScreenPxChanged(f32),
     FramePxChanged(f32),
     HeaderPxChanged(f32),
-    CushionHeightChanged(f32),
-    CushionFalloffChanged(f32),
+    MaxChildrenChanged(f32),
+    MaxRectsChanged(f32),
+    CollapseChainsChanged(bool),
+    GroupSmallFilesChanged(bool),
+    ShowEmptyChanged(bool),
+    LshapeLayoutChanged(bool),
+    AmbientLightChanged(f32),
+    DiffuseLightChanged(f32),
+    LightAzimuthChanged(f32),
+    LightElevationChanged(f32),
+    MinBrightnessChanged(f32),
+    DepthShadingChanged(f32),
+    ColorModeChanged(ColorMode),
+    VibrancyChanged(f32),
+    TintDirectoriesChanged(bool),
     ShowLabelsChanged(bool),
+    LabelTopLevelChanged(bool),
+    ShowFileLabelsChanged(bool),
+    ShowDirWatermarksChanged(bool),
+    FocusHoveredFolderChanged(bool),
+    RecentDaysChanged(f32),
     LabelFontScaleChanged(f32),
     LabelFontPathChanged(String),
+    IncludeSystemMetadataChanged(bool),
+    ScanDepthChanged(f32),
+    AutoRefreshChanged(f32),
+    HoverDelayChanged(f32),
+    MaxAnimationFpsChanged(f32),
+    PollScanProgressChanged(bool),
+    BackgroundLayoutChanged(bool),
+    BackgroundStyleChanged(BackgroundStyle),
+    PercentBasisChanged(PercentBasis),
+    BackgroundImagePathChanged(String),
+    BrowseBackgroundImage,
     Start,
     Cancel,
 }
@@ -70,31 +123,97 @@ struct ConfigDialog {
     min_area: f32,
     min_side: f32,
     recurse_side: f32,
+    recurse_screen_px: f32,
     frame_px: f32,
     header_px: f32,
+    max_children: f32,
+    max_rects: f32,
+    collapse_chains: bool,
+    group_small_files: bool,
+    show_empty: bool,
+    lshape_layout: bool,
     ambient: f32,
     diffuse: f32,
+    /// Compass direction the light comes from, degrees counterclockwise from +x.
+    light_azimuth: f32,
+    /// Degrees above the screen plane; 90 lights the tiles head-on.
+    light_elevation: f32,
+    min_brightness: f32,
+    /// Percent, 0-100.
+    depth_shading: f32,
+    color_mode: ColorMode,
+    vibrancy: f32,
+    tint_directories: bool,
+    /// Not edited here; carried through so closing the dialog keeps them.
+    pinned_colors: BTreeMap<PathBuf, AppColor>,
     show_labels: bool,
+    label_top_level: bool,
+    show_file_labels: bool,
+    show_dir_watermarks: bool,
+    focus_hovered_folder: bool,
+    recent_days: f32,
     label_font_scale: f32,
     label_font_path: String,
+    include_system_metadata: bool,
+    scan_depth: f32,
+    /// Minutes between automatic rescans, 0 = off
+    auto_refresh_minutes: f32,
+    hover_delay_ms: f32,
+    max_animation_fps: f32,
+    poll_scan_progress: bool,
+    background_layout: bool,
+    background_style: BackgroundStyle,
+    background_image_path: String,
+    percent_basis: PercentBasis,
     output: Arc<Mutex<Option<DialogResult>>>,
     show_path_input: bool,
 }
 
 impl ConfigDialog {
     fn new(initial: DialogResult, output: Arc<Mutex<Option<DialogResult>>>, show_path_input: bool) -> Self {
+        let (light_azimuth, light_elevation) = light_angles(initial.cushion.light);
         Self {
             path_text: initial.scan_path.to_string_lossy().to_string(),
             min_area: initial.layout.min_area,
             min_side: initial.layout.min_side,
             recurse_side: initial.layout.recurse_min_side,
+            recurse_screen_px: initial.layout.recurse_min_screen_px,
             frame_px: initial.layout.dir_frame_px,
             header_px: initial.layout.dir_header_px,
+            max_children: initial.layout.max_children_per_dir as f32,
+            max_rects: initial.layout.max_rects as f32,
+            collapse_chains: initial.layout.collapse_chains,
+            group_small_files: initial.layout.group_small_files,
+            show_empty: initial.layout.show_empty,
+            lshape_layout: initial.lshape_layout,
             ambient: initial.cushion.ambient,
             diffuse: initial.cushion.diffuse,
+            light_azimuth,
+            light_elevation,
+            min_brightness: initial.cushion.min_brightness,
+            depth_shading: initial.cushion.depth_shading * 100.0,
+            color_mode: initial.color.mode,
+            vibrancy: initial.color.vibrancy,
+            tint_directories: initial.color.tint_directories,
+            pinned_colors: initial.color.pinned.clone(),
             show_labels: initial.show_labels,
+            label_top_level: initial.label_top_level,
+            show_file_labels: initial.show_file_labels,
+            show_dir_watermarks: initial.show_dir_watermarks,
+            focus_hovered_folder: initial.focus_hovered_folder,
+            recent_days: initial.highlight_recent_days.unwrap_or(0) as f32,
             label_font_scale: initial.label_font_scale,
             label_font_path: initial.label_font_path,
+            include_system_metadata: initial.include_system_metadata,
+            scan_depth: initial.scan_depth_limit.unwrap_or(0) as f32,
+            auto_refresh_minutes: initial.auto_refresh_interval.map_or(0.0, |i| i.as_secs_f32() / 60.0),
+            hover_delay_ms: initial.hover_delay_ms,
+            max_animation_fps: initial.max_animation_fps,
+            poll_scan_progress: initial.poll_scan_progress,
+            background_layout: initial.background_layout,
+            background_style: initial.background_style,
+            background_image_path: initial.background_image_path,
+            percent_basis: initial.percent_basis,
             output,
             show_path_input,
         }
@@ -124,6 +243,10 @@ impl ConfigDialog {
                 self.recurse_side = v;
                 Task::none()
             }
+            Message::RecurseScreenPxChanged(v) => {
+                self.recurse_screen_px = v;
+                Task::none()
+            }
             Message::FramePxChanged(v) => {
                 self.frame_px = v;
                 Task::none()
@@ -132,18 +255,90 @@ impl ConfigDialog {
                 self.header_px = v;
                 Task::none()
             }
-            Message::CushionHeightChanged(v) => {
+            Message::MaxChildrenChanged(v) => {
+                self.max_children = v;
+                Task::none()
+            }
+            Message::MaxRectsChanged(v) => {
+                self.max_rects = v;
+                Task::none()
+            }
+            Message::CollapseChainsChanged(v) => {
+                self.collapse_chains = v;
+                Task::none()
+            }
+            Message::GroupSmallFilesChanged(v) => {
+                self.group_small_files = v;
+                Task::none()
+            }
+            Message::ShowEmptyChanged(v) => {
+                self.show_empty = v;
+                Task::none()
+            }
+            Message::LshapeLayoutChanged(v) => {
+                self.lshape_layout = v;
+                Task::none()
+            }
+            Message::AmbientLightChanged(v) => {
                 self.ambient = v;
                 Task::none()
             }
-            Message::CushionFalloffChanged(v) => {
+            Message::DiffuseLightChanged(v) => {
                 self.diffuse = v;
                 Task::none()
             }
+            Message::LightAzimuthChanged(v) => {
+                self.light_azimuth = v;
+                Task::none()
+            }
+            Message::LightElevationChanged(v) => {
+                self.light_elevation = v;
+                Task::none()
+            }
+            Message::MinBrightnessChanged(v) => {
+                self.min_brightness = v;
+                Task::none()
+            }
+            Message::DepthShadingChanged(v) => {
+                self.depth_shading = v;
+                Task::none()
+            }
+            Message::ColorModeChanged(v) => {
+                self.color_mode = v;
+                Task::none()
+            }
+            Message::VibrancyChanged(v) => {
+                self.vibrancy = v;
+                Task::none()
+            }
+            Message::TintDirectoriesChanged(v) => {
+                self.tint_directories = v;
+                Task::none()
+            }
             Message::ShowLabelsChanged(v) => {
                 self.show_labels = v;
                 Task::none()
             }
+            Message::LabelTopLevelChanged(v) => {
+                self.label_top_level = v;
+                Task::none()
+            }
+            Message::ShowFileLabelsChanged(v) => {
+                self.show_file_labels = v;
+                Task::none()
+            }
+            Message::ShowDirWatermarksChanged(v) => {
+                self.show_dir_watermarks = v;
+                Task::none()
+            }
+            Message::FocusHoveredFolderChanged(v) => {
+                self.focus_hovered_folder = v;
+                Task::none()
+            }
+            Message::RecentDaysChanged(v) => {
+                self.recent_days = v;
+                Task::none()
+            }
             Message::LabelFontScaleChanged(v) => {
                 self.label_font_scale = v;
                 Task::none()
@@ -152,6 +347,53 @@ impl ConfigDialog {
                 self.label_font_path = v;
                 Task::none()
             }
+            Message::IncludeSystemMetadataChanged(v) => {
+                self.include_system_metadata = v;
+                Task::none()
+            }
+            Message::ScanDepthChanged(v) => {
+                self.scan_depth = v;
+                Task::none()
+            }
+            Message::AutoRefreshChanged(v) => {
+                self.auto_refresh_minutes = v;
+                Task::none()
+            }
+            Message::HoverDelayChanged(v) => {
+                self.hover_delay_ms = v;
+                Task::none()
+            }
+            Message::MaxAnimationFpsChanged(v) => {
+                self.max_animation_fps = v;
+                Task::none()
+            }
+            Message::PollScanProgressChanged(v) => {
+                self.poll_scan_progress = v;
+                Task::none()
+            }
+            Message::BackgroundLayoutChanged(v) => {
+                self.background_layout = v;
+                Task::none()
+            }
+            Message::BackgroundStyleChanged(v) => {
+                self.background_style = v;
+                Task::none()
+            }
+            Message::PercentBasisChanged(v) => {
+                self.percent_basis = v;
+                Task::none()
+            }
+            Message::BackgroundImagePathChanged(v) => {
+                self.background_image_path = v;
+                Task::none()
+            }
+            Message::BrowseBackgroundImage => {
+                if let Some(path) = rfd::FileDialog::new().add_filter("PNG image", &["png"]).pick_file() {
+                    self.background_image_path = path.to_string_lossy().to_string();
+                    self.background_style = BackgroundStyle::Image;
+                }
+                Task::none()
+            }
             Message::Start => {
                 let path = PathBuf::from(self.path_text.trim());
                 if path.as_os_str().is_empty() {
@@ -162,21 +404,54 @@ impl ConfigDialog {
                 layout.min_area = self.min_area;
                 layout.min_side = self.min_side;
                 layout.recurse_min_side = self.recurse_side;
+                layout.recurse_min_screen_px = self.recurse_screen_px;
                 layout.dir_frame_px = self.frame_px;
                 layout.dir_header_px = self.header_px;
+                layout.max_children_per_dir = self.max_children.round().max(1.0) as usize;
+                layout.max_rects = self.max_rects.round().max(1_000.0) as usize;
+                layout.collapse_chains = self.collapse_chains;
+                layout.group_small_files = self.group_small_files;
+                layout.show_empty = self.show_empty;
 
                 let mut cushion = CushionConfig::default();
                 cushion.ambient = self.ambient;
                 cushion.diffuse = self.diffuse;
+                cushion.light = light_vector(self.light_azimuth, self.light_elevation);
+                cushion.min_brightness = self.min_brightness;
+                cushion.depth_shading = self.depth_shading / 100.0;
 
                 if let Ok(mut guard) = self.output.lock() {
                     *guard = Some(DialogResult {
                         scan_path: path,
                         layout,
                         cushion,
+                        color: ColorSettings {
+                            mode: self.color_mode,
+                            vibrancy: self.vibrancy,
+                            tint_directories: self.tint_directories,
+                            pinned: self.pinned_colors.clone(),
+                        },
                         show_labels: self.show_labels,
+                        label_top_level: self.label_top_level,
+                        show_file_labels: self.show_file_labels,
+                        show_dir_watermarks: self.show_dir_watermarks,
+                        focus_hovered_folder: self.focus_hovered_folder,
+                        lshape_layout: self.lshape_layout,
+                        highlight_recent_days: Some(self.recent_days.round() as u32).filter(|&d| d > 0),
                         label_font_scale: self.label_font_scale,
                         label_font_path: self.label_font_path.clone(),
+                        include_system_metadata: self.include_system_metadata,
+                        scan_depth_limit: Some(self.scan_depth.round() as usize).filter(|&d| d > 0),
+                        auto_refresh_interval: Some(self.auto_refresh_minutes.round() as u64)
+                            .filter(|&m| m > 0)
+                            .map(|m| Duration::from_secs(m * 60)),
+                        hover_delay_ms: self.hover_delay_ms,
+                        max_animation_fps: self.max_animation_fps,
+                        poll_scan_progress: self.poll_scan_progress,
+                        background_layout: self.background_layout,
+                        background_style: self.background_style,
+                        background_image_path: self.background_image_path.clone(),
+                        percent_basis: self.percent_basis,
                     });
                 }
 
@@ -193,6 +468,20 @@ impl ConfigDialog {
     }
 }
 
+/// Azimuth and elevation in degrees of a light direction vector.
+fn light_angles(light: [f32; 3]) -> (f32, f32) {
+    let [x, y, z] = light;
+    let azimuth = y.atan2(x).to_degrees().rem_euclid(360.0);
+    let elevation = z.atan2((x * x + y * y).sqrt()).to_degrees();
+    (azimuth, elevation)
+}
+
+/// Unit light direction vector for an azimuth and elevation in degrees.
+fn light_vector(azimuth: f32, elevation: f32) -> [f32; 3] {
+    let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+    [el.cos() * az.cos(), el.cos() * az.sin(), el.sin()]
+}
+
 fn setting_slider<'a>(
     label: &'a str,
     value: f32,
@@ -237,6 +526,12 @@ fn view(state: &ConfigDialog) -> Element<'_, Message> {
             8.0..=160.0,
             Message::RecurseSideChanged
         ),
+        setting_slider(
+            "Recurse Min On-Screen (physical px)",
+            state.recurse_screen_px,
+            0.0..=160.0,
+            Message::RecurseScreenPxChanged
+        ),
         setting_slider("Directory Frame (px)", state.frame_px, 0.0..=8.0, Message::FramePxChanged),
         setting_slider(
             "Directory Header (px)",
@@ -244,20 +539,91 @@ fn view(state: &ConfigDialog) -> Element<'_, Message> {
             6.0..=36.0,
             Message::HeaderPxChanged
         ),
+        setting_slider(
+            "Max Children per Folder",
+            state.max_children,
+            50.0..=10_000.0,
+            Message::MaxChildrenChanged
+        ),
+        setting_slider(
+            "Max Tiles per Layout (coarser detail beyond this)",
+            state.max_rects,
+            10_000.0..=1_000_000.0,
+            Message::MaxRectsChanged
+        ),
+        checkbox("Collapse single-folder chains", state.collapse_chains)
+            .on_toggle(Message::CollapseChainsChanged),
+        checkbox("Group small files by extension", state.group_small_files)
+            .on_toggle(Message::GroupSmallFilesChanged),
+        checkbox("Show empty folders and zero-byte files as small tiles", state.show_empty)
+            .on_toggle(Message::ShowEmptyChanged),
+        checkbox("Also fill the space below the sidebar (L-shaped treemap)", state.lshape_layout)
+            .on_toggle(Message::LshapeLayoutChanged),
         text("Cushion").size(18),
         setting_slider(
             "Ambient Light",
             state.ambient,
             0.05..=0.95,
-            Message::CushionHeightChanged
+            Message::AmbientLightChanged
         ),
         setting_slider(
             "Diffuse Light",
             state.diffuse,
             0.05..=1.20,
-            Message::CushionFalloffChanged
+            Message::DiffuseLightChanged
+        ),
+        setting_slider(
+            "Light Direction (°)",
+            state.light_azimuth,
+            0.0..=360.0,
+            Message::LightAzimuthChanged
+        ),
+        setting_slider(
+            "Light Elevation (°, 90 = overhead)",
+            state.light_elevation,
+            10.0..=90.0,
+            Message::LightElevationChanged
         ),
+        setting_slider(
+            "Minimum Brightness",
+            state.min_brightness,
+            0.0..=MIN_BRIGHTNESS_MAX,
+            Message::MinBrightnessChanged
+        ),
+        setting_slider(
+            "Darken Deeper Tiles (%)",
+            state.depth_shading,
+            0.0..=100.0,
+            Message::DepthShadingChanged
+        ),
+        text("Colors").size(18),
+        row![
+            text("Color Mode").size(16),
+            pick_list(ColorMode::ALL, Some(state.color_mode), Message::ColorModeChanged)
+        ]
+        .spacing(8),
+        column![
+            text(format!("Vibrancy: {:.2}", state.vibrancy)).size(16),
+            slider(0.6..=2.0, state.vibrancy, Message::VibrancyChanged).step(0.04)
+        ]
+        .spacing(6),
+        checkbox("Tint folders by the category that fills them", state.tint_directories)
+            .on_toggle(Message::TintDirectoriesChanged),
         checkbox("Show folder labels", state.show_labels).on_toggle(Message::ShowLabelsChanged),
+        checkbox("Always label top-level folders (when they fit)", state.label_top_level)
+            .on_toggle(Message::LabelTopLevelChanged),
+        checkbox("Label large file tiles (name or extension)", state.show_file_labels)
+            .on_toggle(Message::ShowFileLabelsChanged),
+        checkbox("Watermark large folders with their name", state.show_dir_watermarks)
+            .on_toggle(Message::ShowDirWatermarksChanged),
+        checkbox("Dim everything outside the hovered folder", state.focus_hovered_folder)
+            .on_toggle(Message::FocusHoveredFolderChanged),
+        setting_slider(
+            "Outline Files Modified In The Last N Days (0 = off)",
+            state.recent_days,
+            0.0..=90.0,
+            Message::RecentDaysChanged
+        ),
         setting_slider(
             "Label Font Scale",
             state.label_font_scale,
@@ -266,7 +632,63 @@ fn view(state: &ConfigDialog) -> Element<'_, Message> {
         ),
         text_input("Custom font path (optional, .ttf)", &state.label_font_path)
             .on_input(Message::LabelFontPathChanged)
-            .padding(8)
+            .padding(8),
+        setting_slider(
+            "Hover Info Delay (ms)",
+            state.hover_delay_ms,
+            0.0..=1000.0,
+            Message::HoverDelayChanged
+        ),
+        setting_slider(
+            "Animation Frame Cap (fps)",
+            state.max_animation_fps,
+            5.0..=120.0,
+            Message::MaxAnimationFpsChanged
+        ),
+        checkbox("Poll scan progress every frame (instead of on updates)", state.poll_scan_progress)
+            .on_toggle(Message::PollScanProgressChanged),
+        checkbox("Compute layouts on a background thread (keeps the old one up meanwhile)", state.background_layout)
+            .on_toggle(Message::BackgroundLayoutChanged),
+        row![
+            text("Percentages").size(16),
+            pick_list(PercentBasis::ALL, Some(state.percent_basis), Message::PercentBasisChanged)
+        ]
+        .spacing(8),
+        row![
+            text("Background").size(16),
+            pick_list(
+                BackgroundStyle::ALL,
+                Some(state.background_style),
+                Message::BackgroundStyleChanged
+            )
+        ]
+        .spacing(8),
+        row![
+            text_input("Background image path (optional, .png)", &state.background_image_path)
+                .on_input(Message::BackgroundImagePathChanged)
+                .padding(8)
+                .width(Length::Fill),
+            button("Browse").on_press(Message::BrowseBackgroundImage)
+        ]
+        .spacing(8),
+        text("Scan").size(18),
+        checkbox(
+            "Include NTFS system metadata ($MFT, $LogFile, ...) as a top-level node",
+            state.include_system_metadata
+        )
+        .on_toggle(Message::IncludeSystemMetadataChanged),
+        setting_slider(
+            "Shallow Scan Depth (0 = full; deeper folders load on drill-down)",
+            state.scan_depth,
+            0.0..=12.0,
+            Message::ScanDepthChanged
+        ),
+        setting_slider(
+            "Auto-Refresh Every N Minutes (0 = off)",
+            state.auto_refresh_minutes,
+            0.0..=120.0,
+            Message::AutoRefreshChanged
+        )
     ]
     .spacing(10);
 
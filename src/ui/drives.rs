@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::ui::tooltip::{format_size, SizeUnits};
 
 #[derive(Debug, Clone)]
 pub struct DriveEntry {
@@ -8,6 +10,62 @@ pub struct DriveEntry {
     pub available_bytes: u64,
 }
 
+impl DriveEntry {
+    /// Bytes the filesystem reports as in use.
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.available_bytes)
+    }
+}
+
+/// The drive mounted exactly at `path`; subfolder scans have none, since comparing a
+/// folder against a whole drive's usage says nothing useful.
+pub fn drive_for_root<'a>(drives: &'a [DriveEntry], path: &Path) -> Option<&'a DriveEntry> {
+    let trim = |p: &Path| {
+        let s = p.to_string_lossy();
+        s.trim_end_matches(['\\', '/']).to_string()
+    };
+    let target = trim(path);
+    drives.iter().find(|d| trim(&d.path).eq_ignore_ascii_case(&target))
+}
+
+/// One-line comparison of the scanned on-disk total against the drive's used bytes,
+/// naming the usual reasons for the gap. `None` when the drive didn't report usage.
+pub fn reconciliation_summary(
+    scanned: u64,
+    drive: &DriveEntry,
+    metadata_included: bool,
+    units: SizeUnits,
+) -> Option<String> {
+    let used = drive.used_bytes();
+    if used == 0 {
+        return None;
+    }
+    let head = format!(
+        "Scanned {}; drive reports {} used",
+        format_size(scanned, units),
+        format_size(used, units)
+    );
+    if scanned <= used {
+        let mut causes = Vec::new();
+        if !metadata_included {
+            causes.push("MFT/metadata");
+        }
+        causes.extend(["permission-blocked", "reserved"]);
+        Some(format!(
+            "{} ({} unaccounted: {})",
+            head,
+            format_size(used - scanned, units),
+            causes.join("/")
+        ))
+    } else {
+        Some(format!(
+            "{} ({} over: hard links counted per path)",
+            head,
+            format_size(scanned - used, units)
+        ))
+    }
+}
+
 #[cfg(windows)]
 pub fn enumerate_drives() -> Vec<DriveEntry> {
     enumerate_drives_windows()
@@ -40,7 +98,8 @@ fn enumerate_drives_windows() -> Vec<DriveEntry> {
         })
         .collect();
 
-    if entries.is_empty() {
+    // Only fall back to `C:\` if it is really there; an empty list gets its own sidebar state.
+    if entries.is_empty() && Path::new("C:\\").is_dir() {
         entries.push(DriveEntry {
             label: "C:\\".to_string(),
             path: PathBuf::from("C:\\"),
@@ -73,7 +132,7 @@ fn enumerate_drives_linux() -> Vec<DriveEntry> {
         })
         .collect();
 
-    if entries.is_empty() {
+    if entries.is_empty() && Path::new("/").is_dir() {
         entries.push(DriveEntry {
             label: "/".to_string(),
             path: PathBuf::from("/"),
@@ -85,3 +144,42 @@ fn enumerate_drives_linux() -> Vec<DriveEntry> {
     entries.sort_by(|a, b| a.label.cmp(&b.label));
     entries
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drive(total_bytes: u64, available_bytes: u64) -> DriveEntry {
+        DriveEntry {
+            label: "C:".to_string(),
+            path: PathBuf::from("C:\\"),
+            total_bytes,
+            available_bytes,
+        }
+    }
+
+    #[test]
+    fn reconciliation_names_the_gap_either_way() {
+        let c = drive(10_000, 4_000);
+        assert_eq!(
+            reconciliation_summary(5_000, &c, false, SizeUnits::Si).as_deref(),
+            Some("Scanned 5.0 kB; drive reports 6.0 kB used (1.0 kB unaccounted: MFT/metadata/permission-blocked/reserved)")
+        );
+        assert_eq!(
+            reconciliation_summary(5_000, &c, true, SizeUnits::Si).as_deref(),
+            Some("Scanned 5.0 kB; drive reports 6.0 kB used (1.0 kB unaccounted: permission-blocked/reserved)")
+        );
+        assert_eq!(
+            reconciliation_summary(7_000, &c, true, SizeUnits::Si).as_deref(),
+            Some("Scanned 7.0 kB; drive reports 6.0 kB used (1.0 kB over: hard links counted per path)")
+        );
+        assert_eq!(reconciliation_summary(5_000, &drive(0, 0), true, SizeUnits::Si), None);
+    }
+
+    #[test]
+    fn only_a_drive_root_finds_its_drive() {
+        let drives = [drive(10_000, 4_000)];
+        assert!(drive_for_root(&drives, Path::new("c:")).is_some());
+        assert!(drive_for_root(&drives, Path::new("C:\\Users")).is_none());
+    }
+}
@@ -1,5 +1,6 @@
-use winit::event::{ElementState, MouseButton};
-use winit::keyboard::{Key, NamedKey};
+use vello::kurbo::Affine;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
 
 use crate::layout::LayoutRect;
 use crate::tree::arena::NodeId;
@@ -11,10 +12,64 @@ pub struct MouseState {
     pub y: f32,
     pub left_pressed: bool,
     pub right_pressed: bool,
+    /// Middle-button drag in progress (pans the treemap view)
+    pub panning: bool,
 }
 
-/// Hit-test: find which layout rectangle contains the given point.
-/// Returns the topmost (deepest) rectangle at that point.
+/// Free zoom/pan applied to the treemap on top of the navigation hierarchy.
+/// Screen = content * scale + translate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    pub scale: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+}
+
+impl ViewTransform {
+    const MAX_SCALE: f32 = 32.0;
+
+    /// Map a screen-space point into layout (content) coordinates.
+    pub fn to_content(self, x: f32, y: f32) -> (f32, f32) {
+        ((x - self.tx) / self.scale, (y - self.ty) / self.scale)
+    }
+
+    /// Zoom by `factor` keeping the content under (`x`, `y`) fixed on screen.
+    pub fn zoom_at(&mut self, factor: f32, x: f32, y: f32) {
+        let new_scale = (self.scale * factor).clamp(1.0, Self::MAX_SCALE);
+        if new_scale <= 1.0 {
+            *self = Self::default();
+            return;
+        }
+        let (cx, cy) = self.to_content(x, y);
+        self.scale = new_scale;
+        self.tx = x - cx * new_scale;
+        self.ty = y - cy * new_scale;
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        if self.scale > 1.0 {
+            self.tx += dx;
+            self.ty += dy;
+        }
+    }
+
+    pub fn affine(&self) -> Affine {
+        Affine::translate((self.tx as f64, self.ty as f64)) * Affine::scale(self.scale as f64)
+    }
+}
+
+/// Hit-test: find which layout rectangle contains the given point, in layout coordinates
+/// (see `App::layout_point`). Returns the topmost (deepest) rectangle at that point.
 pub fn hit_test(layout_rects: &[LayoutRect], x: f32, y: f32) -> Option<NodeId> {
     // Iterate in reverse since deeper nodes are added later
     for rect in layout_rects.iter().rev() {
@@ -34,8 +89,52 @@ pub enum InputAction {
     DrillDown { node: NodeId },
     /// Right click or backspace (navigate up)
     NavigateUp,
+    /// Jump to the parent of the largest file under the current root and select it
+    RevealLargest,
+    /// Show or hide the details inspector
+    ToggleInspector,
+    /// Search the current view for duplicate files
+    FindDuplicates,
+    /// Open the file-name search prompt
+    OpenSearch,
+    /// Open the prompt for a path to go to (or scan)
+    OpenPathPrompt,
+    /// Step through duplicate groups (+1 next, -1 previous)
+    CycleDuplicates(isize),
+    /// Pin the next color to the hovered folder, or unpin it after the last one
+    CyclePinnedColor,
+    /// Shift+right click: open the pin-color menu for the folder under the cursor
+    OpenPinMenu { x: f32, y: f32 },
+    /// Compare the hovered folder side by side with the current view, or close the comparison
+    ToggleSplitView,
+    /// Switch between the folder treemap and a flat map of the biggest files
+    ToggleFlatLayout,
+    /// Show or hide the color legend
+    ToggleLegend,
+    /// Pick one or more folders to scan; several are merged into one treemap
+    OpenFolders,
+    /// Pick folders to merge into the current scan, e.g. from another drive
+    AddFolders,
+    /// Switch to the next color mode, as the sidebar button does
+    CycleColorMode,
+    /// Step color vibrancy down (-1) or up (+1)
+    AdjustVibrancy(i8),
+    /// Copy the rendered window to the clipboard
+    CopyScreenshot,
+    /// Turn grouping of small files by extension on or off
+    ToggleFileGrouping,
+    /// Pause or resume the running scan
+    ToggleScanPause,
+    /// Show or hide the node, layout and GPU stats overlay
+    ToggleStats,
+    /// Hide the hovered subtree from the layout for this session
+    ExcludeHovered,
+    /// Bring back every hidden subtree
+    RestoreAllExcluded,
     /// Scroll for zoom
     Zoom { delta: f32, x: f32, y: f32 },
+    /// Two-finger trackpad scroll: move the zoomed view by a screen-space delta
+    Pan { dx: f32, dy: f32 },
     /// Window resized
     Resize { width: u32, height: u32 },
     /// No action
@@ -46,7 +145,8 @@ pub enum InputAction {
 pub fn process_mouse_button(
     button: MouseButton,
     state: ElementState,
-    _mouse: &MouseState,
+    modifiers: ModifiersState,
+    mouse: &MouseState,
     _layout_rects: &[LayoutRect],
 ) -> InputAction {
     if state != ElementState::Pressed {
@@ -55,21 +155,216 @@ pub fn process_mouse_button(
 
     match button {
         MouseButton::Left => InputAction::None,
+        MouseButton::Right if modifiers.shift_key() => InputAction::OpenPinMenu { x: mouse.x, y: mouse.y },
         MouseButton::Back | MouseButton::Right => InputAction::NavigateUp,
         _ => InputAction::None,
     }
 }
 
+/// Whether pixel-precise (trackpad) scrolling always pans. Only where winit delivers pinch
+/// gestures; elsewhere a pinch arrives as ctrl+scroll, so scrolling keeps zooming until the
+/// view is zoomed in and there is something to pan.
+const PIXEL_SCROLL_PANS: bool = cfg!(any(target_os = "macos", target_os = "ios"));
+
+/// Trackpad pixels that count as one wheel notch.
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// Vertical distance of a wheel event in px, for scrolling lists; positive is up.
+pub fn scroll_pixels(delta: MouseScrollDelta) -> f32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => y * PIXELS_PER_LINE,
+        MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
+    }
+}
+
+/// Process a mouse wheel event into a zoom around the cursor, or a pan for two-finger
+/// trackpad scrolling.
+pub fn process_scroll(delta: MouseScrollDelta, mouse: &MouseState, zoomed: bool, ctrl: bool) -> InputAction {
+    if let MouseScrollDelta::PixelDelta(pos) = delta {
+        if !ctrl && (zoomed || PIXEL_SCROLL_PANS) {
+            return InputAction::Pan {
+                dx: pos.x as f32,
+                dy: pos.y as f32,
+            };
+        }
+    }
+    let delta = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / PIXELS_PER_LINE,
+    };
+    if delta == 0.0 {
+        return InputAction::None;
+    }
+    InputAction::Zoom {
+        delta,
+        x: mouse.x,
+        y: mouse.y,
+    }
+}
+
+/// Process a trackpad pinch into a zoom around the cursor. `magnification` is the change in
+/// scale since the last event (0.1 = 10% larger).
+pub fn process_pinch(magnification: f64, mouse: &MouseState) -> InputAction {
+    let factor = 1.0 + magnification as f32;
+    if !factor.is_finite() || factor <= 0.0 || magnification == 0.0 {
+        return InputAction::None;
+    }
+    // Zoom deltas are exponents of the wheel step (see `App::zoom_view`).
+    InputAction::Zoom {
+        delta: factor.ln() / 1.15_f32.ln(),
+        x: mouse.x,
+        y: mouse.y,
+    }
+}
+
 /// Process a keyboard event.
-pub fn process_key(key: Key, state: ElementState) -> InputAction {
+pub fn process_key(key: Key, state: ElementState, modifiers: ModifiersState) -> InputAction {
     if state != ElementState::Pressed {
         return InputAction::None;
     }
 
     match key.as_ref() {
+        Key::Character(c) if modifiers.control_key() && modifiers.shift_key() && c.eq_ignore_ascii_case("c") => {
+            InputAction::CopyScreenshot
+        }
         Key::Named(NamedKey::Backspace) | Key::Named(NamedKey::Escape) => {
             InputAction::NavigateUp
         }
+        Key::Character("/") => InputAction::OpenSearch,
+        Key::Character(c) if modifiers.control_key() && c.eq_ignore_ascii_case("l") => InputAction::OpenPathPrompt,
+        Key::Character(c) if c.eq_ignore_ascii_case("l") => InputAction::RevealLargest,
+        Key::Character(c) if c.eq_ignore_ascii_case("i") => InputAction::ToggleInspector,
+        Key::Character(c) if c.eq_ignore_ascii_case("d") => InputAction::FindDuplicates,
+        Key::Character(c) if c.eq_ignore_ascii_case("n") => InputAction::CycleDuplicates(1),
+        Key::Character(c) if c.eq_ignore_ascii_case("p") => InputAction::CycleDuplicates(-1),
+        // Shift+C; plain c pins colors.
+        Key::Character(c) if c.eq_ignore_ascii_case("c") => {
+            if modifiers.shift_key() {
+                InputAction::CycleColorMode
+            } else {
+                InputAction::CyclePinnedColor
+            }
+        }
+        Key::Character(c) if c.eq_ignore_ascii_case("v") => InputAction::ToggleSplitView,
+        Key::Character(c) if c.eq_ignore_ascii_case("f") => InputAction::ToggleFlatLayout,
+        Key::Character(c) if c.eq_ignore_ascii_case("k") => InputAction::ToggleLegend,
+        // Shift+O; plain o replaces the scan.
+        Key::Character(c) if c.eq_ignore_ascii_case("o") => {
+            if modifiers.shift_key() {
+                InputAction::AddFolders
+            } else {
+                InputAction::OpenFolders
+            }
+        }
+        Key::Character(c) if c.eq_ignore_ascii_case("g") => InputAction::ToggleFileGrouping,
+        // Shift+X; plain x hides the hovered folder. The case flips with Caps Lock, so
+        // go by the modifier.
+        Key::Character(c) if c.eq_ignore_ascii_case("x") => {
+            if modifiers.shift_key() {
+                InputAction::RestoreAllExcluded
+            } else {
+                InputAction::ExcludeHovered
+            }
+        }
+        Key::Named(NamedKey::Space) | Key::Named(NamedKey::Pause) => InputAction::ToggleScanPause,
+        Key::Named(NamedKey::F3) => InputAction::ToggleStats,
+        Key::Character("[") => InputAction::AdjustVibrancy(-1),
+        Key::Character("]") => InputAction::AdjustVibrancy(1),
         _ => InputAction::None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{hit_test, ViewTransform};
+    use crate::layout::LayoutRect;
+    use crate::tree::arena::NodeId;
+
+    fn rect(node: u32, x: f32, y: f32, w: f32, h: f32, depth: u16) -> LayoutRect {
+        LayoutRect { node: NodeId(node), x, y, w, h, depth, surface: [0.0; 4], grouped: 0 }
+    }
+
+    #[test]
+    fn screen_points_map_into_layout_through_the_view() {
+        let rects = [rect(0, 0.0, 0.0, 400.0, 200.0, 0), rect(1, 200.0, 0.0, 200.0, 200.0, 1)];
+
+        let identity = ViewTransform::default();
+        assert_eq!(identity.to_content(250.0, 50.0), (250.0, 50.0));
+        let (x, y) = identity.to_content(250.0, 50.0);
+        assert_eq!(hit_test(&rects, x, y), Some(NodeId(1)));
+
+        // Zoomed 2x around (100, 100): that point stays put, everything else halves its distance.
+        let mut zoomed = ViewTransform::default();
+        zoomed.zoom_at(2.0, 100.0, 100.0);
+        assert_eq!(zoomed.to_content(100.0, 100.0), (100.0, 100.0));
+        assert_eq!(zoomed.to_content(300.0, 100.0), (200.0, 100.0));
+        let (x, y) = zoomed.to_content(250.0, 50.0);
+        assert_eq!(hit_test(&rects, x, y), Some(NodeId(0)));
+    }
+
+    #[test]
+    fn shifted_shortcuts_ignore_caps_lock() {
+        use super::{process_key, InputAction};
+        use winit::event::ElementState;
+        use winit::keyboard::{Key, ModifiersState};
+
+        let press = |c: &str, modifiers| process_key(Key::Character(c.into()), ElementState::Pressed, modifiers);
+        // Caps Lock turns a plain press into "X" and a shifted one into "x".
+        for c in ["x", "X"] {
+            assert!(matches!(press(c, ModifiersState::empty()), InputAction::ExcludeHovered));
+            assert!(matches!(press(c, ModifiersState::SHIFT), InputAction::RestoreAllExcluded));
+        }
+        for c in ["c", "C"] {
+            assert!(matches!(press(c, ModifiersState::empty()), InputAction::CyclePinnedColor));
+            assert!(matches!(press(c, ModifiersState::SHIFT), InputAction::CycleColorMode));
+        }
+        for c in ["o", "O"] {
+            assert!(matches!(press(c, ModifiersState::empty()), InputAction::OpenFolders));
+            assert!(matches!(press(c, ModifiersState::SHIFT), InputAction::AddFolders));
+        }
+    }
+
+    #[test]
+    fn line_scrolls_zoom_and_pixel_scrolls_pan_when_zoomed() {
+        use super::{process_scroll, InputAction, MouseState, PIXEL_SCROLL_PANS};
+        use winit::dpi::PhysicalPosition;
+        use winit::event::MouseScrollDelta;
+
+        let mouse = MouseState { x: 10.0, y: 20.0, ..MouseState::default() };
+        let pixels = MouseScrollDelta::PixelDelta(PhysicalPosition::new(6.0, -80.0));
+
+        let action = process_scroll(MouseScrollDelta::LineDelta(0.0, 2.0), &mouse, true, false);
+        assert!(matches!(action, InputAction::Zoom { delta, x, y } if delta == 2.0 && (x, y) == (10.0, 20.0)));
+        assert!(matches!(
+            process_scroll(MouseScrollDelta::LineDelta(3.0, 0.0), &mouse, false, false),
+            InputAction::None
+        ));
+        // Zoomed in, trackpad scrolling moves around; with Ctrl it zooms, 40 px to a line.
+        assert!(matches!(process_scroll(pixels, &mouse, true, false), InputAction::Pan { dx, dy } if (dx, dy) == (6.0, -80.0)));
+        assert!(matches!(process_scroll(pixels, &mouse, true, true), InputAction::Zoom { delta, .. } if delta == -2.0));
+        let unzoomed = process_scroll(pixels, &mouse, false, false);
+        if PIXEL_SCROLL_PANS {
+            assert!(matches!(unzoomed, InputAction::Pan { .. }));
+        } else {
+            assert!(matches!(unzoomed, InputAction::Zoom { delta, .. } if delta == -2.0));
+        }
+    }
+
+    #[test]
+    fn pinches_zoom_in_and_out_by_their_sign() {
+        use super::{process_pinch, InputAction, MouseState};
+
+        let mouse = MouseState::default();
+        let zoom = |magnification| match process_pinch(magnification, &mouse) {
+            InputAction::Zoom { delta, .. } => Some(delta),
+            _ => None,
+        };
+        // One wheel step is a factor of 1.15.
+        assert!((zoom(0.15).unwrap() - 1.0).abs() < 1e-4);
+        assert!(zoom(-0.1).unwrap() < 0.0);
+        assert_eq!(zoom(0.0), None);
+        // Shrinking to nothing or past it has no zoom factor.
+        assert_eq!(zoom(-1.0), None);
+        assert_eq!(zoom(f64::NAN), None);
+    }
+}
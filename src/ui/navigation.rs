@@ -4,6 +4,8 @@ use crate::tree::arena::{FileTree, NodeId};
 pub struct NavigationState {
     /// Stack of view roots (for back navigation)
     history: Vec<NodeId>,
+    /// Roots left via `navigate_up`, most recent last (cleared by a new drill-down)
+    forward: Vec<NodeId>,
     /// Current view root
     pub current_root: NodeId,
 }
@@ -12,10 +14,22 @@ impl NavigationState {
     pub fn new(root: NodeId) -> Self {
         Self {
             history: Vec::new(),
+            forward: Vec::new(),
             current_root: root,
         }
     }
 
+    /// Start at `node`, with its ancestors as the way back up.
+    pub fn at(node: NodeId, tree: &FileTree) -> Self {
+        let mut history: Vec<NodeId> = tree.ancestors(node).collect();
+        history.reverse();
+        Self {
+            history,
+            forward: Vec::new(),
+            current_root: node,
+        }
+    }
+
     /// Drill down into a directory node.
     /// Returns true if navigation happened.
     pub fn drill_down(&mut self, node: NodeId, tree: &FileTree) -> bool {
@@ -37,6 +51,7 @@ impl NavigationState {
         }
 
         self.history.push(self.current_root);
+        self.forward.clear();
         self.current_root = target_id;
         true
     }
@@ -45,6 +60,7 @@ impl NavigationState {
     /// Returns true if navigation happened.
     pub fn navigate_up(&mut self) -> bool {
         if let Some(prev) = self.history.pop() {
+            self.forward.push(self.current_root);
             self.current_root = prev;
             true
         } else {
@@ -52,12 +68,44 @@ impl NavigationState {
         }
     }
 
+    /// Return to the view most recently left with `navigate_up`.
+    /// Returns true if navigation happened.
+    pub fn navigate_forward(&mut self) -> bool {
+        if let Some(next) = self.forward.pop() {
+            self.history.push(self.current_root);
+            self.current_root = next;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether `navigate_forward` has anywhere to go.
+    pub fn can_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
     /// Navigate to the absolute root.
     pub fn navigate_home(&mut self, root: NodeId) {
         self.history.clear();
+        self.forward.clear();
         self.current_root = root;
     }
 
+    /// Drop every view strictly inside `node`, e.g. before its subtree is replaced.
+    /// A current root inside it moves back to `node`.
+    pub fn forget_inside(&mut self, node: NodeId, tree: &FileTree) {
+        let inside = |id: NodeId| tree.ancestors(id).any(|a| a == node);
+        if inside(self.current_root) {
+            self.current_root = node;
+        }
+        self.history.retain(|&id| !inside(id));
+        while self.history.last() == Some(&self.current_root) {
+            self.history.pop();
+        }
+        self.forward.retain(|&id| !inside(id));
+    }
+
     /// Current depth in navigation history.
     pub fn depth(&self) -> usize {
         self.history.len()
@@ -1,31 +1,48 @@
-use vello::kurbo::{Affine, Circle, Rect};
+use vello::kurbo::{self, Affine, BezPath, Circle, Rect, Stroke};
 use vello::peniko::{Brush, Color, Fill};
 use vello::Scene;
 
-use crate::render::colors::{mode_name, ColorSettings};
+use crate::render::colors::{self, mode_name, ColorMode, ColorSettings};
 use crate::render::text::{TextRenderResult, TextRenderer};
-use crate::tree::arena::{FileTree, NodeId};
+use crate::scanner::types::ScanProgress;
+use crate::tree::analytics::Analytics;
+use crate::tree::arena::{FileTree, NodeId, SizeBasis};
 use crate::tree::extensions::FileCategory;
 use crate::ui::drives::DriveEntry;
 use crate::ui::tooltip;
 
-/// Analytics data for the file type breakdown panel.
-#[derive(Debug, Default)]
-pub struct Analytics {
-    /// Total bytes per category
-    pub category_sizes: Vec<(FileCategory, u64)>,
-    /// Total size of all files
-    pub total_size: u64,
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SidebarHitId {
     SelectDrive(std::path::PathBuf),
+    /// Pick a folder to scan (offered when no drives were found)
+    OpenFolder,
     CycleColorMode,
     VibrancyDown,
     VibrancyUp,
     VibrancyTrack,
+    BrightnessDown,
+    BrightnessUp,
+    BrightnessTrack,
+    DepthDown,
+    DepthUp,
+    DepthTrack,
     ToggleHoverInfo,
+    ToggleSizeUnits,
+    ToggleSizeBasis,
+    ToggleCategoryPatterns,
+    ToggleDirectoryTint,
+    ToggleHiddenFiles,
+    ToggleFreezeLayout,
+    ToggleSidebar,
+    CopyTree,
+    /// Pause or resume the running scan (button on the loading panel)
+    ToggleScanPause,
+    /// Bring back a subtree hidden from the layout
+    RestoreExcluded(NodeId),
+    /// Pin-color menu choice: a slot of `PIN_COLORS`, or `None` to unpin
+    PinColor(Option<usize>),
+    NavigateUp,
+    NavigateForward,
 }
 
 #[derive(Debug, Clone)]
@@ -34,7 +51,33 @@ pub struct SidebarHitRegion {
     pub bounds: [f32; 4],
 }
 
-pub fn sidebar_panel_bounds(viewport_height: f32, drive_count: usize) -> [f32; 4] {
+/// Outline the sidebar control that has keyboard focus.
+pub fn render_focus_ring(scene: &mut Scene, bounds: [f32; 4]) {
+    let [x1, y1, x2, y2] = bounds;
+    let ring = Rect::new(x1 as f64, y1 as f64, x2 as f64, y2 as f64).inset(2.0);
+    scene.stroke(
+        &Stroke::new(2.0),
+        Affine::IDENTITY,
+        Color::new([0.45, 0.70, 1.0, 0.95]),
+        None,
+        &ring,
+    );
+}
+
+/// Width of the strip left behind when the sidebar is collapsed.
+const COLLAPSED_SIDEBAR_W: f32 = 20.0;
+
+/// How far the sidebar controls can scroll when they are taller than the panel.
+pub fn sidebar_max_scroll(viewport_height: f32, drive_count: usize) -> f32 {
+    let [_, y1, _, y2] = sidebar_panel_bounds(viewport_height, drive_count, false);
+    (sidebar_height(drive_count.min(12)) - (y2 - y1)).max(0.0)
+}
+
+pub fn sidebar_panel_bounds(viewport_height: f32, drive_count: usize, collapsed: bool) -> [f32; 4] {
+    if collapsed {
+        // Full-height strip so the treemap beside it stays rectangular.
+        return [8.0, 8.0, 8.0 + COLLAPSED_SIDEBAR_W, (viewport_height - 8.0).max(40.0)];
+    }
     let visible_drives = drive_count.min(12);
     let panel_h = sidebar_height(visible_drives).min((viewport_height - 8.0).max(32.0));
     [8.0, 8.0, 196.0, 8.0 + panel_h]
@@ -45,47 +88,44 @@ pub fn vibrancy_value_from_track_x(x: f32, track: [f32; 4]) -> f32 {
     0.6 + t * (2.0 - 0.6)
 }
 
-/// Compute analytics for the current view.
-pub fn compute_analytics(tree: &FileTree, root: NodeId) -> Analytics {
-    let mut category_map = std::collections::HashMap::new();
-    let mut total_size = 0u64;
-
-    // Traverse all descendants
-    let mut stack = vec![root];
-    while let Some(node_id) = stack.pop() {
-        let node = tree.get(node_id);
-
-        if !node.is_dir {
-            // It's a file - categorize it
-            let ext = if node.extension_id > 0 {
-                tree.extensions
-                    .get(node.extension_id as usize)
-                    .map(|s| s.as_str())
-                    .unwrap_or("")
-            } else {
-                ""
-            };
-            let category = crate::tree::extensions::categorize_extension(ext);
-            *category_map.entry(category).or_insert(0u64) += node.size;
-            total_size += node.size;
-        }
+/// Deepest explicit value on the depth slider; one step further means "no limit".
+pub const DEPTH_LIMIT_MAX: u16 = 12;
 
-        // Add children to stack
-        for child_id in tree.children(node_id) {
-            stack.push(child_id);
-        }
+/// Map a cursor x on the depth track to a depth limit (`None` = unlimited).
+pub fn depth_limit_from_track_x(x: f32, track: [f32; 4]) -> Option<u16> {
+    let t = ((x - track[0]) / (track[2] - track[0]).max(1.0)).clamp(0.0, 1.0);
+    let step = (t * DEPTH_LIMIT_MAX as f32).round() as u16 + 1;
+    if step > DEPTH_LIMIT_MAX {
+        None
+    } else {
+        Some(step)
     }
+}
 
-    // Sort by size descending
-    let mut category_sizes: Vec<_> = category_map.into_iter().collect();
-    category_sizes.sort_by(|a, b| b.1.cmp(&a.1));
-
-    Analytics {
-        category_sizes,
-        total_size,
+/// Step the depth limit by `delta`, saturating at 1 and wrapping past the top into "unlimited".
+pub fn step_depth_limit(limit: Option<u16>, delta: i32) -> Option<u16> {
+    let current = limit.unwrap_or(DEPTH_LIMIT_MAX + 1) as i32;
+    let next = (current + delta).clamp(1, DEPTH_LIMIT_MAX as i32 + 1) as u16;
+    if next > DEPTH_LIMIT_MAX {
+        None
+    } else {
+        Some(next)
     }
 }
 
+/// Highest cushion brightness floor the sidebar and settings offer.
+pub const MIN_BRIGHTNESS_MAX: f32 = 0.6;
+
+pub fn min_brightness_from_track_x(x: f32, track: [f32; 4]) -> f32 {
+    let t = ((x - track[0]) / (track[2] - track[0]).max(1.0)).clamp(0.0, 1.0);
+    t * MIN_BRIGHTNESS_MAX
+}
+
+fn depth_limit_track_t(limit: Option<u16>) -> f32 {
+    let step = limit.unwrap_or(DEPTH_LIMIT_MAX + 1).clamp(1, DEPTH_LIMIT_MAX + 1);
+    (step - 1) as f32 / DEPTH_LIMIT_MAX as f32
+}
+
 /// Render the analytics panel on the right side.
 pub fn render_analytics_panel(
     scene: &mut Scene,
@@ -153,8 +193,9 @@ pub fn render_tooltip(
     node_id: NodeId,
     mouse_x: f32,
     mouse_y: f32,
+    size_units: tooltip::SizeUnits,
 ) {
-    let info = tooltip::build_tooltip(tree, node_id);
+    let info = tooltip::build_tooltip(tree, node_id, size_units);
 
     // Tooltip background
     let tooltip_width = 300.0;
@@ -191,6 +232,163 @@ pub fn render_tooltip(
     // Text will be added when we integrate parley
 }
 
+/// Default width of the details inspector.
+pub const INSPECTOR_DEFAULT_W: f32 = 300.0;
+/// Narrowest the inspector can be dragged to.
+pub const INSPECTOR_MIN_W: f32 = 220.0;
+/// Width of the drag handle along the inspector's left edge.
+pub const INSPECTOR_GRIP_W: f32 = 6.0;
+
+/// What the details inspector describes.
+pub struct Inspected<'a> {
+    pub tree: &'a FileTree,
+    pub node_id: NodeId,
+    /// Root of the current view, for the "of view" share.
+    pub view_root: NodeId,
+    /// Used-space reconciliation line, shown when the drive root is inspected.
+    pub reconciliation: Option<&'a str>,
+}
+
+/// Render the details inspector as a full-height panel from `x1` to the right edge.
+pub fn render_inspector_panel(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    inspected: &Inspected,
+    x1: f32,
+    viewport_width: f32,
+    viewport_height: f32,
+    size_units: tooltip::SizeUnits,
+) {
+    let Inspected { tree, node_id, view_root, reconciliation } = *inspected;
+    let panel = Rect::new(x1 as f64, 0.0, viewport_width as f64, viewport_height as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.10, 0.11, 0.13, 0.92]),
+        None,
+        &panel,
+    );
+    let grip = Rect::new(x1 as f64, 0.0, (x1 + 2.0) as f64, viewport_height as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([1.0, 1.0, 1.0, 0.12]),
+        None,
+        &grip,
+    );
+
+    let info = tooltip::build_tooltip(tree, node_id, size_units);
+    let node = tree.get(node_id);
+    let x = x1 + 14.0;
+    let text_w = (viewport_width - x - 12.0).max(40.0);
+    let mut y = 12.0;
+
+    draw_label_with_width(scene, text_renderer, &info.name, x, y, text_w);
+    y += 22.0;
+    if let Some(rendered) = text_renderer.render_text(&info.full_path, "default", 12.0, Some(text_w)) {
+        let h = rendered.height as f32;
+        draw_text(scene, rendered, x, y);
+        y += h + 10.0;
+    }
+
+    let mut rows = vec![
+        ("Size", info.size_display),
+        match tree.size_basis {
+            SizeBasis::Logical => ("Allocated", tooltip::format_size(node.allocated, size_units)),
+            SizeBasis::OnDisk => ("Logical", tooltip::format_size(node.logical, size_units)),
+        },
+        ("Category", info.category),
+        ("Modified", tooltip::format_timestamp(node.modified)),
+    ];
+    if let Some(attributes) = tooltip::format_attributes(node.attributes) {
+        rows.push(("Attributes", attributes));
+    }
+    for (basis, base) in [(tooltip::PercentBasis::View, view_root), (tooltip::PercentBasis::Scan, tree.root)] {
+        if node_id != base {
+            if let Some(share) = tooltip::format_share(node.size, tree.get(base).size) {
+                rows.push(("Share", format!("{} {}", share, basis.label())));
+            }
+        }
+    }
+    if node.is_dir {
+        rows.push(("Files", node.file_count.to_string()));
+        rows.push(("Folders", node.dir_count.to_string()));
+        if let Some(category) = node.dominant_category {
+            rows.push(("Mostly", format!("{:?} ({:.0}%)", category, node.dominant_share * 100.0)));
+        }
+    }
+    for (label, value) in rows {
+        draw_label_with_width(scene, text_renderer, label, x, y, 80.0);
+        draw_label_with_width(scene, text_renderer, &value, x + 84.0, y, text_w - 84.0);
+        y += 20.0;
+    }
+    let reconciliation =
+        reconciliation.and_then(|line| text_renderer.render_text(line, "default", 12.0, Some(text_w)));
+    if let Some(rendered) = reconciliation {
+        let h = rendered.height as f32;
+        draw_text(scene, rendered, x, y + 4.0);
+        y += h + 8.0;
+    }
+
+    if !node.is_dir || node.size == 0 {
+        return;
+    }
+
+    // Child-size distribution: one segment per large child, the remainder lumped at the end.
+    y += 10.0;
+    draw_label(scene, text_renderer, "Contents", x, y);
+    y += 22.0;
+    let bar_h = 14.0;
+    let mut seg_x = x;
+    let children: Vec<NodeId> = tree.children(node_id).take(8).collect();
+    for (i, &child) in children.iter().enumerate() {
+        let c = tree.get(child);
+        let w = (c.size as f64 / node.size as f64) as f32 * text_w;
+        let color = if c.is_dir {
+            let shade = if i % 2 == 0 { 0.42 } else { 0.34 };
+            Color::new([shade, shade + 0.02, shade + 0.06, 1.0])
+        } else {
+            crate::render::colors::category_color(tree.category(child)).to_peniko()
+        };
+        let seg = Rect::new(seg_x as f64, y as f64, (seg_x + w) as f64, (y + bar_h) as f64);
+        scene.fill(Fill::NonZero, Affine::IDENTITY, color, None, &seg);
+        seg_x += w;
+    }
+    let rest = Rect::new(seg_x as f64, y as f64, (x + text_w) as f64, (y + bar_h) as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.22, 0.23, 0.26, 1.0]),
+        None,
+        &rest,
+    );
+    y += bar_h + 10.0;
+
+    let mut listed = 0u64;
+    for &child in children.iter().take(5) {
+        if y + 20.0 > viewport_height {
+            return;
+        }
+        let c = tree.get(child);
+        listed += c.size;
+        let pct = c.size as f64 / node.size as f64 * 100.0;
+        draw_label_with_width(scene, text_renderer, &format!("{:>4.1}%", pct), x, y, 56.0);
+        draw_label_with_width(scene, text_renderer, &c.name, x + 56.0, y, text_w - 56.0);
+        y += 20.0;
+    }
+    let others = node.size.saturating_sub(listed);
+    if others > 0 && y + 20.0 <= viewport_height {
+        draw_label_with_width(
+            scene,
+            text_renderer,
+            &format!("other: {}", tooltip::format_size(others, size_units)),
+            x,
+            y,
+            text_w,
+        );
+    }
+}
+
 /// Render breadcrumb navigation path at the top.
 pub fn render_breadcrumb(
     scene: &mut Scene,
@@ -212,16 +410,34 @@ pub fn render_breadcrumb(
     // For Phase 2, we'll keep it simple without text initially
 }
 
+/// What the left sidebar shows: the drives to pick from and the current settings.
+pub struct Sidebar<'a> {
+    pub drives: &'a [DriveEntry],
+    pub selected_scan_path: &'a std::path::Path,
+    pub color_settings: &'a ColorSettings,
+    pub min_brightness: f32,
+    pub depth_limit: Option<u16>,
+    pub show_hover_info: bool,
+    pub size_units: tooltip::SizeUnits,
+    pub size_basis: SizeBasis,
+    pub show_category_patterns: bool,
+    pub collapsed: bool,
+    pub can_navigate_up: bool,
+    pub can_navigate_forward: bool,
+    pub show_hidden_files: bool,
+    pub freeze_layout: bool,
+    /// How far the controls are scrolled up, in px; clamped to `sidebar_max_scroll`
+    pub scroll: f32,
+}
+
 pub fn render_left_sidebar(
     scene: &mut Scene,
     text_renderer: &mut TextRenderer,
     viewport_height: f32,
-    drives: &[DriveEntry],
-    selected_scan_path: &std::path::Path,
-    color_settings: &ColorSettings,
-    show_hover_info: bool,
+    sidebar: &Sidebar,
 ) -> Vec<SidebarHitRegion> {
-    let [x1, y1, x2, y2] = sidebar_panel_bounds(viewport_height, drives.len());
+    let drives = sidebar.drives;
+    let [x1, y1, x2, y2] = sidebar_panel_bounds(viewport_height, drives.len(), sidebar.collapsed);
     let visible_drives = drives.len().min(12);
     let mut hits = Vec::new();
     let panel = Rect::new(x1 as f64, y1 as f64, x2 as f64, y2 as f64);
@@ -233,11 +449,26 @@ pub fn render_left_sidebar(
         &panel,
     );
 
-    let mut y = y1 + 8.0;
+    // Collapse/expand chevron in the top-right corner of the panel, above the controls.
+    let chevron = [x2 - 20.0, y1 + 6.0, x2 - 2.0, y1 + 24.0];
+    let chevron_hit = SidebarHitRegion {
+        id: SidebarHitId::ToggleSidebar,
+        bounds: chevron,
+    };
+    if sidebar.collapsed {
+        draw_chevron(scene, chevron, true);
+        hits.push(chevron_hit);
+        return hits;
+    }
+
+    // Controls that don't fit a short window scroll within the panel.
+    let scroll = sidebar.scroll.clamp(0.0, sidebar_max_scroll(viewport_height, drives.len()));
+    scene.push_clip_layer(Fill::NonZero, Affine::IDENTITY, &panel);
+    let mut y = y1 + 8.0 - scroll;
     draw_label(scene, text_renderer, "Drives", 14.0, y);
     y += 22.0;
 
-    let selected = selected_scan_path.to_string_lossy().to_lowercase();
+    let selected = sidebar.selected_scan_path.to_string_lossy().to_lowercase();
     for drive in drives.iter().take(visible_drives) {
         let row_h = 26.0_f32;
         let bx1 = 10.0_f32;
@@ -260,98 +491,458 @@ pub fn render_left_sidebar(
         });
         y += row_h + 6.0;
     }
+    if drives.is_empty() {
+        y = draw_no_drives(scene, text_renderer, y, x2, &mut hits);
+    }
+
+    // Up / Forward navigation, grayed out when there is nowhere to go.
+    y += 4.0;
+    let mid = (10.0 + x2 - 10.0) * 0.5;
+    let up = [10.0, y, mid - 3.0, y + 28.0];
+    let forward = [mid + 3.0, y, x2 - 10.0, y + 28.0];
+    for (bounds, label, enabled, id) in [
+        (up, "Up", sidebar.can_navigate_up, SidebarHitId::NavigateUp),
+        (forward, "Forward", sidebar.can_navigate_forward, SidebarHitId::NavigateForward),
+    ] {
+        let [bx1, by1, bx2, by2] = bounds;
+        let r = Rect::new(bx1 as f64, by1 as f64, bx2 as f64, by2 as f64);
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::new([0.16, 0.17, 0.20, 0.78]),
+            None,
+            &r,
+        );
+        let arrow = [bx1 + 4.0, by1 + 5.0, bx1 + 22.0, by2 - 5.0];
+        if matches!(id, SidebarHitId::NavigateUp) {
+            draw_up_arrow(scene, arrow);
+        } else {
+            draw_chevron(scene, arrow, true);
+        }
+        draw_label(scene, text_renderer, label, bx1 + 24.0, by1 + 7.0);
+        if enabled {
+            hits.push(SidebarHitRegion { id, bounds });
+        } else {
+            scene.fill(
+                Fill::NonZero,
+                Affine::IDENTITY,
+                Color::new([0.10, 0.11, 0.13, 0.62]),
+                None,
+                &r,
+            );
+        }
+    }
+    y += 36.0;
 
     y += 8.0;
     draw_label(scene, text_renderer, "Appearance", 14.0, y);
     y += 24.0;
 
+    let color_settings = sidebar.color_settings;
     let mode_text = format!("Mode: {}", mode_name(color_settings.mode));
-    let mode_r = Rect::new(10.0, y as f64, (x2 - 10.0) as f64, (y + 28.0) as f64);
+    y = draw_toggle_row(scene, text_renderer, &mode_text, SidebarHitId::CycleColorMode, y, x2, &mut hits) + 2.0;
+
+    let vib_text = format!("{:.2}", color_settings.vibrancy);
+    let t = ((color_settings.vibrancy - 0.6) / (2.0 - 0.6)).clamp(0.0, 1.0);
+    let (minus, plus, track) =
+        draw_slider_row(scene, text_renderer, "Vibrancy", &vib_text, t, y, x2);
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::VibrancyDown,
+        bounds: minus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::VibrancyUp,
+        bounds: plus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::VibrancyTrack,
+        bounds: track,
+    });
+    y += 54.0;
+
+    let floor_text = format!("{:.2}", sidebar.min_brightness);
+    let t = (sidebar.min_brightness / MIN_BRIGHTNESS_MAX).clamp(0.0, 1.0);
+    let (minus, plus, track) =
+        draw_slider_row(scene, text_renderer, "Floor", &floor_text, t, y, x2);
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::BrightnessDown,
+        bounds: minus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::BrightnessUp,
+        bounds: plus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::BrightnessTrack,
+        bounds: track,
+    });
+    y += 54.0;
+
+    let depth_text = match sidebar.depth_limit {
+        Some(depth) => depth.to_string(),
+        None => "All".to_string(),
+    };
+    let t = depth_limit_track_t(sidebar.depth_limit);
+    let (minus, plus, track) =
+        draw_slider_row(scene, text_renderer, "Depth", &depth_text, t, y, x2);
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::DepthDown,
+        bounds: minus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::DepthUp,
+        bounds: plus,
+    });
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::DepthTrack,
+        bounds: track,
+    });
+    y += 54.0;
+
+    let on_off = |on: bool, label: &str| format!("{}: {}", label, if on { "On" } else { "Off" });
+    let rows = [
+        (on_off(sidebar.show_hover_info, "Hover Info"), SidebarHitId::ToggleHoverInfo),
+        (format!("Units: {}", sidebar.size_units.label()), SidebarHitId::ToggleSizeUnits),
+        (format!("Sizes: {}", sidebar.size_basis.label()), SidebarHitId::ToggleSizeBasis),
+        (on_off(sidebar.show_category_patterns, "Patterns"), SidebarHitId::ToggleCategoryPatterns),
+        (
+            if color_settings.tint_directories { "Folders: By Content" } else { "Folders: Plain" }.to_string(),
+            SidebarHitId::ToggleDirectoryTint,
+        ),
+        (
+            if sidebar.show_hidden_files { "Hidden Files: Shown" } else { "Hidden Files: Excluded" }.to_string(),
+            SidebarHitId::ToggleHiddenFiles,
+        ),
+        (
+            if sidebar.freeze_layout { "Layout: Frozen" } else { "Layout: Live" }.to_string(),
+            SidebarHitId::ToggleFreezeLayout,
+        ),
+        ("Copy Tree to Clipboard".to_string(), SidebarHitId::CopyTree),
+    ];
+    for (label, id) in rows {
+        y = draw_toggle_row(scene, text_renderer, &label, id, y, x2, &mut hits);
+    }
+    scene.pop_layer();
+
+    // Scrolled-away controls can't be clicked; partly visible ones only where they show.
+    hits.retain_mut(|hit| {
+        hit.bounds[1] = hit.bounds[1].max(y1);
+        hit.bounds[3] = hit.bounds[3].min(y2);
+        hit.bounds[1] < hit.bounds[3]
+    });
+    draw_chevron(scene, chevron, false);
+    hits.push(chevron_hit);
+    hits
+}
+
+/// Height of the always-on status bar along the bottom of the treemap.
+pub const STATUS_BAR_H: f32 = 22.0;
+
+/// Diagnostic readings, one per line, in a small panel with its top-left at (`x`, `y`).
+pub fn render_stats_panel(scene: &mut Scene, text_renderer: &mut TextRenderer, lines: &[String], x: f32, y: f32) {
+    const ROW_H: f32 = 16.0;
+    const PAD: f32 = 8.0;
+
+    let w = 220.0;
+    let h = lines.len() as f32 * ROW_H + PAD * 2.0;
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        Color::new([0.08, 0.09, 0.11, 0.85]),
         None,
-        &mode_r,
+        &Rect::new(x as f64, y as f64, (x + w) as f64, (y + h) as f64),
     );
-    draw_label(scene, text_renderer, &mode_text, 18.0, y + 7.0);
-    hits.push(SidebarHitRegion {
-        id: SidebarHitId::CycleColorMode,
-        bounds: [10.0, y, x2 - 10.0, y + 28.0],
-    });
-    y += 38.0;
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(rendered) = text_renderer.render_text(line, "default", 12.0, Some(w - PAD * 2.0)) {
+            draw_text(scene, rendered, x + PAD, y + PAD + i as f32 * ROW_H);
+        }
+    }
+}
 
-    draw_label(scene, text_renderer, "Vibrancy", 14.0, y);
-    let vib_text = format!("{:.2}", color_settings.vibrancy);
-    draw_label(scene, text_renderer, &vib_text, x2 - 70.0, y);
-    y += 18.0;
-    let minus = Rect::new(10.0, y as f64, 42.0, (y + 26.0) as f64);
-    let plus = Rect::new((x2 - 42.0) as f64, y as f64, x2 as f64, (y + 26.0) as f64);
-    let track = [50.0_f32, y, x2 - 50.0, y + 26.0];
-    let track_rect = Rect::new(track[0] as f64, track[1] as f64, track[2] as f64, track[3] as f64);
+/// Subtrees hidden from the layout, one clickable row each (name and size) in a panel
+/// hanging from its top-right corner at (`right`, `y`). Clicking a row restores it.
+pub fn render_excluded_list(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    hidden: &[(NodeId, String, u64)],
+    size_units: tooltip::SizeUnits,
+    right: f32,
+    y: f32,
+) -> Vec<SidebarHitRegion> {
+    const ROW_H: f32 = 18.0;
+    const PAD: f32 = 8.0;
+
+    if hidden.is_empty() {
+        return Vec::new();
+    }
+    let w = 240.0;
+    let x = right - w;
+    let h = (hidden.len() + 1) as f32 * ROW_H + PAD * 2.0;
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        Color::new([0.08, 0.09, 0.11, 0.85]),
         None,
-        &minus,
+        &Rect::new(x as f64, y as f64, right as f64, (y + h) as f64),
     );
+    if let Some(rendered) = text_renderer.render_text("Hidden (click to restore)", "default", 12.0, Some(w - PAD * 2.0)) {
+        draw_text(scene, rendered, x + PAD, y + PAD);
+    }
+
+    let mut regions = Vec::with_capacity(hidden.len());
+    for (i, (id, name, size)) in hidden.iter().enumerate() {
+        let row_y = y + PAD + (i + 1) as f32 * ROW_H;
+        let label = format!("{}  ·  {}", name, tooltip::format_size(*size, size_units));
+        if let Some(rendered) = text_renderer.render_text(&label, "default", 12.0, Some(w - PAD * 2.0)) {
+            draw_text(scene, rendered, x + PAD, row_y);
+        }
+        regions.push(SidebarHitRegion {
+            id: SidebarHitId::RestoreExcluded(*id),
+            bounds: [x, row_y - 2.0, right, row_y + ROW_H - 2.0],
+        });
+    }
+    regions
+}
+
+/// The pin-color context menu for `folder` at (`x`, `y`), kept inside `viewport`
+/// (width, height): a swatch per pin color, the current one outlined, and an unpin row.
+pub fn render_pin_menu(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    folder: &str,
+    current: Option<colors::AppColor>,
+    x: f32,
+    y: f32,
+    viewport: (f32, f32),
+) -> Vec<SidebarHitRegion> {
+    const ROW_H: f32 = 18.0;
+    const PAD: f32 = 8.0;
+    const SWATCH: f32 = 22.0;
+
+    let w = PAD * 2.0 + colors::PIN_COLORS.len() as f32 * (SWATCH + 4.0) - 4.0;
+    let h = PAD * 2.0 + ROW_H + SWATCH + 6.0 + ROW_H;
+    let x = x.min(viewport.0 - w).max(0.0);
+    let y = y.min(viewport.1 - h).max(0.0);
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        Color::new([0.08, 0.09, 0.11, 0.95]),
         None,
-        &plus,
+        &Rect::new(x as f64, y as f64, (x + w) as f64, (y + h) as f64),
     );
+    if let Some(rendered) = text_renderer.render_text(&format!("Pin color: {}", folder), "default", 12.0, Some(w - PAD * 2.0)) {
+        draw_text(scene, rendered, x + PAD, y + PAD);
+    }
+
+    let mut regions = Vec::with_capacity(colors::PIN_COLORS.len() + 1);
+    let swatch_y = y + PAD + ROW_H;
+    for (i, color) in colors::PIN_COLORS.iter().enumerate() {
+        let sx = x + PAD + i as f32 * (SWATCH + 4.0);
+        let swatch = Rect::new(sx as f64, swatch_y as f64, (sx + SWATCH) as f64, (swatch_y + SWATCH) as f64);
+        scene.fill(Fill::NonZero, Affine::IDENTITY, color.to_peniko(), None, &swatch);
+        if current == Some(*color) {
+            scene.stroke(&Stroke::new(2.0), Affine::IDENTITY, Color::WHITE, None, &swatch.inset(2.0));
+        }
+        regions.push(SidebarHitRegion {
+            id: SidebarHitId::PinColor(Some(i)),
+            bounds: [sx, swatch_y, sx + SWATCH, swatch_y + SWATCH],
+        });
+    }
+
+    let unpin_y = swatch_y + SWATCH + 6.0;
+    draw_label(scene, text_renderer, "No pin", x + PAD, unpin_y);
+    regions.push(SidebarHitRegion {
+        id: SidebarHitId::PinColor(None),
+        bounds: [x, unpin_y - 2.0, x + w, unpin_y + ROW_H - 2.0],
+    });
+    regions
+}
+
+/// Totals for the current view in a full-width strip from `x1` to `x2` at the bottom.
+pub fn render_status_bar(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    text: &str,
+    x1: f32,
+    x2: f32,
+    viewport_height: f32,
+) {
+    let y = viewport_height - STATUS_BAR_H;
+    let bar = Rect::new(x1 as f64, y as f64, x2 as f64, viewport_height as f64);
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.20, 0.22, 0.26, 0.86]),
+        Color::new([0.08, 0.09, 0.11, 0.95]),
         None,
-        &track_rect,
+        &bar,
     );
-    let t = ((color_settings.vibrancy - 0.6) / (2.0 - 0.6)).clamp(0.0, 1.0);
-    let thumb_x = track[0] + (track[2] - track[0]) * t;
-    let thumb = Rect::new((thumb_x - 4.0) as f64, (y + 2.0) as f64, (thumb_x + 4.0) as f64, (y + 24.0) as f64);
+    if let Some(rendered) = text_renderer.render_text(text, "default", 12.0, Some((x2 - x1 - 16.0).max(40.0))) {
+        let text_y = y + ((STATUS_BAR_H - rendered.height as f32) * 0.5).max(0.0);
+        draw_text(scene, rendered, x1 + 8.0, text_y);
+    }
+}
+
+/// What the color legend shows for the current mode.
+#[derive(Debug, PartialEq)]
+pub struct ColorLegend {
+    /// Heading naming the color mode
+    pub title: String,
+    /// Categories that get a swatch, largest first
+    pub categories: Vec<FileCategory>,
+    pub note: Option<&'static str>,
+}
+
+/// Category modes list a swatch per category present in `analytics`; the extension
+/// modes have no fixed mapping, so they only say so.
+pub fn color_legend(settings: &ColorSettings, analytics: &Analytics) -> ColorLegend {
+    let note = match settings.mode {
+        ColorMode::Category => None,
+        ColorMode::CategoryExtension => Some("Shades vary by extension"),
+        ColorMode::ExtensionHash => Some("Each extension gets an arbitrary color"),
+        ColorMode::ExtensionPalette => Some("Each extension gets its own evenly spaced color"),
+    };
+    let categories = match settings.mode {
+        ColorMode::ExtensionHash | ColorMode::ExtensionPalette => Vec::new(),
+        _ => analytics.category_sizes.iter().map(|&(category, _)| category).collect(),
+    };
+    ColorLegend {
+        title: format!("Colors: {}", mode_name(settings.mode)),
+        categories,
+        note,
+    }
+}
+
+/// Key to the current color mode (see `color_legend`), anchored at its bottom-left
+/// corner (`x`, `bottom`).
+pub fn render_color_legend(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    settings: &ColorSettings,
+    analytics: &Analytics,
+    x: f32,
+    bottom: f32,
+) {
+    const ROW_H: f32 = 16.0;
+    const SWATCH: f32 = 10.0;
+    const PAD: f32 = 8.0;
+
+    let legend = color_legend(settings, analytics);
+    if legend.categories.is_empty() && legend.note.is_none() {
+        return;
+    }
+    let rows = 1 + legend.categories.len() + usize::from(legend.note.is_some());
+
+    let w = 190.0;
+    let h = rows as f32 * ROW_H + PAD * 2.0;
+    let y = bottom - h;
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.78, 0.82, 0.92, 0.95]),
+        Color::new([0.08, 0.09, 0.11, 0.85]),
         None,
-        &thumb,
+        &Rect::new(x as f64, y as f64, (x + w) as f64, bottom as f64),
     );
-    draw_label(scene, text_renderer, "-", 24.0, y + 3.0);
-    draw_label(scene, text_renderer, "+", x2 - 30.0, y + 3.0);
-    hits.push(SidebarHitRegion {
-        id: SidebarHitId::VibrancyDown,
-        bounds: [10.0, y, 42.0, y + 26.0],
-    });
-    hits.push(SidebarHitRegion {
-        id: SidebarHitId::VibrancyUp,
-        bounds: [x2 - 42.0, y, x2, y + 26.0],
-    });
-    hits.push(SidebarHitRegion {
-        id: SidebarHitId::VibrancyTrack,
-        bounds: track,
-    });
-    y += 36.0;
 
-    let hover_r = Rect::new(10.0, y as f64, (x2 - 10.0) as f64, (y + 28.0) as f64);
+    let mut row_y = y + PAD;
+    if let Some(rendered) = text_renderer.render_text(&legend.title, "default", 12.0, Some(w - PAD * 2.0)) {
+        draw_text(scene, rendered, x + PAD, row_y);
+    }
+    row_y += ROW_H;
+    for category in legend.categories {
+        let swatch_y = row_y + (ROW_H - SWATCH) * 0.5;
+        let swatch = Rect::new(
+            (x + PAD) as f64,
+            swatch_y as f64,
+            (x + PAD + SWATCH) as f64,
+            (swatch_y + SWATCH) as f64,
+        );
+        let color = colors::category_swatch(category, settings).to_peniko();
+        scene.fill(Fill::NonZero, Affine::IDENTITY, color, None, &swatch);
+        if let Some(rendered) =
+            text_renderer.render_text(category.label(), "default", 12.0, Some(w - SWATCH - PAD * 3.0))
+        {
+            draw_text(scene, rendered, x + PAD * 2.0 + SWATCH, row_y);
+        }
+        row_y += ROW_H;
+    }
+    if let Some(note) = legend.note {
+        if let Some(rendered) = text_renderer.render_text(note, "default", 12.0, Some(w - PAD * 2.0)) {
+            draw_text(scene, rendered, x + PAD, row_y);
+        }
+    }
+}
+
+/// One line of status text in a dark strip along the bottom edge.
+pub fn render_status_banner(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    text: &str,
+    viewport_width: f32,
+    viewport_height: f32,
+) {
+    let Some(rendered) = text_renderer.render_text(text, "default", 13.0, Some(viewport_width - 40.0)) else {
+        return;
+    };
+    let h = rendered.height as f32 + 12.0;
+    let w = rendered.width as f32 + 20.0;
+    let x = ((viewport_width - w) * 0.5).max(8.0);
+    let y = viewport_height - h - 10.0;
+    let bg = Rect::new(x as f64, y as f64, (x + w) as f64, (y + h) as f64);
     scene.fill(
         Fill::NonZero,
         Affine::IDENTITY,
-        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        Color::new([0.05, 0.06, 0.07, 0.90]),
         None,
-        &hover_r,
+        &bg,
     );
-    let hover_text = if show_hover_info { "Hover Info: On" } else { "Hover Info: Off" };
-    draw_label(scene, text_renderer, hover_text, 18.0, y + 7.0);
-    hits.push(SidebarHitRegion {
-        id: SidebarHitId::ToggleHoverInfo,
-        bounds: [10.0, y, x2 - 10.0, y + 28.0],
-    });
+    draw_text(scene, rendered, x + 10.0, y + 6.0);
+}
 
-    hits
+/// Highlight shown while a file or folder is dragged over the window.
+pub fn render_drop_target(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    target: &std::path::Path,
+    viewport_width: f32,
+    viewport_height: f32,
+) {
+    let bounds = Rect::new(0.0, 0.0, viewport_width as f64, viewport_height as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.10, 0.18, 0.30, 0.45]),
+        None,
+        &bounds,
+    );
+    scene.stroke(
+        &Stroke::new(3.0).with_dashes(0.0, [12.0, 8.0]),
+        Affine::IDENTITY,
+        Color::new([0.45, 0.70, 1.0, 0.95]),
+        None,
+        &bounds.inset(-12.0).to_rounded_rect(10.0),
+    );
+
+    let label = format!("Drop to scan {}", target.display());
+    if let Some(rendered) = text_renderer.render_text(&label, "default", 18.0, Some(viewport_width - 80.0)) {
+        let x = (viewport_width - rendered.width as f32) * 0.5;
+        let y = (viewport_height - rendered.height as f32) * 0.5;
+        draw_text(scene, rendered, x, y);
+    }
+}
+
+/// What the loading overlay shows about the running scan.
+pub struct ScanStatus<'a> {
+    /// Time since the scan started, which drives the spinner.
+    pub elapsed_seconds: f32,
+    /// Folder the scanner is in, when it walks folder by folder.
+    pub current_path: Option<&'a std::path::Path>,
+    /// Latest progress report from the scanner.
+    pub progress: Option<&'a ScanProgress>,
+    /// Share of the drive's used bytes seen so far, when that is known.
+    pub fraction: Option<f32>,
+    /// Warn that the scan falls back to the slow walker without elevation.
+    pub show_admin_warning: bool,
+    pub paused: bool,
 }
 
 pub fn render_loading_overlay(
@@ -359,11 +950,15 @@ pub fn render_loading_overlay(
     text_renderer: &mut TextRenderer,
     viewport_width: f32,
     viewport_height: f32,
-    elapsed_seconds: f32,
-    show_admin_warning: bool,
-) {
+    status: &ScanStatus,
+    size_units: tooltip::SizeUnits,
+) -> SidebarHitRegion {
+    let ScanStatus { elapsed_seconds, current_path, progress, fraction, show_admin_warning, paused } = *status;
     let panel_w = (viewport_width * 0.54).clamp(420.0, 760.0);
-    let panel_h = if show_admin_warning { 126.0 } else { 92.0 };
+    let counts_h = 20.0;
+    let path_h = if current_path.is_some() { 22.0 } else { 0.0 };
+    let button_h = 36.0;
+    let panel_h = if show_admin_warning { 126.0 } else { 92.0 } + counts_h + path_h + button_h;
     let x = (viewport_width - panel_w) * 0.5;
     let y = (viewport_height - panel_h) * 0.5;
     let panel = Rect::new(x as f64, y as f64, (x + panel_w) as f64, (y + panel_h) as f64);
@@ -376,9 +971,9 @@ pub fn render_loading_overlay(
         &panel,
     );
 
-    // Center-justified loading line with spinner directly above it.
-    let text_result =
-        text_renderer.render_text("Loading drive data...", "default", 14.0, Some(panel_w - 32.0));
+    // Center-justified loading line with the progress ring directly above it.
+    let status = if paused { "Scan paused" } else { "Loading drive data..." };
+    let text_result = text_renderer.render_text(status, "default", 14.0, Some(panel_w - 32.0));
     let text_y = if let Some(rendered) = text_result {
         let tx = x + ((panel_w - rendered.width as f32) * 0.5).max(16.0);
         let ty = y + 47.0;
@@ -388,24 +983,32 @@ pub fn render_loading_overlay(
         y + 47.0
     };
 
-    let spinner_cx = x + panel_w * 0.5;
-    let spinner_cy = text_y - 16.0;
-    let spinner_r = 7.0;
-    let step = ((elapsed_seconds * 10.0) as i32).rem_euclid(12) as usize;
-    for i in 0..12usize {
-        let angle = (i as f32 / 12.0) * std::f32::consts::TAU;
-        let px = spinner_cx + angle.cos() * spinner_r;
-        let py = spinner_cy + angle.sin() * spinner_r;
-        let dist = ((12 + i as i32 - step as i32) % 12) as f32;
-        let alpha = (1.0 - dist / 12.0) * 0.9 + 0.08;
-        let dot = Circle::new((px as f64, py as f64), 1.7);
-        scene.fill(
-            Fill::NonZero,
-            Affine::IDENTITY,
-            &Color::new([0.88, 0.90, 0.95, alpha]),
-            None,
-            &dot,
+    let center = ((x + panel_w * 0.5) as f64, (text_y - 17.0) as f64);
+    draw_progress_ring(scene, center, 8.0, elapsed_seconds, fraction, paused);
+
+    if let Some(ScanProgress::Progress { files_scanned, dirs_scanned, total_bytes, .. }) = progress {
+        let mut counts = format!(
+            "{} files  ·  {} folders  ·  {}",
+            tooltip::format_count(*files_scanned),
+            tooltip::format_count(*dirs_scanned),
+            tooltip::format_size(*total_bytes, size_units)
         );
+        if let Some(fraction) = fraction {
+            counts.push_str(&format!("  ·  ~{:.0}%", fraction * 100.0));
+        }
+        if let Some(rendered) = text_renderer.render_text(&counts, "default", 12.0, Some(panel_w - 32.0)) {
+            let tx = x + ((panel_w - rendered.width as f32) * 0.5).max(16.0);
+            draw_text(scene, rendered, tx, text_y + 22.0);
+        }
+    }
+
+    if let Some(path) = current_path {
+        let max_chars = ((panel_w - 32.0) / (12.0 * 0.58)) as usize;
+        let shown = elide_middle(&path.display().to_string(), max_chars);
+        if let Some(rendered) = text_renderer.render_text(&shown, "default", 12.0, Some(panel_w - 32.0)) {
+            let tx = x + ((panel_w - rendered.width as f32) * 0.5).max(16.0);
+            draw_text(scene, rendered, tx, text_y + 24.0 + counts_h);
+        }
     }
 
     if show_admin_warning {
@@ -414,10 +1017,82 @@ pub fn render_loading_overlay(
             text_renderer,
             "Program not started with administrator permissions, loading will be 10x slower.",
             x + 14.0,
-            text_y + 28.0,
+            text_y + 28.0 + counts_h + path_h,
             panel_w - 32.0,
         );
     }
+
+    let (bw, bh) = (150.0, 28.0);
+    let bx = x + (panel_w - bw) * 0.5;
+    let by = y + panel_h - button_h - 4.0;
+    let button = Rect::new(bx as f64, by as f64, (bx + bw) as f64, (by + bh) as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.16, 0.17, 0.20, 0.90]),
+        None,
+        &button,
+    );
+    let label = if paused { "Resume (Space)" } else { "Pause (Space)" };
+    if let Some(rendered) = text_renderer.render_text(label, "default", 14.0, Some(bw - 16.0)) {
+        let tx = bx + (bw - rendered.width as f32) * 0.5;
+        draw_text(scene, rendered, tx, by + 6.0);
+    }
+    SidebarHitRegion {
+        id: SidebarHitId::ToggleScanPause,
+        bounds: [bx, by, bx + bw, by + bh],
+    }
+}
+
+/// Loading ring around `center`: a faint track with an arc filled to `fraction`, or a
+/// quarter arc circling smoothly while the total is unknown. Paused scans hold still.
+fn draw_progress_ring(
+    scene: &mut Scene,
+    center: (f64, f64),
+    radius: f64,
+    elapsed_seconds: f32,
+    fraction: Option<f32>,
+    paused: bool,
+) {
+    let stroke = Stroke::new(2.5);
+    scene.stroke(
+        &stroke,
+        Affine::IDENTITY,
+        Color::new([0.88, 0.90, 0.95, 0.16]),
+        None,
+        &Circle::new(center, radius),
+    );
+    let top = -std::f64::consts::FRAC_PI_2;
+    let (start, sweep) = match fraction {
+        Some(fraction) => (top, fraction.clamp(0.0, 1.0) as f64 * std::f64::consts::TAU),
+        None if paused => (top, std::f64::consts::FRAC_PI_2),
+        None => (top + elapsed_seconds as f64 * 4.0, std::f64::consts::FRAC_PI_2),
+    };
+    if sweep <= 0.0 {
+        return;
+    }
+    let alpha = if paused { 0.40 } else { 0.92 };
+    scene.stroke(
+        &stroke,
+        Affine::IDENTITY,
+        Color::new([0.88, 0.90, 0.95, alpha]),
+        None,
+        &kurbo::Arc::new(center, (radius, radius), start, sweep, 0.0),
+    );
+}
+
+/// Shorten `text` to `max_chars` by cutting out its middle, keeping the start and the
+/// (usually more telling) end of a path.
+fn elide_middle(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars || max_chars < 5 {
+        return text.to_string();
+    }
+    let tail = (max_chars - 1) * 2 / 3;
+    let head = max_chars - 1 - tail;
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(count - tail).collect();
+    format!("{}…{}", start, end)
 }
 
 fn draw_text(scene: &mut Scene, text_result: TextRenderResult, x: f32, y: f32) {
@@ -440,6 +1115,44 @@ fn draw_text(scene: &mut Scene, text_result: TextRenderResult, x: f32, y: f32) {
         );
 }
 
+/// Small triangle pointing right (`expand`) or left inside `bounds`.
+fn draw_chevron(scene: &mut Scene, bounds: [f32; 4], expand: bool) {
+    let [bx1, by1, bx2, by2] = bounds.map(|v| v as f64);
+    let cx = (bx1 + bx2) * 0.5;
+    let cy = (by1 + by2) * 0.5;
+    let (tip, back) = if expand { (cx + 4.0, cx - 3.0) } else { (cx - 4.0, cx + 3.0) };
+    let mut path = BezPath::new();
+    path.move_to((back, cy - 5.0));
+    path.line_to((tip, cy));
+    path.line_to((back, cy + 5.0));
+    path.close_path();
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.78, 0.82, 0.92, 0.90]),
+        None,
+        &path,
+    );
+}
+
+fn draw_up_arrow(scene: &mut Scene, bounds: [f32; 4]) {
+    let [bx1, by1, bx2, by2] = bounds.map(|v| v as f64);
+    let cx = (bx1 + bx2) * 0.5;
+    let cy = (by1 + by2) * 0.5;
+    let mut path = BezPath::new();
+    path.move_to((cx - 5.0, cy + 3.0));
+    path.line_to((cx, cy - 4.0));
+    path.line_to((cx + 5.0, cy + 3.0));
+    path.close_path();
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        Color::new([0.78, 0.82, 0.92, 0.90]),
+        None,
+        &path,
+    );
+}
+
 fn draw_label(scene: &mut Scene, text_renderer: &mut TextRenderer, text: &str, x: f32, y: f32) {
     draw_label_with_width(scene, text_renderer, text, x, y, 210.0);
 }
@@ -457,10 +1170,118 @@ fn draw_label_with_width(
     }
 }
 
+/// Draw a full-width sidebar button reading `label`, register it as `id`, and return
+/// the y of the next row.
+fn draw_toggle_row(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    label: &str,
+    id: SidebarHitId,
+    y: f32,
+    x2: f32,
+    hits: &mut Vec<SidebarHitRegion>,
+) -> f32 {
+    let bounds = [10.0, y, x2 - 10.0, y + 28.0];
+    let r = Rect::new(bounds[0] as f64, bounds[1] as f64, bounds[2] as f64, bounds[3] as f64);
+    scene.fill(Fill::NonZero, Affine::IDENTITY, Color::new([0.16, 0.17, 0.20, 0.78]), None, &r);
+    draw_label(scene, text_renderer, label, 18.0, y + 7.0);
+    hits.push(SidebarHitRegion { id, bounds });
+    y + 36.0
+}
+
+/// Draw a labelled `- [track] +` slider row. Returns the minus, plus and track bounds.
+fn draw_slider_row(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    label: &str,
+    value_text: &str,
+    t: f32,
+    y: f32,
+    x2: f32,
+) -> ([f32; 4], [f32; 4], [f32; 4]) {
+    draw_label(scene, text_renderer, label, 14.0, y);
+    draw_label(scene, text_renderer, value_text, x2 - 70.0, y);
+    let y = y + 18.0;
+    let minus = Rect::new(10.0, y as f64, 42.0, (y + 26.0) as f64);
+    let plus = Rect::new((x2 - 42.0) as f64, y as f64, x2 as f64, (y + 26.0) as f64);
+    let track = [50.0_f32, y, x2 - 50.0, y + 26.0];
+    let track_rect = Rect::new(track[0] as f64, track[1] as f64, track[2] as f64, track[3] as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        None,
+        &minus,
+    );
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        &Color::new([0.16, 0.17, 0.20, 0.78]),
+        None,
+        &plus,
+    );
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        &Color::new([0.20, 0.22, 0.26, 0.86]),
+        None,
+        &track_rect,
+    );
+    let thumb_x = track[0] + (track[2] - track[0]) * t.clamp(0.0, 1.0);
+    let thumb = Rect::new((thumb_x - 4.0) as f64, (y + 2.0) as f64, (thumb_x + 4.0) as f64, (y + 24.0) as f64);
+    scene.fill(
+        Fill::NonZero,
+        Affine::IDENTITY,
+        &Color::new([0.78, 0.82, 0.92, 0.95]),
+        None,
+        &thumb,
+    );
+    draw_label(scene, text_renderer, "-", 24.0, y + 3.0);
+    draw_label(scene, text_renderer, "+", x2 - 30.0, y + 3.0);
+    ([10.0, y, 42.0, y + 26.0], [x2 - 42.0, y, x2, y + 26.0], track)
+}
+
+/// Height of the "no drives" notice and its Open Folder button.
+const NO_DRIVES_H: f32 = 56.0 + 26.0 + 6.0;
+
+/// Empty state for the drive list: there is nothing to select, so point at the other ways
+/// in. Returns the y below it.
+fn draw_no_drives(
+    scene: &mut Scene,
+    text_renderer: &mut TextRenderer,
+    y: f32,
+    x2: f32,
+    hits: &mut Vec<SidebarHitRegion>,
+) -> f32 {
+    let text_w = x2 - 24.0;
+    if let Some(rendered) = text_renderer.render_text(
+        "No scannable drives detected — drag a folder here or use Open Folder",
+        "default",
+        12.0,
+        Some(text_w),
+    ) {
+        draw_text(scene, rendered, 14.0, y + 2.0);
+    }
+    let y = y + 56.0;
+    let bounds = [10.0, y, x2 - 10.0, y + 26.0];
+    let r = Rect::new(bounds[0] as f64, bounds[1] as f64, bounds[2] as f64, bounds[3] as f64);
+    scene.fill(Fill::NonZero, Affine::IDENTITY, Color::new([0.23, 0.30, 0.42, 0.86]), None, &r);
+    draw_label_centered(scene, text_renderer, "Open Folder…", 18.0, y, 14.0, 26.0);
+    hits.push(SidebarHitRegion {
+        id: SidebarHitId::OpenFolder,
+        bounds,
+    });
+    y + 26.0 + 6.0
+}
+
 fn sidebar_height(visible_drives: usize) -> f32 {
-    let drives_h = visible_drives as f32 * (26.0 + 6.0);
+    let drives_h = if visible_drives == 0 {
+        NO_DRIVES_H
+    } else {
+        visible_drives as f32 * (26.0 + 6.0)
+    };
     // Header + section padding + appearance controls.
-    14.0 + 22.0 + drives_h + 8.0 + 24.0 + 38.0 + 18.0 + 36.0 + 36.0 + 8.0
+    14.0 + 22.0 + drives_h + 4.0 + 36.0 + 8.0 + 24.0 + 38.0 + 54.0 + 54.0 + 54.0 + 36.0 + 36.0 + 36.0 + 36.0 + 36.0 + 36.0 + 36.0 + 36.0 + 8.0
 }
 
 fn draw_label_centered(
@@ -477,3 +1298,91 @@ fn draw_label_centered(
         draw_text(scene, rendered, x, y);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legend_names_the_mode_and_lists_present_categories() {
+        let analytics = Analytics {
+            category_sizes: vec![(FileCategory::Video, 900), (FileCategory::DiskImage, 100)],
+            ..Analytics::default()
+        };
+        let settings = ColorSettings {
+            mode: ColorMode::Category,
+            ..ColorSettings::default()
+        };
+        let legend = color_legend(&settings, &analytics);
+        assert_eq!(legend.title, "Colors: Category");
+        assert_eq!(legend.categories, [FileCategory::Video, FileCategory::DiskImage]);
+        assert_eq!(legend.note, None);
+        let labels: Vec<&str> = legend.categories.iter().map(|c| c.label()).collect();
+        assert_eq!(labels, ["Video", "Disk images"]);
+
+        let settings = ColorSettings {
+            mode: ColorMode::ExtensionHash,
+            ..settings
+        };
+        let legend = color_legend(&settings, &analytics);
+        assert_eq!(legend.title, "Colors: Ext Hash");
+        assert!(legend.categories.is_empty() && legend.note.is_some());
+    }
+
+    #[test]
+    fn elision_keeps_more_of_the_end() {
+        assert_eq!(elide_middle("/home/user/projects/silvaview", 16), "/home…/silvaview");
+        assert_eq!(elide_middle("/home/user/projects/silvaview", 16).chars().count(), 16);
+        assert_eq!(elide_middle("/tmp/x", 16), "/tmp/x");
+        // Counted in characters, not bytes.
+        assert_eq!(elide_middle("ééééééééé", 7), "éé…éééé");
+        assert_eq!(elide_middle("/home/user", 4), "/home/user");
+    }
+
+    #[test]
+    fn short_windows_scroll_the_sidebar_instead_of_cutting_it_off() {
+        let drives = [DriveEntry {
+            label: "C:".to_string(),
+            path: std::path::PathBuf::from("C:\\"),
+            total_bytes: 100,
+            available_bytes: 50,
+        }];
+        let color_settings = ColorSettings::default();
+        let mut sidebar = Sidebar {
+            drives: &drives,
+            selected_scan_path: std::path::Path::new("C:\\"),
+            color_settings: &color_settings,
+            min_brightness: 0.2,
+            depth_limit: None,
+            show_hover_info: true,
+            size_units: tooltip::SizeUnits::default(),
+            size_basis: SizeBasis::default(),
+            show_category_patterns: false,
+            collapsed: false,
+            can_navigate_up: false,
+            can_navigate_forward: false,
+            show_hidden_files: true,
+            freeze_layout: false,
+            scroll: 0.0,
+        };
+        let viewport_height = 400.0;
+        let [_, y1, _, y2] = sidebar_panel_bounds(viewport_height, drives.len(), false);
+        let mut render = |sidebar: &Sidebar| {
+            render_left_sidebar(&mut Scene::new(), &mut TextRenderer::new(), viewport_height, sidebar)
+        };
+        let shows = |hits: &[SidebarHitRegion], id: SidebarHitId| hits.iter().any(|h| h.id == id);
+
+        let top = render(&sidebar);
+        assert!(top.iter().all(|h| h.bounds[1] >= y1 && h.bounds[3] <= y2));
+        assert!(shows(&top, SidebarHitId::SelectDrive(drives[0].path.clone())));
+        assert!(!shows(&top, SidebarHitId::CopyTree));
+
+        sidebar.scroll = f32::MAX;
+        let bottom = render(&sidebar);
+        assert!(bottom.iter().all(|h| h.bounds[1] >= y1 && h.bounds[3] <= y2));
+        assert!(shows(&bottom, SidebarHitId::CopyTree));
+        assert!(shows(&bottom, SidebarHitId::ToggleSidebar));
+        assert!(sidebar_max_scroll(viewport_height, drives.len()) > 0.0);
+        assert_eq!(sidebar_max_scroll(2_000.0, drives.len()), 0.0);
+    }
+}
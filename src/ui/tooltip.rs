@@ -1,5 +1,7 @@
+use crate::scanner::types::{
+    ATTRIBUTE_SHORT_NAME_ONLY, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM, FILE_ATTRIBUTE_TEMPORARY,
+};
 use crate::tree::arena::{FileTree, NodeId};
-use crate::tree::extensions::categorize_extension;
 
 /// Information to display in the tooltip when hovering over a node.
 #[derive(Debug)]
@@ -12,23 +14,94 @@ pub struct TooltipInfo {
     pub child_count: Option<usize>,
 }
 
-/// Build tooltip info for a node.
-pub fn build_tooltip(tree: &FileTree, node_id: NodeId) -> TooltipInfo {
-    let node = tree.get(node_id);
+/// Unit system used when formatting byte counts for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// Powers of 1024, labelled KiB / MiB / GiB / TiB.
+    Iec,
+    /// Powers of 1000, labelled kB / MB / GB / TB.
+    Si,
+}
+
+impl Default for SizeUnits {
+    /// Match the host OS file manager: Windows reports 1024-based sizes,
+    /// while macOS and most Linux desktops report SI sizes.
+    fn default() -> Self {
+        if cfg!(windows) {
+            SizeUnits::Iec
+        } else {
+            SizeUnits::Si
+        }
+    }
+}
+
+impl SizeUnits {
+    pub fn toggled(self) -> Self {
+        match self {
+            SizeUnits::Iec => SizeUnits::Si,
+            SizeUnits::Si => SizeUnits::Iec,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeUnits::Iec => "IEC (GiB)",
+            SizeUnits::Si => "SI (GB)",
+        }
+    }
+}
+
+/// What a node's percentage share is measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PercentBasis {
+    /// The folder being viewed
+    #[default]
+    View,
+    /// The scanned root, whatever the navigation depth; a drive only when a
+    /// whole drive was scanned
+    Scan,
+}
+
+impl PercentBasis {
+    pub const ALL: [PercentBasis; 2] = [Self::View, Self::Scan];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PercentBasis::View => "of view",
+            PercentBasis::Scan => "of scan",
+        }
+    }
+}
+
+impl std::fmt::Display for PercentBasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "% {}", self.label())
+    }
+}
 
-    let ext = if node.extension_id > 0 {
-        tree.extensions
-            .get(node.extension_id as usize)
-            .map(|s| s.as_str())
-            .unwrap_or("")
+/// `part` as a percentage of `total` ("4.0%", "<0.1%"); `None` when `total` is zero.
+pub fn format_share(part: u64, total: u64) -> Option<String> {
+    if total == 0 {
+        return None;
+    }
+    let pct = part as f64 / total as f64 * 100.0;
+    Some(if part > 0 && pct < 0.1 {
+        "<0.1%".to_string()
     } else {
-        ""
-    };
+        format!("{:.1}%", pct)
+    })
+}
+
+/// Build tooltip info for a node.
+pub fn build_tooltip(tree: &FileTree, node_id: NodeId, units: SizeUnits) -> TooltipInfo {
+    let node = tree.get(node_id);
 
     let category = if node.is_dir {
         "Directory".to_string()
+    } else if !node.reclaimable {
+        "System file (not reclaimable)".to_string()
     } else {
-        format!("{:?}", categorize_extension(ext))
+        format!("{:?}", tree.category(node_id))
     };
 
     let child_count = if node.is_dir {
@@ -43,44 +116,216 @@ pub fn build_tooltip(tree: &FileTree, node_id: NodeId) -> TooltipInfo {
     TooltipInfo {
         name: node.name.to_string(),
         full_path,
-        size_display: format_size(node.size),
+        size_display: format_size(node.size, units),
         category,
         is_dir: node.is_dir,
         child_count,
     }
 }
 
+/// How `format_size_opts` renders a byte count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub units: SizeUnits,
+    /// Decimals from MB up; KB gets one fewer and plain bytes none.
+    pub precision: usize,
+    /// Single-letter suffixes without a space (`4.1G`), for tight labels.
+    pub compact: bool,
+}
+
+impl SizeFormat {
+    /// Full unit names at two decimals (`4.12 GiB`), as in tooltips.
+    pub fn verbose(units: SizeUnits) -> Self {
+        Self {
+            units,
+            precision: 2,
+            compact: false,
+        }
+    }
+
+    /// `du -h` style (`4.1G`), for treemap labels.
+    pub fn compact(units: SizeUnits) -> Self {
+        Self {
+            units,
+            precision: 1,
+            compact: true,
+        }
+    }
+}
+
 /// Format bytes into human-readable size string.
-pub fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-    const TB: u64 = 1024 * GB;
-
-    if bytes >= TB {
-        format!("{:.2} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+pub fn format_size(bytes: u64, units: SizeUnits) -> String {
+    format_size_opts(bytes, &SizeFormat::verbose(units))
+}
+
+/// Format bytes with explicit units, precision and suffix style.
+pub fn format_size_opts(bytes: u64, format: &SizeFormat) -> String {
+    let base: u64 = match format.units {
+        SizeUnits::Iec => 1024,
+        SizeUnits::Si => 1000,
+    };
+    let labels = match (format.units, format.compact) {
+        (_, true) => ["K", "M", "G", "T"],
+        (SizeUnits::Iec, false) => ["KiB", "MiB", "GiB", "TiB"],
+        (SizeUnits::Si, false) => ["kB", "MB", "GB", "TB"],
+    };
+    let sep = if format.compact { "" } else { " " };
+
+    let mut scale = base.pow(4);
+    for (i, label) in labels.iter().enumerate().rev() {
+        if bytes >= scale {
+            let precision = if i == 0 {
+                format.precision.saturating_sub(1)
+            } else {
+                format.precision
+            };
+            return format!("{:.*}{}{}", precision, bytes as f64 / scale as f64, sep, label);
+        }
+        scale /= base;
+    }
+    format!("{}{}B", bytes, sep)
+}
+
+/// Format a count with thousands separators (`48800` → `48,800`).
+pub fn format_count(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Format Unix seconds as `YYYY-MM-DD HH:MM UTC` (0 means the scanner didn't report one).
+pub fn format_timestamp(unix_secs: u64) -> String {
+    if unix_secs == 0 {
+        return "unknown".to_string();
     }
+    let days = (unix_secs / 86_400) as i64;
+    let secs_of_day = unix_secs % 86_400;
+
+    // Civil-from-days (Howard Hinnant), proleptic Gregorian calendar.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60
+    )
+}
+
+/// Name the FILE_ATTRIBUTE flags worth showing, e.g. `Hidden, System` (None if none are set).
+pub fn format_attributes(attributes: u32) -> Option<String> {
+    let names: Vec<&str> = [
+        (FILE_ATTRIBUTE_HIDDEN, "Hidden"),
+        (FILE_ATTRIBUTE_SYSTEM, "System"),
+        (FILE_ATTRIBUTE_READONLY, "Read-only"),
+        (FILE_ATTRIBUTE_TEMPORARY, "Temporary"),
+        (ATTRIBUTE_SHORT_NAME_ONLY, "8.3 name only"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| attributes & flag != 0)
+    .map(|(_, name)| name)
+    .collect();
+    (!names.is_empty()).then(|| names.join(", "))
 }
 
 /// Build the full path of a node by walking up the tree.
 pub fn build_path(tree: &FileTree, node_id: NodeId) -> String {
-    let mut parts = Vec::new();
-    let mut current = Some(node_id);
+    let mut parts: Vec<&str> = std::iter::once(node_id)
+        .chain(tree.ancestors(node_id))
+        .map(|id| tree.get(id).name.as_str())
+        .collect();
+    parts.reverse();
 
-    while let Some(id) = current {
-        let node = tree.get(id);
-        parts.push(node.name.to_string());
-        current = node.parent;
+    // Root names like `C:\` and `\\server\share\` already end in a separator.
+    let mut path = String::new();
+    for part in parts {
+        if !path.is_empty() && !path.ends_with('\\') {
+            path.push('\\');
+        }
+        path.push_str(part);
     }
+    path
+}
 
-    parts.reverse();
-    parts.join("\\")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_formats_keep_verbose_output_and_support_compact_labels() {
+        assert_eq!(format_size(512, SizeUnits::Iec), "512 B");
+        assert_eq!(format_size(1536, SizeUnits::Iec), "1.5 KiB");
+        assert_eq!(format_size(4_120_000_000, SizeUnits::Si), "4.12 GB");
+        assert_eq!(format_size_opts(4_120_000_000, &SizeFormat::compact(SizeUnits::Si)), "4.1G");
+        assert_eq!(format_size_opts(12_000, &SizeFormat::compact(SizeUnits::Si)), "12K");
+        assert_eq!(format_size_opts(900, &SizeFormat::compact(SizeUnits::Iec)), "900B");
+    }
+
+    #[test]
+    fn attributes_are_named_in_a_fixed_order() {
+        assert_eq!(format_attributes(0), None);
+        // FILE_ATTRIBUTE_ARCHIVE isn't worth showing on its own.
+        assert_eq!(format_attributes(0x20), None);
+        assert_eq!(format_attributes(FILE_ATTRIBUTE_READONLY).as_deref(), Some("Read-only"));
+        assert_eq!(
+            format_attributes(FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_HIDDEN | 0x20).as_deref(),
+            Some("Hidden, System")
+        );
+        assert_eq!(
+            format_attributes(ATTRIBUTE_SHORT_NAME_ONLY | FILE_ATTRIBUTE_TEMPORARY).as_deref(),
+            Some("Temporary, 8.3 name only")
+        );
+    }
+
+    #[test]
+    fn tooltip_size_follows_the_size_basis() {
+        use crate::tree::arena::{FileNode, SizeBasis};
+
+        let mut tree = FileTree::new("data");
+        let file = FileNode {
+            allocated: 4096,
+            ..FileNode::test_file("note.txt", 300)
+        };
+        let file = tree.add_child(tree.root, file);
+        crate::tree::set_size_basis(&mut tree, SizeBasis::Logical);
+        assert_eq!(build_tooltip(&tree, file, SizeUnits::Iec).size_display, "300 B");
+        crate::tree::set_size_basis(&mut tree, SizeBasis::OnDisk);
+        assert_eq!(build_tooltip(&tree, file, SizeUnits::Iec).size_display, "4.0 KiB");
+    }
+
+    #[test]
+    fn shares_round_to_a_tenth_and_flag_slivers() {
+        assert_eq!(format_share(1, 4).as_deref(), Some("25.0%"));
+        assert_eq!(format_share(7, 7).as_deref(), Some("100.0%"));
+        assert_eq!(format_share(1, 10_000).as_deref(), Some("<0.1%"));
+        assert_eq!(format_share(0, 10_000).as_deref(), Some("0.0%"));
+        assert_eq!(format_share(5, 0), None);
+        // Folder scans are common, so the root isn't called a drive.
+        assert_eq!(PercentBasis::Scan.to_string(), "% of scan");
+    }
+
+    #[test]
+    fn counts_group_thousands() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_000), "1,000");
+        assert_eq!(format_count(48_800), "48,800");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+    }
 }
@@ -0,0 +1,189 @@
+//! The CPU half of the pipeline, end to end: walk a directory, build the tree, lay it out
+//! and rasterize the cushions. Everything after this needs a GPU.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use silvaview_rs::layout::{compute_layout_in_rect, Layout, LayoutConfig};
+use silvaview_rs::render::cushion::CushionConfig;
+use silvaview_rs::scanner::types::{ScanControl, ScanOptions};
+use silvaview_rs::scanner::{estimate, scan, ScanError, ScanMethod};
+use silvaview_rs::tree::arena::FileTree;
+use silvaview_rs::tree::merge_trees;
+use silvaview_rs::{layout_and_rasterize, scan_and_build};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+
+/// A directory under the system temp dir, removed again on drop.
+struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("silvaview-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    /// Write a file of `size` bytes at `rel`, creating its folders.
+    fn file(&self, rel: &str, size: usize) -> &Self {
+        let path = self.root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; size]).unwrap();
+        self
+    }
+
+    fn dir(&self, rel: &str) -> &Self {
+        fs::create_dir_all(self.root.join(rel)).unwrap();
+        self
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn sample(name: &str) -> Fixture {
+    let fixture = Fixture::new(name);
+    fixture
+        .file("videos/holiday.mkv", 60_000)
+        .file("videos/clips/a.mp4", 12_000)
+        .file("videos/clips/b.mp4", 8_000)
+        .file("docs/report.pdf", 9_000)
+        .file("docs/notes.txt", 1_500)
+        .file("src/main.rs", 3_000)
+        .file("src/lib/util.rs", 2_000)
+        .file("backup.zip", 25_000)
+        .dir("empty");
+    fixture
+}
+
+fn scan_tree(path: &Path) -> FileTree {
+    scan_and_build(path, ScanMethod::WalkDir).unwrap()
+}
+
+fn layout(tree: &FileTree) -> Layout {
+    compute_layout_in_rect(tree, tree.root, 0.0, 0.0, WIDTH as f32, HEIGHT as f32, &LayoutConfig::default())
+}
+
+#[test]
+fn scan_builds_the_expected_tree() {
+    let fixture = sample("tree");
+    let tree = scan_tree(&fixture.root);
+
+    let root = tree.get(tree.root);
+    assert_eq!(root.size, 120_500);
+    assert_eq!(root.file_count, 8);
+    // videos, clips, docs, src, lib, empty
+    assert_eq!(root.dir_count, 6);
+
+    // Scanning the same folder twice gives the same tree.
+    assert_eq!(scan_tree(&fixture.root).len(), tree.len());
+}
+
+#[test]
+fn estimate_counts_without_building_a_tree() {
+    let fixture = sample("estimate");
+    let estimate = estimate(&fixture.root, ScanMethod::WalkDir).unwrap();
+
+    assert_eq!(estimate.method, "walkdir");
+    assert_eq!(estimate.files, 8);
+    assert_eq!(estimate.total_bytes, 120_500);
+    assert_eq!(estimate.entries as u64, estimate.files + estimate.dirs);
+}
+
+#[test]
+fn paused_scans_wait_and_cancelled_ones_stop() {
+    let fixture = sample("control");
+    let run = |control: &Arc<ScanControl>| {
+        let (path, control) = (fixture.root.clone(), control.clone());
+        std::thread::spawn(move || {
+            let options = ScanOptions { control: Some(control), ..Default::default() };
+            scan(&path, ScanMethod::WalkDir, options, std::sync::mpsc::channel().0)
+        })
+    };
+
+    let control = Arc::new(ScanControl::default());
+    control.pause();
+    let paused = run(&control);
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!paused.is_finished());
+    control.resume();
+    assert_eq!(paused.join().unwrap().unwrap().len(), 15);
+
+    let control = Arc::new(ScanControl::default());
+    control.pause();
+    let cancelled = run(&control);
+    control.cancel();
+    assert!(matches!(cancelled.join().unwrap(), Err(ScanError::Cancelled)));
+}
+
+#[test]
+fn merged_scans_keep_each_folder_at_the_top_level() {
+    let (first, second) = (sample("merge-a"), Fixture::new("merge-b"));
+    second.file("music/song.flac", 30_000);
+    let scans = vec![
+        (first.root.clone(), scan_tree(&first.root)),
+        (second.root.clone(), scan_tree(&second.root)),
+    ];
+
+    let tree = merge_trees("Selected folders", &scans);
+    let root = tree.get(tree.root);
+    assert_eq!(root.size, 150_500);
+    assert_eq!(root.file_count, 9);
+
+    // Biggest first, each named by the folder it was scanned from.
+    let tops: Vec<_> = tree.children(tree.root).map(|id| tree.get(id).name.to_string()).collect();
+    assert_eq!(tops, [first.root.to_string_lossy(), second.root.to_string_lossy()]);
+}
+
+#[test]
+fn layout_rects_are_finite_and_inside_the_viewport() {
+    let fixture = sample("layout");
+    let tree = scan_tree(&fixture.root);
+    let layout = layout(&tree);
+
+    assert!(layout.rects.len() > 8, "only {} rects", layout.rects.len());
+    for rect in &layout.rects {
+        assert!(rect.x.is_finite() && rect.y.is_finite() && rect.w.is_finite() && rect.h.is_finite());
+        assert!(rect.w >= 0.0 && rect.h >= 0.0);
+        assert!(rect.x >= -0.5 && rect.y >= -0.5);
+        assert!(rect.x + rect.w <= WIDTH as f32 + 0.5 && rect.y + rect.h <= HEIGHT as f32 + 0.5);
+    }
+    for (&node, &idx) in &layout.node_to_rect {
+        assert_eq!(layout.rects[idx].node, node);
+    }
+
+    // Top-level tiles cover most of the viewport without exceeding it.
+    let top_area: f32 = layout
+        .rects
+        .iter()
+        .filter(|r| r.depth == 1)
+        .map(|r| r.w * r.h)
+        .sum();
+    let viewport_area = (WIDTH * HEIGHT) as f32;
+    assert!(top_area <= viewport_area * 1.001, "top-level area {} exceeds the viewport", top_area);
+    assert!(top_area >= viewport_area * 0.5, "top-level area {} covers too little", top_area);
+}
+
+#[test]
+fn cushions_paint_the_viewport() {
+    let fixture = sample("raster");
+    let tree = scan_tree(&fixture.root);
+
+    let pixels = layout_and_rasterize(&tree, WIDTH, HEIGHT, &CushionConfig::default());
+    assert_eq!(pixels.len(), (WIDTH * HEIGHT * 4) as usize);
+
+    let lit = pixels
+        .chunks_exact(4)
+        .filter(|p| p[0].max(p[1]).max(p[2]) > 40)
+        .count();
+    assert!(lit > (WIDTH * HEIGHT / 2) as usize, "only {} lit pixels", lit);
+}